@@ -21,8 +21,8 @@ pub use actix_web::middleware::cors::Cors;
 
 use actix::{Addr, System};
 use actix_web::{
-    self, error::ResponseError, server::{HttpServer, IntoHttpHandler, StopServer}, AsyncResponder,
-    FromRequest, HttpMessage, HttpResponse, Query,
+    self, error::ResponseError, http::Method, server::{HttpServer, IntoHttpHandler, StopServer},
+    AsyncResponder, Body, FromRequest, HttpMessage, HttpResponse, Query,
 };
 use failure;
 use futures::{Future, IntoFuture};
@@ -84,6 +84,11 @@ impl ApiBuilder {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Returns `true` if no handlers are added to the builder.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
 }
 
 impl ServiceApiBackend for ApiBuilder {
@@ -97,6 +102,13 @@ impl ServiceApiBackend for ApiBuilder {
 
     fn wire(&self, mut output: Self::Backend) -> Self::Backend {
         for handler in self.handlers.clone() {
+            if handler.method == Method::GET {
+                // `HEAD` requests are served by the `GET` handler without the response body.
+                let inner = Arc::clone(&handler.inner);
+                output = output.route(&handler.name, Method::HEAD, move |request| {
+                    head_response(inner(request))
+                });
+            }
             let inner = handler.inner;
             output = output.route(&handler.name, handler.method.clone(), move |request| {
                 inner(request)
@@ -106,6 +118,18 @@ impl ServiceApiBackend for ApiBuilder {
     }
 }
 
+/// Converts the response of a `GET` handler into the response to a `HEAD` request,
+/// which has the same status and headers, but no body.
+fn head_response(response: FutureResponse) -> FutureResponse {
+    let future = response.then(|outcome| -> result::Result<_, actix_web::Error> {
+        let mut response =
+            outcome.unwrap_or_else(|error| error.as_response_error().error_response());
+        response.set_body(Body::Empty);
+        Ok(response)
+    });
+    Box::new(future)
+}
+
 // TODO: remove this workaround for a regression in actix-web 0.7.3 (ECR-2149)
 fn sanitize(mut handler: RequestHandler) -> RequestHandler {
     if !handler.name.starts_with('/') {
@@ -549,3 +573,28 @@ fn allow_origin_from_str() {
         AllowOrigin::Whitelist(vec!["http://a.org".to_string(), "http://b.org".to_string()]),
     );
 }
+
+#[test]
+fn head_response_has_no_body() {
+    use actix_web::http::StatusCode;
+    use futures::future;
+
+    fn is_empty(response: &HttpResponse) -> bool {
+        match *response.body() {
+            Body::Empty => true,
+            _ => false,
+        }
+    }
+
+    let ok: FutureResponse = Box::new(future::ok(HttpResponse::Ok().json("value")));
+    let response = head_response(ok).wait().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(is_empty(&response));
+
+    // Handler errors are converted into error statuses with empty bodies.
+    let error = ApiError::NotFound("Unknown item".to_owned());
+    let not_found: FutureResponse = Box::new(future::err(error.into()));
+    let response = head_response(not_found).wait().unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(is_empty(&response));
+}
@@ -135,6 +135,11 @@ impl ServiceApiScope {
     pub fn web_backend(&mut self) -> &mut actix::ApiBuilder {
         &mut self.actix_backend
     }
+
+    /// Returns `true` if no endpoints are added to the scope.
+    pub fn is_empty(&self) -> bool {
+        self.actix_backend.is_empty()
+    }
 }
 
 /// Service API builder, which is used to add service-specific endpoints to the node API.
@@ -317,11 +322,13 @@ impl ApiAggregator {
             ApiAccess::Public => backend.extend(
                 self.inner
                     .iter()
+                    .filter(|&(_, builder)| !builder.public_scope.is_empty())
                     .map(|(name, builder)| (name.as_ref(), &builder.public_scope)),
             ),
             ApiAccess::Private => backend.extend(
                 self.inner
                     .iter()
+                    .filter(|&(_, builder)| !builder.private_scope.is_empty())
                     .map(|(name, builder)| (name.as_ref(), &builder.private_scope)),
             ),
         }
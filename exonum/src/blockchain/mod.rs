@@ -125,6 +125,12 @@ impl Blockchain {
         &self.service_map
     }
 
+    /// Returns the public key of the service keypair used by the blockchain
+    /// to sign transactions created by services.
+    pub fn service_public_key(&self) -> &PublicKey {
+        &self.service_keypair.0
+    }
+
     /// Creates a read-only snapshot of the current storage state.
     pub fn snapshot(&self) -> Box<dyn Snapshot> {
         self.db.snapshot()
@@ -201,6 +201,34 @@ pub trait Service: Send + Sync + 'static {
     /// *Try not to perform long operations in this handler*.
     fn after_commit(&self, context: &ServiceContext) {}
 
+    /// Returns the name of the type of the given transaction of this service. The name
+    /// is used for debugging purposes, e.g., by the testkit.
+    ///
+    /// Services declaring their transactions with the `transactions!` macro may implement
+    /// the method via [`TransactionSet`]:
+    ///
+    /// ```ignore
+    /// fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+    ///     ServiceTransactions::transaction_type_name(tx.raw())
+    /// }
+    /// ```
+    ///
+    /// *Default implementation returns `None`.*
+    ///
+    /// [`TransactionSet`]: trait.TransactionSet.html#method.transaction_type_name
+    fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+        None
+    }
+
+    /// Exports the state of this service as a JSON value, e.g., built with the schema
+    /// accessors of the service. The exported state is used for testing purposes,
+    /// e.g., by the testkit to compare the service state before and after an upgrade.
+    ///
+    /// *Default implementation returns `None`.*
+    fn export_state(&self, snapshot: &dyn Snapshot) -> Option<Value> {
+        None
+    }
+
     /// Extends API by handlers of this service. The request handlers are mounted on
     /// the `/api/services/{service_name}` path at the listen address of every
     /// full node in the blockchain network.
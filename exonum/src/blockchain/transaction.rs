@@ -362,6 +362,15 @@ pub trait TransactionSet:
 {
     /// Parses a transaction from this set from a `RawMessage`.
     fn tx_from_raw(raw: RawTransaction) -> Result<Self, encoding::Error>;
+
+    /// Returns the name of the transaction type from this set corresponding to the message,
+    /// or `None` if the message does not belong to the set.
+    ///
+    /// *Default implementation returns `None`.* The implementation generated
+    /// by the `transactions!` macro returns the name of the transaction struct.
+    fn transaction_type_name(_raw: &RawTransaction) -> Option<&'static str> {
+        None
+    }
 }
 
 /// `transactions!` is used to declare a set of transactions of a particular service.
@@ -575,6 +584,19 @@ macro_rules! transactions {
                     _ => return Err($crate::encoding::Error::IncorrectMessageType { message_type })
                 }
             }
+
+            fn transaction_type_name(
+                raw: &$crate::messages::RawTransaction
+            ) -> ::std::option::Option<&'static str> {
+                match raw.message_type() {
+                    $(
+                    <$name as $crate::messages::ServiceMessage>::MESSAGE_ID => {
+                        Some(stringify!($name))
+                    }
+                    )*
+                    _ => None,
+                }
+            }
         }
 
         impl Into<Box<dyn $crate::blockchain::Transaction>> for $transaction_set {
@@ -14,7 +14,7 @@
 
 //! Common widely used type definitions.
 
-use std::{fmt, num::ParseIntError, str::FromStr};
+use std::{fmt, num::ParseIntError, ops::{Add, Sub}, str::FromStr};
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -253,6 +253,79 @@ impl ValidatorId {
     pub fn zero() -> Self {
         ValidatorId(0)
     }
+
+    /// Returns the validator id `rhs` positions after this one, wrapping around
+    /// `modulus`, which is usually the number of validators in the network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modulus` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::helpers::ValidatorId;
+    ///
+    /// let id = ValidatorId(2);
+    /// assert_eq!(ValidatorId(0), id.wrapping_add(2, 4));
+    /// assert_eq!(ValidatorId(3), id.wrapping_add(5, 4));
+    /// ```
+    pub fn wrapping_add(self, rhs: u16, modulus: u16) -> Self {
+        assert!(modulus > 0, "Modulus should be positive");
+        let sum = (u32::from(self.0) + u32::from(rhs)) % u32::from(modulus);
+        ValidatorId(sum as u16)
+    }
+}
+
+/// Adds an offset to the validator id. Unlike [`wrapping_add`], the addition does not wrap
+/// around the number of validators.
+///
+/// # Panics
+///
+/// Panics if the sum exceeds `u16::max_value()`.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::helpers::ValidatorId;
+///
+/// assert_eq!(ValidatorId(3), ValidatorId(1) + 2);
+/// ```
+///
+/// [`wrapping_add`]: #method.wrapping_add
+impl Add<u16> for ValidatorId {
+    type Output = Self;
+
+    fn add(self, rhs: u16) -> Self {
+        let sum = self.0
+            .checked_add(rhs)
+            .unwrap_or_else(|| panic!("Validator id overflow: {} + {}", self.0, rhs));
+        ValidatorId(sum)
+    }
+}
+
+/// Subtracts an offset from the validator id.
+///
+/// # Panics
+///
+/// Panics if the offset exceeds the validator id.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::helpers::ValidatorId;
+///
+/// assert_eq!(ValidatorId(1), ValidatorId(3) - 2);
+/// ```
+impl Sub<u16> for ValidatorId {
+    type Output = Self;
+
+    fn sub(self, rhs: u16) -> Self {
+        let difference = self.0
+            .checked_sub(rhs)
+            .unwrap_or_else(|| panic!("Validator id underflow: {} - {}", self.0, rhs));
+        ValidatorId(difference)
+    }
 }
 
 impl fmt::Display for Height {
@@ -352,3 +425,27 @@ impl Iterator for RoundRangeIter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ValidatorId;
+
+    #[test]
+    fn test_validator_id_arithmetic() {
+        assert_eq!(ValidatorId(1) + 2, ValidatorId(3));
+        assert_eq!(ValidatorId(3) - 3, ValidatorId(0));
+        assert_eq!(ValidatorId(u16::max_value() - 1) + 1, ValidatorId(u16::max_value()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Validator id underflow: 0 - 1")]
+    fn test_validator_id_underflow() {
+        let _ = ValidatorId(0) - 1;
+    }
+
+    #[test]
+    #[should_panic(expected = "Validator id overflow: 65535 + 1")]
+    fn test_validator_id_overflow() {
+        let _ = ValidatorId(u16::max_value()) + 1;
+    }
+}
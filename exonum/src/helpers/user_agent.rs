@@ -18,6 +18,25 @@ use os_info;
 
 static USER_AGENT: &str = include_str!(concat!(env!("OUT_DIR"), "/user_agent"));
 
+/// Returns the part of the "user agent" string containing information about Exonum
+/// and Rust versions, for example, `exonum 0.9.0/rustc 1.29.0 (aa3ca1994 2018-09-11)`.
+///
+/// Unlike [`get`], the returned string is determined at compile time.
+///
+/// [`get`]: fn.get.html
+///
+/// # Examples
+///
+/// ```
+/// use exonum::helpers::user_agent;
+///
+/// let versions = user_agent::versions();
+/// assert!(versions.starts_with("exonum "));
+/// ```
+pub fn versions() -> &'static str {
+    USER_AGENT.trim()
+}
+
 /// Returns "user agent" string containing information about Exonum, Rust and OS versions.
 ///
 /// # Examples
@@ -30,7 +49,7 @@ static USER_AGENT: &str = include_str!(concat!(env!("OUT_DIR"), "/user_agent"));
 /// ```
 pub fn get() -> String {
     let os = os_info::get();
-    format!("{}/{}", USER_AGENT, os)
+    format!("{}/{}", versions(), os)
 }
 
 #[cfg(test)]
@@ -48,4 +67,14 @@ mod tests {
             assert!(!val.is_empty());
         }
     }
+
+    // Checks that the compile-time part of user agent does not contain line breaks.
+    #[test]
+    fn versions_components() {
+        let versions = versions();
+        assert!(!versions.contains('\n'));
+        let components: Vec<_> = versions.split('/').collect();
+        assert_eq!(components.len(), 2);
+        assert!(components[0].starts_with("exonum "));
+    }
 }
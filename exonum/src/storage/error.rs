@@ -32,7 +32,9 @@ pub struct Error {
 
 impl Error {
     /// Creates a new storage error with an information message about the reason.
-    pub(crate) fn new<T: Into<String>>(message: T) -> Self {
+    ///
+    /// The constructor may be used by custom `Database` implementations.
+    pub fn new<T: Into<String>>(message: T) -> Self {
         Self {
             message: message.into(),
         }
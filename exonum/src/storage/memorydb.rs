@@ -46,6 +46,15 @@ impl MemoryDB {
     }
 }
 
+impl Clone for MemoryDB {
+    /// Creates a deep copy of the database.
+    fn clone(&self) -> Self {
+        Self {
+            map: RwLock::new(self.map.read().unwrap().clone()),
+        }
+    }
+}
+
 impl Database for MemoryDB {
     fn snapshot(&self) -> Box<dyn Snapshot> {
         Box::new(Self {
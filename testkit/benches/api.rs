@@ -0,0 +1,76 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#[macro_use]
+extern crate bencher;
+extern crate exonum;
+extern crate exonum_testkit;
+
+use bencher::Bencher;
+use exonum::{api::node::public::system::HealthCheckInfo, crypto};
+use exonum_testkit::{
+    samples::{CounterService, TransactionResponse, TxIncrement, COUNTER_SERVICE_NAME},
+    ApiKind, TestKitBuilder,
+};
+
+fn bench_api_get(bencher: &mut Bencher) {
+    let testkit = TestKitBuilder::validator().create();
+    let api = testkit.api();
+    bencher.iter(|| {
+        let info: HealthCheckInfo = api.public(ApiKind::System)
+            .get("v1/healthcheck")
+            .unwrap();
+        info
+    });
+}
+
+fn bench_service_api_get(bencher: &mut Bencher) {
+    let testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .create();
+    let api = testkit.api();
+    bencher.iter(|| {
+        let count: u64 = api.public(ApiKind::Service(COUNTER_SERVICE_NAME))
+            .get("count")
+            .unwrap();
+        count
+    });
+}
+
+fn bench_service_api_post(bencher: &mut Bencher) {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .create();
+    let api = testkit.api();
+    let (pubkey, key) = crypto::gen_keypair();
+    let mut by = 0;
+    bencher.iter(|| {
+        by += 1;
+        let tx = TxIncrement::new(&pubkey, by, &key);
+        let response: TransactionResponse = api.public(ApiKind::Service(COUNTER_SERVICE_NAME))
+            .query(&tx)
+            .post("count")
+            .unwrap();
+        // Drain the sent transaction so that the events channel does not overflow.
+        testkit.poll_events();
+        response
+    });
+}
+
+benchmark_group!(
+    benches,
+    bench_api_get,
+    bench_service_api_get,
+    bench_service_api_post
+);
+benchmark_main!(benches);
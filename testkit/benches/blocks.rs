@@ -0,0 +1,118 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#[macro_use]
+extern crate bencher;
+extern crate exonum;
+extern crate exonum_testkit;
+
+use bencher::Bencher;
+use exonum::{blockchain::Schema, crypto, messages::Message};
+use exonum_testkit::{
+    samples::{CounterService, TxIncrement}, TestKitBuilder,
+};
+
+const BLOCKS_COUNT: u64 = 100;
+const TXS_IN_BLOCK: u64 = 100;
+// The number of blocks with transactions is reduced unless long benchmarks are enabled.
+#[cfg(feature = "long_benchmarks")]
+const BLOCKS_WITH_TXS_COUNT: u64 = 1_000;
+#[cfg(not(feature = "long_benchmarks"))]
+const BLOCKS_WITH_TXS_COUNT: u64 = 10;
+
+fn bench_create_block_loop(bencher: &mut Bencher) {
+    bencher.iter(|| {
+        let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+        for _ in 0..BLOCKS_COUNT {
+            testkit.create_block();
+        }
+        testkit
+    });
+}
+
+fn bench_fast_forward(bencher: &mut Bencher) {
+    bencher.iter(|| {
+        let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+        testkit.fast_forward(BLOCKS_COUNT);
+        testkit
+    });
+}
+
+fn bench_create_blocks_with_transactions(bencher: &mut Bencher) {
+    let (pubkey, key) = crypto::gen_keypair();
+    let blocks: Vec<Vec<TxIncrement>> = (0..BLOCKS_WITH_TXS_COUNT)
+        .map(|block| {
+            (0..TXS_IN_BLOCK)
+                .map(|i| TxIncrement::new(&pubkey, block * TXS_IN_BLOCK + i + 1, &key))
+                .collect()
+        })
+        .collect();
+
+    bencher.iter(|| {
+        let mut testkit = TestKitBuilder::validator()
+            .with_validators(4)
+            .with_service(CounterService)
+            .create();
+        for txs in &blocks {
+            testkit.create_block_with_transactions(txs.iter().cloned().map(Into::into));
+        }
+        testkit
+    });
+}
+
+fn bench_create_blocks_from_pool(bencher: &mut Bencher) {
+    let (pubkey, key) = crypto::gen_keypair();
+    let txs: Vec<TxIncrement> = (0..TXS_IN_BLOCK)
+        .map(|i| TxIncrement::new(&pubkey, i + 1, &key))
+        .collect();
+
+    bencher.iter(|| {
+        let mut testkit = TestKitBuilder::validator()
+            .with_validators(4)
+            .with_service(CounterService)
+            .create();
+        let mut fork = testkit.blockchain_mut().fork();
+        {
+            let mut schema = Schema::new(&mut fork);
+            for tx in &txs {
+                schema.add_transaction_into_pool(tx.raw().clone());
+            }
+        }
+        testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+        let block = testkit.create_block();
+        assert_eq!(block.len(), TXS_IN_BLOCK as usize);
+        testkit
+    });
+}
+
+fn bench_probe(bencher: &mut Bencher) {
+    let (pubkey, key) = crypto::gen_keypair();
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with_service(CounterService)
+        .create();
+    testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 1, &key));
+    let tx = TxIncrement::new(&pubkey, 2, &key);
+
+    bencher.iter(|| testkit.probe(tx.clone()));
+}
+
+benchmark_group!(
+    benches,
+    bench_create_block_loop,
+    bench_fast_forward,
+    bench_create_blocks_with_transactions,
+    bench_create_blocks_from_pool,
+    bench_probe
+);
+benchmark_main!(benches);
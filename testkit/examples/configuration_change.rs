@@ -17,10 +17,7 @@ extern crate exonum_testkit;
 extern crate serde;
 extern crate serde_json;
 
-use exonum::{
-    blockchain::Schema, crypto::CryptoHash, helpers::{Height, ValidatorId},
-};
-use exonum_testkit::TestKitBuilder;
+use exonum_testkit::prelude::*;
 
 fn main() {
     let mut testkit = TestKitBuilder::auditor().with_validators(3).create();
@@ -49,7 +46,7 @@ fn main() {
     assert_eq!(testkit.validator(ValidatorId(3)), testkit.network().us());
     assert_eq!(testkit.actual_configuration(), stored);
     assert_eq!(
-        Schema::new(&testkit.snapshot())
+        CoreSchema::new(&testkit.snapshot())
             .previous_configuration()
             .unwrap()
             .hash(),
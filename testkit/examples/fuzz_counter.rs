@@ -0,0 +1,128 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fuzz target for the sample counter service.
+//!
+//! The target decodes arbitrary bytes into counter transactions and block boundaries
+//! and applies them to a testkit with `exonum_testkit::fuzz`. To run it with `cargo-fuzz`,
+//! copy `CounterDecoder` and `fuzz_target` into a fuzz target of the `cargo fuzz init`
+//! crate and call `fuzz_target` from the `fuzz_target!` macro of `libfuzzer-sys`:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| fuzz_target(data));
+//! ```
+//!
+//! Without `cargo-fuzz`, the example replays the inputs stored in the files passed
+//! as command line arguments, e.g., crashes found by the fuzzer:
+//!
+//! ```text
+//! cargo run --example fuzz_counter --features "fuzz examples" -- crash-file
+//! ```
+//!
+//! Since `TxIncrement` panics on the counter overflow in debug builds, the fuzzer
+//! is expected to find inputs with large increments quickly.
+
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::blockchain::Transaction;
+use exonum::crypto::{self, PublicKey, SecretKey, Seed, SEED_LENGTH};
+use exonum_testkit::TestKitBuilder;
+use exonum_testkit::fuzz::{FuzzDecoder, FuzzOp, FuzzRunner, FuzzStats};
+use exonum_testkit::samples::{CounterService, TxIncrement, TxReset};
+
+use std::env;
+use std::fs;
+
+/// Number of distinct authors of the transactions.
+const AUTHOR_COUNT: u8 = 4;
+
+/// Decoder of counter operations. Each operation starts with an opcode byte:
+///
+/// - `0`: create a block.
+/// - `1`: roll back the latest block.
+/// - `2`: increment, followed by the author byte and 8 bytes of the increment (little endian).
+/// - `3`: probe an increment, encoded in the same way.
+/// - `4`: reset, followed by the author byte.
+///
+/// Opcodes above `4` are reduced modulo 5.
+struct CounterDecoder {
+    keys: Vec<(PublicKey, SecretKey)>,
+}
+
+impl CounterDecoder {
+    fn new() -> Self {
+        let keys = (0..AUTHOR_COUNT)
+            .map(|i| crypto::gen_keypair_from_seed(&Seed::new([i; SEED_LENGTH])))
+            .collect();
+        CounterDecoder { keys }
+    }
+
+    fn author(&self, input: &mut &[u8]) -> Option<&(PublicKey, SecretKey)> {
+        let index = take_bytes(input, 1)?[0] % AUTHOR_COUNT;
+        Some(&self.keys[index as usize])
+    }
+
+    fn increment(&self, input: &mut &[u8]) -> Option<Box<dyn Transaction>> {
+        let &(ref pubkey, ref key) = self.author(input)?;
+        let by = take_bytes(input, 8)?
+            .iter()
+            .rev()
+            .fold(0_u64, |acc, &byte| (acc << 8) | u64::from(byte));
+        Some(Box::new(TxIncrement::new(pubkey, by, key)))
+    }
+}
+
+impl FuzzDecoder for CounterDecoder {
+    fn decode_op(&self, input: &mut &[u8]) -> Option<FuzzOp> {
+        let opcode = take_bytes(input, 1)?[0] % 5;
+        match opcode {
+            0 => Some(FuzzOp::CreateBlock),
+            1 => Some(FuzzOp::Rollback),
+            2 => self.increment(input).map(FuzzOp::Transaction),
+            3 => self.increment(input).map(FuzzOp::Probe),
+            _ => {
+                let &(ref pubkey, ref key) = self.author(input)?;
+                Some(FuzzOp::Transaction(Box::new(TxReset::new(pubkey, key))))
+            }
+        }
+    }
+}
+
+/// Splits off the specified number of bytes from the beginning of the input.
+fn take_bytes<'a>(input: &mut &'a [u8], count: usize) -> Option<&'a [u8]> {
+    let data = *input;
+    if data.len() < count {
+        return None;
+    }
+    let (bytes, rest) = data.split_at(count);
+    *input = rest;
+    Some(bytes)
+}
+
+fn fuzz_target(data: &[u8]) -> FuzzStats {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .create();
+    FuzzRunner::new()
+        .with_rollbacks()
+        .run(&mut testkit, &CounterDecoder::new(), data)
+}
+
+fn main() {
+    for path in env::args().skip(1) {
+        let data = fs::read(&path).unwrap_or_else(|e| panic!("Cannot read {}: {}", path, e));
+        println!("{}: {:?}", path, fuzz_target(&data));
+    }
+}
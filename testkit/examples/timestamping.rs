@@ -18,13 +18,8 @@ extern crate exonum;
 extern crate exonum_testkit;
 extern crate serde_json;
 
-use exonum::{
-    api::node::public::explorer::{BlocksQuery, BlocksRange, TransactionQuery},
-    blockchain::{ExecutionResult, Schema, Service, Transaction, TransactionSet},
-    crypto::{gen_keypair, CryptoHash, Hash, PublicKey}, encoding,
-    messages::{Message, RawTransaction}, storage::{Fork, Snapshot},
-};
-use exonum_testkit::{ApiKind, TestKitBuilder};
+use exonum::api::node::public::explorer::{BlocksQuery, BlocksRange, TransactionQuery};
+use exonum_testkit::prelude::*;
 
 // Simple service implementation.
 
@@ -79,7 +74,7 @@ fn main() {
         .with_service(TimestampingService)
         .create();
     // Create few transactions.
-    let keypair = gen_keypair();
+    let keypair = crypto::gen_keypair();
     let tx1 = TxTimestamp::new(&keypair.0, "Down To Earth", &keypair.1);
     let tx2 = TxTimestamp::new(&keypair.0, "Cry Over Spilt Milk", &keypair.1);
     let tx3 = TxTimestamp::new(&keypair.0, "Dropping Like Flies", &keypair.1);
@@ -92,7 +87,7 @@ fn main() {
 
     // Check results with schema.
     let snapshot = testkit.snapshot();
-    let schema = Schema::new(&snapshot);
+    let schema = CoreSchema::new(&snapshot);
     assert!(schema.transactions().contains(&tx1.hash()));
     assert!(schema.transactions().contains(&tx2.hash()));
     assert!(schema.transactions().contains(&tx3.hash()));
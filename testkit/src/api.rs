@@ -15,21 +15,80 @@
 //! API encapsulation for the testkit.
 
 pub use exonum::api::ApiAccess;
+pub use reqwest::StatusCode;
 
 use actix_web::{test::TestServer, App};
-use reqwest::{Client, Response, StatusCode};
+use reqwest::{Client, RequestBuilder as ClientRequestBuilder, Response};
 use serde_json;
 use serde_urlencoded;
 
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::{self, Display};
+use std::iter;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use exonum::{
-    api::{self, ApiAggregator, ServiceApiState}, blockchain::{SharedNodeState, Transaction},
-    encoding::serialize::reexport::{DeserializeOwned, Serialize},
-    node::{ApiSender, TransactionSend},
+    api::{
+        self, node::public::explorer::{BlocksQuery, BlocksRange}, ApiAggregator,
+        ExtendApiBackend, ServiceApiBuilder, ServiceApiState,
+    },
+    blockchain::{Blockchain, SharedNodeState, Transaction},
+    encoding::serialize::reexport::{DeserializeOwned, Serialize}, helpers::Height,
+    messages::Message, node::{ApiSender, TransactionSend},
 };
 
-use TestKit;
+use compare::JsonDiff;
+use {Error, TestKit};
+
+// Name of the test server hosting the testkit API in panic messages. The address of the server
+// is assigned on start, so it does not tell by itself where a failed request was sent.
+const TESTKIT_VIRTUAL_HOST: &str = "testkit virtual host";
+
+/// Components of the user agent string reported by an Exonum node, which has
+/// the `name version/rust-version/os` format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgent {
+    /// Name of the Exonum package, e.g., `exonum`.
+    pub name: String,
+    /// Version of the Exonum package, e.g., `0.9.0`.
+    pub version: String,
+    /// Version of the Rust compiler used to build the node, as reported by `rustc -V`.
+    pub rust_version: String,
+    /// Information about the operating system of the node.
+    pub os: String,
+}
+
+impl FromStr for UserAgent {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let malformed = || Error::MalformedUserAgent(s.to_owned());
+        let is_valid = |component: &str| !component.is_empty() && component.trim() == component;
+
+        let mut components = s.splitn(3, '/');
+        let package = components.next().ok_or_else(malformed)?;
+        let rust_version = components.next().ok_or_else(malformed)?;
+        let os = components.next().ok_or_else(malformed)?;
+
+        let mut package_components = package.split(' ');
+        let name = package_components.next().ok_or_else(malformed)?;
+        let version = package_components.next().ok_or_else(malformed)?;
+        if package_components.next().is_some() {
+            return Err(malformed());
+        }
+        if ![name, version, rust_version, os].iter().all(|c| is_valid(c)) {
+            return Err(malformed());
+        }
+
+        Ok(UserAgent {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            rust_version: rust_version.to_owned(),
+            os: os.to_owned(),
+        })
+    }
+}
 
 /// Kind of public or private REST API of an Exonum node.
 ///
@@ -60,49 +119,133 @@ impl ::fmt::Display for ApiKind {
 /// for REST-ful endpoints of services.
 pub struct TestKitApi {
     test_server: TestServer,
+    test_server_url: String,
     test_client: Client,
     api_sender: ApiSender,
+    api_surfaces: BTreeMap<String, (bool, bool)>,
 }
 
 impl fmt::Debug for TestKitApi {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        f.debug_struct("TestKitApi").finish()
+        f.debug_struct("TestKitApi")
+            .field("address", &self.test_server.addr())
+            .finish()
     }
 }
 
 impl TestKitApi {
     /// Creates a new instance of API.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the API mounts of two services collide, i.e., if the services have the same
+    ///   name or the name of one service is a path prefix of the name of another service
+    ///   (e.g., `foo` and `foo/v1`). The panic message contains the names of both services
+    ///   and the conflicting path.
     pub fn new(testkit: &TestKit) -> Self {
+        Self::for_blockchain(testkit.blockchain().clone(), testkit.api_sender.clone())
+    }
+
+    pub(crate) fn for_blockchain(blockchain: Blockchain, api_sender: ApiSender) -> Self {
+        check_mount_collisions(&blockchain);
+        let api_surfaces = api_surfaces(&blockchain);
         Self::from_raw_parts(
-            ApiAggregator::new(testkit.blockchain().clone(), SharedNodeState::new(10_000)),
-            testkit.api_sender.clone(),
+            ApiAggregator::new(blockchain, SharedNodeState::new(10_000)),
+            api_surfaces,
+            api_sender,
         )
     }
 
-    pub(crate) fn from_raw_parts(aggregator: ApiAggregator, api_sender: ApiSender) -> Self {
+    pub(crate) fn from_raw_parts(
+        aggregator: ApiAggregator,
+        api_surfaces: BTreeMap<String, (bool, bool)>,
+        api_sender: ApiSender,
+    ) -> Self {
         trace!("Created testkit api: {:#?}", aggregator);
+        Self::with_test_server(create_test_server(aggregator), api_surfaces, api_sender)
+    }
+
+    /// Creates an instance of API, in which only the public and private APIs of the service
+    /// with the specified name are mounted. The service API is mounted under its usual prefix,
+    /// so it is accessed with `ApiKind::Service` in the same way as in the complete API.
+    ///
+    /// Since no other APIs are mounted, the isolated API can be created for services
+    /// with colliding prefixes (e.g., `foo` and `foo/v1`), for which the complete API
+    /// cannot be created.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `UnknownService` error if the service is not registered in the blockchain.
+    pub fn isolated(testkit: &TestKit, service_name: &str) -> Result<Self, Error> {
+        let blockchain = testkit.blockchain().clone();
+        let mut builder = {
+            let service = blockchain
+                .service_map()
+                .values()
+                .find(|service| service.service_name() == service_name)
+                .ok_or_else(|| Error::UnknownService(service_name.to_owned()))?;
+            let mut builder = ServiceApiBuilder::new();
+            service.wire_api(&mut builder);
+            builder
+        };
+        let prefix = format!("services/{}", service_name);
+        let mut api_surfaces = BTreeMap::new();
+        api_surfaces.insert(service_name.to_owned(), api_surface(&mut builder));
 
+        trace!("Created isolated testkit api for service {}", service_name);
+        let test_server = create_isolated_test_server(blockchain, builder, prefix);
+        Ok(Self::with_test_server(
+            test_server,
+            api_surfaces,
+            testkit.api_sender.clone(),
+        ))
+    }
+
+    fn with_test_server(
+        test_server: TestServer,
+        api_surfaces: BTreeMap<String, (bool, bool)>,
+        api_sender: ApiSender,
+    ) -> Self {
+        let test_server_url = test_server.url("");
         TestKitApi {
-            test_server: create_test_server(aggregator),
+            test_server,
+            test_server_url,
             test_client: Client::new(),
             api_sender,
+            api_surfaces,
         }
     }
 
     /// Sends a transaction to the node via `ApiSender`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the transaction cannot be sent, e.g., because the testkit is dropped.
     pub fn send<T>(&self, transaction: T)
+    where
+        T: Into<Box<dyn Transaction>>,
+    {
+        let transaction = transaction.into();
+        let tx_hash = transaction.hash();
+        self.try_send(transaction)
+            .unwrap_or_else(|e| panic!("Failed to send transaction {:?}: {}", tx_hash, e))
+    }
+
+    /// Sends a transaction to the node via `ApiSender`, or returns an error
+    /// if the transaction cannot be sent.
+    pub fn try_send<T>(&self, transaction: T) -> Result<(), Error>
     where
         T: Into<Box<dyn Transaction>>,
     {
         self.api_sender
             .send(transaction.into())
-            .expect("Cannot send transaction");
+            .map_err(Error::ChannelSend)
     }
 
     /// Creates a requests builder for the public API scope.
     pub fn public(&self, kind: impl Display) -> RequestBuilder {
         RequestBuilder::new(
-            self.test_server.url(""),
+            &self.test_server_url,
             &self.test_client,
             ApiAccess::Public,
             kind.to_string(),
@@ -112,12 +255,536 @@ impl TestKitApi {
     /// Creates a requests builder for the private API scope.
     pub fn private(&self, kind: impl Display) -> RequestBuilder {
         RequestBuilder::new(
-            self.test_server.url(""),
+            &self.test_server_url,
             &self.test_client,
             ApiAccess::Private,
             kind.to_string(),
         )
     }
+
+    /// Sends a head request to the endpoint of the public API scope and returns the status
+    /// of the response. This is a shortcut for `public(kind).head(endpoint)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// use exonum_testkit::{ApiKind, StatusCode, TestKitBuilder};
+    ///
+    /// # fn main() {
+    /// let testkit = TestKitBuilder::validator().create();
+    /// let api = testkit.api();
+    /// assert_eq!(api.head(ApiKind::System, "v1/healthcheck"), StatusCode::Ok);
+    /// assert_eq!(api.head(ApiKind::System, "v1/unknown"), StatusCode::NotFound);
+    /// # }
+    /// ```
+    pub fn head(&self, kind: impl Display, endpoint: &str) -> StatusCode {
+        self.public(kind).head(endpoint)
+    }
+
+    /// Creates a requests builder bound to the public API of the service with the given name.
+    ///
+    /// This is a shortcut for `public(ApiKind::Service(name))`. The returned builder may be
+    /// stored and reused throughout a test.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service with the given name is not registered in the testkit.
+    /// - Panics if the service has no public API.
+    pub fn service_public(&self, service_name: &str) -> RequestBuilder {
+        check_api_side(service_name, self.check_service(service_name), ApiAccess::Public);
+        RequestBuilder::new(
+            &self.test_server_url,
+            &self.test_client,
+            ApiAccess::Public,
+            format!("api/services/{}", service_name),
+        )
+    }
+
+    /// Creates a requests builder bound to the private API of the service with the given name.
+    ///
+    /// Services that expose administrative endpoints only in the private scope respond
+    /// with `404 Not Found` to requests sent via the public mount. Binding both the service
+    /// and the API access at once rules out such mistakes: the returned builder may be
+    /// stored and reused for all requests to the private API of the service in a test.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service with the given name is not registered in the testkit.
+    /// - Panics if the service has no private API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::doctest_support::DoctestService;
+    /// use exonum_testkit::TestKit;
+    ///
+    /// # fn main() {
+    /// let testkit = TestKit::for_service(DoctestService);
+    /// let api = testkit.api();
+    /// let admin = api.service_private("doctest");
+    /// let sum: u64 = admin.get("sum").unwrap();
+    /// assert_eq!(sum, 0);
+    /// # }
+    /// ```
+    pub fn service_private(&self, service_name: &str) -> RequestBuilder {
+        check_api_side(service_name, self.check_service(service_name), ApiAccess::Private);
+        RequestBuilder::new(
+            &self.test_server_url,
+            &self.test_client,
+            ApiAccess::Private,
+            format!("api/services/{}", service_name),
+        )
+    }
+
+    /// Creates a handle to the API of the service with the given name. The handle sends
+    /// requests to the public API of the service; use [`ServiceApi::private`] to switch
+    /// to the private API.
+    ///
+    /// The requests made with the handle panic if the service has no API with the bound access
+    /// (e.g., if the service only has the private API, and the handle is not switched
+    /// to the private API).
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service with the given name is not registered in the testkit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::doctest_support::{DoctestService, TxAdd};
+    /// use exonum::crypto::{self, Hash};
+    /// use exonum::messages::Message;
+    /// use exonum_testkit::TestKit;
+    ///
+    /// # fn main() {
+    /// let mut testkit = TestKit::for_service(DoctestService);
+    /// let api = testkit.api();
+    /// let service = api.service("doctest");
+    ///
+    /// let (pubkey, key) = crypto::gen_keypair();
+    /// let tx = TxAdd::new(&pubkey, 5, &key);
+    /// let tx_hash: Hash = service.post("add", &tx);
+    /// assert_eq!(tx_hash, tx.hash());
+    /// testkit.create_block();
+    ///
+    /// let sum: u64 = service.get("sum");
+    /// assert_eq!(sum, 5);
+    /// let sum: u64 = service.private().get("sum");
+    /// assert_eq!(sum, 5);
+    /// # }
+    /// ```
+    ///
+    /// [`ServiceApi::private`]: struct.ServiceApi.html#method.private
+    pub fn service(&self, service_name: &str) -> ServiceApi {
+        let surface = self.check_service(service_name);
+        ServiceApi {
+            api: self,
+            service_name: service_name.to_owned(),
+            surface,
+            access: ApiAccess::Public,
+        }
+    }
+
+    /// Checks that the service with the given name is registered in the testkit,
+    /// and returns whether the service has public and private APIs.
+    fn check_service(&self, service_name: &str) -> (bool, bool) {
+        *self.api_surfaces.get(service_name).unwrap_or_else(|| {
+            panic!(
+                "Service `{}` is not registered in the testkit; registered services: {:?}",
+                service_name,
+                self.api_surfaces.keys().collect::<Vec<_>>()
+            )
+        })
+    }
+
+    /// Requests the user agent of the node from the public system API and parses it.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    /// - Panics if the user agent does not match the `name version/rust-version/os` format.
+    pub fn user_agent(&self) -> UserAgent {
+        let user_agent: String = self.public(ApiKind::System)
+            .get("v1/user_agent")
+            .unwrap_or_else(|e| panic!("Request to endpoint v1/user_agent failed: {}", e));
+        user_agent.parse().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Sends a get request to the public API endpoint and measures the time elapsed
+    /// until the response is received and decoded.
+    ///
+    /// The measurement covers the whole round trip through the in-process test server,
+    /// so it may be used to assert that an endpoint responds fast enough
+    /// for a given blockchain state.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn timed_get<D>(&self, kind: ApiKind, endpoint: &str) -> (D, Duration)
+    where
+        D: DeserializeOwned + 'static,
+    {
+        let request = self.public(kind);
+        let start = Instant::now();
+        let response = request.get(endpoint);
+        let elapsed = start.elapsed();
+
+        trace!("GET {} took {:?}", endpoint, elapsed);
+        let response = response.unwrap_or_else(|e| {
+            panic!("Timed request to endpoint {} failed: {}", endpoint, e)
+        });
+        (response, elapsed)
+    }
+
+    /// Sends get requests to the public API endpoints in the specified order and returns
+    /// the decoded responses. This is convenient to check that several endpoints agree
+    /// on the blockchain state.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of endpoints responds with an error. The panic message contains
+    ///   the kind of the API and the endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::doctest_support::DoctestService;
+    /// use exonum_testkit::{ApiKind, TestKit};
+    ///
+    /// # fn main() {
+    /// let testkit = TestKit::for_service(DoctestService);
+    /// let api = testkit.api();
+    /// let sums: Vec<u64> = api.get_many(vec![
+    ///     (ApiKind::Service("doctest"), "sum"),
+    ///     (ApiKind::Service("doctest"), "sum"),
+    /// ]);
+    /// assert_eq!(sums, vec![0, 0]);
+    /// # }
+    /// ```
+    pub fn get_many<D>(&self, requests: Vec<(ApiKind, &str)>) -> Vec<D>
+    where
+        D: DeserializeOwned + 'static,
+    {
+        requests
+            .into_iter()
+            .map(|(kind, endpoint)| {
+                self.public(kind).get(endpoint).unwrap_or_else(|e| {
+                    panic!("Request to endpoint {} of {} failed: {}", endpoint, kind, e)
+                })
+            })
+            .collect()
+    }
+
+    /// Sends get requests to the public API endpoint before and after the testkit
+    /// is modified by `act`, and returns the structural difference between the responses.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::doctest_support::{DoctestService, TxAdd};
+    /// use exonum::crypto;
+    /// use exonum_testkit::{ApiKind, TestKit};
+    ///
+    /// # fn main() {
+    /// let mut testkit = TestKit::for_service(DoctestService);
+    /// let api = testkit.api();
+    /// let (pubkey, key) = crypto::gen_keypair();
+    ///
+    /// let diff = api.diff_after(&mut testkit, ApiKind::Service("doctest"), "sum", |testkit| {
+    ///     testkit.create_block_with_transaction(TxAdd::new(&pubkey, 5, &key));
+    /// });
+    /// assert!(!diff.is_empty());
+    /// # }
+    /// ```
+    pub fn diff_after<F>(
+        &self,
+        testkit: &mut TestKit,
+        kind: ApiKind,
+        endpoint: &str,
+        act: F,
+    ) -> JsonDiff
+    where
+        F: FnOnce(&mut TestKit),
+    {
+        let get = || -> serde_json::Value {
+            self.public(kind)
+                .get(endpoint)
+                .unwrap_or_else(|e| panic!("Request to endpoint {} failed: {}", endpoint, e))
+        };
+        let old = get();
+        act(testkit);
+        let new = get();
+        JsonDiff::new(&old, &new)
+    }
+
+    /// Sends get requests to the paginated public API endpoint, following the pages
+    /// until the last one, and returns the items from all pages.
+    ///
+    /// Each page is expected to be a JSON object with the `items` array and the optional
+    /// `next_page_token` string. The token of the next page is passed to the endpoint
+    /// as the URL parameter named `page_param`; the first page is requested without
+    /// this parameter.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    /// - Panics if the endpoint returns the token of an already requested page.
+    pub fn get_list<D>(&self, kind: ApiKind, base_endpoint: &str, page_param: &str) -> Vec<D>
+    where
+        D: DeserializeOwned + 'static,
+    {
+        let mut items = Vec::new();
+        let mut seen_tokens = HashSet::new();
+        let mut token: Option<String> = None;
+        loop {
+            let request = self.public(kind);
+            let response: api::Result<Page<D>> = match token {
+                Some(ref token) => request.query(&vec![(page_param, token)]).get(base_endpoint),
+                None => request.get(base_endpoint),
+            };
+            let page = response.unwrap_or_else(|e| {
+                panic!(
+                    "Request to endpoint {} with page token {:?} failed: {}",
+                    base_endpoint, token, e
+                )
+            });
+            items.extend(page.items);
+
+            token = match page.next_page_token {
+                Some(next) => {
+                    assert!(
+                        seen_tokens.insert(next.clone()),
+                        "Endpoint {} returned the token {:?} of an already requested page",
+                        base_endpoint,
+                        next
+                    );
+                    Some(next)
+                }
+                None => return items,
+            };
+        }
+    }
+
+    /// Requests the headers of the blocks from the `v1/blocks` endpoint of the explorer API.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn explorer_blocks(&self, query: &BlocksQuery) -> BlocksRange {
+        self.public(ApiKind::Explorer)
+            .query(query)
+            .get("v1/blocks")
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Request to endpoint v1/blocks of {} failed: {}",
+                    ApiKind::Explorer,
+                    e
+                )
+            })
+    }
+
+    /// Asserts that the block at the specified height is considered empty by the explorer API,
+    /// that is, the block is returned by the `v1/blocks` endpoint, but is skipped if
+    /// the `skip_empty_blocks` parameter is set. Blocks without transactions are empty
+    /// even if a configuration change takes effect at their height; see
+    /// [`create_config_activation_block`][activation_block] of the testkit.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the block is not returned by the endpoint, or is not skipped with
+    ///   `skip_empty_blocks`.
+    ///
+    /// [activation_block]: struct.TestKit.html#method.create_config_activation_block
+    pub fn assert_block_skipped_as_empty(&self, height: Height) {
+        let contains_block = |skip_empty_blocks| {
+            let query = BlocksQuery {
+                count: 1,
+                latest: Some(height),
+                skip_empty_blocks,
+            };
+            let range = self.explorer_blocks(&query);
+            range.blocks.iter().any(|block| block.height() == height)
+        };
+        assert!(
+            contains_block(false),
+            "Block at height {} is not returned by the explorer API",
+            height
+        );
+        assert!(
+            !contains_block(true),
+            "Block at height {} is not skipped by the explorer API with `skip_empty_blocks`",
+            height
+        );
+    }
+}
+
+/// Page of a paginated API response.
+#[derive(Debug, Deserialize)]
+struct Page<D> {
+    items: Vec<D>,
+    next_page_token: Option<String>,
+}
+
+/// Handle to the API of a single service, created with [`TestKitApi::service`].
+///
+/// The handle binds the service name and the API access, so that requests are made with
+/// just the endpoint suffix. Unlike `RequestBuilder`, the handle panics if a request fails,
+/// which makes the tests more concise; use [`get_err`] to check erroneous responses.
+///
+/// [`TestKitApi::service`]: struct.TestKitApi.html#method.service
+/// [`get_err`]: #method.get_err
+#[derive(Debug, Clone)]
+pub struct ServiceApi<'a> {
+    api: &'a TestKitApi,
+    service_name: String,
+    surface: (bool, bool),
+    access: ApiAccess,
+}
+
+impl<'a> ServiceApi<'a> {
+    /// Returns a handle to the public API of the service.
+    pub fn public(&self) -> Self {
+        ServiceApi {
+            access: ApiAccess::Public,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a handle to the private API of the service.
+    pub fn private(&self) -> Self {
+        ServiceApi {
+            access: ApiAccess::Private,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the request builder for the service API with the bound access. The builder
+    /// may be used for the requests not covered by the handle, e.g., to inspect
+    /// the response status with `head`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service has no API with the bound access.
+    pub fn request_builder(&self) -> RequestBuilder {
+        check_api_side(&self.service_name, self.surface, self.access);
+        RequestBuilder::new(
+            &self.api.test_server_url,
+            &self.api.test_client,
+            self.access,
+            format!("api/services/{}", self.service_name),
+        )
+    }
+
+    /// Sends a get request to the endpoint and decodes the response.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn get<R>(&self, endpoint: &str) -> R
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let result = self.request_builder().get(endpoint);
+        self.unwrap_response("GET", endpoint, result)
+    }
+
+    /// Sends a get request with the given query parameters to the endpoint and decodes
+    /// the response.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn get_with_query<Q, R>(&self, endpoint: &str, query: &Q) -> R
+    where
+        Q: Serialize,
+        R: DeserializeOwned + 'static,
+    {
+        let result = self.request_builder().query(query).get(endpoint);
+        self.unwrap_response("GET", endpoint, result)
+    }
+
+    /// Sends a get request to the endpoint and returns the error it responds with.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds successfully.
+    pub fn get_err(&self, endpoint: &str) -> api::Error {
+        match self.request_builder().get::<serde_json::Value>(endpoint) {
+            Ok(response) => panic!(
+                "GET request to endpoint {} of service {} succeeded unexpectedly: {}",
+                endpoint, self.service_name, response
+            ),
+            Err(e) => e,
+        }
+    }
+
+    /// Sends a post request with the given body to the endpoint and decodes the response.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn post<Q, R>(&self, endpoint: &str, body: &Q) -> R
+    where
+        Q: Serialize,
+        R: DeserializeOwned + 'static,
+    {
+        let result = self.request_builder().query(body).post(endpoint);
+        self.unwrap_response("POST", endpoint, result)
+    }
+
+    /// Sends a put request with the given body to the endpoint and decodes the response.
+    ///
+    /// Note that the endpoints defined with `ServiceApiBuilder` accept only get and post
+    /// requests; put requests are handled by the services extending the API backend directly.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn put<Q, R>(&self, endpoint: &str, body: &Q) -> R
+    where
+        Q: Serialize,
+        R: DeserializeOwned + 'static,
+    {
+        let result = self.request_builder().query(body).put(endpoint);
+        self.unwrap_response("PUT", endpoint, result)
+    }
+
+    /// Sends a delete request to the endpoint and decodes the response.
+    ///
+    /// Note that the endpoints defined with `ServiceApiBuilder` accept only get and post
+    /// requests; delete requests are handled by the services extending the API backend
+    /// directly.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the endpoint responds with an error.
+    pub fn delete<R>(&self, endpoint: &str) -> R
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let result = self.request_builder().delete(endpoint);
+        self.unwrap_response("DELETE", endpoint, result)
+    }
+
+    fn unwrap_response<R>(&self, method: &str, endpoint: &str, result: api::Result<R>) -> R {
+        result.unwrap_or_else(|e| {
+            panic!(
+                "{} request to endpoint {} of service {} failed: {}",
+                method, endpoint, self.service_name, e
+            )
+        })
+    }
 }
 
 /// An HTTP requests builder. This type can be used to send requests to
@@ -126,7 +793,7 @@ pub struct RequestBuilder<'a, 'b, Q = ()>
 where
     Q: 'b,
 {
-    test_server_url: String,
+    test_server_url: &'a str,
     test_client: &'a Client,
     access: ApiAccess,
     prefix: String,
@@ -151,7 +818,7 @@ where
     Q: 'b + Serialize,
 {
     fn new(
-        test_server_url: String,
+        test_server_url: &'a str,
         test_client: &'a Client,
         access: ApiAccess,
         prefix: String,
@@ -168,7 +835,7 @@ where
     /// Sets a query data of the current request.
     pub fn query<T>(&'a self, query: &'b T) -> RequestBuilder<'a, 'b, T> {
         RequestBuilder {
-            test_server_url: self.test_server_url.clone(),
+            test_server_url: self.test_server_url,
             test_client: self.test_client,
             access: self.access,
             prefix: self.prefix.clone(),
@@ -182,46 +849,41 @@ where
     where
         R: DeserializeOwned + 'static,
     {
-        let params = self.query
-            .as_ref()
-            .map(|query| {
-                format!(
-                    "?{}",
-                    serde_urlencoded::to_string(query).expect("Unable to serialize query.")
-                )
-            })
-            .unwrap_or_default();
-        let url = format!(
-            "{url}{access}/{prefix}/{endpoint}{query}",
-            url = self.test_server_url,
-            access = self.access,
-            prefix = self.prefix,
-            endpoint = endpoint,
-            query = params
-        );
+        let url = self.url(endpoint, &self.query_params());
 
         trace!("GET {}", url);
 
-        let response = self.test_client
-            .get(&url)
-            .send()
-            .expect("Unable to send request");
+        let response = Self::send(&mut self.test_client.get(&url), &url);
         Self::response_to_api_result(response)
     }
 
+    /// Sends a head request to the testing API endpoint and returns the status
+    /// of the response. This is useful for existence checks, which do not need
+    /// to parse the response body. `HEAD` requests are served by the handlers
+    /// of `GET` requests to the same endpoint.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the response has a non-empty body.
+    pub fn head(&self, endpoint: &str) -> StatusCode {
+        let url = self.url(endpoint, &self.query_params());
+
+        trace!("HEAD {}", url);
+
+        let mut response = Self::send(&mut self.test_client.head(&url), &url);
+        trace!("Response status: {}", response.status());
+        let body = response.text().expect("Unable to get response text");
+        assert!(body.is_empty(), "Response to HEAD {} has a body: {}", url, body);
+        response.status()
+    }
+
     /// Sends a post request to the testing API endpoint and decodes response as
     /// the corresponding type.
     pub fn post<R>(&self, endpoint: &str) -> api::Result<R>
     where
         R: DeserializeOwned + 'static,
     {
-        let url = format!(
-            "{url}{access}/{prefix}/{endpoint}",
-            url = self.test_server_url,
-            access = self.access,
-            prefix = self.prefix,
-            endpoint = endpoint
-        );
+        let url = self.url(endpoint, "");
 
         trace!("POST {}", url);
 
@@ -232,10 +894,105 @@ where
         } else {
             builder.json(&serde_json::Value::Null)
         };
-        let response = builder.send().expect("Unable to send request");
+        let response = Self::send(&mut builder, &url);
         Self::response_to_api_result(response)
     }
 
+    /// Sends a put request to the testing API endpoint and decodes response as
+    /// the corresponding type.
+    pub fn put<R>(&self, endpoint: &str) -> api::Result<R>
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let url = self.url(endpoint, "");
+
+        trace!("PUT {}", url);
+
+        let mut builder = self.test_client.put(&url);
+        if let Some(ref query) = self.query.as_ref() {
+            trace!("Body: {}", serde_json::to_string_pretty(&query).unwrap());
+            builder.json(query)
+        } else {
+            builder.json(&serde_json::Value::Null)
+        };
+        let response = Self::send(&mut builder, &url);
+        Self::response_to_api_result(response)
+    }
+
+    /// Sends a delete request to the testing API endpoint and decodes response as
+    /// the corresponding type.
+    pub fn delete<R>(&self, endpoint: &str) -> api::Result<R>
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let url = self.url(endpoint, &self.query_params());
+
+        trace!("DELETE {}", url);
+
+        let response = Self::send(&mut self.test_client.delete(&url), &url);
+        Self::response_to_api_result(response)
+    }
+
+    /// Sends a get request to the testing API endpoint and returns the status
+    /// and the raw body of the response, whatever the status is.
+    #[cfg(feature = "conformance")]
+    pub(crate) fn get_raw(&self, endpoint: &str) -> (StatusCode, String) {
+        let url = self.url(endpoint, &self.query_params());
+
+        trace!("GET {}", url);
+
+        let mut response = Self::send(&mut self.test_client.get(&url), &url);
+        let body = response.text().expect("Unable to get response text");
+        (response.status(), body)
+    }
+
+    /// Sends the request to the given URL, panicking if the test server is unreachable.
+    fn send(request: &mut ClientRequestBuilder, url: &str) -> Response {
+        request.send().unwrap_or_else(|e| {
+            panic!(
+                "Unable to send request to the {} at {}: {}",
+                TESTKIT_VIRTUAL_HOST, url, e
+            )
+        })
+    }
+
+    /// Serializes the query of the current request into URL parameters.
+    fn query_params(&self) -> String {
+        self.query
+            .as_ref()
+            .map(|query| serde_urlencoded::to_string(query).expect("Unable to serialize query."))
+            .unwrap_or_default()
+    }
+
+    /// Builds the full URL of the given endpoint. The URL is written into a single buffer
+    /// of the sufficient capacity to avoid reallocations.
+    fn url(&self, endpoint: &str, query: &str) -> String {
+        let access = match self.access {
+            ApiAccess::Public => "public",
+            ApiAccess::Private => "private",
+        };
+        // Separators between the URL components: `/`, `/` and `?`.
+        let capacity = self.test_server_url.len()
+            + access.len()
+            + self.prefix.len()
+            + endpoint.len()
+            + query.len()
+            + 3;
+
+        let mut url = String::with_capacity(capacity);
+        url.push_str(self.test_server_url);
+        url.push_str(access);
+        url.push('/');
+        url.push_str(&self.prefix);
+        url.push('/');
+        url.push_str(endpoint);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(query);
+        }
+        url
+    }
+
     /// Converts reqwest Response to api::Result.
     fn response_to_api_result<R>(mut response: Response) -> api::Result<R>
     where
@@ -277,6 +1034,72 @@ where
     }
 }
 
+/// Collects the names of the services registered in the blockchain, together with flags
+/// indicating whether each service has public and private APIs.
+pub(crate) fn api_surfaces(blockchain: &Blockchain) -> BTreeMap<String, (bool, bool)> {
+    blockchain
+        .service_map()
+        .values()
+        .map(|service| {
+            let mut builder = ServiceApiBuilder::new();
+            service.wire_api(&mut builder);
+            (service.service_name().to_owned(), api_surface(&mut builder))
+        })
+        .collect()
+}
+
+/// Checks whether the service API builder has public and private endpoints.
+fn api_surface(builder: &mut ServiceApiBuilder) -> (bool, bool) {
+    (
+        !builder.public_scope().is_empty(),
+        !builder.private_scope().is_empty(),
+    )
+}
+
+/// Checks that the service has the API with the given access.
+fn check_api_side(service_name: &str, (has_public, has_private): (bool, bool), access: ApiAccess) {
+    let (exists, side) = match access {
+        ApiAccess::Public => (has_public, "public"),
+        ApiAccess::Private => (has_private, "private"),
+    };
+    assert!(exists, "Service `{}` has no {} API", service_name, side);
+}
+
+/// Checks that the API mounts of the services registered in the blockchain do not collide.
+fn check_mount_collisions(blockchain: &Blockchain) {
+    let names: Vec<&str> = blockchain
+        .service_map()
+        .values()
+        .map(|service| service.service_name())
+        .collect();
+    if let Some((outer, inner)) = find_mount_collision(&names) {
+        panic!(
+            "API mounts of services `{}` and `{}` collide at path `api/services/{}`",
+            outer, inner, outer
+        );
+    }
+}
+
+/// Finds a pair of service names, for which the mount path of the first service
+/// is equal to or is a prefix of the mount path of the second one.
+fn find_mount_collision<'a>(names: &[&'a str]) -> Option<(&'a str, &'a str)> {
+    for (i, &first) in names.iter().enumerate() {
+        for &second in &names[i + 1..] {
+            let (outer, inner) = if first.len() <= second.len() {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            if inner.starts_with(outer)
+                && (inner.len() == outer.len() || inner[outer.len()..].starts_with('/'))
+            {
+                return Some((outer, inner));
+            }
+        }
+    }
+    None
+}
+
 /// Creates a test server.
 fn create_test_server(aggregator: ApiAggregator) -> TestServer {
     let server = TestServer::with_factory(move || {
@@ -296,3 +1119,98 @@ fn create_test_server(aggregator: ApiAggregator) -> TestServer {
 
     server
 }
+
+/// Creates a test server with only the given service API mounted under the given prefix.
+fn create_isolated_test_server(
+    blockchain: Blockchain,
+    builder: ServiceApiBuilder,
+    prefix: String,
+) -> TestServer {
+    let server = TestServer::with_factory(move || {
+        let state = ServiceApiState::new(blockchain.clone());
+        let mut builder = builder.clone();
+        let public_scope = builder.public_scope().clone();
+        let private_scope = builder.private_scope().clone();
+        App::with_state(state)
+            .scope("public/api", |scope| {
+                scope.extend(iter::once((prefix.as_str(), &public_scope)))
+            })
+            .scope("private/api", |scope| {
+                scope.extend(iter::once((prefix.as_str(), &private_scope)))
+            })
+    });
+
+    info!("Isolated test server created on {}", server.addr());
+
+    server
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+
+    use super::{find_mount_collision, ApiAccess, RequestBuilder, UserAgent};
+
+    #[test]
+    fn test_request_url() {
+        let client = Client::new();
+        let builder: RequestBuilder = RequestBuilder::new(
+            "http://127.0.0.1:8080/",
+            &client,
+            ApiAccess::Private,
+            "api/services/counter".to_owned(),
+        );
+
+        let url = builder.url("v1/count", "");
+        assert_eq!(url, "http://127.0.0.1:8080/private/api/services/counter/v1/count");
+
+        let url = builder.url("v1/count", "from=1&to=2");
+        assert_eq!(
+            url,
+            "http://127.0.0.1:8080/private/api/services/counter/v1/count?from=1&to=2"
+        );
+    }
+
+    #[test]
+    fn test_mount_collisions() {
+        assert_eq!(find_mount_collision(&["counter", "timestamping"]), None);
+        assert_eq!(find_mount_collision(&["counter", "counter2"]), None);
+        assert_eq!(
+            find_mount_collision(&["counter", "timestamping", "counter"]),
+            Some(("counter", "counter"))
+        );
+        assert_eq!(
+            find_mount_collision(&["info/v1", "counter", "info"]),
+            Some(("info", "info/v1"))
+        );
+    }
+
+    #[test]
+    fn test_user_agent_parsing() {
+        let user_agent: UserAgent = "exonum 0.9.0/rustc 1.29.0 (aa3ca1994 2018-09-11)/Mac OS 10.13"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            user_agent,
+            UserAgent {
+                name: "exonum".to_owned(),
+                version: "0.9.0".to_owned(),
+                rust_version: "rustc 1.29.0 (aa3ca1994 2018-09-11)".to_owned(),
+                os: "Mac OS 10.13".to_owned(),
+            }
+        );
+
+        let malformed = [
+            "",
+            "exonum 0.9.0",
+            "exonum 0.9.0/rustc 1.29.0",
+            "exonum/rustc 1.29.0/Linux",
+            "exonum 0.9.0 beta/rustc 1.29.0/Linux",
+            "exonum 0.9.0/rustc 1.29.0\n/Linux",
+            "exonum 0.9.0//Linux",
+        ];
+        for s in &malformed {
+            assert!(s.parse::<UserAgent>().is_err(), "{:?} was parsed", s);
+        }
+    }
+}
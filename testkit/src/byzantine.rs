@@ -0,0 +1,78 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Byzantine behavior of validators emulated by the testkit.
+
+use exonum::{
+    crypto::{self, Hash}, helpers::Height, messages::{Precommit, Propose},
+};
+
+use std::time::SystemTime;
+
+use network::TestNode;
+
+/// Byzantine behavior of a validator, which is exhibited in the blocks created by the testkit.
+/// See [`TestKit::byzantine_validator`] for details.
+///
+/// [`TestKit::byzantine_validator`]: struct.TestKit.html#method.byzantine_validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByzantineBehavior {
+    /// The validator signs two different proposes for each block it proposes: the propose
+    /// of the committed block and a conflicting one.
+    Equivocate,
+    /// The validator does not propose blocks; the blocks are proposed by the next validator.
+    /// The validator still signs precommits for the committed blocks.
+    DropPropose,
+    /// The validator signs two different precommits for each block: the precommit
+    /// for the committed block and a precommit for a conflicting block.
+    SignConflictingPrecommits,
+}
+
+/// Pair of conflicting messages signed by a Byzantine validator at the same height.
+/// The first message of the pair relates to the committed block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictingMessages {
+    /// Conflicting proposes.
+    Proposes(Propose, Propose),
+    /// Conflicting precommits.
+    Precommits(Precommit, Precommit),
+}
+
+/// Creates a propose conflicting with the given one, which is signed by the same validator.
+pub(crate) fn conflicting_propose(
+    node: &TestNode,
+    propose: &Propose,
+    height: Height,
+    last_hash: &Hash,
+) -> Propose {
+    // The conflicting propose contains no transactions, unless the original one is empty.
+    let tx_hashes = if propose.transactions().is_empty() {
+        vec![crypto::hash(last_hash.as_ref())]
+    } else {
+        Vec::new()
+    };
+    node.create_propose(height, last_hash, &tx_hashes)
+}
+
+/// Creates a precommit for a block conflicting with the specified one.
+pub(crate) fn conflicting_precommit(
+    node: &TestNode,
+    propose: &Propose,
+    block_hash: &Hash,
+    time: SystemTime,
+) -> Precommit {
+    let conflicting_hash = crypto::hash(block_hash.as_ref());
+    node.try_create_precommit_at(propose, &conflicting_hash, time)
+        .unwrap()
+}
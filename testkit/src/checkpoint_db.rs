@@ -99,7 +99,21 @@ impl<T: Database> CheckpointDbHandler<T> {
     }
 }
 
-#[derive(Debug)]
+impl<T: Database + Clone> CheckpointDbHandler<T> {
+    /// Creates a checkpointed database with a deep copy of the data and the checkpoints
+    /// of this database. The copy evolves independently of this database.
+    pub(crate) fn copy_db(&self) -> CheckpointDb<T> {
+        let inner = self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for copy")
+            .clone();
+        CheckpointDb {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 struct CheckpointDbInner<T> {
     db: T,
     backup_stack: Vec<Vec<Patch>>,
@@ -401,6 +415,28 @@ mod tests {
         assert_eq!(snapshot.get("foo", &[]), None);
     }
 
+    #[test]
+    fn test_copy_db() {
+        let db = CheckpointDb::new(MemoryDB::new());
+        let handler = db.handler();
+        let mut fork = db.fork();
+        fork.put("foo", vec![], vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+        handler.checkpoint();
+
+        let copy = handler.copy_db();
+        let mut fork = copy.fork();
+        fork.put("foo", vec![], vec![2]);
+        copy.merge(fork.into_patch()).unwrap();
+        assert_eq!(copy.snapshot().get("foo", &[]), Some(vec![2]));
+        assert_eq!(db.snapshot().get("foo", &[]), Some(vec![1]));
+
+        // The checkpoint is copied as well.
+        copy.handler().rollback();
+        assert_eq!(copy.snapshot().get("foo", &[]), Some(vec![1]));
+        handler.rollback();
+    }
+
     #[test]
     #[should_panic]
     fn test_extra_rollback() {
@@ -14,6 +14,11 @@
 
 //! Routines for comparison between 2 states.
 
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
 use exonum::storage::Snapshot;
 
 /// Facilitation of comparison between 2 states.
@@ -198,17 +203,26 @@ impl<T: PartialEq + ::std::fmt::Debug> Comparison<T> {
 ///
 /// Typical usage involves `map`ping the resulting comparison through the schema:
 ///
-/// ```ignore
-/// let mut testkit = ...;
+/// ```
+/// # extern crate exonum;
+/// # extern crate exonum_testkit;
+/// # use exonum_testkit::doctest_support::DoctestSchema as ServiceSchema;
+/// # use exonum_testkit::doctest_support::{DoctestService, TxAdd};
+/// use exonum::crypto;
+/// use exonum_testkit::{ComparableSnapshot, TestKit};
+///
+/// # fn main() {
+/// let mut testkit = TestKit::for_service(DoctestService);
 /// let old_snapshot = testkit.snapshot();
 /// // Mutate the testkit state somehow...
+/// let (pubkey, key) = crypto::gen_keypair();
+/// testkit.create_block_with_transaction(TxAdd::new(&pubkey, 5, &key));
 ///
 /// testkit.snapshot()
 ///     .compare(old_snapshot)
 ///     .map(ServiceSchema::new)
-///     .assert("Something about the schema", |old, schema| {
-///         // Assertions...
-///     });
+///     .assert("Sum increases", |old, schema| schema.sum() > old.sum());
+/// # }
 /// ```
 ///
 /// Here `ServiceSchema` is a public struct defined in a service library that has public `new`
@@ -224,6 +238,185 @@ impl ComparableSnapshot<Box<dyn Snapshot>> for Box<dyn Snapshot> {
     }
 }
 
+/// Change of a single value in a JSON document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonChange {
+    /// Value has been added.
+    Added(Value),
+    /// Value has been removed.
+    Removed(Value),
+    /// Value has been changed.
+    Changed {
+        /// Old value.
+        old: Value,
+        /// New value.
+        new: Value,
+    },
+}
+
+/// Structural difference between 2 JSON documents.
+///
+/// Changes are keyed by paths of the changed values, in which object keys and array indexes
+/// are separated by dots (e.g., `history.2.amount`); the path of the root value is empty.
+/// Objects and arrays are compared element-wise, so a change of a nested value is reported
+/// at the path of this value rather than at the path of the enclosing object or array.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate exonum_testkit;
+/// # use exonum_testkit::compare::{JsonChange, JsonDiff};
+/// # fn main() {
+/// let old = json!({ "balance": 10, "history": [1], "name": "Alice" });
+/// let new = json!({ "balance": 15, "history": [1, 2], "name": "Alice" });
+/// let diff = JsonDiff::new(&old, &new);
+/// assert_eq!(diff.changes()["history.1"], JsonChange::Added(json!(2)));
+/// diff.assert_only_changed(&["balance", "history"]);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JsonDiff {
+    changes: BTreeMap<String, JsonChange>,
+}
+
+impl JsonDiff {
+    /// Computes the difference between the old and the new JSON documents.
+    pub fn new(old: &Value, new: &Value) -> Self {
+        let mut diff = JsonDiff::default();
+        diff.compare(String::new(), old, new);
+        diff
+    }
+
+    fn compare(&mut self, path: String, old: &Value, new: &Value) {
+        match (old, new) {
+            (&Value::Object(ref old), &Value::Object(ref new)) => {
+                for (key, old_value) in old {
+                    let nested = nested_path(&path, key);
+                    match new.get(key) {
+                        Some(new_value) => self.compare(nested, old_value, new_value),
+                        None => self.insert(nested, JsonChange::Removed(old_value.clone())),
+                    }
+                }
+                for (key, new_value) in new {
+                    if !old.contains_key(key) {
+                        let nested = nested_path(&path, key);
+                        self.insert(nested, JsonChange::Added(new_value.clone()));
+                    }
+                }
+            }
+            (&Value::Array(ref old), &Value::Array(ref new)) => {
+                for i in 0..old.len().max(new.len()) {
+                    let nested = nested_path(&path, &i.to_string());
+                    match (old.get(i), new.get(i)) {
+                        (Some(old_value), Some(new_value)) => {
+                            self.compare(nested, old_value, new_value)
+                        }
+                        (Some(old_value), None) => {
+                            self.insert(nested, JsonChange::Removed(old_value.clone()))
+                        }
+                        (None, Some(new_value)) => {
+                            self.insert(nested, JsonChange::Added(new_value.clone()))
+                        }
+                        (None, None) => unreachable!(),
+                    }
+                }
+            }
+            _ => if old != new {
+                let change = JsonChange::Changed {
+                    old: old.clone(),
+                    new: new.clone(),
+                };
+                self.insert(path, change);
+            },
+        }
+    }
+
+    fn insert(&mut self, path: String, change: JsonChange) {
+        self.changes.insert(path, change);
+    }
+
+    /// Returns the changes keyed by the paths of the changed values.
+    pub fn changes(&self) -> &BTreeMap<String, JsonChange> {
+        &self.changes
+    }
+
+    /// Returns `true` if the documents are equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Asserts that exactly the values at the given paths have changed. A change
+    /// of a nested value is attributed to each of the enclosing paths, e.g., a change
+    /// at `history.2` matches the `history` path.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if a value not covered by the given paths has changed.
+    /// - Panics if no value has changed at one of the given paths.
+    pub fn assert_only_changed(&self, paths: &[&str]) -> &Self {
+        let unexpected: Vec<_> = self.changes
+            .keys()
+            .filter(|changed| !paths.iter().any(|path| is_nested(changed, path)))
+            .collect();
+        assert!(
+            unexpected.is_empty(),
+            "Unexpected changes at paths {:?}\n{}",
+            unexpected,
+            self
+        );
+
+        let unchanged: Vec<_> = paths
+            .iter()
+            .filter(|path| !self.changes.keys().any(|changed| is_nested(changed, path)))
+            .collect();
+        assert!(
+            unchanged.is_empty(),
+            "Expected changes at paths {:?}\n{}",
+            unchanged,
+            self
+        );
+        self
+    }
+}
+
+impl fmt::Display for JsonDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return f.write_str("No changes");
+        }
+        for (i, (path, change)) in self.changes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            let path = if path.is_empty() { "<root>" } else { path };
+            match *change {
+                JsonChange::Added(ref value) => write!(f, "+ {}: {}", path, value)?,
+                JsonChange::Removed(ref value) => write!(f, "- {}: {}", path, value)?,
+                JsonChange::Changed { ref old, ref new } => {
+                    write!(f, "~ {}: {} -> {}", path, old, new)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn nested_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Checks whether the changed path is equal to or is nested in the given path.
+fn is_nested(changed: &str, path: &str) -> bool {
+    path.is_empty()
+        || changed == path
+        || (changed.starts_with(path) && changed[path.len()..].starts_with('.'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,4 +469,99 @@ mod tests {
         let comp = Comparison::new(vec![1, 2, 3], vec![4]);
         comp.assert_inv("Array length more than 1", |v| v.len() > 1);
     }
+
+    #[test]
+    fn test_json_diff_nested_objects() {
+        let old = json!({
+            "wallet": { "balance": 10, "owner": { "name": "Alice", "age": 30 } },
+            "removed": true,
+        });
+        let new = json!({
+            "wallet": { "balance": 15, "owner": { "name": "Alice", "age": 31 } },
+            "added": null,
+        });
+        let diff = JsonDiff::new(&old, &new);
+
+        let expected: BTreeMap<_, _> = vec![
+            ("added".to_owned(), JsonChange::Added(json!(null))),
+            ("removed".to_owned(), JsonChange::Removed(json!(true))),
+            (
+                "wallet.balance".to_owned(),
+                JsonChange::Changed {
+                    old: json!(10),
+                    new: json!(15),
+                },
+            ),
+            (
+                "wallet.owner.age".to_owned(),
+                JsonChange::Changed {
+                    old: json!(30),
+                    new: json!(31),
+                },
+            ),
+        ].into_iter()
+            .collect();
+        assert_eq!(*diff.changes(), expected);
+        diff.assert_only_changed(&["added", "removed", "wallet"]);
+        diff.assert_only_changed(&["added", "removed", "wallet.balance", "wallet.owner.age"]);
+        assert_eq!(
+            diff.to_string(),
+            "+ added: null\n- removed: true\n~ wallet.balance: 10 -> 15\n\
+             ~ wallet.owner.age: 30 -> 31"
+        );
+    }
+
+    #[test]
+    fn test_json_diff_nested_arrays() {
+        let old = json!({ "history": [{ "amount": 1 }, { "amount": 2 }, [1, 2]], "len": 3 });
+        let new = json!({ "history": [{ "amount": 1 }, { "amount": 5 }], "len": 2 });
+        let diff = JsonDiff::new(&old, &new);
+
+        assert_eq!(diff.changes().len(), 3);
+        assert_eq!(
+            diff.changes()["history.1.amount"],
+            JsonChange::Changed {
+                old: json!(2),
+                new: json!(5),
+            }
+        );
+        assert_eq!(
+            diff.changes()["history.2"],
+            JsonChange::Removed(json!([1, 2]))
+        );
+        diff.assert_only_changed(&["history", "len"]);
+    }
+
+    #[test]
+    fn test_json_diff_equal_and_root_values() {
+        let value = json!({ "items": [1, { "a": [] }] });
+        let diff = JsonDiff::new(&value, &value);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "No changes");
+        diff.assert_only_changed(&[]);
+
+        let diff = JsonDiff::new(&json!(1), &json!("1"));
+        assert_eq!(diff.to_string(), "~ <root>: 1 -> \"1\"");
+        diff.assert_only_changed(&[""]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected changes at paths [\"history_len\"]")]
+    fn test_json_diff_unexpected_change() {
+        let diff = JsonDiff::new(
+            &json!({ "balance": 1, "history_len": 0 }),
+            &json!({ "balance": 2, "history_len": 1 }),
+        );
+        diff.assert_only_changed(&["balance", "history"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected changes at paths [\"history_len\"]")]
+    fn test_json_diff_missing_change() {
+        let diff = JsonDiff::new(
+            &json!({ "balance": 1, "history_len": 0 }),
+            &json!({ "balance": 2, "history_len": 0 }),
+        );
+        diff.assert_only_changed(&["balance", "history_len"]);
+    }
 }
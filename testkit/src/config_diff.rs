@@ -0,0 +1,331 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structural differences between blockchain configurations.
+
+use serde_json::{self, Value};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use exonum::blockchain::{StoredConfiguration, ValidatorKeys};
+
+use compare::JsonDiff;
+
+/// Change of the keys of a validator at a certain position in the validator list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidatorKeysChange {
+    /// Validator is added to the list.
+    Added(ValidatorKeys),
+    /// Validator is removed from the list.
+    Removed(ValidatorKeys),
+    /// Keys of the validator are replaced.
+    Changed {
+        /// Keys in the old configuration.
+        old: ValidatorKeys,
+        /// Keys in the new configuration.
+        new: ValidatorKeys,
+    },
+}
+
+/// Difference between two blockchain configurations, which is computed
+/// by [`config_diff`].
+///
+/// The difference covers consensus parameters, validator keys and service configurations.
+/// The `previous_cfg_hash` and `actual_from` fields are ignored, since they differ
+/// for any two configurations in the blockchain.
+///
+/// [`config_diff`]: fn.config_diff.html
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConfigDiff {
+    consensus: JsonDiff,
+    validators: BTreeMap<usize, ValidatorKeysChange>,
+    services: BTreeMap<String, JsonDiff>,
+}
+
+/// Computes the structural difference between the old and the new blockchain configurations.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate exonum_testkit;
+/// # use exonum_testkit::{config_diff, TestKitBuilder};
+/// # fn main() {
+/// let testkit = TestKitBuilder::validator().with_validators(2).create();
+/// let old = testkit.actual_configuration();
+/// let mut new = old.clone();
+/// new.consensus.txs_block_limit = 500;
+/// new.services.insert("my_service".to_owned(), json!({ "limit": 10 }));
+///
+/// let diff = config_diff(&old, &new);
+/// assert!(diff.consensus().changes().contains_key("txs_block_limit"));
+/// assert!(!diff.only_services_changed(&["my_service"]));
+/// println!("{}", diff);
+///
+/// new.consensus = old.consensus.clone();
+/// assert!(config_diff(&old, &new).only_services_changed(&["my_service"]));
+/// # }
+/// ```
+pub fn config_diff(old: &StoredConfiguration, new: &StoredConfiguration) -> ConfigDiff {
+    let consensus = JsonDiff::new(&to_json(&old.consensus), &to_json(&new.consensus));
+
+    let validator_count = old.validator_keys.len().max(new.validator_keys.len());
+    let validators = (0..validator_count)
+        .filter_map(|i| {
+            let change = match (old.validator_keys.get(i), new.validator_keys.get(i)) {
+                (Some(old), Some(new)) if old == new => return None,
+                (Some(&old), Some(&new)) => ValidatorKeysChange::Changed { old, new },
+                (Some(&old), None) => ValidatorKeysChange::Removed(old),
+                (None, Some(&new)) => ValidatorKeysChange::Added(new),
+                (None, None) => unreachable!(),
+            };
+            Some((i, change))
+        })
+        .collect();
+
+    let services = old.services
+        .keys()
+        .chain(new.services.keys().filter(|name| !old.services.contains_key(*name)))
+        .filter_map(|name| {
+            let old_config = old.services.get(name).unwrap_or(&Value::Null);
+            let new_config = new.services.get(name).unwrap_or(&Value::Null);
+            let diff = JsonDiff::new(old_config, new_config);
+            if diff.is_empty() {
+                None
+            } else {
+                Some((name.clone(), diff))
+            }
+        })
+        .collect();
+
+    ConfigDiff {
+        consensus,
+        validators,
+        services,
+    }
+}
+
+fn to_json<T: ::serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("Cannot serialize configuration")
+}
+
+impl ConfigDiff {
+    /// Returns the changes of the consensus parameters keyed by the parameter names.
+    pub fn consensus(&self) -> &JsonDiff {
+        &self.consensus
+    }
+
+    /// Returns the changes of the validator keys keyed by the validator positions
+    /// in the validator list.
+    pub fn validators(&self) -> &BTreeMap<usize, ValidatorKeysChange> {
+        &self.validators
+    }
+
+    /// Returns the changes of the service configurations keyed by the service names.
+    /// The configuration of a service absent from one of the configurations is represented
+    /// by the JSON `null`.
+    pub fn services(&self) -> &BTreeMap<String, JsonDiff> {
+        &self.services
+    }
+
+    /// Returns `true` if the configurations are equal, ignoring `previous_cfg_hash`
+    /// and `actual_from`.
+    pub fn is_empty(&self) -> bool {
+        self.consensus.is_empty() && self.validators.is_empty() && self.services.is_empty()
+    }
+
+    /// Returns `true` if the configurations of exactly the given services have changed,
+    /// while the consensus parameters, validator keys and the configurations of other
+    /// services are the same.
+    pub fn only_services_changed(&self, service_names: &[&str]) -> bool {
+        self.consensus.is_empty()
+            && self.validators.is_empty()
+            && self.services.len() == service_names.len()
+            && service_names
+                .iter()
+                .all(|name| self.services.contains_key(*name))
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("No changes");
+        }
+
+        let mut sections = Vec::new();
+        if !self.consensus.is_empty() {
+            sections.push(format!("consensus:\n{}", indent(&self.consensus)));
+        }
+        for (i, change) in &self.validators {
+            let description = match *change {
+                ValidatorKeysChange::Added(ref keys) => format!("+ {}", describe_keys(keys)),
+                ValidatorKeysChange::Removed(ref keys) => format!("- {}", describe_keys(keys)),
+                ValidatorKeysChange::Changed { ref old, ref new } => {
+                    format!("~ {} -> {}", describe_keys(old), describe_keys(new))
+                }
+            };
+            sections.push(format!("validator {}:\n  {}", i, description));
+        }
+        for (name, diff) in &self.services {
+            sections.push(format!("service `{}`:\n{}", name, indent(diff)));
+        }
+        f.write_str(&sections.join("\n"))
+    }
+}
+
+fn describe_keys(keys: &ValidatorKeys) -> String {
+    format!(
+        "consensus key {:?}, service key {:?}",
+        keys.consensus_key, keys.service_key
+    )
+}
+
+fn indent(diff: &JsonDiff) -> String {
+    diff.to_string()
+        .lines()
+        .map(|line| format!("  {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::blockchain::ConsensusConfig;
+    use exonum::crypto::{gen_keypair, Hash};
+    use exonum::helpers::Height;
+
+    use compare::JsonChange;
+
+    use super::*;
+
+    fn validator_keys() -> ValidatorKeys {
+        ValidatorKeys {
+            consensus_key: gen_keypair().0,
+            service_key: gen_keypair().0,
+        }
+    }
+
+    fn configuration(validator_count: usize) -> StoredConfiguration {
+        let mut services = BTreeMap::new();
+        services.insert("first".to_owned(), json!({ "limit": 10, "names": ["a"] }));
+        services.insert("second".to_owned(), json!({ "value": 1 }));
+        StoredConfiguration {
+            previous_cfg_hash: Hash::zero(),
+            actual_from: Height(0),
+            validator_keys: (0..validator_count).map(|_| validator_keys()).collect(),
+            consensus: ConsensusConfig::default(),
+            services,
+        }
+    }
+
+    #[test]
+    fn test_equal_configurations() {
+        let old = configuration(4);
+        let mut new = old.clone();
+        new.actual_from = Height(10);
+        new.previous_cfg_hash = Hash::new([1; 32]);
+
+        let diff = config_diff(&old, &new);
+        assert!(diff.is_empty());
+        assert!(!diff.only_services_changed(&[]));
+        assert_eq!(diff.to_string(), "No changes");
+    }
+
+    #[test]
+    fn test_consensus_changes() {
+        let old = configuration(4);
+        let mut new = old.clone();
+        new.consensus.txs_block_limit = 500;
+        new.consensus.first_round_timeout = 1000;
+
+        let diff = config_diff(&old, &new);
+        diff.consensus()
+            .assert_only_changed(&["txs_block_limit", "first_round_timeout"]);
+        assert!(diff.validators().is_empty());
+        assert!(diff.services().is_empty());
+        assert!(!diff.only_services_changed(&[]));
+        assert_eq!(
+            diff.to_string(),
+            "consensus:\n  ~ first_round_timeout: 3000 -> 1000\n  ~ txs_block_limit: 1000 -> 500"
+        );
+    }
+
+    #[test]
+    fn test_validator_changes() {
+        let old = configuration(4);
+        let mut new = old.clone();
+        new.validator_keys[1] = validator_keys();
+        new.validator_keys.truncate(3);
+        new.validator_keys.push(validator_keys());
+        new.validator_keys.push(validator_keys());
+
+        let diff = config_diff(&old, &new);
+        assert!(diff.consensus().is_empty());
+        assert_eq!(
+            diff.validators().keys().cloned().collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+        assert_eq!(
+            diff.validators()[&1],
+            ValidatorKeysChange::Changed {
+                old: old.validator_keys[1],
+                new: new.validator_keys[1],
+            }
+        );
+        assert_eq!(
+            diff.validators()[&4],
+            ValidatorKeysChange::Added(new.validator_keys[4])
+        );
+
+        let diff = config_diff(&new, &old);
+        assert_eq!(
+            diff.validators()[&4],
+            ValidatorKeysChange::Removed(new.validator_keys[4])
+        );
+        assert!(diff.to_string().contains("validator 4:\n  - consensus key"));
+    }
+
+    #[test]
+    fn test_service_changes() {
+        let old = configuration(4);
+        let mut new = old.clone();
+        new.services
+            .insert("first".to_owned(), json!({ "limit": 20, "names": ["a"] }));
+        new.services.remove("second");
+        new.services.insert("third".to_owned(), json!({ "enabled": true }));
+
+        let diff = config_diff(&old, &new);
+        assert!(diff.only_services_changed(&["first", "second", "third"]));
+        assert!(!diff.only_services_changed(&["first", "third"]));
+        assert!(!diff.only_services_changed(&["first", "second", "third", "fourth"]));
+
+        diff.services()["first"].assert_only_changed(&["limit"]);
+        assert_eq!(
+            diff.services()["third"].changes()[""],
+            JsonChange::Changed {
+                old: Value::Null,
+                new: json!({ "enabled": true }),
+            }
+        );
+        assert_eq!(
+            diff.services()["second"].changes()[""],
+            JsonChange::Changed {
+                old: json!({ "value": 1 }),
+                new: Value::Null,
+            }
+        );
+    }
+}
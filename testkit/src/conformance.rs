@@ -0,0 +1,307 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance checks of the API mounted by the testkit against the API of a running node.
+//!
+//! The checks issue the same `GET` requests to a live Exonum node and to [`TestKitApi`],
+//! and compare the responses by their status codes and by the structure of their JSON bodies:
+//! the keys present in objects and the types of values. The values themselves are not
+//! compared, since the blockchain states of the node and the testkit generally differ.
+//!
+//! The module is available with the `conformance` feature of the crate.
+//!
+//! [`TestKitApi`]: ../struct.TestKitApi.html
+
+use reqwest::Client;
+use serde_json::{self, Value};
+
+use std::fmt;
+
+use {ApiKind, StatusCode, TestKitApi};
+
+/// Route checked for conformance: the kind of the API and the endpoint relative to it,
+/// possibly with the query string, e.g., `v1/blocks?count=10`.
+#[derive(Debug, Clone, Copy)]
+pub struct Route<'a> {
+    /// Kind of the API the endpoint belongs to.
+    pub kind: ApiKind,
+    /// Endpoint of the route, including the query string, if any.
+    pub endpoint: &'a str,
+}
+
+impl<'a> Route<'a> {
+    /// Creates a route for the specified endpoint.
+    pub fn new(kind: ApiKind, endpoint: &'a str) -> Self {
+        Route { kind, endpoint }
+    }
+}
+
+impl<'a> fmt::Display for Route<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.kind, self.endpoint)
+    }
+}
+
+/// Difference between the responses of the node and the testkit for a single route.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// Status codes of the responses differ.
+    Status {
+        /// Status returned by the node.
+        node: StatusCode,
+        /// Status returned by the testkit.
+        testkit: StatusCode,
+    },
+    /// The body of one of the responses is not a valid JSON.
+    MalformedBody {
+        /// Whether the malformed body is returned by the node (`true`) or by the testkit.
+        node: bool,
+    },
+    /// The key at the specified path is present in the response of the node only.
+    MissingKey(String),
+    /// The key at the specified path is present in the response of the testkit only.
+    ExtraKey(String),
+    /// Values at the specified path have different types.
+    Type {
+        /// JSON pointer to the value.
+        path: String,
+        /// Type of the value returned by the node.
+        node: &'static str,
+        /// Type of the value returned by the testkit.
+        testkit: &'static str,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Status { node, testkit } => write!(
+                f,
+                "status {} on the node, {} in the testkit",
+                node.as_u16(),
+                testkit.as_u16()
+            ),
+            Mismatch::MalformedBody { node: true } => write!(f, "malformed JSON on the node"),
+            Mismatch::MalformedBody { node: false } => write!(f, "malformed JSON in the testkit"),
+            Mismatch::MissingKey(path) => write!(f, "`{}` is missing in the testkit", path),
+            Mismatch::ExtraKey(path) => write!(f, "`{}` is absent on the node", path),
+            Mismatch::Type {
+                path,
+                node,
+                testkit,
+            } => write!(
+                f,
+                "`{}` is {} on the node, {} in the testkit",
+                path, node, testkit
+            ),
+        }
+    }
+}
+
+/// Report of an API conformance check.
+#[derive(Debug, Default)]
+pub struct ConformanceReport {
+    checked: usize,
+    mismatches: Vec<(String, Vec<Mismatch>)>,
+}
+
+impl ConformanceReport {
+    /// Returns `true` if the responses of the node and the testkit conform to each other
+    /// for all checked routes.
+    pub fn is_conformant(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Returns the number of checked routes.
+    pub fn checked_routes(&self) -> usize {
+        self.checked
+    }
+
+    /// Returns the mismatches grouped by the routes they are found for.
+    pub fn mismatches(&self) -> &[(String, Vec<Mismatch>)] {
+        &self.mismatches
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} routes conform",
+            self.checked - self.mismatches.len(),
+            self.checked
+        )?;
+        for (route, mismatches) in &self.mismatches {
+            writeln!(f, "{}:", route)?;
+            for mismatch in mismatches {
+                writeln!(f, "  - {}", mismatch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the conformance of the public API mounted by the testkit to the public API
+/// of a running node. `node_url` is the base URL of the public API of the node,
+/// e.g., `http://127.0.0.1:8200`.
+///
+/// # Panics
+///
+/// - Panics if the node cannot be reached.
+pub fn check_conformance(node_url: &str, api: &TestKitApi, routes: &[Route]) -> ConformanceReport {
+    let client = Client::new();
+    let node_url = node_url.trim_right_matches('/');
+
+    let mut report = ConformanceReport::default();
+    for route in routes {
+        let url = format!("{}/{}", node_url, route);
+        trace!("GET {}", url);
+        let mut response = client
+            .get(&url)
+            .send()
+            .unwrap_or_else(|e| panic!("Unable to send request to {}: {}", url, e));
+        let node_body = response.text().expect("Unable to get response text");
+        let node_response = (response.status(), node_body);
+
+        let testkit_response = api.public(route.kind).get_raw(route.endpoint);
+
+        let mismatches = compare_responses(&node_response, &testkit_response);
+        report.checked += 1;
+        if !mismatches.is_empty() {
+            report.mismatches.push((route.to_string(), mismatches));
+        }
+    }
+    report
+}
+
+fn compare_responses(node: &(StatusCode, String), testkit: &(StatusCode, String)) -> Vec<Mismatch> {
+    if node.0 != testkit.0 {
+        return vec![Mismatch::Status {
+            node: node.0,
+            testkit: testkit.0,
+        }];
+    }
+    // Error responses may contain plain text descriptions.
+    if !node.0.is_success() {
+        return Vec::new();
+    }
+
+    let node_json = serde_json::from_str::<Value>(&node.1);
+    let testkit_json = serde_json::from_str::<Value>(&testkit.1);
+    match (node_json, testkit_json) {
+        (Ok(node), Ok(testkit)) => {
+            let mut mismatches = Vec::new();
+            compare_structure("", &node, &testkit, &mut mismatches);
+            mismatches
+        }
+        (Err(_), _) => vec![Mismatch::MalformedBody { node: true }],
+        (_, Err(_)) => vec![Mismatch::MalformedBody { node: false }],
+    }
+}
+
+/// Compares the structure of JSON values, recording the differences into `mismatches`.
+/// Elements of arrays are compared pairwise; the lengths of arrays are not compared.
+fn compare_structure(path: &str, node: &Value, testkit: &Value, mismatches: &mut Vec<Mismatch>) {
+    match (node, testkit) {
+        (Value::Object(node), Value::Object(testkit)) => {
+            for (key, node_value) in node {
+                let path = format!("{}/{}", path, key);
+                match testkit.get(key) {
+                    Some(testkit_value) => {
+                        compare_structure(&path, node_value, testkit_value, mismatches)
+                    }
+                    None => mismatches.push(Mismatch::MissingKey(path)),
+                }
+            }
+            for key in testkit.keys().filter(|key| !node.contains_key(*key)) {
+                mismatches.push(Mismatch::ExtraKey(format!("{}/{}", path, key)));
+            }
+        }
+        (Value::Array(node), Value::Array(testkit)) => {
+            for (i, (node, testkit)) in node.iter().zip(testkit).enumerate() {
+                compare_structure(&format!("{}/{}", path, i), node, testkit, mismatches);
+            }
+        }
+        // Nulls are compatible with any type, since optional values may be absent
+        // in one of the states.
+        (Value::Null, _) | (_, Value::Null) => {}
+        _ => {
+            let (node_type, testkit_type) = (type_name(node), type_name(testkit));
+            if node_type != testkit_type {
+                mismatches.push(Mismatch::Type {
+                    path: if path.is_empty() { "/".to_owned() } else { path.to_owned() },
+                    node: node_type,
+                    testkit: testkit_type,
+                });
+            }
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_structure() {
+        let node = json!({ "height": 5, "blocks": [{ "hash": "00" }], "extra": null, "a": 1 });
+        let testkit = json!({ "height": 1, "blocks": [{ "hash": 1 }, {}], "b": true });
+
+        let mut mismatches = Vec::new();
+        compare_structure("", &node, &testkit, &mut mismatches);
+        assert_eq!(
+            mismatches,
+            vec![
+                Mismatch::MissingKey("/a".to_owned()),
+                Mismatch::Type {
+                    path: "/blocks/0/hash".to_owned(),
+                    node: "a string",
+                    testkit: "a number",
+                },
+                Mismatch::MissingKey("/extra".to_owned()),
+                Mismatch::ExtraKey("/b".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_responses() {
+        let ok = (StatusCode::Ok, "[1, 2]".to_owned());
+        let not_found = (StatusCode::NotFound, "Not found".to_owned());
+        assert!(compare_responses(&ok, &(StatusCode::Ok, "[]".to_owned())).is_empty());
+        assert!(compare_responses(&not_found, &not_found).is_empty());
+        assert_eq!(
+            compare_responses(&ok, &not_found),
+            vec![Mismatch::Status {
+                node: StatusCode::Ok,
+                testkit: StatusCode::NotFound,
+            }]
+        );
+        assert_eq!(
+            compare_responses(&ok, &(StatusCode::Ok, "[".to_owned())),
+            vec![Mismatch::MalformedBody { node: false }]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of transactions that are committed, but have no effect on the blockchain state.
+
+use exonum::{
+    blockchain::{Blockchain, Schema as CoreSchema, Transaction}, crypto::Hash,
+    storage::{Fork, Snapshot},
+};
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Checker of the transactions committed by the testkit, which allows to detect transactions
+/// that are committed, but have no effect because of an unmet precondition (e.g.,
+/// a prerequisite transaction is committed after the dependent one).
+///
+/// The checking function receives each committed transaction together with the snapshots
+/// of the blockchain state right before and right after its execution, and returns `true`
+/// if the transaction should be flagged as having no effect. The snapshots reflect
+/// the changes made by the transactions of the block executed so far; core tables
+/// (e.g., transaction results) are not updated in them.
+///
+/// See [`TestKit::set_tx_dependency_checker`] for details.
+///
+/// [`TestKit::set_tx_dependency_checker`]:
+/// struct.TestKit.html#method.set_tx_dependency_checker
+pub struct TxDependencyChecker {
+    check: Box<dyn Fn(&dyn Transaction, &dyn Snapshot, &dyn Snapshot) -> bool + Send + Sync>,
+    panic_on_flag: bool,
+}
+
+impl fmt::Debug for TxDependencyChecker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("TxDependencyChecker")
+            .field("panic_on_flag", &self.panic_on_flag)
+            .finish()
+    }
+}
+
+impl TxDependencyChecker {
+    /// Creates a checker with the given checking function. The checker does not panic
+    /// when transactions are flagged.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&dyn Transaction, &dyn Snapshot, &dyn Snapshot) -> bool + Send + Sync + 'static,
+    {
+        TxDependencyChecker {
+            check: Box::new(check),
+            panic_on_flag: false,
+        }
+    }
+
+    /// Makes the testkit panic after creating a block with flagged transactions.
+    pub fn panicking(mut self) -> Self {
+        self.panic_on_flag = true;
+        self
+    }
+
+    /// Returns `true` if the testkit panics after creating a block with flagged transactions.
+    pub fn panics_on_flag(&self) -> bool {
+        self.panic_on_flag
+    }
+
+    /// Replays the transactions with the given hashes on top of the current blockchain state
+    /// and returns the hashes of the flagged transactions. The transactions should be stored
+    /// in the blockchain.
+    pub(crate) fn flagged_transactions(
+        &self,
+        blockchain: &Blockchain,
+        tx_hashes: &[Hash],
+    ) -> Vec<Hash> {
+        let snapshot = blockchain.snapshot();
+        let schema = CoreSchema::new(&snapshot);
+        let transactions = schema.transactions();
+
+        // Both forks replay the same transactions; `after` is always one transaction ahead.
+        let mut before = blockchain.fork();
+        let mut after = blockchain.fork();
+        let mut flagged = Vec::new();
+        for tx_hash in tx_hashes {
+            let raw = transactions
+                .get(tx_hash)
+                .unwrap_or_else(|| panic!("Transaction {:?} is missing", tx_hash));
+            let tx = blockchain
+                .tx_from_raw(raw)
+                .unwrap_or_else(|e| panic!("Cannot parse transaction {:?}: {}", tx_hash, e));
+
+            execute(tx.as_ref(), &mut after);
+            if (self.check)(tx.as_ref(), &before, &after) {
+                flagged.push(*tx_hash);
+            }
+            execute(tx.as_ref(), &mut before);
+        }
+        flagged
+    }
+}
+
+/// Executes the transaction in the same way as the blockchain does, discarding its changes
+/// if the execution fails.
+fn execute(tx: &dyn Transaction, fork: &mut Fork) {
+    fork.checkpoint();
+    match panic::catch_unwind(AssertUnwindSafe(|| tx.execute(fork))) {
+        Ok(Ok(())) => fork.commit(),
+        Ok(Err(_)) | Err(_) => fork.rollback(),
+    }
+}
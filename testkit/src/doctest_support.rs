@@ -0,0 +1,143 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal service used in the documentation examples of the crate.
+//!
+//! The service maintains a single integer, which can be increased with `TxAdd` transactions
+//! and read via the `sum` endpoint of the public and private APIs. Transactions can be
+//! submitted to the `add` endpoint of the public API.
+//!
+//! The module is not a part of the public API of the crate and may change at any time.
+
+use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState},
+    blockchain::{ExecutionResult, Service, Transaction, TransactionSet},
+    crypto::{Hash, PublicKey}, encoding, messages::{Message, RawTransaction},
+    node::TransactionSend, storage::{Entry, Fork, Snapshot},
+};
+
+/// Identifier of the service.
+pub const SERVICE_ID: u16 = 2_000;
+/// Name of the service.
+pub const SERVICE_NAME: &str = "doctest";
+
+/// Schema of the service.
+#[derive(Debug)]
+pub struct DoctestSchema<T> {
+    view: T,
+}
+
+impl<T: AsRef<dyn Snapshot>> DoctestSchema<T> {
+    /// Creates a new schema instance.
+    pub fn new(view: T) -> Self {
+        DoctestSchema { view }
+    }
+
+    fn entry(&self) -> Entry<&dyn Snapshot, u64> {
+        Entry::new("doctest.sum", self.view.as_ref())
+    }
+
+    /// Returns the current value of the sum.
+    pub fn sum(&self) -> u64 {
+        self.entry().get().unwrap_or_default()
+    }
+}
+
+impl<'a> DoctestSchema<&'a mut Fork> {
+    fn add(&mut self, value: u64) {
+        let sum = self.sum() + value;
+        Entry::new("doctest.sum", &mut *self.view).set(sum);
+    }
+}
+
+transactions! {
+    /// Transactions of the service.
+    pub DoctestTransactions {
+        const SERVICE_ID = SERVICE_ID;
+
+        /// Transaction adding a value to the sum.
+        struct TxAdd {
+            /// Author of the transaction.
+            author: &PublicKey,
+            /// Value to add.
+            value: u64,
+        }
+    }
+}
+
+impl Transaction for TxAdd {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        DoctestSchema::new(fork).add(self.value());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DoctestApi;
+
+impl DoctestApi {
+    fn sum(state: &ServiceApiState, _query: ()) -> api::Result<u64> {
+        let snapshot = state.snapshot();
+        Ok(DoctestSchema::new(&snapshot).sum())
+    }
+
+    fn add(state: &ServiceApiState, transaction: TxAdd) -> api::Result<Hash> {
+        let tx_hash = transaction.hash();
+        state.sender().send(transaction.into())?;
+        Ok(tx_hash)
+    }
+
+    fn wire(builder: &mut ServiceApiBuilder) {
+        builder
+            .public_scope()
+            .endpoint("sum", Self::sum)
+            .endpoint_mut("add", Self::add);
+        builder.private_scope().endpoint("sum", Self::sum);
+    }
+}
+
+/// Service maintaining a single integer.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DoctestService;
+
+impl Service for DoctestService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        SERVICE_NAME
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        let tx = DoctestTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+
+    fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+        DoctestTransactions::transaction_type_name(tx.raw())
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        DoctestApi::wire(builder)
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Workaround for `failure` see https://github.com/rust-lang-nursery/failure/issues/223 and
+// ECR-1771 for the details.
+#![allow(bare_trait_objects)]
+
+//! The set of errors returned by the fallible testkit operations.
+
+use failure;
+use serde_json;
+
+use std::fmt;
+
+use exonum::{crypto::{Hash, PublicKey}, helpers::{Height, ValidatorId}, storage};
+
+/// List of possible testkit errors.
+///
+/// The errors are returned by the `try_`-prefixed methods of the testkit; the corresponding
+/// methods without the prefix panic with the error description instead.
+#[derive(Fail, Debug)]
+pub enum Error {
+    /// Transaction cannot be sent to the testkit. This error occurs if the testkit
+    /// that has created the API is dropped.
+    #[fail(display = "Cannot send transaction: {}", _0)]
+    ChannelSend(failure::Error),
+
+    /// Operation that can be performed only by a validator was invoked for an auditor node.
+    /// The error contains the consensus key of the node.
+    #[fail(display = "Node with consensus key {:?} is not a validator", _0)]
+    NotAValidator(PublicKey),
+
+    /// Validator with the specified identifier is absent in the current validator set.
+    #[fail(display = "Validator {} is absent in the current validator set", _0)]
+    UnknownValidator(ValidatorId),
+
+    /// Service with the specified name is not registered in the blockchain.
+    #[fail(display = "Service `{}` is not registered in the blockchain", _0)]
+    UnknownService(String),
+
+    /// Validator with the specified identifier is shut down or is separated from our node
+    /// by a network partition, and thus cannot propose blocks.
+    #[fail(display = "Validator {} is shut down or unreachable", _0)]
+    ValidatorOffline(ValidatorId),
+
+    /// Transaction is already committed to the blockchain.
+    #[fail(display = "Transaction is already committed: {:?}", _0)]
+    TransactionAlreadyCommitted(Hash),
+
+    /// Transaction is specified more than once in the list of transactions for a block.
+    #[fail(display = "Transaction {:?} is included into the block more than once", _0)]
+    DuplicateTransaction(Hash),
+
+    /// Some transactions are not in the pool.
+    #[fail(display = "Transactions are not in the pool: {}", _0)]
+    TransactionsNotInPool(MissingTransactions),
+
+    /// Configuration proposal cannot be scheduled, e.g., because its `actual_from` height
+    /// has already been reached or it conflicts with an already scheduled proposal.
+    #[fail(display = "{}", _0)]
+    StaleConfigurationProposal(String),
+
+    /// Block would have fewer precommits than required by the Byzantine Fault Tolerance
+    /// consensus.
+    #[fail(
+        display = "Block would have {} precommits, while at least {} are required",
+        precommits,
+        majority_count
+    )]
+    InsufficientPrecommits {
+        /// Number of precommits the block would have.
+        precommits: usize,
+        /// Minimal number of precommits for the block to be accepted.
+        majority_count: usize,
+    },
+
+    /// Service key of the blockchain passed to `TestKit::from_blockchain` differs from
+    /// the service key of the tested node.
+    #[fail(
+        display = "Service key {:?} of the blockchain differs from the service key {:?} \
+                   of the tested node",
+        actual,
+        expected
+    )]
+    ServiceKeyMismatch {
+        /// Service key of the tested node.
+        expected: PublicKey,
+        /// Service key of the blockchain.
+        actual: PublicKey,
+    },
+
+    /// Validators of the actual configuration of the blockchain passed to
+    /// `TestKit::from_blockchain` differ from the validators of the test network.
+    #[fail(display = "Validators of the blockchain differ from the validators of the test network")]
+    ValidatorsMismatch,
+
+    /// Operation is not supported by the testkit, e.g., because the testkit is created
+    /// around an existing blockchain.
+    #[fail(display = "Operation is not supported: {}", _0)]
+    Unsupported(String),
+
+    /// Genesis block cannot be created.
+    #[fail(display = "Cannot create genesis block: {}", _0)]
+    Genesis(#[cause] storage::Error),
+
+    /// Blockchain cannot be exported.
+    #[fail(display = "Cannot export blockchain: {}", _0)]
+    Export(#[cause] serde_json::Error),
+
+    /// Transaction is stored in the blockchain under a hash that differs from the hash
+    /// of its content.
+    #[fail(
+        display = "Transaction is stored under hash {:?}, while the hash of its content is {:?}",
+        stored,
+        actual
+    )]
+    TransactionHashMismatch {
+        /// Hash under which the transaction is stored.
+        stored: Hash,
+        /// Actual hash of the transaction content.
+        actual: Hash,
+    },
+
+    /// User agent string reported by the node does not match the
+    /// `name version/rust-version/os` format.
+    #[fail(
+        display = "User agent {:?} does not match the `name version/rust-version/os` format",
+        _0
+    )]
+    MalformedUserAgent(String),
+
+    /// Created block cannot be committed to the blockchain storage.
+    #[fail(
+        display = "Cannot commit block at height {} proposed by validator {} \
+                   with transactions {:?}: {}",
+        height,
+        proposer,
+        tx_hashes,
+        error
+    )]
+    Commit {
+        /// Height of the block.
+        height: Height,
+        /// Validator that has proposed the block.
+        proposer: ValidatorId,
+        /// Hashes of the transactions in the block.
+        tx_hashes: Vec<Hash>,
+        /// Error returned by the storage.
+        #[cause]
+        error: storage::Error,
+    },
+}
+
+/// Index of a validator does not fit into `ValidatorId`.
+#[derive(Fail, Debug, Clone, Copy, PartialEq, Eq)]
+#[fail(display = "Validator index {} exceeds the maximal validator id", _0)]
+pub struct ValidatorIdOverflow(pub usize);
+
+/// Transaction that is expected to be in the pool, but is absent from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingTransaction {
+    /// Transaction is already committed to the blockchain.
+    Committed(Hash),
+    /// Transaction is unknown to the testkit, i.e., it has never been sent to the testkit.
+    Unknown(Hash),
+}
+
+impl MissingTransaction {
+    /// Returns the hash of the missing transaction.
+    pub fn hash(&self) -> Hash {
+        match *self {
+            MissingTransaction::Committed(hash) | MissingTransaction::Unknown(hash) => hash,
+        }
+    }
+}
+
+impl fmt::Display for MissingTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MissingTransaction::Committed(ref hash) => {
+                write!(f, "{:?} (already committed)", hash)
+            }
+            MissingTransaction::Unknown(ref hash) => {
+                write!(f, "{:?} (unknown, has never been sent to the testkit)", hash)
+            }
+        }
+    }
+}
+
+/// List of transactions that are absent from the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingTransactions(pub Vec<MissingTransaction>);
+
+impl fmt::Display for MissingTransactions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, tx) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", tx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inconsistency of the blockchain found by `TestKit::verify_chain`.
+#[derive(Fail, Debug, Clone, PartialEq)]
+pub enum ChainVerificationError {
+    /// Block with the hash stored for the specified height is absent.
+    #[fail(display = "Block {:?} at height {} is absent", hash, height)]
+    MissingBlock {
+        /// Height of the block.
+        height: Height,
+        /// Hash of the block stored for the height.
+        hash: Hash,
+    },
+
+    /// Block is stored under a hash that differs from its actual hash.
+    #[fail(
+        display = "Block at height {} is stored under hash {:?}, while its hash is {:?}",
+        height,
+        stored,
+        actual
+    )]
+    BlockHashMismatch {
+        /// Height of the block.
+        height: Height,
+        /// Hash under which the block is stored.
+        stored: Hash,
+        /// Actual hash of the block.
+        actual: Hash,
+    },
+
+    /// Block stored for the specified height has a different height.
+    #[fail(display = "Block stored at height {} has height {}", height, actual)]
+    HeightMismatch {
+        /// Height for which the block is stored.
+        height: Height,
+        /// Height specified in the block.
+        actual: Height,
+    },
+
+    /// Hash of the previous block specified in the block differs from the hash
+    /// of the block stored at the previous height.
+    #[fail(
+        display = "Block at height {} refers to the previous block {:?}, while its hash is {:?}",
+        height,
+        actual,
+        expected
+    )]
+    PrevHashMismatch {
+        /// Height of the block.
+        height: Height,
+        /// Hash of the block stored at the previous height.
+        expected: Hash,
+        /// Hash of the previous block specified in the block.
+        actual: Hash,
+    },
+
+    /// Number of transactions specified in the block differs from the number of stored ones.
+    #[fail(
+        display = "Block at height {} has {} transactions, while {} are stored for it",
+        height,
+        expected,
+        actual
+    )]
+    TxCountMismatch {
+        /// Height of the block.
+        height: Height,
+        /// Number of transactions specified in the block.
+        expected: u32,
+        /// Number of transactions stored for the block.
+        actual: u64,
+    },
+
+    /// Root hash of the transactions specified in the block differs from the root hash
+    /// of the transactions stored for it.
+    #[fail(
+        display = "Block at height {} has transactions root {:?}, while the root of stored \
+                   transactions is {:?}",
+        height,
+        expected,
+        actual
+    )]
+    TxHashMismatch {
+        /// Height of the block.
+        height: Height,
+        /// Root hash of the transactions specified in the block.
+        expected: Hash,
+        /// Root hash of the transactions stored for the block.
+        actual: Hash,
+    },
+
+    /// Transaction of the block is absent from the blockchain.
+    #[fail(display = "Transaction {:?} of the block at height {} is absent", tx_hash, height)]
+    MissingTransaction {
+        /// Height of the block.
+        height: Height,
+        /// Hash of the absent transaction.
+        tx_hash: Hash,
+    },
+
+    /// Transaction of the block is stored under a hash that differs from the hash
+    /// of its content.
+    #[fail(
+        display = "Transaction of the block at height {} is stored under hash {:?}, \
+                   while the hash of its content is {:?}",
+        height,
+        stored,
+        actual
+    )]
+    TransactionHashMismatch {
+        /// Height of the block.
+        height: Height,
+        /// Hash under which the transaction is stored.
+        stored: Hash,
+        /// Actual hash of the transaction content.
+        actual: Hash,
+    },
+
+    /// Precommit stored for the block is invalid.
+    #[fail(
+        display = "Precommit #{} of validator {} for the block at height {} is invalid: {}",
+        index,
+        validator,
+        height,
+        reason
+    )]
+    InvalidPrecommit {
+        /// Height of the block.
+        height: Height,
+        /// Position of the precommit among the precommits stored for the block.
+        index: u64,
+        /// Validator specified in the precommit.
+        validator: ValidatorId,
+        /// Description of the defect.
+        reason: String,
+    },
+
+    /// Block has fewer precommits than required by the validator set active
+    /// at its height.
+    #[fail(
+        display = "Block at height {} has {} precommits, while at least {} are required",
+        height,
+        count,
+        required
+    )]
+    InsufficientPrecommits {
+        /// Height of the block.
+        height: Height,
+        /// Number of precommits stored for the block.
+        count: usize,
+        /// Minimal number of precommits for the block.
+        required: usize,
+    },
+
+    /// State hash specified in the latest block differs from the root hash
+    /// of the state hash aggregator.
+    #[fail(
+        display = "Block at height {} has state hash {:?}, while the root of the state hash \
+                   aggregator is {:?}",
+        height,
+        expected,
+        actual
+    )]
+    StateHashMismatch {
+        /// Height of the latest block.
+        height: Height,
+        /// State hash specified in the block.
+        expected: Hash,
+        /// Root hash of the state hash aggregator.
+        actual: Hash,
+    },
+
+    /// Root hash of a table differs from the one aggregated into the state hash
+    /// of the latest block, e.g., because the table has been changed bypassing
+    /// transactions.
+    #[fail(
+        display = "Table #{} of service {} differs from its state at height {}",
+        table_idx,
+        service_id,
+        height
+    )]
+    StateTableMismatch {
+        /// Height of the latest block.
+        height: Height,
+        /// Identifier of the service owning the table; `0` for the core tables.
+        service_id: u16,
+        /// Index of the table among the tables returned by `Service::state_hash`.
+        table_idx: usize,
+    },
+}
@@ -0,0 +1,72 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serialization format of the blockchains exported by the testkit.
+
+use exonum::{
+    blockchain::{GenesisConfig, StoredConfiguration}, crypto::Hash,
+    encoding::serialize::{decode_hex, encode_hex}, helpers::{Height, ValidatorId},
+    messages::{Message, RawMessage},
+};
+
+use network::TestNetwork;
+use ConfigurationProposalState;
+
+/// Blockchain exported by `TestKit::export_blockchain`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExportedBlockchain {
+    /// Genesis configuration of the blockchain.
+    pub genesis: GenesisConfig,
+    /// Hash of the genesis block.
+    pub genesis_hash: Hash,
+    /// Test network at the moment of the export.
+    pub network: TestNetwork,
+    /// Blocks following the genesis block.
+    pub blocks: Vec<ExportedBlock>,
+    /// Configuration proposals which have not been activated at the moment of the export.
+    pub configuration_proposals: Vec<(Height, ConfigurationProposalState)>,
+    /// Hex-encoded transactions from the pool.
+    pub pool: Vec<String>,
+}
+
+/// Block of the exported blockchain.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExportedBlock {
+    /// Hash of the block.
+    pub hash: Hash,
+    /// Identifier of the validator that has proposed the block.
+    pub proposer_id: ValidatorId,
+    /// Hex-encoded transactions in the order of their execution.
+    pub transactions: Vec<String>,
+    /// Hex-encoded precommits for the block.
+    pub precommits: Vec<String>,
+    /// Configurations committed together with the block.
+    pub configurations: Vec<StoredConfiguration>,
+}
+
+/// Encodes a message as a hex string.
+pub(crate) fn encode_message<T: Message>(message: &T) -> String {
+    encode_hex(message.raw())
+}
+
+/// Decodes a message from a hex string.
+///
+/// # Panics
+///
+/// - Panics if the string is not a valid hex string.
+pub(crate) fn decode_message(hex: &str) -> RawMessage {
+    let bytes = decode_hex(hex)
+        .unwrap_or_else(|e| panic!("Exported message {} is not a valid hex string: {}", hex, e));
+    RawMessage::from_vec(bytes)
+}
@@ -0,0 +1,290 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic driver for fuzzing services, e.g., with `cargo-fuzz`.
+//!
+//! A fuzz target maps an arbitrary byte stream into a sequence of [operations] with
+//! a user-defined [`FuzzDecoder`]: service transactions, block boundaries, probes
+//! and rollbacks. The operations are then applied to a testkit by [`run_transactions`]
+//! or a [`FuzzRunner`]. Expected errors, such as transactions failing verification,
+//! transactions executed with error codes, or blocks that cannot be created because
+//! of already committed transactions, are swallowed. Panics during transaction execution,
+//! which are caught by the blockchain, are propagated, so that the fuzzer registers them
+//! as crashes.
+//!
+//! The module is available with the `fuzz` feature of the crate.
+//!
+//! # Examples
+//!
+//! A fuzz target for a simple service may look as follows:
+//!
+//! ```
+//! # extern crate exonum;
+//! # extern crate exonum_testkit;
+//! # use exonum_testkit::doctest_support::{DoctestService, TxAdd};
+//! use exonum::crypto::{self, Seed, SEED_LENGTH};
+//! use exonum_testkit::TestKitBuilder;
+//! use exonum_testkit::fuzz::{self, FuzzDecoder, FuzzOp};
+//!
+//! fn take_byte(input: &mut &[u8]) -> Option<u8> {
+//!     let data = *input;
+//!     let (&byte, rest) = data.split_first()?;
+//!     *input = rest;
+//!     Some(byte)
+//! }
+//!
+//! struct Decoder;
+//!
+//! impl FuzzDecoder for Decoder {
+//!     fn decode_op(&self, input: &mut &[u8]) -> Option<FuzzOp> {
+//!         let opcode = take_byte(input)?;
+//!         match opcode % 3 {
+//!             0 => Some(FuzzOp::CreateBlock),
+//!             1 => Some(FuzzOp::Rollback),
+//!             _ => {
+//!                 let value = take_byte(input)?;
+//!                 let seed = Seed::new([opcode; SEED_LENGTH]);
+//!                 let (pubkey, key) = crypto::gen_keypair_from_seed(&seed);
+//!                 Some(FuzzOp::Transaction(Box::new(TxAdd::new(&pubkey, value.into(), &key))))
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! fn fuzz_target(data: &[u8]) {
+//!     let mut testkit = TestKitBuilder::validator()
+//!         .with_service(DoctestService)
+//!         .create();
+//!     fuzz::run_transactions(&mut testkit, &Decoder, data);
+//! }
+//! # fn main() {
+//! # fuzz_target(&[2, 1, 5, 3, 0, 1, 2, 8]);
+//! # }
+//! ```
+//!
+//! [operations]: enum.FuzzOp.html
+//! [`FuzzDecoder`]: trait.FuzzDecoder.html
+//! [`run_transactions`]: fn.run_transactions.html
+//! [`FuzzRunner`]: struct.FuzzRunner.html
+
+use std::fmt;
+use std::mem;
+
+use exonum::{
+    blockchain::{Schema as CoreSchema, Transaction, TransactionErrorType}, crypto::Hash,
+    storage::Snapshot,
+};
+
+use TestKit;
+
+/// Operation applied to the testkit by the fuzz driver.
+pub enum FuzzOp {
+    /// Adds the transaction to the next block created by the driver.
+    Transaction(Box<dyn Transaction>),
+    /// Creates a block with the transactions added since the previous block.
+    CreateBlock,
+    /// Executes the transaction on top of the latest block without committing the results.
+    Probe(Box<dyn Transaction>),
+    /// Discards the latest block created by the driver. The operation is ignored
+    /// if rollbacks are not enabled with [`FuzzRunner::with_rollbacks`], or if there are
+    /// no blocks to discard.
+    ///
+    /// [`FuzzRunner::with_rollbacks`]: struct.FuzzRunner.html#method.with_rollbacks
+    Rollback,
+}
+
+impl fmt::Debug for FuzzOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FuzzOp::Transaction(ref tx) => f.debug_tuple("Transaction").field(&tx.hash()).finish(),
+            FuzzOp::CreateBlock => f.write_str("CreateBlock"),
+            FuzzOp::Probe(ref tx) => f.debug_tuple("Probe").field(&tx.hash()).finish(),
+            FuzzOp::Rollback => f.write_str("Rollback"),
+        }
+    }
+}
+
+/// Decoder of fuzzer inputs into operations of the fuzz driver.
+pub trait FuzzDecoder {
+    /// Decodes an operation from the beginning of `input` and advances `input` past
+    /// the consumed bytes. Returns `None` if the remaining input cannot be decoded,
+    /// which ends the run.
+    ///
+    /// The decoding should be deterministic, so that crashes found by the fuzzer
+    /// can be reproduced; e.g., keys for signing transactions should be generated
+    /// from seeds derived from the input rather than randomly.
+    fn decode_op(&self, input: &mut &[u8]) -> Option<FuzzOp>;
+}
+
+/// Statistics of a fuzz driver run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FuzzStats {
+    /// Number of decoded operations.
+    pub operations: usize,
+    /// Number of committed blocks, including the blocks discarded by rollbacks.
+    pub blocks: usize,
+    /// Number of blocks that could not be created, e.g., because they contained
+    /// already committed transactions.
+    pub rejected_blocks: usize,
+    /// Number of probes.
+    pub probes: usize,
+    /// Number of performed rollbacks.
+    pub rollbacks: usize,
+    /// Number of transactions executed with an error code, including probed ones.
+    pub failed_transactions: usize,
+}
+
+/// Configurable fuzz driver.
+#[derive(Debug, Clone, Default)]
+pub struct FuzzRunner {
+    rollbacks: bool,
+}
+
+impl FuzzRunner {
+    /// Creates a driver with the default settings: rollbacks are disabled.
+    pub fn new() -> Self {
+        FuzzRunner::default()
+    }
+
+    /// Enables `FuzzOp::Rollback` operations. If enabled, the driver sets a checkpoint
+    /// before each block it creates, so that the block can be discarded.
+    ///
+    /// The testkit passed to [`run`] should support checkpoints, i.e., it should not be
+    /// created with `TestKit::from_blockchain`.
+    ///
+    /// [`run`]: #method.run
+    pub fn with_rollbacks(mut self) -> Self {
+        self.rollbacks = true;
+        self
+    }
+
+    /// Decodes operations from `bytes` and applies them to the testkit. Transactions
+    /// remaining after the last block boundary are committed in a final block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if a transaction panics during execution. The panic message contains
+    ///   the hash of the transaction and the description of the panic.
+    /// - Panics if the decoder returns an operation without consuming any input.
+    pub fn run<D>(&self, testkit: &mut TestKit, decoder: &D, bytes: &[u8]) -> FuzzStats
+    where
+        D: FuzzDecoder + ?Sized,
+    {
+        let mut stats = FuzzStats::default();
+        let mut pending = Vec::new();
+        // Number of checkpoints set by the runner, i.e., blocks that can be rolled back.
+        let mut checkpoints = 0;
+        let mut input = bytes;
+
+        loop {
+            let remaining = input.len();
+            let op = match decoder.decode_op(&mut input) {
+                Some(op) => op,
+                None => break,
+            };
+            assert!(
+                input.len() < remaining,
+                "Fuzz decoder returned {:?} without consuming input",
+                op
+            );
+            stats.operations += 1;
+
+            match op {
+                FuzzOp::Transaction(tx) => pending.push(tx),
+                FuzzOp::CreateBlock => {
+                    let txs = mem::replace(&mut pending, Vec::new());
+                    self.create_block(testkit, txs, &mut checkpoints, &mut stats);
+                }
+                FuzzOp::Probe(tx) => {
+                    let tx_hash = tx.hash();
+                    stats.probes += 1;
+                    if let Ok(snapshot) = testkit.try_probe_all(vec![tx]) {
+                        check_results(&*snapshot, &[tx_hash], &mut stats);
+                    }
+                }
+                FuzzOp::Rollback => {
+                    if checkpoints > 0 {
+                        testkit.rollback();
+                        checkpoints -= 1;
+                        stats.rollbacks += 1;
+                    }
+                }
+            }
+        }
+
+        if !pending.is_empty() {
+            self.create_block(testkit, pending, &mut checkpoints, &mut stats);
+        }
+        stats
+    }
+
+    fn create_block(
+        &self,
+        testkit: &mut TestKit,
+        txs: Vec<Box<dyn Transaction>>,
+        checkpoints: &mut usize,
+        stats: &mut FuzzStats,
+    ) {
+        if self.rollbacks {
+            testkit.checkpoint();
+        }
+        match testkit.try_create_block_with_transactions(txs) {
+            Ok(block) => {
+                let tx_hashes: Vec<_> = block.iter().map(|tx| tx.content().hash()).collect();
+                check_results(&*testkit.snapshot(), &tx_hashes, stats);
+                stats.blocks += 1;
+                if self.rollbacks {
+                    *checkpoints += 1;
+                }
+            }
+            Err(_) => {
+                stats.rejected_blocks += 1;
+                if self.rollbacks {
+                    // Remove the unused checkpoint; the blockchain state is not changed.
+                    testkit.rollback();
+                }
+            }
+        }
+    }
+}
+
+/// Decodes operations from `bytes` and applies them to the testkit with the default
+/// settings of [`FuzzRunner`]. See [`FuzzRunner::run`] for details.
+///
+/// [`FuzzRunner`]: struct.FuzzRunner.html
+/// [`FuzzRunner::run`]: struct.FuzzRunner.html#method.run
+pub fn run_transactions<D>(testkit: &mut TestKit, decoder: &D, bytes: &[u8]) -> FuzzStats
+where
+    D: FuzzDecoder + ?Sized,
+{
+    FuzzRunner::new().run(testkit, decoder, bytes)
+}
+
+/// Propagates panics of the executed transactions and counts failed ones.
+fn check_results(snapshot: &dyn Snapshot, tx_hashes: &[Hash], stats: &mut FuzzStats) {
+    let schema = CoreSchema::new(snapshot);
+    let results = schema.transaction_results();
+    for tx_hash in tx_hashes {
+        if let Some(Err(e)) = results.get(tx_hash) {
+            match e.error_type() {
+                TransactionErrorType::Panic => panic!(
+                    "Transaction {:?} panicked during execution: {}",
+                    tx_hash,
+                    e.description().unwrap_or("no description")
+                ),
+                TransactionErrorType::Code(_) => stats.failed_transactions += 1,
+            }
+        }
+    }
+}
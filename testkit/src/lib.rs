@@ -138,7 +138,7 @@ extern crate actix_web;
 #[cfg_attr(test, macro_use)]
 #[cfg(test)]
 extern crate assert_matches;
-#[cfg_attr(test, macro_use)]
+#[macro_use]
 extern crate exonum;
 #[macro_use]
 extern crate failure;
@@ -149,42 +149,97 @@ extern crate reqwest;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[cfg_attr(test, macro_use)]
 extern crate serde_json;
 extern crate serde_urlencoded;
 extern crate tokio_core;
 
-pub use api::{ApiKind, TestKitApi};
-pub use compare::ComparableSnapshot;
-pub use network::{TestNetwork, TestNetworkConfiguration, TestNode};
+pub use api::{ApiKind, ServiceApi, StatusCode, TestKitApi, UserAgent};
+pub use byzantine::{ByzantineBehavior, ConflictingMessages};
+pub use compare::{ComparableSnapshot, JsonDiff};
+pub use config_diff::{config_diff, ConfigDiff, ValidatorKeysChange};
+pub use dependency_checker::TxDependencyChecker;
+pub use error::{
+    ChainVerificationError, Error, MissingTransaction, MissingTransactions, ValidatorIdOverflow,
+};
+pub use metrics::{BlockExecutionMetrics, ExecutionMetrics};
+pub use network::{byzantine_majority_count, TestNetwork, TestNetworkConfiguration, TestNode};
+pub use scenario::{Scenario, ScenarioError};
+pub use service_wrappers::{ServiceCall, ServiceCalls, ServiceSpy};
 
 pub mod compare;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[doc(hidden)]
+pub mod doctest_support;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod prelude;
+#[cfg(feature = "examples")]
+pub mod samples;
 
 use futures::{sync::mpsc, Future, Stream};
+use serde::de::DeserializeOwned;
 use tokio_core::reactor::Core;
 
-use std::sync::{Arc, RwLock};
-use std::{fmt, net::SocketAddr};
+use std::io::{Read, Write};
+use std::sync::{Arc, Barrier, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use std::{collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque}, fmt, net::SocketAddr};
 
 use exonum::{
     api::{
-        backends::actix::{ApiRuntimeConfig, SystemRuntimeConfig}, ApiAccess,
+        backends::actix::{ApiRuntimeConfig, SystemRuntimeConfig},
+        node::public::explorer::BlockInfo, ApiAccess,
+    },
+    blockchain::{
+        Block, Blockchain, ConsensusConfig, GenesisConfig, Schema as CoreSchema, Service,
+        StoredConfiguration, Transaction, TransactionError,
+    },
+    crypto::{self, CryptoHash, Hash}, explorer::{BlockWithTransactions, BlockchainExplorer},
+    helpers::{Height, ValidatorId}, messages::{Message, Precommit, Propose, RawMessage},
+    node::{ApiSender, ConnectInfo, ExternalMessage},
+    storage::{
+        Entry, Iterator as StorageIterator, KeySetIndex, MapIndex, MemoryDB, Patch, Snapshot,
     },
-    blockchain::{Blockchain, Schema as CoreSchema, Service, StoredConfiguration, Transaction},
-    crypto::{self, Hash}, explorer::{BlockWithTransactions, BlockchainExplorer},
-    helpers::{Height, ValidatorId}, messages::RawMessage,
-    node::{ApiSender, ExternalMessage, State as NodeState}, storage::{MemoryDB, Patch, Snapshot},
 };
 
 use checkpoint_db::{CheckpointDb, CheckpointDbHandler};
-use poll_events::poll_events;
+use export::{decode_message, encode_message, ExportedBlock, ExportedBlockchain};
+use poll_events::{poll_events, poll_events_for};
+use raw_index::{export_entries, index_names, IndexElements};
+use metrics::ExecutionLog;
+use service_wrappers::{GenesisConfigOverride, SharedService, TimedService};
 
 #[macro_use]
 mod macros;
 mod api;
+mod byzantine;
 mod checkpoint_db;
+mod config_diff;
+mod dependency_checker;
+mod error;
+mod export;
+mod metrics;
 mod network;
 mod poll_events;
+mod raw_index;
+mod scenario;
 mod server;
+mod service_wrappers;
+mod shuffle;
+
+// Targets of the log records emitted by the testkit.
+const BLOCK_LOG_TARGET: &str = "exonum_testkit::block";
+const TX_LOG_TARGET: &str = "exonum_testkit::tx";
+const CONFIG_LOG_TARGET: &str = "exonum_testkit::config";
+const PROBE_LOG_TARGET: &str = "exonum_testkit::probe";
+
+// Names of the core indexes, which are modified directly to forge transaction hashes.
+const CORE_TRANSACTIONS: &str = "core.transactions";
+const CORE_TRANSACTIONS_POOL: &str = "core.transactions_pool";
+const CORE_TRANSACTIONS_POOL_LEN: &str = "core.transactions_pool_len";
 
 /// Builder for `TestKit`.
 ///
@@ -272,7 +327,22 @@ pub struct TestKitBuilder {
     our_validator_id: Option<ValidatorId>,
     validator_count: Option<u16>,
     services: Vec<Box<dyn Service>>,
+    genesis_service_configs: BTreeMap<String, serde_json::Value>,
     logger: bool,
+    pool_size_tracking: bool,
+    genesis_block: bool,
+    shuffled_blocks_seed: Option<u64>,
+    execution_metrics: bool,
+    execution_timing: bool,
+    event_delay: Option<Duration>,
+    retained_block_messages: usize,
+    // Calls of the hooks of the services added with `with_spied_service`.
+    service_spies: BTreeMap<String, ServiceCalls>,
+    // The channel is created in advance, so that services may hold its sending end.
+    api_sender: ApiSender,
+    api_receiver: mpsc::Receiver<ExternalMessage>,
+    // Blockchain restored by `create`, if the builder is created from an exported blockchain.
+    exported: Option<ExportedBlockchain>,
 }
 
 impl fmt::Debug for TestKitBuilder {
@@ -291,7 +361,16 @@ impl fmt::Debug for TestKitBuilder {
                     .map(|x| x.service_name())
                     .collect::<Vec<_>>(),
             )
+            .field("genesis_service_configs", &self.genesis_service_configs)
             .field("logger", &self.logger)
+            .field("pool_size_tracking", &self.pool_size_tracking)
+            .field("genesis_block", &self.genesis_block)
+            .field("shuffled_blocks_seed", &self.shuffled_blocks_seed)
+            .field("execution_metrics", &self.execution_metrics)
+            .field("execution_timing", &self.execution_timing)
+            .field("event_delay", &self.event_delay)
+            .field("retained_block_messages", &self.retained_block_messages)
+            .field("service_spies", &self.service_spies.keys().collect::<Vec<_>>())
             .finish()
     }
 }
@@ -299,59 +378,414 @@ impl fmt::Debug for TestKitBuilder {
 impl TestKitBuilder {
     /// Creates testkit for the validator node.
     pub fn validator() -> Self {
-        TestKitBuilder {
-            validator_count: None,
-            our_validator_id: Some(ValidatorId(0)),
-            services: Vec::new(),
-            logger: false,
-        }
+        Self::with_our_role(Some(ValidatorId(0)))
     }
 
     /// Creates testkit for the auditor node.
     pub fn auditor() -> Self {
+        Self::with_our_role(None)
+    }
+
+    /// Creates testkit restoring the blockchain exported with [`TestKit::export_blockchain`].
+    /// This allows to commit pre-built blockchains with complex states to the repository
+    /// as test fixtures instead of building them from scratch in every test.
+    ///
+    /// The test network, including the role of the tested node, is restored from
+    /// the exported blockchain, so the number of validators cannot be specified.
+    /// The services of the testkit should be the same as the services of the testkit
+    /// which has exported the blockchain; blocks are replayed with these services
+    /// on [`create`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the exported blockchain cannot be read or deserialized.
+    /// - [`create`] panics if any of the replayed blocks differs from the exported one,
+    ///   e.g., because the services of the testkit differ from the original ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// testkit.create_blocks_until(Height(10));
+    /// let mut fixture = Vec::new();
+    /// testkit.export_blockchain(&mut fixture).unwrap();
+    ///
+    /// let restored = TestKitBuilder::from_exported_blockchain(&fixture[..]).create();
+    /// assert_eq!(restored.height(), Height(10));
+    /// assert_eq!(restored.last_block_hash(), testkit.last_block_hash());
+    /// # }
+    /// ```
+    ///
+    /// [`TestKit::export_blockchain`]: struct.TestKit.html#method.export_blockchain
+    /// [`create`]: #method.create
+    pub fn from_exported_blockchain<R: Read>(reader: R) -> Self {
+        let exported: ExportedBlockchain = serde_json::from_reader(reader)
+            .unwrap_or_else(|e| panic!("Cannot read exported blockchain: {}", e));
+        let mut builder = Self::with_our_role(exported.network.us().validator_id());
+        builder.exported = Some(exported);
+        builder
+    }
+
+    fn with_our_role(our_validator_id: Option<ValidatorId>) -> Self {
+        let (api_sender, api_receiver) = api_channel();
         TestKitBuilder {
             validator_count: None,
-            our_validator_id: None,
+            our_validator_id,
             services: Vec::new(),
+            genesis_service_configs: BTreeMap::new(),
             logger: false,
+            pool_size_tracking: false,
+            genesis_block: true,
+            shuffled_blocks_seed: None,
+            execution_metrics: false,
+            execution_timing: false,
+            event_delay: None,
+            retained_block_messages: 1,
+            service_spies: BTreeMap::new(),
+            api_sender,
+            api_receiver,
+            exported: None,
         }
     }
 
     /// Sets the number of validator nodes in the test network.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of validators is already specified.
+    /// - Panics if the builder is created with [`from_exported_blockchain`].
+    ///
+    /// [`from_exported_blockchain`]: #method.from_exported_blockchain
     pub fn with_validators(mut self, validator_count: u16) -> Self {
         assert!(
             self.validator_count.is_none(),
             "Number of validators is already specified"
         );
+        assert!(
+            self.exported.is_none(),
+            "Number of validators is determined by the exported blockchain"
+        );
         self.validator_count = Some(validator_count);
         self
     }
 
     /// Adds a service to the testkit.
-    pub fn with_service<S>(mut self, service: S) -> Self
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the identifier or the name of the service coincides with the identifier
+    ///   or the name of a previously added service.
+    pub fn with_service<S>(self, service: S) -> Self
+    where
+        S: Service + 'static,
+    {
+        self.with_boxed_service(Box::new(service))
+    }
+
+    /// Adds a boxed service to the testkit. This method is useful for services
+    /// that are only available as trait objects, e.g., ones created by a service factory.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the identifier or the name of the service coincides with the identifier
+    ///   or the name of a previously added service.
+    pub fn with_boxed_service(mut self, service: Box<dyn Service>) -> Self {
+        for existing in &self.services {
+            assert!(
+                existing.service_id() != service.service_id(),
+                "Service `{}` has the same id {} as already added service `{}`",
+                service.service_name(),
+                service.service_id(),
+                existing.service_name()
+            );
+            assert!(
+                existing.service_name() != service.service_name(),
+                "Service with id {} has the same name `{}` as already added service with id {}",
+                service.service_id(),
+                service.service_name(),
+                existing.service_id()
+            );
+        }
+        self.services.push(service);
+        self
+    }
+
+    /// Adds a service constructed by the given closure to the testkit. The closure receives
+    /// the `ApiSender` of the testkit, so that the service may broadcast transactions,
+    /// e.g., from its API handlers, exactly as it would do in a real node.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the identifier or the name of the service coincides with the identifier
+    ///   or the name of a previously added service.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::blockchain::{Service, Transaction};
+    /// # use exonum::crypto::Hash;
+    /// # use exonum::encoding;
+    /// # use exonum::messages::RawTransaction;
+    /// # use exonum::node::ApiSender;
+    /// # use exonum::storage::Snapshot;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// struct BroadcastingService {
+    ///     sender: ApiSender,
+    /// }
+    /// #
+    /// # impl Service for BroadcastingService {
+    /// #     fn service_id(&self) -> u16 { 1 }
+    /// #     fn service_name(&self) -> &str { "broadcasting" }
+    /// #     fn state_hash(&self, _: &Snapshot) -> Vec<Hash> { vec![] }
+    /// #     fn tx_from_raw(
+    /// #         &self,
+    /// #         _: RawTransaction,
+    /// #     ) -> Result<Box<Transaction>, encoding::Error> {
+    /// #         unimplemented!()
+    /// #     }
+    /// # }
+    ///
+    /// # fn main() {
+    /// let testkit = TestKitBuilder::validator()
+    ///     .with_service_fn(|sender| BroadcastingService { sender })
+    ///     .create();
+    /// # }
+    /// ```
+    pub fn with_service_fn<F, S>(self, factory: F) -> Self
+    where
+        F: FnOnce(ApiSender) -> S,
+        S: Service + 'static,
+    {
+        let service = factory(self.api_sender.clone());
+        self.with_service(service)
+    }
+
+    /// Adds a service to the testkit, wrapping it into a [`ServiceSpy`]. The calls
+    /// of the service hooks are available via [`TestKit::service_calls`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the identifier or the name of the service coincides with the identifier
+    ///   or the name of a previously added service.
+    ///
+    /// [`ServiceSpy`]: struct.ServiceSpy.html
+    /// [`TestKit::service_calls`]: struct.TestKit.html#method.service_calls
+    pub fn with_spied_service<S>(self, service: S) -> Self
     where
-        S: Into<Box<dyn Service>>,
+        S: Service + 'static,
     {
-        self.services.push(service.into());
+        let name = service.service_name().to_owned();
+        let mut builder = self.with_service(service);
+        builder.service_spies.insert(name, ServiceCalls::default());
+        builder
+    }
+
+    /// Overrides the configuration of the service with the given name in the genesis block.
+    ///
+    /// The `initialize` method of the service is still invoked when the genesis block
+    /// is created, so that the service may put its initial data into the storage;
+    /// however, the configuration returned by the method is replaced with `config`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics in [`create`] if no service with the given name is added to the testkit.
+    ///
+    /// [`create`]: #method.create
+    pub fn with_genesis_service_config(
+        mut self,
+        service_name: &str,
+        config: serde_json::Value,
+    ) -> Self {
+        self.genesis_service_configs.insert(service_name.to_owned(), config);
         self
     }
 
     /// Enables a logger inside the testkit.
+    ///
+    /// The logger is configured with the `RUST_LOG` environment variable in the same way
+    /// as `env_logger`; it is installed only if no other logger is set up in the process.
+    /// The testkit emits log records with the following targets:
+    ///
+    /// - `exonum_testkit::block`: created blocks (`debug` level).
+    /// - `exonum_testkit::tx`: transactions added to the pool or rejected (`trace` level).
+    /// - `exonum_testkit::config`: scheduled, committed and activated configuration changes
+    ///   (`debug` level).
+    /// - `exonum_testkit::probe`: probed transactions (`debug` level).
+    ///
+    /// For example, `RUST_LOG=exonum_testkit::block=debug` outputs created blocks only.
     pub fn with_logger(mut self) -> Self {
         self.logger = true;
         self
     }
 
+    /// Enables tracking of the transaction pool size. If enabled, the testkit records
+    /// the number of transactions in the pool before creating each block; the records
+    /// are available via [`TestKit::transaction_pool_size_history`].
+    ///
+    /// [`TestKit::transaction_pool_size_history`]:
+    /// struct.TestKit.html#method.transaction_pool_size_history
+    pub fn with_pool_size_tracking(mut self) -> Self {
+        self.pool_size_tracking = true;
+        self
+    }
+
+    /// Makes the testkit start with completely empty storage: the genesis block
+    /// is not created when the testkit is assembled. Instead, the genesis block
+    /// (and thus the initialization of all services) is created by the first call
+    /// to [`create_block`] or another block-creating method, right before the requested
+    /// block. This allows to test the startup behavior of services.
+    ///
+    /// Until the genesis block is created, methods relying on the blockchain state,
+    /// such as [`height`], panic. The setting has no effect on testkits restored
+    /// from an exported blockchain.
+    ///
+    /// [`create_block`]: struct.TestKit.html#method.create_block
+    /// [`height`]: struct.TestKit.html#method.height
+    pub fn without_genesis_block(mut self) -> Self {
+        self.genesis_block = false;
+        self
+    }
+
+    /// Makes the testkit shuffle the pool transactions in each block created with
+    /// [`create_block`] and similar methods taking transactions from the pool. The shuffle
+    /// is deterministic: the same seed results in the same order of the same transactions,
+    /// so a failing order can be replayed by creating the testkit with the same seed.
+    /// The seed is included into the panic messages of the testkit assertions.
+    ///
+    /// The shuffle is applied on top of the [transaction ordering]; it is not applied
+    /// to the `Custom` ordering and to the order set with
+    /// [`randomly_reorder_mempool_transactions`].
+    ///
+    /// [`create_block`]: struct.TestKit.html#method.create_block
+    /// [transaction ordering]: struct.TestKit.html#method.set_tx_ordering
+    /// [`randomly_reorder_mempool_transactions`]:
+    /// struct.TestKit.html#method.randomly_reorder_mempool_transactions
+    pub fn with_shuffled_blocks(mut self, seed: u64) -> Self {
+        self.shuffled_blocks_seed = Some(seed);
+        self
+    }
+
+    /// Enables the collection of execution metrics. If enabled, the testkit measures
+    /// the wall-clock time of the `execute` method of each committed transaction and the time
+    /// of the block patch creation; the metrics are available via
+    /// [`TestKit::execution_metrics`]. If disabled, no time measurements are performed.
+    ///
+    /// The metrics are rough, since they include the overhead of the testkit, and are intended
+    /// to spot performance regressions rather than to benchmark services.
+    ///
+    /// [`TestKit::execution_metrics`]: struct.TestKit.html#method.execution_metrics
+    pub fn with_execution_metrics(mut self) -> Self {
+        self.execution_metrics = true;
+        self
+    }
+
+    /// Enables timing of the block execution. If enabled, the testkit measures the wall-clock
+    /// time of `Blockchain::create_patch` for each created block; the timings are available
+    /// via [`TestKit::block_execution_time`] and [`TestKit::mean_block_execution_time`].
+    ///
+    /// Unlike [`with_execution_metrics`], the timing does not wrap services, so it adds
+    /// no overhead to the execution of individual transactions and is better suited
+    /// for throughput benchmarks.
+    ///
+    /// [`TestKit::block_execution_time`]: struct.TestKit.html#method.block_execution_time
+    /// [`TestKit::mean_block_execution_time`]:
+    /// struct.TestKit.html#method.mean_block_execution_time
+    /// [`with_execution_metrics`]: #method.with_execution_metrics
+    pub fn with_execution_timing(mut self) -> Self {
+        self.execution_timing = true;
+        self
+    }
+
+    /// Makes the testkit sleep for the specified number of milliseconds before processing
+    /// each event (e.g., a transaction sent to the API), emulating a slow network.
+    /// See [`TestKit::simulate_slow_network`] for details.
+    ///
+    /// [`TestKit::simulate_slow_network`]: struct.TestKit.html#method.simulate_slow_network
+    pub fn with_event_delay(mut self, delay_ms: u64) -> Self {
+        self.event_delay = Some(Duration::from_millis(delay_ms));
+        self
+    }
+
+    /// Sets the number of the latest blocks, for which the testkit retains the propose
+    /// and precommit messages it has generated. By default, the messages are retained
+    /// only for the latest block. See [`TestKit::block_messages`] for details.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `count` is zero.
+    ///
+    /// [`TestKit::block_messages`]: struct.TestKit.html#method.block_messages
+    pub fn with_retained_block_messages(mut self, count: usize) -> Self {
+        assert!(count > 0, "Messages should be retained for at least one block");
+        self.retained_block_messages = count;
+        self
+    }
+
     /// Creates the testkit.
     pub fn create(self) -> TestKit {
         if self.logger {
             exonum::helpers::init_logger().ok();
         }
         crypto::init();
-        TestKit::assemble(
-            self.services,
-            TestNetwork::with_our_role(self.our_validator_id, self.validator_count.unwrap_or(1)),
-        )
+        let services: Vec<Arc<dyn Service>> =
+            override_genesis_configs(self.services, self.genesis_service_configs)
+                .into_iter()
+                .map(Arc::from)
+                .collect();
+        let execution_log = if self.execution_metrics {
+            Some(ExecutionLog::default())
+        } else {
+            None
+        };
+        let wrapped_services =
+            wrap_services(&services, &self.service_spies, execution_log.as_ref());
+
+        if let Some(exported) = self.exported {
+            let mut testkit = TestKit::assemble(
+                wrapped_services,
+                exported.network.clone(),
+                exported.genesis.clone(),
+                true,
+                self.pool_size_tracking,
+                (self.api_sender, self.api_receiver),
+            );
+            testkit.execution_log = execution_log;
+            testkit.execution_timing = self.execution_timing;
+            testkit.block_messages_capacity = self.retained_block_messages;
+            testkit.services = services;
+            testkit.service_spies = self.service_spies;
+            testkit.set_event_delay(self.event_delay);
+            testkit.import_blockchain(exported);
+            testkit.shuffled_blocks_seed = self.shuffled_blocks_seed;
+            return testkit;
+        }
+
+        let network =
+            TestNetwork::with_our_role(self.our_validator_id, self.validator_count.unwrap_or(1));
+        let genesis = network.genesis_config();
+        let mut testkit = TestKit::assemble(
+            wrapped_services,
+            network,
+            genesis,
+            self.genesis_block,
+            self.pool_size_tracking,
+            (self.api_sender, self.api_receiver),
+        );
+        testkit.shuffled_blocks_seed = self.shuffled_blocks_seed;
+        testkit.execution_log = execution_log;
+        testkit.execution_timing = self.execution_timing;
+        testkit.block_messages_capacity = self.retained_block_messages;
+        testkit.services = services;
+        testkit.service_spies = self.service_spies;
+        testkit.set_event_delay(self.event_delay);
+        testkit
     }
 
     /// Starts a testkit web server, which listens to public and private APIs exposed by
@@ -367,15 +801,223 @@ impl TestKitBuilder {
     }
 }
 
+/// Wraps services, which configuration in the genesis block is overridden.
+fn override_genesis_configs(
+    services: Vec<Box<dyn Service>>,
+    mut configs: BTreeMap<String, serde_json::Value>,
+) -> Vec<Box<dyn Service>> {
+    let services: Vec<_> = services
+        .into_iter()
+        .map(|service| {
+            let config = configs.remove(service.service_name());
+            match config {
+                Some(config) => {
+                    Box::new(GenesisConfigOverride::new(service, config)) as Box<dyn Service>
+                }
+                None => service,
+            }
+        })
+        .collect();
+    if let Some(name) = configs.keys().next() {
+        panic!(
+            "Cannot override genesis configuration of service `{}`: no such service",
+            name
+        );
+    }
+    services
+}
+
+/// Wraps services shared among the testkit and its copies, so that each testkit records
+/// the calls of the spied services and the execution times of transactions separately.
+fn wrap_services(
+    services: &[Arc<dyn Service>],
+    spies: &BTreeMap<String, ServiceCalls>,
+    execution_log: Option<&ExecutionLog>,
+) -> Vec<Box<dyn Service>> {
+    services
+        .iter()
+        .map(|service| {
+            let service: Arc<dyn Service> = match spies.get(service.service_name()) {
+                Some(calls) => Arc::new(ServiceSpy::with_calls(
+                    Arc::clone(service),
+                    calls.clone(),
+                )),
+                None => Arc::clone(service),
+            };
+            match execution_log {
+                Some(log) => Box::new(TimedService::new(service, log.clone())) as Box<dyn Service>,
+                None => Box::new(SharedService::new(service)),
+            }
+        })
+        .collect()
+}
+
+/// Checks the transactions stored for the block against the block header.
+fn verify_block_transactions<T: AsRef<dyn Snapshot>>(
+    schema: &CoreSchema<T>,
+    block: &Block,
+) -> Result<(), ChainVerificationError> {
+    let height = block.height();
+    let tx_hashes = schema.block_transactions(height);
+    if tx_hashes.len() != u64::from(block.tx_count()) {
+        return Err(ChainVerificationError::TxCountMismatch {
+            height,
+            expected: block.tx_count(),
+            actual: tx_hashes.len(),
+        });
+    }
+    let root = tx_hashes.merkle_root();
+    if root != *block.tx_hash() {
+        return Err(ChainVerificationError::TxHashMismatch {
+            height,
+            expected: *block.tx_hash(),
+            actual: root,
+        });
+    }
+
+    let transactions = schema.transactions();
+    for tx_hash in tx_hashes.iter() {
+        let raw = transactions
+            .get(&tx_hash)
+            .ok_or_else(|| ChainVerificationError::MissingTransaction { height, tx_hash })?;
+        let actual = raw.hash();
+        if actual != tx_hash {
+            return Err(ChainVerificationError::TransactionHashMismatch {
+                height,
+                stored: tx_hash,
+                actual,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the precommits stored for the block against the validator set active at its height.
+fn verify_block_precommits<T: AsRef<dyn Snapshot>>(
+    schema: &CoreSchema<T>,
+    height: Height,
+    block_hash: &Hash,
+) -> Result<(), ChainVerificationError> {
+    let validator_keys = schema.configuration_by_height(height).validator_keys;
+    let mut signed_validators = HashSet::new();
+
+    for (index, precommit) in schema.precommits(block_hash).iter().enumerate() {
+        let validator = precommit.validator();
+        let reason = if precommit.height() != height {
+            Some(format!("precommit is for height {}", precommit.height()))
+        } else if precommit.block_hash() != block_hash {
+            Some(format!("precommit is for block {:?}", precommit.block_hash()))
+        } else {
+            match validator_keys.get(validator.0 as usize) {
+                None => Some("validator is absent in the validator set".to_owned()),
+                Some(keys) if !precommit.verify_signature(&keys.consensus_key) => {
+                    Some("signature is invalid".to_owned())
+                }
+                Some(_) => None,
+            }
+        };
+        let reason = reason.or_else(|| {
+            if signed_validators.insert(validator) {
+                None
+            } else {
+                Some("validator has already signed a precommit".to_owned())
+            }
+        });
+        if let Some(reason) = reason {
+            return Err(ChainVerificationError::InvalidPrecommit {
+                height,
+                index: index as u64,
+                validator,
+                reason,
+            });
+        }
+    }
+
+    let required = byzantine_majority_count(validator_keys.len());
+    if signed_validators.len() < required {
+        return Err(ChainVerificationError::InsufficientPrecommits {
+            height,
+            count: signed_validators.len(),
+            required,
+        });
+    }
+    Ok(())
+}
+
+/// Creates a channel for the events processed by the testkit.
+fn api_channel() -> (ApiSender, mpsc::Receiver<ExternalMessage>) {
+    let (api_sender, api_receiver) = mpsc::channel(1_000);
+    (ApiSender::new(api_sender), api_receiver)
+}
+
 /// Testkit for testing blockchain services. It offers simple network configuration emulation
 /// (with no real network setup).
 pub struct TestKit {
     blockchain: Blockchain,
-    db_handler: CheckpointDbHandler<MemoryDB>,
+    // Handler of the checkpointed database; absent for testkits created around
+    // an existing blockchain.
+    db_handler: Option<CheckpointDbHandler<MemoryDB>>,
     events_stream: Box<dyn Stream<Item = (), Error = ()> + Send + Sync>,
     network: TestNetwork,
     api_sender: ApiSender,
-    cfg_proposal: Option<ConfigurationProposalState>,
+    cfg_proposals: BTreeMap<Height, ConfigurationProposalState>,
+    // Peers added by the `PeerAdd` events, which are not yet moved into `known_peers`.
+    added_peers: Arc<Mutex<Vec<SocketAddr>>>,
+    known_peers: Vec<SocketAddr>,
+    // Delay before processing each event in the event loop, if any.
+    event_delay: Arc<Mutex<Option<Duration>>>,
+    // Pool sizes before creating blocks at the specified heights, if tracking is enabled.
+    pool_size_history: Option<Vec<(Height, usize)>>,
+    // Configurations committed by the testkit together with the blocks at the specified heights.
+    committed_configs: Vec<(Height, StoredConfiguration)>,
+    // Order of the pool transactions for the next block created with `create_block`,
+    // if it has been changed from the default one.
+    pool_order: Option<Vec<crypto::Hash>>,
+    // Ordering of the pool transactions in the blocks created with `create_block`.
+    tx_ordering: Arc<TxOrdering>,
+    // Hashes of transactions in the order of their addition to the pool. The list may contain
+    // transactions that have been removed from the pool since then.
+    pool_arrivals: Arc<Mutex<Vec<crypto::Hash>>>,
+    // Whether configuration changes are activated only with `activate_pending_configuration`.
+    manual_config_activation: bool,
+    // Whether testkit operations are printed to `stderr`.
+    trace_mode: bool,
+    // Offsets of the validator clocks applied to the times of their precommits;
+    // the flag indicates that the offset is negative.
+    clock_offsets: HashMap<ValidatorId, (Duration, bool)>,
+    // Checker of the committed transactions, if any.
+    tx_dependency_checker: Option<Arc<TxDependencyChecker>>,
+    // Transactions flagged by the dependency checker in the blocks at the specified heights.
+    flagged_txs: BTreeMap<Height, Vec<crypto::Hash>>,
+    // Genesis configuration of the blockchain, if the creation of the genesis block
+    // is deferred until the first block is created.
+    pending_genesis: Option<GenesisConfig>,
+    // Validators that neither propose blocks nor sign precommits for them.
+    offline_validators: BTreeSet<ValidatorId>,
+    // Seed of the shuffle applied to the pool transactions in each block, if any.
+    shuffled_blocks_seed: Option<u64>,
+    // Seed of the latest shuffle of transactions, reported by the assertions.
+    last_shuffle_seed: Option<u64>,
+    // Byzantine behavior of validators exhibited in the created blocks.
+    byzantine_validators: BTreeMap<ValidatorId, ByzantineBehavior>,
+    // Conflicting messages signed by Byzantine validators at the specified heights.
+    conflicting_messages: BTreeMap<Height, Vec<ConflictingMessages>>,
+    // Log of transaction execution times, if execution metrics are enabled.
+    execution_log: Option<ExecutionLog>,
+    // Metrics of the created blocks, recorded if execution metrics or timing are enabled.
+    execution_metrics: ExecutionMetrics,
+    // Whether the block execution timing is enabled.
+    execution_timing: bool,
+    // Propose and precommits generated for the latest blocks, in the ascending order of heights.
+    block_messages: VecDeque<(Height, Propose, Vec<Precommit>)>,
+    // Maximum number of blocks, for which the generated messages are retained.
+    block_messages_capacity: usize,
+    // Number of events processed by the event loop.
+    events_processed: Arc<Mutex<u64>>,
+    // Calls of the hooks of the services added with `TestKitBuilder::with_spied_service`.
+    service_spies: BTreeMap<String, ServiceCalls>,
+    // Services of the blockchain before wrapping, which are shared with the testkit copies.
+    services: Vec<Arc<dyn Service>>,
 }
 
 impl fmt::Debug for TestKit {
@@ -383,7 +1025,8 @@ impl fmt::Debug for TestKit {
         f.debug_struct("TestKit")
             .field("blockchain", &self.blockchain)
             .field("network", &self.network)
-            .field("cfg_change_proposal", &self.cfg_proposal)
+            .field("cfg_change_proposals", &self.cfg_proposals)
+            .field("known_peers", &self.known_peers)
             .finish()
     }
 }
@@ -392,15 +1035,112 @@ impl TestKit {
     /// Creates a new `TestKit` with a single validator with the given service.
     pub fn for_service<S>(service: S) -> Self
     where
-        S: Into<Box<dyn Service>>,
+        S: Service + 'static,
     {
         TestKitBuilder::validator().with_service(service).create()
     }
 
-    fn assemble(services: Vec<Box<dyn Service>>, network: TestNetwork) -> Self {
-        let api_channel = mpsc::channel(1_000);
-        let api_sender = ApiSender::new(api_channel.0.clone());
+    /// Creates a testkit around an existing blockchain, e.g., one created by the same
+    /// factory code as used in production. The testkit takes over block production
+    /// and processing of the events sent by services; the blockchain is rewired
+    /// to send events to the testkit instead of the original channel.
+    ///
+    /// If the blockchain has no genesis block, it is created from the genesis configuration
+    /// of `network`. Otherwise, the existing blockchain state is used as is, provided that
+    /// the validators of the actual configuration match the validators of `network`.
+    ///
+    /// Unlike testkits created with [`TestKitBuilder`], the testkit does not support
+    /// [`checkpoint`] and [`rollback`], since the blockchain database cannot be checkpointed.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `ServiceKeyMismatch` if the service key of the blockchain differs from
+    ///   the service key of `network.us()`.
+    /// - Returns `ValidatorsMismatch` if the blockchain is already initialized and its actual
+    ///   configuration lists other validators than `network`.
+    /// - Returns `Genesis` if the genesis block cannot be created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # extern crate futures;
+    /// # use exonum::{blockchain::Blockchain, helpers::Height, node::ApiSender};
+    /// # use exonum::storage::MemoryDB;
+    /// # use exonum_testkit::{TestKit, TestNetwork};
+    /// # fn main() {
+    /// let network = TestNetwork::new(4);
+    /// let (service_key, service_secret_key) = network.us().service_keypair();
+    /// // The blockchain is created in the same way as for a real node.
+    /// let blockchain = Blockchain::new(
+    ///     MemoryDB::new(),
+    ///     vec![],
+    ///     *service_key,
+    ///     service_secret_key.clone(),
+    ///     ApiSender::new(futures::sync::mpsc::channel(1).0),
+    /// );
+    /// let mut testkit = TestKit::from_blockchain(blockchain, network).unwrap();
+    /// testkit.create_block();
+    /// assert_eq!(testkit.height(), Height(1));
+    /// # }
+    /// ```
+    ///
+    /// [`TestKitBuilder`]: struct.TestKitBuilder.html
+    /// [`checkpoint`]: #method.checkpoint
+    /// [`rollback`]: #method.rollback
+    pub fn from_blockchain(blockchain: Blockchain, network: TestNetwork) -> Result<Self, Error> {
+        crypto::init();
+
+        let our_service_key = *network.us().service_keypair().0;
+        if *blockchain.service_public_key() != our_service_key {
+            return Err(Error::ServiceKeyMismatch {
+                expected: our_service_key,
+                actual: *blockchain.service_public_key(),
+            });
+        }
+
+        let (api_sender, api_receiver) = api_channel();
+        let mut blockchain = blockchain.clone_with_api_sender(api_sender.clone());
+
+        let has_genesis_block = !CoreSchema::new(&blockchain.snapshot())
+            .block_hashes_by_height()
+            .is_empty();
+        if has_genesis_block {
+            let actual_keys = CoreSchema::new(&blockchain.snapshot())
+                .actual_configuration()
+                .validator_keys;
+            let network_keys: Vec<_> = network
+                .validators()
+                .iter()
+                .map(TestNode::public_keys)
+                .collect();
+            if actual_keys != network_keys {
+                return Err(Error::ValidatorsMismatch);
+            }
+        } else {
+            blockchain
+                .initialize(network.genesis_config())
+                .map_err(Error::Genesis)?;
+        }
+
+        Ok(Self::wire(
+            blockchain,
+            None,
+            network,
+            false,
+            (api_sender, api_receiver),
+        ))
+    }
 
+    fn assemble(
+        services: Vec<Box<dyn Service>>,
+        network: TestNetwork,
+        genesis: GenesisConfig,
+        create_genesis_block: bool,
+        pool_size_tracking: bool,
+        api_channel: (ApiSender, mpsc::Receiver<ExternalMessage>),
+    ) -> Self {
         let db = CheckpointDb::new(MemoryDB::new());
         let db_handler = db.handler();
 
@@ -409,27 +1149,91 @@ impl TestKit {
             services,
             *network.us().service_keypair().0,
             network.us().service_keypair().1.clone(),
-            api_sender.clone(),
+            api_channel.0.clone(),
         );
 
-        let genesis = network.genesis_config();
-        blockchain.initialize(genesis.clone()).unwrap();
+        let pending_genesis = if create_genesis_block {
+            blockchain.initialize(genesis).unwrap();
+            None
+        } else {
+            Some(genesis)
+        };
+
+        let mut testkit = Self::wire(
+            blockchain,
+            Some(db_handler),
+            network,
+            pool_size_tracking,
+            api_channel,
+        );
+        testkit.pending_genesis = pending_genesis;
+        testkit
+    }
+
+    /// Creates the genesis block if its creation has been deferred with
+    /// `TestKitBuilder::without_genesis_block`.
+    fn ensure_genesis_block(&mut self) {
+        if let Some(genesis) = self.pending_genesis.take() {
+            self.trace(format_args!("creating deferred genesis block"));
+            self.blockchain
+                .initialize(genesis)
+                .unwrap_or_else(|e| panic!("{}", Error::Genesis(e)));
+        }
+    }
 
+    /// Wires the event loop processing the events from `api_channel` into the given
+    /// initialized blockchain.
+    fn wire(
+        blockchain: Blockchain,
+        db_handler: Option<CheckpointDbHandler<MemoryDB>>,
+        network: TestNetwork,
+        pool_size_tracking: bool,
+        api_channel: (ApiSender, mpsc::Receiver<ExternalMessage>),
+    ) -> Self {
+        let (api_sender, api_receiver) = api_channel;
+
+        let added_peers = Arc::new(Mutex::new(Vec::new()));
+        let pool_arrivals = Arc::new(Mutex::new(Vec::new()));
+        let event_delay = Arc::new(Mutex::new(None));
+        let events_processed = Arc::new(Mutex::new(0));
         let events_stream: Box<dyn Stream<Item = (), Error = ()> + Send + Sync> = {
             let mut blockchain = blockchain.clone();
-            Box::new(api_channel.1.and_then(move |event| {
+            let added_peers = Arc::clone(&added_peers);
+            let pool_arrivals = Arc::clone(&pool_arrivals);
+            let event_delay = Arc::clone(&event_delay);
+            let events_processed = Arc::clone(&events_processed);
+            Box::new(api_receiver.and_then(move |event| {
+                if let Some(delay) = *event_delay.lock().unwrap() {
+                    thread::sleep(delay);
+                }
+                *events_processed.lock().unwrap() += 1;
                 let mut fork = blockchain.fork();
                 {
                     let mut schema = CoreSchema::new(&mut fork);
                     match event {
                         ExternalMessage::Transaction(tx) => {
                             let hash = tx.hash();
-                            if !schema.transactions().contains(&hash) {
+                            if schema.transactions().contains(&hash) {
+                                trace!(
+                                    target: TX_LOG_TARGET,
+                                    "Rejected transaction {:?} received via API: \
+                                     already committed",
+                                    hash
+                                );
+                            } else {
                                 schema.add_transaction_into_pool(tx.raw().clone());
+                                pool_arrivals.lock().unwrap().push(hash);
+                                trace!(
+                                    target: TX_LOG_TARGET,
+                                    "Pooled transaction {:?} received via API",
+                                    hash
+                                );
                             }
                         }
-                        ExternalMessage::PeerAdd(_)
-                        | ExternalMessage::Enable(_)
+                        ExternalMessage::PeerAdd(info) => {
+                            added_peers.lock().unwrap().push(info.address);
+                        }
+                        ExternalMessage::Enable(_)
                         | ExternalMessage::Rebroadcast
                         | ExternalMessage::Shutdown => { /* Ignored */ }
                     }
@@ -445,44 +1249,457 @@ impl TestKit {
             api_sender,
             events_stream,
             network,
-            cfg_proposal: None,
+            cfg_proposals: BTreeMap::new(),
+            added_peers,
+            known_peers: Vec::new(),
+            event_delay,
+            pool_size_history: if pool_size_tracking {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            committed_configs: Vec::new(),
+            pool_order: None,
+            tx_ordering: Arc::new(TxOrdering::ByHash),
+            pool_arrivals,
+            manual_config_activation: false,
+            trace_mode: false,
+            clock_offsets: HashMap::new(),
+            tx_dependency_checker: None,
+            flagged_txs: BTreeMap::new(),
+            pending_genesis: None,
+            offline_validators: BTreeSet::new(),
+            shuffled_blocks_seed: None,
+            last_shuffle_seed: None,
+            byzantine_validators: BTreeMap::new(),
+            conflicting_messages: BTreeMap::new(),
+            execution_log: None,
+            execution_metrics: ExecutionMetrics::default(),
+            execution_timing: false,
+            block_messages: VecDeque::new(),
+            block_messages_capacity: 1,
+            events_processed,
+            service_spies: BTreeMap::new(),
+            services: Vec::new(),
         }
     }
 
+    /// Returns a sender of the events processed by the testkit, such as transactions.
+    /// Transactions sent via the returned sender are added to the pool
+    /// on the next [`poll_events`] call.
+    ///
+    /// [`poll_events`]: #method.poll_events
+    pub fn api_sender(&self) -> ApiSender {
+        self.api_sender.clone()
+    }
+
     /// Creates an instance of `TestKitApi` to test the API provided by services.
     pub fn api(&self) -> TestKitApi {
+        self.trace(format_args!("api()"));
         TestKitApi::new(self)
     }
 
-    /// Polls the *existing* events from the event loop until exhaustion. Does not wait
-    /// until new events arrive.
-    pub fn poll_events(&mut self) {
-        poll_events(&mut self.events_stream);
-    }
-
-    /// Returns a snapshot of the current blockchain state.
-    pub fn snapshot(&self) -> Box<dyn Snapshot> {
-        self.blockchain.snapshot()
+    /// Returns whether the service with the given name has public and private APIs,
+    /// respectively. An API is considered present if the service mounts at least one endpoint
+    /// on it in `Service::wire_api`.
+    ///
+    /// The service-scoped handles of `TestKitApi` (e.g., [`TestKitApi::service`])
+    /// panic with a descriptive message when used with an absent API.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service with the given name is not registered in the testkit.
+    ///
+    /// [`TestKitApi::service`]: struct.TestKitApi.html#method.service
+    pub fn service_api_surface(&self, service_name: &str) -> (bool, bool) {
+        match api::api_surfaces(&self.blockchain).get(service_name) {
+            Some(&surface) => surface,
+            None => panic!("{}", Error::UnknownService(service_name.to_owned())),
+        }
     }
 
-    /// Returns a reference to the blockchain used by the testkit.
-    pub fn blockchain(&self) -> &Blockchain {
-        &self.blockchain
+    /// Returns the identifiers of all services registered in the testkit, in the order
+    /// in which the services were added to [`TestKitBuilder`].
+    ///
+    /// For a testkit created with [`from_blockchain`], the order of registration is unknown,
+    /// so the identifiers are sorted ascending, i.e., in the order in which the blockchain
+    /// invokes the service hooks (such as `before_commit` and `after_commit`).
+    ///
+    /// [`TestKitBuilder`]: struct.TestKitBuilder.html
+    /// [`from_blockchain`]: #method.from_blockchain
+    pub fn all_service_ids(&self) -> Vec<u16> {
+        if self.services.is_empty() {
+            self.blockchain
+                .service_map()
+                .keys()
+                .map(|id| id as u16)
+                .collect()
+        } else {
+            self.services
+                .iter()
+                .map(|service| service.service_id())
+                .collect()
+        }
     }
 
-    /// Returns a blockchain instance for low level manipulations with storage.
-    pub fn blockchain_mut(&mut self) -> &mut Blockchain {
-        &mut self.blockchain
+    /// Enables or disables the trace mode. In the trace mode, the testkit prints a line
+    /// to `stderr` for each created block, committed configuration change, probe,
+    /// event polling and API instantiation, together with their arguments and results.
+    /// The trace mode is disabled by default.
+    ///
+    /// Unlike logging, the trace mode does not require to set up a logger, and shows
+    /// only the operations of the testkit in question.
+    pub fn trace_mode(&mut self, enabled: bool) {
+        self.trace_mode = enabled;
     }
 
-    /// Sets a checkpoint for a future [`rollback`](#method.rollback).
-    pub fn checkpoint(&mut self) {
-        self.db_handler.checkpoint()
+    fn trace(&self, args: fmt::Arguments) {
+        if self.trace_mode {
+            eprintln!("[testkit] {}", args);
+        }
     }
 
-    /// Rolls the blockchain back to the latest [`checkpoint`](#method.checkpoint).
+    /// Makes the testkit sleep for the specified number of milliseconds before processing
+    /// each event, such as a transaction sent to the API or a peer added via `ApiSender`.
+    /// The delay emulates a slow network and allows to test the behavior of services
+    /// sensitive to timeouts, e.g., their retry logic. The delay applies to the events
+    /// processed by [`poll_events`] and [`poll_events_for`], including those processed
+    /// implicitly when a block is created.
     ///
-    /// # Examples
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_event_delay(100).create();
+    /// // Events are processed with a shorter delay.
+    /// testkit.simulate_slow_network(20);
+    /// // ...and without a delay.
+    /// testkit.clear_event_delay();
+    /// # }
+    /// ```
+    ///
+    /// [`poll_events`]: #method.poll_events
+    /// [`poll_events_for`]: #method.poll_events_for
+    pub fn simulate_slow_network(&mut self, delay_ms: u64) {
+        self.set_event_delay(Some(Duration::from_millis(delay_ms)));
+    }
+
+    /// Removes the delay of event processing set with [`simulate_slow_network`]
+    /// or `TestKitBuilder::with_event_delay`.
+    ///
+    /// [`simulate_slow_network`]: #method.simulate_slow_network
+    pub fn clear_event_delay(&mut self) {
+        self.set_event_delay(None);
+    }
+
+    fn set_event_delay(&self, delay: Option<Duration>) {
+        *self.event_delay.lock().unwrap() = delay;
+    }
+
+    /// Polls the *existing* events from the event loop until exhaustion. Does not wait
+    /// until new events arrive.
+    pub fn poll_events(&mut self) {
+        self.trace(format_args!("poll_events()"));
+        poll_events(&mut self.events_stream);
+        self.update_known_peers();
+    }
+
+    /// Polls the events sent to the testkit during the given time interval, processing
+    /// them as they arrive. Returns the number of processed events.
+    ///
+    /// Unlike [`poll_events`], which processes only the events already queued, this method
+    /// waits for the events sent later, e.g., by threads spawned by services. The current
+    /// thread is parked while there are no events to process. The method always waits
+    /// for the whole `timeout`.
+    ///
+    /// [`poll_events`]: #method.poll_events
+    pub fn poll_events_for(&mut self, timeout: Duration) -> usize {
+        let count = poll_events_for(&mut self.events_stream, timeout);
+        self.update_known_peers();
+        self.trace(format_args!("poll_events_for({:?}) -> {}", timeout, count));
+        count
+    }
+
+    /// Returns the total number of events processed by the event loop of the testkit, such as
+    /// transactions and `PeerAdd` messages received via the API sender. Events are processed
+    /// by [`poll_events`], which is also invoked by the testkit itself before creating blocks.
+    ///
+    /// Besides the events, the counter includes polls of the event loop performed right after
+    /// committing blocks created with [`create_block`] and similar methods; such a poll
+    /// is counted once per block, even if no events are pending.
+    ///
+    /// The counter is not affected by [`rollback`].
+    ///
+    /// [`poll_events`]: #method.poll_events
+    /// [`create_block`]: #method.create_block
+    /// [`rollback`]: #method.rollback
+    pub fn events_processed_count(&self) -> u64 {
+        *self.events_processed.lock().unwrap()
+    }
+
+    fn update_known_peers(&mut self) {
+        let mut added_peers = self.added_peers.lock().unwrap();
+        for address in added_peers.drain(..) {
+            if !self.known_peers.contains(&address) {
+                self.known_peers.push(address);
+            }
+        }
+    }
+
+    /// Returns addresses of the peers added with `PeerAdd` messages, in the order
+    /// of their addition. Messages are taken into account once they are processed
+    /// by [`poll_events`](#method.poll_events).
+    pub fn known_peers(&self) -> &[SocketAddr] {
+        &self.known_peers
+    }
+
+    /// Sends a `PeerAdd` message with the given peer address to the testkit
+    /// and processes it, in the same way as if the message was sent by a service.
+    /// The public key of the peer is generated randomly.
+    pub fn inject_peer_add_message(&mut self, address: SocketAddr) {
+        let info = ConnectInfo {
+            address,
+            public_key: crypto::gen_keypair().0,
+        };
+        self.api_sender
+            .peer_add(info)
+            .expect("Cannot send `PeerAdd` message");
+        self.poll_events();
+    }
+
+    /// Returns a snapshot of the current blockchain state.
+    pub fn snapshot(&self) -> Box<dyn Snapshot> {
+        self.blockchain.snapshot()
+    }
+
+    /// Returns a reference to the blockchain used by the testkit.
+    pub fn blockchain(&self) -> &Blockchain {
+        &self.blockchain
+    }
+
+    /// Returns a blockchain instance for low level manipulations with storage.
+    pub fn blockchain_mut(&mut self) -> &mut Blockchain {
+        &mut self.blockchain
+    }
+
+    /// Returns statistics about the storage occupied by the index with the given name
+    /// in the current blockchain state.
+    ///
+    /// The statistics are computed over raw storage entries of the index, so they include
+    /// auxiliary entries (e.g., the length of a list or the branch nodes of Merkelized
+    /// indexes) and, for index families, the entries of all indexes in the family.
+    /// An index that does not exist in the storage yields zero statistics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::storage::MapIndex;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// let mut fork = testkit.blockchain_mut().fork();
+    /// {
+    ///     let mut index: MapIndex<_, u8, String> = MapIndex::new("test.index", &mut fork);
+    ///     index.put(&1, "one".to_owned());
+    ///     index.put(&2, "two".to_owned());
+    /// }
+    /// testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+    ///
+    /// let stats = testkit.storage_index_stats("test.index");
+    /// assert_eq!(stats.entry_count, 2);
+    /// assert!(stats.byte_estimate > 0);
+    /// # }
+    /// ```
+    pub fn storage_index_stats(&self, index_name: &str) -> IndexStats {
+        let snapshot = self.snapshot();
+        let mut iter = snapshot.iter(index_name, &[]);
+        let mut stats = IndexStats::default();
+        while let Some((key, value)) = iter.next() {
+            stats.entry_count += 1;
+            stats.byte_estimate += (key.len() + value.len()) as u64;
+        }
+        stats
+    }
+
+    /// Asserts that the ordinary index with the given full name has no elements
+    /// in the current blockchain state. An index that does not exist in the storage
+    /// is considered empty.
+    ///
+    /// Unlike [`storage_index_stats`], the method counts only the elements of the index,
+    /// skipping auxiliary storage entries, so the index type need not be known. Elements are
+    /// the items of lists, the entries of maps and the values of sets and entries.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the index has elements. The panic message contains the hex
+    ///   representation of the raw keys of several elements.
+    /// - Panics if the name corresponds to an index family.
+    ///
+    /// [`storage_index_stats`]: #method.storage_index_stats
+    pub fn assert_index_empty(&self, index_name: &str) {
+        let elements = IndexElements::load(&*self.snapshot(), index_name);
+        assert!(
+            elements.count == 0,
+            "Index `{}` is expected to be empty, but it contains {} element(s) with keys: {}{}",
+            index_name,
+            elements.count,
+            elements.describe_sample(),
+            self.shuffle_note()
+        );
+    }
+
+    /// Asserts that the ordinary index with the given full name has the expected number
+    /// of elements in the current blockchain state. See [`assert_index_empty`]
+    /// for the definition of elements.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the number of elements differs from the expected one. The panic message
+    ///   contains the hex representation of the raw keys of several elements.
+    /// - Panics if the name corresponds to an index family.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::storage::ProofListIndex;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// let mut fork = testkit.blockchain_mut().fork();
+    /// {
+    ///     let mut index = ProofListIndex::new("test.list", &mut fork);
+    ///     index.extend(vec![1_u64, 2, 3]);
+    /// }
+    /// testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+    ///
+    /// // Branch nodes and the length of the list are not counted.
+    /// testkit.assert_index_len("test.list", 3);
+    /// testkit.assert_index_empty("test.other_list");
+    /// # }
+    /// ```
+    ///
+    /// [`assert_index_empty`]: #method.assert_index_empty
+    pub fn assert_index_len(&self, index_name: &str, expected_len: u64) {
+        let elements = IndexElements::load(&*self.snapshot(), index_name);
+        assert!(
+            elements.count == expected_len,
+            "Index `{}` is expected to contain {} element(s), but it contains {} with keys: {}{}",
+            index_name,
+            expected_len,
+            elements.count,
+            elements.describe_sample(),
+            self.shuffle_note()
+        );
+    }
+
+    /// Exports the storage state of the service with the given name as a JSON value,
+    /// which can be compared with other snapshots (e.g., using [`JsonDiff`]) or stored
+    /// for migration and upgrade tests.
+    ///
+    /// If the service implements `Service::export_state`, the state returned by the service
+    /// is used. Otherwise, the state includes all indexes whose names start with
+    /// `"{service_name}."`. The returned value is an object mapping the names of such indexes
+    /// to objects, which in turn map the hex representation of raw storage keys of index
+    /// elements to the hex representation of raw values. Auxiliary entries of indexes
+    /// (e.g., the length of a list or the branch nodes of a `ProofMapIndex`) are skipped,
+    /// so the state does not depend on the way the index elements are hashed.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service is not registered in the blockchain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::blockchain::{Service, Transaction};
+    /// # use exonum::crypto::Hash;
+    /// # use exonum::encoding;
+    /// # use exonum::messages::RawTransaction;
+    /// # use exonum::storage::{MapIndex, Snapshot};
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # use exonum_testkit::compare::JsonDiff;
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// #     fn service_id(&self) -> u16 { 1 }
+    /// #     fn service_name(&self) -> &str { "my_service" }
+    /// #     fn state_hash(&self, _: &Snapshot) -> Vec<Hash> { vec![] }
+    /// #     fn tx_from_raw(
+    /// #         &self,
+    /// #         _: RawTransaction,
+    /// #     ) -> Result<Box<Transaction>, encoding::Error> {
+    /// #         unimplemented!()
+    /// #     }
+    /// # }
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_service(MyService).create();
+    /// let before = testkit.export_service_state("my_service");
+    ///
+    /// let mut fork = testkit.blockchain_mut().fork();
+    /// {
+    ///     let mut index: MapIndex<_, u8, u8> = MapIndex::new("my_service.values", &mut fork);
+    ///     index.put(&1, 2);
+    /// }
+    /// testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+    ///
+    /// let after = testkit.export_service_state("my_service");
+    /// assert_eq!(after["my_service.values"]["01"], "02");
+    /// assert!(!JsonDiff::new(&before, &after).is_empty());
+    /// # }
+    /// ```
+    ///
+    /// [`JsonDiff`]: compare/struct.JsonDiff.html
+    pub fn export_service_state(&self, service_name: &str) -> serde_json::Value {
+        let snapshot = self.snapshot();
+        let service = self
+            .blockchain
+            .service_map()
+            .values()
+            .find(|service| service.service_name() == service_name)
+            .unwrap_or_else(|| panic!("{}", Error::UnknownService(service_name.to_owned())));
+        if let Some(state) = service.export_state(&*snapshot) {
+            return state;
+        }
+
+        let prefix = format!("{}.", service_name);
+        let state = index_names(&*snapshot, &prefix)
+            .into_iter()
+            .map(|name| {
+                let entries = export_entries(&*snapshot, &name);
+                (name, entries)
+            })
+            .collect();
+        serde_json::Value::Object(state)
+    }
+
+    /// Sets a checkpoint for a future [`rollback`](#method.rollback).
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the testkit was created with [`from_blockchain`].
+    ///
+    /// [`from_blockchain`]: #method.from_blockchain
+    pub fn checkpoint(&mut self) {
+        self.checkpoint_db_handler().checkpoint()
+    }
+
+    fn checkpoint_db_handler(&self) -> &CheckpointDbHandler<MemoryDB> {
+        self.db_handler.as_ref().expect(
+            "Checkpoints are not supported for testkits created with `TestKit::from_blockchain`",
+        )
+    }
+
+    /// Rolls the blockchain back to the latest [`checkpoint`](#method.checkpoint).
+    ///
+    /// # Examples
     ///
     /// Rollbacks are useful in testing alternative scenarios (e.g., transactions executed
     /// in different order and/or in different blocks) that require an expensive setup:
@@ -553,14 +1770,320 @@ impl TestKit {
     /// # }
     /// ```
     pub fn rollback(&mut self) {
-        self.db_handler.rollback()
+        self.checkpoint_db_handler().rollback();
+        // Forget the pool sizes recorded for the blocks that have been rolled back.
+        let height = self.height();
+        if let Some(ref mut history) = self.pool_size_history {
+            history.retain(|&(block_height, _)| block_height <= height);
+        }
+        self.committed_configs.retain(|&(block_height, _)| block_height <= height);
+        self.flagged_txs.split_off(&height.next());
+        self.conflicting_messages.split_off(&height.next());
+        self.execution_metrics.truncate(height);
+        self.block_messages.retain(|&(block_height, ..)| block_height <= height);
+        for calls in self.service_spies.values() {
+            calls.truncate(height);
+        }
+    }
+
+    /// Creates an independent copy of the testkit, e.g., to run several test cases starting
+    /// from the same expensive setup. The copy has a deep copy of the blockchain storage
+    /// (including the checkpoints set with [`checkpoint`]), the same emulated network,
+    /// scheduled configuration changes and settings, and its own event loop; thus, the blocks
+    /// created by the copy and the transactions sent to its API do not affect this testkit,
+    /// and vice versa.
+    ///
+    /// Service instances are shared between the testkit and its copy. In particular,
+    /// services created with [`TestKitBuilder::with_service_fn`] send events to this testkit
+    /// rather than to the copy. Events sent to this testkit, but not yet processed
+    /// (see [`poll_events`]), are not copied. The execution metrics and the calls
+    /// of the spied services (see [`service_calls`]) are copied as well, and are recorded
+    /// separately by the testkit and its copy afterwards.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `Unsupported` error if the testkit is created with [`from_blockchain`],
+    ///   since its storage cannot be copied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// testkit.create_blocks_until(Height(5));
+    ///
+    /// let mut copy = testkit.fork_testkit().unwrap();
+    /// copy.create_blocks_until(Height(10));
+    /// assert_eq!(copy.height(), Height(10));
+    /// assert_eq!(testkit.height(), Height(5));
+    /// # }
+    /// ```
+    ///
+    /// [`checkpoint`]: #method.checkpoint
+    /// [`TestKitBuilder::with_service_fn`]:
+    /// struct.TestKitBuilder.html#method.with_service_fn
+    /// [`poll_events`]: #method.poll_events
+    /// [`service_calls`]: #method.service_calls
+    /// [`from_blockchain`]: #method.from_blockchain
+    pub fn fork_testkit(&self) -> Result<TestKit, Error> {
+        let db = self.db_handler
+            .as_ref()
+            .ok_or_else(|| {
+                Error::Unsupported(
+                    "testkits created with `TestKit::from_blockchain` cannot be forked".to_owned(),
+                )
+            })?
+            .copy_db();
+        let db_handler = db.handler();
+        let (api_sender, api_receiver) = api_channel();
+        let service_spies: BTreeMap<_, _> = self.service_spies
+            .iter()
+            .map(|(name, calls)| (name.clone(), calls.deep_clone()))
+            .collect();
+        let execution_log = self.execution_log.as_ref().map(ExecutionLog::deep_clone);
+        let blockchain = Blockchain::new(
+            db,
+            wrap_services(&self.services, &service_spies, execution_log.as_ref()),
+            *self.network.us().service_keypair().0,
+            self.network.us().service_keypair().1.clone(),
+            api_sender.clone(),
+        );
+        let testkit = Self::wire(
+            blockchain,
+            Some(db_handler),
+            self.network.clone(),
+            false,
+            (api_sender, api_receiver),
+        );
+        testkit
+            .added_peers
+            .lock()
+            .unwrap()
+            .extend(self.added_peers.lock().unwrap().iter().cloned());
+        testkit
+            .pool_arrivals
+            .lock()
+            .unwrap()
+            .extend(self.pool_arrivals.lock().unwrap().iter().cloned());
+        testkit.set_event_delay(*self.event_delay.lock().unwrap());
+
+        Ok(TestKit {
+            cfg_proposals: self.cfg_proposals.clone(),
+            known_peers: self.known_peers.clone(),
+            pool_size_history: self.pool_size_history.clone(),
+            committed_configs: self.committed_configs.clone(),
+            pool_order: self.pool_order.clone(),
+            tx_ordering: Arc::clone(&self.tx_ordering),
+            manual_config_activation: self.manual_config_activation,
+            trace_mode: self.trace_mode,
+            clock_offsets: self.clock_offsets.clone(),
+            tx_dependency_checker: self.tx_dependency_checker.clone(),
+            flagged_txs: self.flagged_txs.clone(),
+            pending_genesis: self.pending_genesis.clone(),
+            offline_validators: self.offline_validators.clone(),
+            shuffled_blocks_seed: self.shuffled_blocks_seed,
+            last_shuffle_seed: self.last_shuffle_seed,
+            byzantine_validators: self.byzantine_validators.clone(),
+            conflicting_messages: self.conflicting_messages.clone(),
+            execution_log,
+            execution_metrics: self.execution_metrics.clone(),
+            execution_timing: self.execution_timing,
+            block_messages: self.block_messages.clone(),
+            block_messages_capacity: self.block_messages_capacity,
+            service_spies,
+            services: self.services.clone(),
+            ..testkit
+        })
+    }
+
+    /// Exports the blockchain to the given writer, so that it can be restored with
+    /// [`TestKitBuilder::from_exported_blockchain`]. The exported blockchain
+    /// is a JSON document containing all blocks together with their transactions and
+    /// precommits, the test network, the transaction pool and the configuration proposals
+    /// which have not been activated yet.
+    ///
+    /// Configuration changes are exported only if they have been committed by this testkit,
+    /// so blockchains of testkits created with [`from_blockchain`] may be impossible
+    /// to restore.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `Export` error if the blockchain cannot be serialized or written.
+    ///
+    /// [`TestKitBuilder::from_exported_blockchain`]:
+    /// struct.TestKitBuilder.html#method.from_exported_blockchain
+    /// [`from_blockchain`]: #method.from_blockchain
+    pub fn export_blockchain<W: Write>(&mut self, writer: W) -> Result<(), Error> {
+        self.poll_events();
+
+        let schema = self.core_schema();
+        let genesis_config = schema.configuration_by_height(Height(0));
+        let genesis = GenesisConfig::new_with_consensus(
+            genesis_config.consensus,
+            genesis_config.validator_keys.into_iter(),
+        );
+        let transactions = schema.transactions();
+        let blocks = (1..=self.height().0)
+            .map(Height)
+            .map(|height| {
+                let hash = schema.block_hash_by_height(height).unwrap();
+                let block = schema.blocks().get(&hash).unwrap();
+                ExportedBlock {
+                    hash,
+                    proposer_id: block.proposer_id(),
+                    transactions: schema
+                        .block_transactions(height)
+                        .iter()
+                        .map(|tx_hash| encode_message(&transactions.get(&tx_hash).unwrap()))
+                        .collect(),
+                    precommits: schema
+                        .precommits(&hash)
+                        .iter()
+                        .map(|precommit| encode_message(&precommit))
+                        .collect(),
+                    configurations: self.committed_configs
+                        .iter()
+                        .filter(|&&(block_height, _)| block_height == height)
+                        .map(|&(_, ref config)| config.clone())
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let exported = ExportedBlockchain {
+            genesis,
+            genesis_hash: schema.block_hash_by_height(Height(0)).unwrap(),
+            network: self.network.clone(),
+            blocks,
+            configuration_proposals: self.cfg_proposals
+                .iter()
+                .map(|(&height, proposal)| (height, proposal.clone()))
+                .collect(),
+            pool: schema
+                .transactions_pool()
+                .iter()
+                .map(|tx_hash| encode_message(&transactions.get(&tx_hash).unwrap()))
+                .collect(),
+        };
+        serde_json::to_writer(writer, &exported).map_err(Error::Export)
+    }
+
+    /// Replays the blocks of the exported blockchain on top of the genesis block
+    /// and restores the remaining testkit state.
+    fn import_blockchain(&mut self, exported: ExportedBlockchain) {
+        assert_eq!(
+            self.last_block_hash(),
+            exported.genesis_hash,
+            "Genesis block differs from the exported one; make sure that the testkit \
+             has the same services as the one which has exported the blockchain"
+        );
+
+        for block in exported.blocks {
+            let height = self.height().next();
+            let txs: Vec<_> = block
+                .transactions
+                .iter()
+                .map(|tx| self.decode_transaction(tx))
+                .collect();
+            let tx_hashes = self.add_transactions_into_pool(txs)
+                .unwrap_or_else(|e| panic!("Cannot import block at height {}: {}", height, e));
+
+            let (block_hash, patch) =
+                self.blockchain
+                    .create_patch(block.proposer_id, height, &tx_hashes);
+            assert_eq!(
+                block_hash, block.hash,
+                "Block at height {} differs from the exported one; make sure that the testkit \
+                 has the same services as the one which has exported the blockchain",
+                height
+            );
+            let patch = if block.configurations.is_empty() {
+                patch
+            } else {
+                let mut fork = self.blockchain.fork();
+                for config in &block.configurations {
+                    CoreSchema::new(&mut fork).commit_configuration(config.clone());
+                    self.committed_configs.push((height, config.clone()));
+                }
+                fork.merge(patch);
+                fork.into_patch()
+            };
+
+            let precommits: Vec<_> = block
+                .precommits
+                .iter()
+                .map(|precommit| {
+                    Precommit::from_raw(decode_message(precommit)).unwrap_or_else(|e| {
+                        panic!("Cannot decode precommit at height {}: {}", height, e)
+                    })
+                })
+                .collect();
+            self.blockchain
+                .commit(&patch, block_hash, precommits.iter())
+                .unwrap_or_else(|error| {
+                    let error = Error::Commit {
+                        height,
+                        proposer: block.proposer_id,
+                        tx_hashes,
+                        error,
+                    };
+                    panic!("Cannot import block at height {}: {}", height, error)
+                });
+        }
+
+        let pool: Vec<_> = exported
+            .pool
+            .iter()
+            .map(|tx| self.decode_transaction(tx))
+            .collect();
+        self.add_transactions_into_pool(pool)
+            .unwrap_or_else(|e| panic!("Cannot import transaction pool: {}", e));
+        self.cfg_proposals = exported.configuration_proposals.into_iter().collect();
+        self.poll_events();
+    }
+
+    /// Decodes a hex-encoded transaction with the services of the testkit.
+    fn decode_transaction(&self, tx: &str) -> Box<dyn Transaction> {
+        self.blockchain
+            .tx_from_raw(decode_message(tx))
+            .unwrap_or_else(|e| panic!("Cannot decode exported transaction {}: {}", tx, e))
     }
 
     /// Executes a list of transactions given the current state of the blockchain, but does not
     /// commit execution results to the blockchain. The execution result is the same
     /// as if transactions were included into a new block; for example,
     /// transactions included into one of previous blocks do not lead to any state changes.
+    ///
+    /// The transactions observe the same blockchain height during execution as if they were
+    /// included into a new block; see [`execution_height`] for details.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the block with transactions cannot be created; see [`try_probe_all`]
+    ///   for details.
+    ///
+    /// [`try_probe_all`]: #method.try_probe_all
+    /// [`execution_height`]: #method.execution_height
     pub fn probe_all<I>(&mut self, transactions: I) -> Box<dyn Snapshot>
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        let height = self.height();
+        self.try_probe_all(transactions).unwrap_or_else(|e| {
+            panic!("Cannot probe transactions at height {}: {}", height, e)
+        })
+    }
+
+    /// Executes a list of transactions given the current state of the blockchain, but does not
+    /// commit execution results to the blockchain. Unlike [`probe_all`], returns an error
+    /// if the block with transactions cannot be created.
+    ///
+    /// [`probe_all`]: #method.probe_all
+    pub fn try_probe_all<I>(&mut self, transactions: I) -> Result<Box<dyn Snapshot>, Error>
     where
         I: IntoIterator<Item = Box<dyn Transaction>>,
     {
@@ -568,329 +2091,2949 @@ impl TestKit {
         // Filter out already committed transactions; otherwise,
         // `create_block_with_transactions()` will panic.
         let schema = CoreSchema::new(self.snapshot());
-        let uncommitted_txs = transactions.into_iter().filter(|tx| {
-            !schema.transactions().contains(&tx.hash())
-                || schema.transactions_pool().contains(&tx.hash())
-        });
+        let uncommitted_txs: Vec<_> = transactions
+            .into_iter()
+            .filter(|tx| {
+                !schema.transactions().contains(&tx.hash())
+                    || schema.transactions_pool().contains(&tx.hash())
+            })
+            .collect();
+        let tx_count = uncommitted_txs.len();
+        let tx_hashes: Vec<_> = uncommitted_txs.iter().map(|tx| tx.hash()).collect();
+        self.trace(format_args!("probe_all({:?})", tx_hashes));
 
         self.checkpoint();
-        self.create_block_with_transactions(uncommitted_txs);
-        let snapshot = self.snapshot();
+        let result = self.try_create_probe_block(uncommitted_txs)
+            .map(|_| self.snapshot());
         self.rollback();
-        snapshot
+        debug!(
+            target: PROBE_LOG_TARGET,
+            "Probed {} transactions at height {}, success: {}",
+            tx_count,
+            self.height().next(),
+            result.is_ok()
+        );
+        result
     }
 
     /// Executes a transaction given the current state of the blockchain but does not
     /// commit execution results to the blockchain. The execution result is the same
     /// as if a transaction was included into a new block; for example,
     /// a transaction included into one of previous blocks does not lead to any state changes.
+    ///
+    /// The transaction observes the same blockchain height during execution as if it was
+    /// included into a new block; see [`execution_height`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::doctest_support::{DoctestSchema, DoctestService, TxAdd};
+    /// use exonum::crypto;
+    /// use exonum_testkit::TestKit;
+    ///
+    /// # fn main() {
+    /// let mut testkit = TestKit::for_service(DoctestService);
+    /// let (pubkey, key) = crypto::gen_keypair();
+    ///
+    /// let snapshot = testkit.probe(TxAdd::new(&pubkey, 5, &key));
+    /// assert_eq!(DoctestSchema::new(&snapshot).sum(), 5);
+    /// // The probed transaction does not change the blockchain state.
+    /// assert_eq!(DoctestSchema::new(&testkit.snapshot()).sum(), 0);
+    /// # }
+    /// ```
+    ///
+    /// [`execution_height`]: #method.execution_height
     pub fn probe<T: Transaction>(&mut self, transaction: T) -> Box<dyn Snapshot> {
         self.probe_all(vec![Box::new(transaction) as Box<dyn Transaction>])
     }
 
-    fn do_create_block(&mut self, tx_hashes: &[crypto::Hash]) -> BlockWithTransactions {
-        let new_block_height = self.height().next();
-        let last_hash = self.last_block_hash();
+    /// Executes a list of transactions as if they were included into a new block and reports
+    /// the execution outcome of each transaction. Similarly to [`probe_all`], the block
+    /// is not committed to the blockchain.
+    ///
+    /// Transactions that are already committed, do not pass verification or are duplicated
+    /// in the list are reported as skipped.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `Unsupported` error if the testkit is created with [`from_blockchain`],
+    ///   since the block cannot be rolled back.
+    /// - Returns an error if the block with transactions cannot be created; see
+    ///   [`try_probe_all`] for details. The blockchain state is left intact in this case.
+    ///
+    /// [`probe_all`]: #method.probe_all
+    /// [`from_blockchain`]: #method.from_blockchain
+    /// [`try_probe_all`]: #method.try_probe_all
+    pub fn dry_run_block<I>(&mut self, transactions: I) -> Result<BlockReport, Error>
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        if self.db_handler.is_none() {
+            return Err(Error::Unsupported(
+                "testkits created with `TestKit::from_blockchain` cannot dry-run blocks"
+                    .to_owned(),
+            ));
+        }
+        self.poll_events();
 
-        let config_patch = self.update_configuration(new_block_height);
-        let (block_hash, patch) = {
-            let validator_id = self.leader().validator_id().unwrap();
-            self.blockchain
-                .create_patch(validator_id, new_block_height, tx_hashes)
+        let mut report = BlockReport::default();
+        let included: Vec<_> = {
+            let schema = self.core_schema();
+            let mut seen = HashSet::new();
+            transactions
+                .into_iter()
+                .filter(|tx| {
+                    let tx_hash = tx.hash();
+                    let committed = schema.transactions().contains(&tx_hash)
+                        && !schema.transactions_pool().contains(&tx_hash);
+                    if committed || !tx.verify() || !seen.insert(tx_hash) {
+                        report.skipped.push(tx_hash);
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .collect()
         };
+        let tx_hashes: Vec<_> = included.iter().map(|tx| tx.hash()).collect();
 
-        let patch = if let Some(config_patch) = config_patch {
-            let mut fork = self.blockchain.fork();
-            fork.merge(config_patch);
-            fork.merge(patch);
-            fork.into_patch()
+        self.checkpoint();
+        if let Err(e) = self.try_create_probe_block(included) {
+            self.rollback();
+            return Err(e);
+        }
+        {
+            let schema = self.core_schema();
+            let results = schema.transaction_results();
+            for tx_hash in tx_hashes {
+                match results.get(&tx_hash) {
+                    Some(Ok(())) => report.executed.push(tx_hash),
+                    Some(Err(e)) => report.errored.push((tx_hash, e)),
+                    None => unreachable!("Transaction {:?} was not executed", tx_hash),
+                }
+            }
+        }
+        self.rollback();
+        Ok(report)
+    }
+
+    /// Returns the core schema for the current state of the blockchain.
+    ///
+    /// Each snapshot of the testkit storage is relatively expensive to create,
+    /// so methods should reuse the returned schema for the duration of an operation
+    /// instead of calling this method repeatedly.
+    fn core_schema(&self) -> CoreSchema<Box<dyn Snapshot>> {
+        CoreSchema::new(self.snapshot())
+    }
+
+    /// Creates a block with the given transactions and parameters on top of the blockchain
+    /// state represented by `schema`.
+    fn do_create_block(
+        &mut self,
+        schema: &CoreSchema<Box<dyn Snapshot>>,
+        tx_hashes: &[crypto::Hash],
+        params: BlockParams,
+    ) -> Result<BlockWithTransactions, Error> {
+        self.do_create_block_with_callback(
+            schema,
+            tx_hashes,
+            params,
+            None::<fn(Block, &dyn Snapshot)>,
+        )
+    }
+
+    /// Same as `do_create_block`, but additionally calls `on_commit` with the committed block
+    /// and the blockchain snapshot right after the commit, before the events are polled.
+    fn do_create_block_with_callback<F>(
+        &mut self,
+        schema: &CoreSchema<Box<dyn Snapshot>>,
+        tx_hashes: &[crypto::Hash],
+        params: BlockParams,
+        on_commit: Option<F>,
+    ) -> Result<BlockWithTransactions, Error>
+    where
+        F: FnOnce(Block, &dyn Snapshot),
+    {
+        let new_block_height = schema.height().next();
+        let last_hash = schema
+            .block_hashes_by_height()
+            .last()
+            .expect("Genesis block is not created");
+        let pool_size = if self.pool_size_history.is_some() {
+            Some(schema.transactions_pool().iter().count())
         } else {
-            patch
+            None
         };
+        // Pool transactions not included into the block, which must remain in the pool.
+        let skipped_txs: Vec<_> = if cfg!(debug_assertions) {
+            let included: HashSet<_> = tx_hashes.iter().collect();
+            schema
+                .transactions_pool()
+                .iter()
+                .filter(|hash| !included.contains(hash))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let block_hash = self.commit_block(new_block_height, &last_hash, tx_hashes, params)?;
+        if let (Some(history), Some(pool_size)) = (self.pool_size_history.as_mut(), pool_size) {
+            history.push((new_block_height, pool_size));
+        }
+        if !skipped_txs.is_empty() {
+            let snapshot = self.snapshot();
+            let schema = CoreSchema::new(&snapshot);
+            let pool = schema.transactions_pool();
+            let locations = schema.transactions_locations();
+            debug_assert!(
+                skipped_txs
+                    .iter()
+                    .all(|hash| pool.contains(hash) && !locations.contains(hash)),
+                "Transactions not included into the block at height {} are removed from the pool",
+                new_block_height
+            );
+        }
 
-        let propose = self.leader()
-            .create_propose(new_block_height, &last_hash, tx_hashes);
-        let precommits: Vec<_> = self.network()
-            .validators()
-            .iter()
-            .map(|v| v.create_precommit(&propose, &block_hash))
-            .collect();
-
-        self.blockchain
-            .commit(&patch, block_hash, precommits.iter())
-            .unwrap();
+        if let Some(on_commit) = on_commit {
+            let snapshot = self.snapshot();
+            let block = CoreSchema::new(&snapshot)
+                .blocks()
+                .get(&block_hash)
+                .expect("Committed block is not stored in the blockchain");
+            on_commit(block, &*snapshot);
+        }
 
+        *self.events_processed.lock().unwrap() += 1;
         self.poll_events();
 
-        BlockchainExplorer::new(&self.blockchain)
-            .block_with_txs(self.height())
-            .unwrap()
+        Ok(BlockchainExplorer::new(&self.blockchain)
+            .block_with_txs(new_block_height)
+            .unwrap())
     }
 
-    /// Update test network configuration if such an update has been scheduled
-    /// with `commit_configuration_change`.
-    fn update_configuration(&mut self, new_block_height: Height) -> Option<Patch> {
-        use ConfigurationProposalState::*;
+    /// Creates and commits a block at the given height on top of the block with `last_hash`,
+    /// returning the hash of the new block. Unlike `do_create_block`, this method
+    /// neither polls events nor loads the created block back from the storage.
+    fn commit_block(
+        &mut self,
+        new_block_height: Height,
+        last_hash: &crypto::Hash,
+        tx_hashes: &[crypto::Hash],
+        params: BlockParams,
+    ) -> Result<crypto::Hash, Error> {
+        let validator_id = match params.custom_messages {
+            Some((propose, _)) => propose.validator(),
+            None => self.check_precommits(params)?,
+        };
+        let block_patch = self.create_block_patch(validator_id, new_block_height, tx_hashes);
+        let block_hash = block_patch.block_hash;
 
-        let actual_from = new_block_height.next();
-        if let Some(cfg_proposal) = self.cfg_proposal.take() {
-            match cfg_proposal {
-                Uncommitted(cfg_proposal) => {
-                    // Commit configuration proposal
-                    let stored = cfg_proposal.stored_configuration().clone();
+        let (propose, precommits, conflicting_messages) = match params.custom_messages {
+            Some((propose, precommits)) => (propose.clone(), precommits.to_vec(), Vec::new()),
+            None => self.create_block_messages(
+                validator_id,
+                new_block_height,
+                last_hash,
+                &block_hash,
+                tx_hashes,
+                params,
+            ),
+        };
 
-                    let mut fork = self.blockchain.fork();
-                    CoreSchema::new(&mut fork).commit_configuration(stored);
-                    self.cfg_proposal = Some(Committed(cfg_proposal));
+        let flagged_txs = if params.skip_dependency_check {
+            Vec::new()
+        } else {
+            self.check_tx_dependencies(tx_hashes)
+        };
+        self.blockchain
+            .commit(&block_patch.patch, block_hash, precommits.iter())
+            .map_err(|error| Error::Commit {
+                height: new_block_height,
+                proposer: validator_id,
+                tx_hashes: tx_hashes.to_vec(),
+                error,
+            })?;
+        self.apply_block_patch(new_block_height, block_patch);
+        debug!(
+            target: BLOCK_LOG_TARGET,
+            "Created block {:?} at height {} with {} transactions, proposed by validator {}",
+            block_hash,
+            new_block_height,
+            tx_hashes.len(),
+            validator_id
+        );
+        self.trace(format_args!(
+            "create block at height {} with transactions {:?}, proposed by validator {} -> {:?}",
+            new_block_height, tx_hashes, validator_id, block_hash
+        ));
+        self.record_flagged_transactions(new_block_height, flagged_txs);
+        if conflicting_messages.is_empty() {
+            self.conflicting_messages.remove(&new_block_height);
+        } else {
+            self.conflicting_messages
+                .insert(new_block_height, conflicting_messages);
+        }
+        if self.block_messages.len() >= self.block_messages_capacity {
+            self.block_messages.pop_front();
+        }
+        self.block_messages.push_back((new_block_height, propose, precommits));
+        Ok(block_hash)
+    }
 
-                    return Some(fork.into_patch());
-                }
-                Committed(cfg_proposal) => {
-                    if cfg_proposal.actual_from() == actual_from {
-                        // Modify the self configuration
-                        self.network_mut().update_configuration(cfg_proposal);
-                    } else {
-                        self.cfg_proposal = Some(Committed(cfg_proposal));
-                    }
-                }
+    /// Checks that enough validators sign the block created with the given parameters,
+    /// and returns the id of the validator proposing the block.
+    fn check_precommits(&self, params: BlockParams) -> Result<ValidatorId, Error> {
+        let validator_id = match params.proposer {
+            Some(proposer) => proposer,
+            None => self.online_proposer()?,
+        };
+        let precommit_count = self.network()
+            .validators()
+            .iter()
+            .map(|v| v.validator_id().unwrap())
+            .filter(|id| !params.excluded_precommits.contains(id))
+            .filter(|&id| self.is_validator_available(id))
+            .count();
+        let majority_count = self.majority_count();
+        if precommit_count < majority_count {
+            return Err(Error::InsufficientPrecommits {
+                precommits: precommit_count,
+                majority_count,
+            });
+        }
+        Ok(validator_id)
+    }
+
+    /// Creates the propose and precommits for the block with the given hash, together with
+    /// the conflicting messages signed by byzantine validators.
+    fn create_block_messages(
+        &self,
+        validator_id: ValidatorId,
+        new_block_height: Height,
+        last_hash: &crypto::Hash,
+        block_hash: &crypto::Hash,
+        tx_hashes: &[crypto::Hash],
+        params: BlockParams,
+    ) -> (Propose, Vec<Precommit>, Vec<ConflictingMessages>) {
+        let mut conflicting_messages = Vec::new();
+        let propose = {
+            let proposer = self.validator(validator_id);
+            let propose = proposer.create_propose(new_block_height, last_hash, tx_hashes);
+            if self.byzantine_behavior(validator_id) == Some(ByzantineBehavior::Equivocate) {
+                let other =
+                    byzantine::conflicting_propose(proposer, &propose, new_block_height, last_hash);
+                conflicting_messages.push(ConflictingMessages::Proposes(propose.clone(), other));
+            }
+            propose
+        };
+
+        let now = SystemTime::now();
+        let mut precommits = Vec::new();
+        for v in self.network().validators() {
+            let id = v.validator_id().unwrap();
+            if params.excluded_precommits.contains(&id) || !self.is_validator_available(id) {
+                continue;
+            }
+            let time = self.validator_clock(id, now);
+            let precommit = v.try_create_precommit_at(&propose, block_hash, time).unwrap();
+            if self.byzantine_behavior(id) == Some(ByzantineBehavior::SignConflictingPrecommits) {
+                let other = byzantine::conflicting_precommit(v, &propose, block_hash, time);
+                conflicting_messages
+                    .push(ConflictingMessages::Precommits(precommit.clone(), other));
             }
+            precommits.push(precommit);
         }
+        (propose, precommits, conflicting_messages)
+    }
 
-        None
+    /// Returns the hashes of transactions flagged by the dependency checker, if any.
+    /// The transactions should be stored in the blockchain.
+    fn check_tx_dependencies(&self, tx_hashes: &[crypto::Hash]) -> Vec<crypto::Hash> {
+        match self.tx_dependency_checker {
+            Some(ref checker) => checker.flagged_transactions(&self.blockchain, tx_hashes),
+            None => Vec::new(),
+        }
     }
 
-    /// Returns a reference to the scheduled configuration proposal, or `None` if
-    /// there is no such proposal.
-    pub fn next_configuration(&self) -> Option<&TestNetworkConfiguration> {
-        use ConfigurationProposalState::*;
+    fn record_flagged_transactions(&mut self, height: Height, flagged_txs: Vec<crypto::Hash>) {
+        self.flagged_txs.remove(&height);
+        if flagged_txs.is_empty() {
+            return;
+        }
 
-        self.cfg_proposal.as_ref().map(|p| match *p {
-            Committed(ref proposal) | Uncommitted(ref proposal) => proposal,
-        })
+        warn!(
+            target: BLOCK_LOG_TARGET,
+            "Transactions committed at height {} had no effect: {:?}", height, flagged_txs
+        );
+        let panic_on_flag = self.tx_dependency_checker
+            .as_ref()
+            .map_or(false, |checker| checker.panics_on_flag());
+        if panic_on_flag {
+            panic!(
+                "Transactions committed at height {} had no effect: {:?}",
+                height, flagged_txs
+            );
+        }
+        self.flagged_txs.insert(height, flagged_txs);
     }
 
-    /// Creates a block with the given transactions.
-    /// Transactions that are in the pool will be ignored.
-    ///
-    /// # Return value
-    ///
-    /// Returns information about the created block.
-    ///
-    /// # Panics
+    /// Creates the patch of the block proposed by `proposer_id` at the given height,
+    /// which includes the changes caused by configuration proposals.
     ///
-    /// - Panics if any of transactions has been already committed to the blockchain.
-    pub fn create_block_with_transactions<I>(&mut self, txs: I) -> BlockWithTransactions
-    where
-        I: IntoIterator<Item = Box<dyn Transaction>>,
-    {
-        let tx_hashes: Vec<_> = {
-            let blockchain = self.blockchain_mut();
-            let mut fork = blockchain.fork();
-            let hashes = {
-                let mut schema = CoreSchema::new(&mut fork);
-
-                txs.into_iter()
-                    .filter(|tx| tx.verify())
-                    .map(|tx| {
-                        let tx_id = tx.hash();
-                        let tx_not_found = !schema.transactions().contains(&tx_id);
-                        let tx_in_pool = schema.transactions_pool().contains(&tx_id);
-                        assert!(
-                            tx_not_found || tx_in_pool,
-                            "Transaction is already committed: {:?}",
-                            tx
-                        );
-                        schema.add_transaction_into_pool(tx.raw().clone());
-
-                        tx_id
-                    })
-                    .collect()
-            };
-            blockchain.merge(fork.into_patch()).unwrap();
-            hashes
+    /// The testkit state is not modified; the returned bookkeeping is applied
+    /// with `apply_block_patch` once the block is committed.
+    fn create_block_patch(
+        &self,
+        proposer_id: ValidatorId,
+        new_block_height: Height,
+        tx_hashes: &[crypto::Hash],
+    ) -> BlockPatch {
+        let (committed_configs, config_patch) = self.configuration_patch(new_block_height);
+        if let Some(ref log) = self.execution_log {
+            // Discard the records of transactions executed outside of blocks, e.g., probed.
+            log.take();
+        }
+        // No time measurements are performed unless metrics or timing are enabled.
+        let start = if self.execution_log.is_some() || self.execution_timing {
+            Some(Instant::now())
+        } else {
+            None
         };
+        let (block_hash, patch) =
+            self.blockchain
+                .create_patch(proposer_id, new_block_height, tx_hashes);
+        let metrics = start.map(|start| BlockExecutionMetrics {
+            height: new_block_height,
+            patch_creation_time: start.elapsed(),
+            transactions: self.execution_log
+                .as_ref()
+                .map_or_else(Vec::new, ExecutionLog::take),
+        });
 
-        self.create_block_with_tx_hashes(&tx_hashes)
+        let patch = if let Some(config_patch) = config_patch {
+            let mut fork = self.blockchain.fork();
+            fork.merge(config_patch);
+            fork.merge(patch);
+            fork.into_patch()
+        } else {
+            patch
+        };
+        BlockPatch {
+            block_hash,
+            patch,
+            committed_configs,
+            metrics,
+        }
     }
 
-    /// Creates a block with the given transaction.
-    /// Transactions that are in the pool will be ignored.
-    ///
-    /// # Return value
-    ///
-    /// Returns information about the created block.
-    ///
-    /// # Panics
-    ///
-    /// - Panics if given transaction has been already committed to the blockchain.
-    pub fn create_block_with_transaction<T: Transaction>(
-        &mut self,
-        tx: T,
-    ) -> BlockWithTransactions {
-        self.create_block_with_transactions(txvec![tx])
+    /// Updates the testkit state after the block with the given patch has been committed
+    /// at the given height.
+    fn apply_block_patch(&mut self, new_block_height: Height, block_patch: BlockPatch) {
+        self.update_configuration(new_block_height, &block_patch.committed_configs);
+        if let Some(metrics) = block_patch.metrics {
+            self.execution_metrics.insert(metrics);
+        }
     }
 
-    /// Creates block with the specified transactions. The transactions must be previously
-    /// sent to the node via API or directly put into the `channel()`.
-    ///
-    /// # Return value
-    ///
-    /// Returns information about the created block.
-    ///
-    /// # Panics
-    ///
-    /// - Panics in the case any of transaction hashes are not in the pool.
-    pub fn create_block_with_tx_hashes(
-        &mut self,
-        tx_hashes: &[crypto::Hash],
-    ) -> BlockWithTransactions {
-        self.poll_events();
+    /// Returns `actual_from` heights of the committed configuration proposals
+    /// which should be activated no later than at the given height.
+    fn due_configurations(&self, actual_from: Height) -> Vec<Height> {
+        self.cfg_proposals
+            .range(..=actual_from)
+            .filter_map(|(&height, proposal)| match *proposal {
+                ConfigurationProposalState::Committed(_) => Some(height),
+                ConfigurationProposalState::Uncommitted(_) => None,
+            })
+            .collect()
+    }
 
-        {
-            let snapshot = self.blockchain.snapshot();
-            let schema = CoreSchema::new(&snapshot);
-            for hash in tx_hashes {
-                assert!(schema.transactions_pool().contains(hash));
+    /// Activates committed configuration proposals with `actual_from` height not exceeding
+    /// the given one, returning the number of activated proposals.
+    fn activate_configurations(&mut self, actual_from: Height) -> usize {
+        let activated = self.due_configurations(actual_from);
+        for &height in &activated {
+            if let Some(ConfigurationProposalState::Committed(cfg_proposal)) =
+                self.cfg_proposals.remove(&height)
+            {
+                debug!(
+                    target: CONFIG_LOG_TARGET,
+                    "Activated configuration {:?} at height {}",
+                    cfg_proposal.stored_configuration().hash(),
+                    height
+                );
+                // Modify the self configuration
+                self.network_mut().update_configuration(cfg_proposal);
             }
         }
-
-        self.do_create_block(tx_hashes)
+        activated.len()
     }
 
-    /// Creates block with all transactions in the pool.
+    /// Enables or disables manual activation of configuration changes.
     ///
-    /// # Return value
+    /// By default, a committed configuration change is activated automatically once
+    /// the block preceding its `actual_from` height is committed, i.e., the test network
+    /// is updated with the new validators before the block at `actual_from` height
+    /// is created. With manual activation,
+    /// the change needs to be activated with [`activate_pending_configuration`]
+    /// instead. This allows to observe the blockchain state in which the configuration
+    /// is committed, but not yet activated by the testkit; e.g., to test services reading
+    /// the following configuration.
     ///
-    /// Returns information about the created block.
-    pub fn create_block(&mut self) -> BlockWithTransactions {
-        self.poll_events();
-
-        let snapshot = self.blockchain.snapshot();
-        let schema = CoreSchema::new(&snapshot);
-        let txs = schema.transactions_pool();
-        let tx_hashes: Vec<_> = txs.iter().collect();
-        {
-            let blockchain = self.blockchain_mut();
-            let fork = blockchain.fork();
-            blockchain.merge(fork.into_patch()).unwrap();
-        }
-        self.do_create_block(&tx_hashes)
-    }
-
-    /// Adds transaction into persistent pool.
-    pub fn add_tx(&mut self, transaction: RawMessage) {
-        let mut fork = self.blockchain.fork();
-        let mut schema = CoreSchema::new(&mut fork);
-        schema.add_transaction_into_pool(transaction)
-    }
-
-    /// Checks if transaction can be found in pool
-    pub fn is_tx_in_pool(&self, tx_hash: &Hash) -> bool {
-        let snapshot = self.blockchain.snapshot();
-        let schema = CoreSchema::new(&snapshot);
-        schema.transactions_pool().contains(tx_hash)
+    /// When manual activation is enabled, creating the block preceding `actual_from` height
+    /// of a committed configuration change (or any later block) without activating
+    /// the change panics.
+    ///
+    /// [`activate_pending_configuration`]: #method.activate_pending_configuration
+    pub fn set_manual_config_activation(&mut self, manual: bool) {
+        self.manual_config_activation = manual;
     }
 
-    /// Creates a chain of blocks until a given height.
+    /// Activates committed configuration changes which are due to be activated
+    /// while creating the next block. See [`set_manual_config_activation`] for details.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there are no such configuration changes.
     ///
     /// # Example
     ///
     /// ```
-    /// # extern crate exonum_testkit;
     /// # extern crate exonum;
-    /// # use exonum::helpers::Height;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::{blockchain::Schema, helpers::Height};
     /// # use exonum_testkit::TestKitBuilder;
     /// # fn main() {
     /// let mut testkit = TestKitBuilder::validator().create();
-    /// testkit.create_blocks_until(Height(5));
-    /// assert_eq!(Height(5), testkit.height());
+    /// testkit.set_manual_config_activation(true);
+    /// let proposal = testkit.propose_configuration_at(3);
+    /// let stored = proposal.stored_configuration().clone();
+    /// testkit.commit_configuration_change(proposal);
+    /// testkit.create_block();
+    ///
+    /// // The configuration is committed, but not yet activated.
+    /// let snapshot = testkit.snapshot();
+    /// assert_eq!(Schema::new(&snapshot).following_configuration(), Some(stored.clone()));
+    /// assert!(testkit.next_configuration().is_some());
+    ///
+    /// testkit.activate_pending_configuration();
+    /// assert!(testkit.next_configuration().is_none());
+    /// testkit.create_block();
+    /// assert_eq!(testkit.actual_configuration(), stored);
     /// # }
-    pub fn create_blocks_until(&mut self, height: Height) {
-        while self.height() < height {
-            self.create_block();
-        }
-    }
-
-    /// Returns the hash of latest committed block.
-    pub fn last_block_hash(&self) -> crypto::Hash {
-        self.blockchain.last_hash()
-    }
-
-    /// Returns the height of latest committed block.
-    pub fn height(&self) -> Height {
-        self.blockchain.last_block().height()
-    }
-
-    /// Returns the blockchain explorer instance.
-    pub fn explorer(&self) -> BlockchainExplorer {
-        BlockchainExplorer::new(&self.blockchain)
-    }
-
-    /// Returns the actual blockchain configuration.
-    pub fn actual_configuration(&self) -> StoredConfiguration {
-        CoreSchema::new(&self.snapshot()).actual_configuration()
+    /// ```
+    ///
+    /// [`set_manual_config_activation`]: #method.set_manual_config_activation
+    pub fn activate_pending_configuration(&mut self) {
+        let actual_from = self.height().next().next();
+        let activated = self.activate_configurations(actual_from);
+        assert!(
+            activated > 0,
+            "No committed configuration change is due to be activated before height {}",
+            actual_from
+        );
     }
 
-    /// Returns reference to validator with the given identifier.
+    /// Returns `actual_from` heights of the uncommitted configuration proposals
+    /// and the patch committing them in the block at the given height, if there are
+    /// any such proposals.
     ///
     /// # Panics
     ///
-    /// - Panics if validator with the given id is absent in test network.
-    pub fn validator(&self, id: ValidatorId) -> &TestNode {
-        &self.network.validators()[id.0 as usize]
-    }
+    /// - Panics if manual configuration activation is enabled and a committed
+    ///   configuration change is due to be activated.
+    fn configuration_patch(&self, new_block_height: Height) -> (Vec<Height>, Option<Patch>) {
+        use ConfigurationProposalState::*;
 
-    /// Returns sufficient number of validators for the Byzantine Fault Tolerance consensus.
-    pub fn majority_count(&self) -> usize {
-        NodeState::byzantine_majority_count(self.network().validators().len())
-    }
+        if self.manual_config_activation {
+            if let Some(height) = self.due_configurations(new_block_height.next()).first() {
+                panic!(
+                    "Configuration with actual_from height {} should be activated with \
+                     `activate_pending_configuration` before creating block at height {}",
+                    height, new_block_height
+                );
+            }
+        }
 
-    /// Returns the leader on the current height. At the moment first validator.
-    pub fn leader(&self) -> &TestNode {
-        &self.network().validators()[0]
-    }
+        // Commit configuration proposals in the order of their `actual_from` heights.
+        let uncommitted: Vec<_> = self.cfg_proposals
+            .iter()
+            .filter_map(|(&height, proposal)| match *proposal {
+                Uncommitted(ref cfg_proposal) => Some((height, cfg_proposal)),
+                Committed(_) => None,
+            })
+            .collect();
+        if uncommitted.is_empty() {
+            return (Vec::new(), None);
+        }
 
-    /// Returns the reference to test network.
-    pub fn network(&self) -> &TestNetwork {
-        &self.network
+        let mut fork = self.blockchain.fork();
+        for &(_, cfg_proposal) in &uncommitted {
+            let stored = cfg_proposal.stored_configuration().clone();
+            CoreSchema::new(&mut fork).commit_configuration(stored);
+        }
+        let heights = uncommitted.into_iter().map(|(height, _)| height).collect();
+        (heights, Some(fork.into_patch()))
     }
 
-    /// Returns the mutable reference to test network for manual modifications.
-    pub fn network_mut(&mut self) -> &mut TestNetwork {
-        &mut self.network
-    }
+    /// Update test network configuration if such an update has been scheduled
+    /// with `commit_configuration_change`. `committed` lists `actual_from` heights
+    /// of the proposals committed in the block at the given height.
+    fn update_configuration(&mut self, new_block_height: Height, committed: &[Height]) {
+        use ConfigurationProposalState::*;
 
-    /// Returns a copy of the actual configuration of the testkit.
-    /// The returned configuration could be modified for use with
-    /// `commit_configuration_change` method.
-    pub fn configuration_change_proposal(&self) -> TestNetworkConfiguration {
-        let stored_configuration = CoreSchema::new(&self.snapshot()).actual_configuration();
-        TestNetworkConfiguration::new(self.network(), stored_configuration)
+        // Activate committed proposals.
+        if !self.manual_config_activation {
+            self.activate_configurations(new_block_height.next());
+        }
+
+        for &height in committed {
+            if let Some(Uncommitted(cfg_proposal)) = self.cfg_proposals.remove(&height) {
+                let stored = cfg_proposal.stored_configuration().clone();
+                debug!(
+                    target: CONFIG_LOG_TARGET,
+                    "Committed configuration {:?} with actual_from height {}",
+                    stored.hash(),
+                    height
+                );
+                self.committed_configs.push((new_block_height, stored));
+                self.cfg_proposals.insert(height, Committed(cfg_proposal));
+            }
+        }
     }
 
-    /// Adds a new configuration proposal. Remember, to add this proposal to the blockchain,
-    /// you should create at least one block.
+    /// Returns the number of transactions in the pool before creating each block, together
+    /// with the height of the created block. Records for blocks discarded by a [`rollback`]
+    /// are removed from the history.
     ///
     /// # Panics
     ///
-    /// - Panics if `actual_from` is less than current height or equals.
-    /// - Panics if configuration change has been already proposed but not executed.
+    /// - Panics if pool size tracking is not enabled with
+    ///   `TestKitBuilder::with_pool_size_tracking`.
     ///
     /// # Example
     ///
     /// ```
-    /// extern crate exonum;
-    /// extern crate exonum_testkit;
-    /// extern crate serde;
-    /// extern crate serde_json;
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator()
+    ///     .with_pool_size_tracking()
+    ///     .create();
+    /// testkit.create_blocks_until(Height(2));
+    /// assert_eq!(
+    ///     testkit.transaction_pool_size_history(),
+    ///     vec![(Height(1), 0), (Height(2), 0)]
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`rollback`]: #method.rollback
+    pub fn transaction_pool_size_history(&self) -> Vec<(Height, usize)> {
+        self.pool_size_history
+            .clone()
+            .expect("Pool size tracking is not enabled; use `with_pool_size_tracking`")
+    }
+
+    /// Returns the execution metrics of the blocks created by the testkit, aggregated
+    /// across the blocks. The metrics of rolled back blocks are discarded.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if execution metrics are not enabled with
+    ///   `TestKitBuilder::with_execution_metrics`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator()
+    ///     .with_execution_metrics()
+    ///     .create();
+    /// testkit.create_block();
+    /// let metrics = testkit.execution_metrics();
+    /// let block_metrics = metrics.block(Height(1)).unwrap();
+    /// assert!(block_metrics.transactions.is_empty());
+    /// assert!(block_metrics.total_tx_time() <= block_metrics.patch_creation_time);
+    /// # }
+    /// ```
+    pub fn execution_metrics(&self) -> &ExecutionMetrics {
+        assert!(
+            self.execution_log.is_some(),
+            "Execution metrics are not enabled; use `with_execution_metrics`"
+        );
+        &self.execution_metrics
+    }
+
+    /// Returns the wall-clock time of the block patch creation at the specified height,
+    /// or `None` if the block has not been created by the testkit with the timing enabled.
+    /// Timings of the blocks discarded by a [`rollback`] are removed.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if execution timing is not enabled with
+    ///   `TestKitBuilder::with_execution_timing`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator()
+    ///     .with_execution_timing()
+    ///     .create();
+    /// testkit.create_blocks_until(Height(3));
+    /// assert!(testkit.block_execution_time(Height(3)).is_some());
+    /// assert!(testkit.block_execution_time(Height(4)).is_none());
+    /// # }
+    /// ```
+    ///
+    /// [`rollback`]: #method.rollback
+    pub fn block_execution_time(&self, height: Height) -> Option<Duration> {
+        self.timed_blocks()
+            .block(height)
+            .map(|block| block.patch_creation_time)
+    }
+
+    /// Returns the mean wall-clock time of the block patch creation across all blocks
+    /// created with the timing enabled, or zero duration if no such blocks exist.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if execution timing is not enabled with
+    ///   `TestKitBuilder::with_execution_timing`.
+    pub fn mean_block_execution_time(&self) -> Duration {
+        let blocks = self.timed_blocks().blocks();
+        if blocks.is_empty() {
+            return Duration::default();
+        }
+        let total = blocks
+            .iter()
+            .fold(Duration::default(), |total, block| total + block.patch_creation_time);
+        total / blocks.len() as u32
+    }
+
+    /// Returns the calls of the hooks of the service with the given name, which are recorded
+    /// since the testkit creation. The calls related to the blocks discarded by [`rollback`],
+    /// including the blocks created by [`probe`] and similar methods, are not returned.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the service is not added with `TestKitBuilder::with_spied_service`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::doctest_support::{DoctestService, SERVICE_NAME};
+    /// use exonum::helpers::Height;
+    /// use exonum_testkit::TestKitBuilder;
+    ///
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator()
+    ///     .with_spied_service(DoctestService)
+    ///     .create();
+    /// testkit.create_blocks_until(Height(3));
+    ///
+    /// let calls = testkit.service_calls(SERVICE_NAME);
+    /// assert_eq!(calls.initialize_count(), 1);
+    /// assert_eq!(calls.after_commit_heights(), vec![Height(1), Height(2), Height(3)]);
+    /// # }
+    /// ```
+    ///
+    /// [`rollback`]: #method.rollback
+    /// [`probe`]: #method.probe
+    pub fn service_calls(&self, service_name: &str) -> ServiceCalls {
+        self.service_spies
+            .get(service_name)
+            .cloned()
+            .unwrap_or_else(|| {
+                panic!(
+                    "Service `{}` is not spied; use `TestKitBuilder::with_spied_service`",
+                    service_name
+                )
+            })
+    }
+
+    /// Returns the metrics of the blocks created with the timing enabled; the patch creation
+    /// time of the blocks is recorded together with the other metrics.
+    fn timed_blocks(&self) -> &ExecutionMetrics {
+        assert!(
+            self.execution_timing,
+            "Execution timing is not enabled; use `with_execution_timing`"
+        );
+        &self.execution_metrics
+    }
+
+    /// Returns the propose and precommits of the latest block.
+    /// See [`block_messages`] for details.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the messages of the latest block are not retained by the testkit,
+    ///   e.g., if the latest block is the genesis block.
+    ///
+    /// [`block_messages`]: #method.block_messages
+    pub fn last_block_messages(&self) -> (Propose, Vec<Precommit>) {
+        let height = self.height();
+        self.block_messages(height).unwrap_or_else(|| {
+            panic!(
+                "Messages of the block at height {} are not retained by the testkit",
+                height
+            )
+        })
+    }
+
+    /// Returns the propose and precommits of the block at the specified height,
+    /// or `None` if the messages are not retained.
+    ///
+    /// The messages are retained for the blocks created by the testkit (including the blocks
+    /// created with [`fast_forward`]), but not for the imported blocks. For the blocks
+    /// created with [`create_block_with_custom_precommits`], the supplied messages
+    /// are retained. Only the messages for the latest blocks are retained;
+    /// the number of such blocks is set with [`TestKitBuilder::with_retained_block_messages`]
+    /// and is 1 by default. The messages of the blocks discarded by a [`rollback`]
+    /// are forgotten.
+    ///
+    /// [`fast_forward`]: #method.fast_forward
+    /// [`create_block_with_custom_precommits`]: #method.create_block_with_custom_precommits
+    /// [`rollback`]: #method.rollback
+    /// [`TestKitBuilder::with_retained_block_messages`]:
+    /// struct.TestKitBuilder.html#method.with_retained_block_messages
+    pub fn block_messages(&self, height: Height) -> Option<(Propose, Vec<Precommit>)> {
+        self.block_messages
+            .iter()
+            .find(|&&(block_height, ..)| block_height == height)
+            .map(|&(_, ref propose, ref precommits)| (propose.clone(), precommits.clone()))
+    }
+
+    /// Returns a reference to the nearest scheduled configuration proposal, or `None` if
+    /// there is no such proposal.
+    pub fn next_configuration(&self) -> Option<&TestNetworkConfiguration> {
+        self.cfg_proposals.values().next().map(|p| p.proposal())
+    }
+
+    /// Returns references to all scheduled configuration proposals in the increasing order
+    /// of their `actual_from` heights.
+    pub fn scheduled_configurations(&self) -> Vec<&TestNetworkConfiguration> {
+        self.cfg_proposals.values().map(|p| p.proposal()).collect()
+    }
+
+    /// Creates a block with the given transactions.
+    /// Transactions that are in the pool will be ignored.
+    ///
+    /// Transactions that do not pass verification are skipped, and transactions occurring
+    /// in the list several times are included into the block once, at the position of their
+    /// first occurrence. Given transactions that are already in the pool (e.g., sent to
+    /// the testkit via the API) are included into the block without being added to the pool
+    /// once again.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block. The transactions in the block
+    /// are listed in the order of their execution.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of transactions has been already committed to the blockchain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate exonum_testkit;
+    /// # extern crate exonum;
+    /// # use exonum_testkit::doctest_support::{DoctestSchema, DoctestService, TxAdd};
+    /// use exonum::crypto;
+    /// use exonum::helpers::Height;
+    /// use exonum_testkit::TestKit;
+    ///
+    /// # fn main() {
+    /// let mut testkit = TestKit::for_service(DoctestService);
+    /// let (pubkey, key) = crypto::gen_keypair();
+    ///
+    /// let block = testkit.create_block_with_transactions(txvec![
+    ///     TxAdd::new(&pubkey, 2, &key),
+    ///     TxAdd::new(&pubkey, 3, &key)
+    /// ]);
+    /// assert_eq!(block.height(), Height(1));
+    /// assert_eq!(block.len(), 2);
+    /// assert_eq!(DoctestSchema::new(&testkit.snapshot()).sum(), 5);
+    /// # }
+    /// ```
+    pub fn create_block_with_transactions<I>(&mut self, txs: I) -> BlockWithTransactions
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        self.ensure_genesis_block();
+        let height = self.height().next();
+        self.try_create_block_with_transactions(txs)
+            .unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e))
+    }
+
+    /// Creates a block with the given transactions, or returns an error if any
+    /// of transactions has been already committed to the blockchain. See
+    /// [`create_block_with_transactions`] for details on the handling of transactions.
+    ///
+    /// [`create_block_with_transactions`]: #method.create_block_with_transactions
+    pub fn try_create_block_with_transactions<I>(
+        &mut self,
+        txs: I,
+    ) -> Result<BlockWithTransactions, Error>
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        self.ensure_genesis_block();
+        // Transactions sent to the testkit should be put into the pool beforehand,
+        // so that they are not added to the pool twice.
+        self.poll_events();
+        let tx_hashes = self.add_transactions_into_pool(txs)?;
+        self.try_create_block_with_tx_hashes(&tx_hashes)
+    }
+
+    /// Creates a block with the given transactions, which is rolled back by the caller
+    /// right after inspection, e.g., when probing transactions. Such blocks are not checked
+    /// by the dependency checker.
+    fn try_create_probe_block<I>(&mut self, txs: I) -> Result<BlockWithTransactions, Error>
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        self.ensure_genesis_block();
+        self.poll_events();
+        let tx_hashes = self.add_transactions_into_pool(txs)?;
+        let schema = self.core_schema();
+        check_pool_transactions(&schema, &tx_hashes)?;
+        let params = BlockParams {
+            skip_dependency_check: true,
+            ..BlockParams::default()
+        };
+        self.do_create_block(&schema, &tx_hashes, params)
+    }
+
+    /// Adds correct transactions into the pool, returning the deduplicated list
+    /// of their hashes. Transactions already present in the pool are not added
+    /// to it once again. If any of transactions has been already committed to
+    /// the blockchain, the pool is left intact.
+    fn add_transactions_into_pool<I>(&mut self, txs: I) -> Result<Vec<crypto::Hash>, Error>
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        let mut fork = self.blockchain.fork();
+        let mut hashes = Vec::new();
+        let mut pooled = Vec::new();
+        {
+            let mut schema = CoreSchema::new(&mut fork);
+            let mut seen_hashes = HashSet::new();
+
+            for tx in txs {
+                let tx_id = tx.hash();
+                if !tx.verify() {
+                    trace!(
+                        target: TX_LOG_TARGET,
+                        "Rejected transaction {:?}: verification failed",
+                        tx_id
+                    );
+                    continue;
+                }
+                if !seen_hashes.insert(tx_id) {
+                    continue;
+                }
+                if !schema.transactions_pool().contains(&tx_id) {
+                    if schema.transactions().contains(&tx_id) {
+                        trace!(
+                            target: TX_LOG_TARGET,
+                            "Rejected transaction {:?}: already committed",
+                            tx_id
+                        );
+                        return Err(Error::TransactionAlreadyCommitted(tx_id));
+                    }
+                    schema.add_transaction_into_pool(tx.raw().clone());
+                    pooled.push(tx_id);
+                    trace!(
+                        target: TX_LOG_TARGET,
+                        "Pooled transaction {:?} passed to the testkit directly",
+                        tx_id
+                    );
+                }
+                hashes.push(tx_id);
+            }
+        }
+        self.blockchain.merge(fork.into_patch()).unwrap();
+        self.pool_arrivals.lock().unwrap().extend(pooled);
+        Ok(hashes)
+    }
+
+    /// Creates a block with the given transactions in the same way as
+    /// [`create_block_with_transactions`], and calls `on_commit` with the committed block
+    /// and the snapshot of the blockchain immediately after the block is committed.
+    ///
+    /// The callback is called before the testkit processes any events (e.g., transactions
+    /// sent by the services when the block is committed), so it observes the blockchain state
+    /// exactly as it is after the commit.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of transactions has been already committed to the blockchain.
+    ///
+    /// [`create_block_with_transactions`]: #method.create_block_with_transactions
+    pub fn create_block_with_commit_callback<F>(
+        &mut self,
+        txs: Vec<Box<dyn Transaction>>,
+        on_commit: F,
+    ) -> BlockWithTransactions
+    where
+        F: FnOnce(Block, &dyn Snapshot),
+    {
+        self.ensure_genesis_block();
+        let height = self.height().next();
+        // Transactions sent to the testkit should be put into the pool beforehand,
+        // so that they are not added to the pool twice.
+        self.poll_events();
+        let result = self.add_transactions_into_pool(txs).and_then(|tx_hashes| {
+            let schema = self.core_schema();
+            check_pool_transactions(&schema, &tx_hashes)?;
+            self.do_create_block_with_callback(
+                &schema,
+                &tx_hashes,
+                BlockParams::default(),
+                Some(on_commit),
+            )
+        });
+        result.unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e))
+    }
+
+    /// Creates a block with the given transaction.
+    /// Transactions that are in the pool will be ignored.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if given transaction has been already committed to the blockchain.
+    pub fn create_block_with_transaction<T: Transaction>(
+        &mut self,
+        tx: T,
+    ) -> BlockWithTransactions {
+        self.create_block_with_transactions(txvec![tx])
+    }
+
+    /// Creates block with the specified transactions. The transactions must be previously
+    /// sent to the node via API or directly put into the `channel()`.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics in the case any of transaction hashes are not in the pool or are duplicated.
+    ///   The panic message lists all missing hashes and states for each of them whether
+    ///   the transaction is already committed or is unknown to the testkit.
+    pub fn create_block_with_tx_hashes(
+        &mut self,
+        tx_hashes: &[crypto::Hash],
+    ) -> BlockWithTransactions {
+        self.ensure_genesis_block();
+        let height = self.height().next();
+        self.try_create_block_with_tx_hashes(tx_hashes)
+            .unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e))
+    }
+
+    /// Creates block with the specified transactions, or returns an error if any
+    /// of transaction hashes are not in the pool or are duplicated.
+    pub fn try_create_block_with_tx_hashes(
+        &mut self,
+        tx_hashes: &[crypto::Hash],
+    ) -> Result<BlockWithTransactions, Error> {
+        self.ensure_genesis_block();
+        self.poll_events();
+
+        let schema = self.core_schema();
+        check_pool_transactions(&schema, tx_hashes)?;
+        self.do_create_block(&schema, tx_hashes, BlockParams::default())
+    }
+
+    /// Creates a block with the given transaction, which is stored in the blockchain
+    /// under `forged_hash` instead of its real hash. The transaction is executed
+    /// and recorded in the block under the forged hash.
+    ///
+    /// This method is intended to test the detection of tampered blockchain data,
+    /// e.g., with [`verify_transaction_hashes`].
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `forged_hash` coincides with the real hash of the transaction.
+    /// - Panics if a transaction with `forged_hash` is already known to the testkit.
+    ///
+    /// [`verify_transaction_hashes`]: #method.verify_transaction_hashes
+    pub fn create_block_with_forged_hash(
+        &mut self,
+        real_tx: Box<dyn Transaction>,
+        forged_hash: crypto::Hash,
+    ) -> BlockWithTransactions {
+        assert_ne!(
+            real_tx.hash(),
+            forged_hash,
+            "Forged hash coincides with the real hash of the transaction"
+        );
+        self.ensure_genesis_block();
+        self.poll_events();
+
+        let mut fork = self.blockchain.fork();
+        {
+            let mut transactions: MapIndex<_, crypto::Hash, RawMessage> =
+                MapIndex::new(CORE_TRANSACTIONS, &mut fork);
+            assert!(
+                !transactions.contains(&forged_hash),
+                "Transaction with hash {:?} is already known to the testkit",
+                forged_hash
+            );
+            transactions.put(&forged_hash, real_tx.raw().clone());
+        }
+        KeySetIndex::new(CORE_TRANSACTIONS_POOL, &mut fork).insert(forged_hash);
+        {
+            let mut pool_len: Entry<_, u64> = Entry::new(CORE_TRANSACTIONS_POOL_LEN, &mut fork);
+            let len = pool_len.get().unwrap_or(0);
+            pool_len.set(len + 1);
+        }
+        self.blockchain
+            .merge(fork.into_patch())
+            .expect("Cannot add transaction with forged hash into the pool");
+
+        self.create_block_with_tx_hashes(&[forged_hash])
+    }
+
+    /// Checks that every transaction known to the blockchain is stored under the hash
+    /// of its content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::TransactionHashMismatch` for the first transaction (in the order
+    /// of stored hashes) which hash differs from the hash of its content.
+    pub fn verify_transaction_hashes(&self) -> Result<(), Error> {
+        let snapshot = self.snapshot();
+        let schema = CoreSchema::new(&snapshot);
+        for (stored, raw) in schema.transactions().iter() {
+            let actual = raw.hash();
+            if actual != stored {
+                return Err(Error::TransactionHashMismatch { stored, actual });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the integrity of the blockchain, e.g., after importing it or manipulating
+    /// it with rollbacks and blocks created with custom parameters. For every committed
+    /// block, the method checks that:
+    ///
+    /// - The block is stored under its hash for its height.
+    /// - The block refers to the block stored at the previous height.
+    /// - The number and the root hash of the transactions specified in the block match
+    ///   the transactions stored for it, and the transactions are stored under the hashes
+    ///   of their content.
+    /// - The block (other than the genesis one) has precommits of a Byzantine majority
+    ///   of the validators active at its height, with valid signatures and no duplicates.
+    ///
+    /// Since the blockchain state is stored only for the latest block, the state hash
+    /// is checked for the latest block: it should be equal to the root hash of the state hash
+    /// aggregator, which in its turn should contain the actual root hashes of the service
+    /// tables. The core tables are not checked, since the testkit commits configuration
+    /// changes into them after the state hash of a block is calculated.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first found inconsistency, in the ascending order of heights.
+    pub fn verify_chain(&self) -> Result<(), ChainVerificationError> {
+        let snapshot = self.snapshot();
+        let schema = CoreSchema::new(&snapshot);
+        let blocks = schema.blocks();
+        let mut prev_hash = Hash::default();
+        let mut last_block = None;
+
+        for (i, block_hash) in schema.block_hashes_by_height().iter().enumerate() {
+            let height = Height(i as u64);
+            let block = blocks.get(&block_hash).ok_or_else(|| {
+                ChainVerificationError::MissingBlock {
+                    height,
+                    hash: block_hash,
+                }
+            })?;
+            let actual_hash = block.hash();
+            if actual_hash != block_hash {
+                return Err(ChainVerificationError::BlockHashMismatch {
+                    height,
+                    stored: block_hash,
+                    actual: actual_hash,
+                });
+            }
+            if block.height() != height {
+                return Err(ChainVerificationError::HeightMismatch {
+                    height,
+                    actual: block.height(),
+                });
+            }
+            if *block.prev_hash() != prev_hash {
+                return Err(ChainVerificationError::PrevHashMismatch {
+                    height,
+                    expected: prev_hash,
+                    actual: *block.prev_hash(),
+                });
+            }
+            verify_block_transactions(&schema, &block)?;
+            if height > Height(0) {
+                verify_block_precommits(&schema, height, &block_hash)?;
+            }
+            prev_hash = block_hash;
+            last_block = Some(block);
+        }
+
+        match last_block {
+            Some(block) => self.verify_state_hash(&*snapshot, &block),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the state hash of the latest block against the current blockchain state.
+    fn verify_state_hash(
+        &self,
+        snapshot: &dyn Snapshot,
+        block: &Block,
+    ) -> Result<(), ChainVerificationError> {
+        let height = block.height();
+        let schema = CoreSchema::new(snapshot);
+        let aggregator = schema.state_hash_aggregator();
+        let root = aggregator.merkle_root();
+        if root != *block.state_hash() {
+            return Err(ChainVerificationError::StateHashMismatch {
+                height,
+                expected: *block.state_hash(),
+                actual: root,
+            });
+        }
+
+        for service in self.blockchain.service_map().values() {
+            let service_id = service.service_id();
+            for (table_idx, table_hash) in service.state_hash(snapshot).into_iter().enumerate() {
+                let key = Blockchain::service_table_unique_key(service_id, table_idx);
+                if aggregator.get(&key) != Some(table_hash) {
+                    return Err(ChainVerificationError::StateTableMismatch {
+                        height,
+                        service_id,
+                        table_idx,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the signatures of the transactions with the specified hashes. The transactions
+    /// are restored from the raw messages stored in the blockchain, which include both
+    /// the committed transactions and the transactions in the pool.
+    ///
+    /// The signature of a transaction is checked with `Transaction::verify`; transactions
+    /// that cannot be deserialized by the services are reported as invalid.
+    ///
+    /// # Return value
+    ///
+    /// Returns the verification results in the order of the passed hashes.
+    pub fn verify_transaction_signatures(
+        &self,
+        hashes: &[Hash],
+    ) -> Vec<(Hash, SignatureVerificationResult)> {
+        let schema = self.core_schema();
+        let transactions = schema.transactions();
+        hashes
+            .iter()
+            .map(|hash| {
+                let result = match transactions.get(hash) {
+                    None => SignatureVerificationResult::NotFound,
+                    Some(raw) => match self.blockchain.tx_from_raw(raw) {
+                        Ok(ref tx) if tx.verify() => SignatureVerificationResult::Valid,
+                        _ => SignatureVerificationResult::Invalid,
+                    },
+                };
+                (*hash, result)
+            })
+            .collect()
+    }
+
+    /// Returns the name of the type of the transaction with the specified hash, as reported
+    /// by `Service::transaction_type_name` of the service the transaction belongs to.
+    /// The transaction is looked up among the committed transactions and the transactions
+    /// in the pool.
+    ///
+    /// Returns `None` if the transaction is unknown, cannot be deserialized by its service,
+    /// or if the service does not report transaction type names.
+    pub fn transaction_type_name(&self, hash: &Hash) -> Option<String> {
+        let raw = self.core_schema().transactions().get(hash)?;
+        let service = self.blockchain.service_map().get(raw.service_id() as usize)?;
+        let tx = service.tx_from_raw(raw).ok()?;
+        service.transaction_type_name(&*tx).map(str::to_owned)
+    }
+
+    /// Creates block with all transactions in the pool.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    pub fn create_block(&mut self) -> BlockWithTransactions {
+        self.ensure_genesis_block();
+        let height = self.height().next();
+        self.try_create_block()
+            .unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e))
+    }
+
+    /// Creates block with all transactions in the pool, or returns an error
+    /// if the block cannot be committed.
+    pub fn try_create_block(&mut self) -> Result<BlockWithTransactions, Error> {
+        self.create_block_from_pool(BlockParams::default())
+    }
+
+    /// Creates block with all transactions in the pool and asserts that all transactions
+    /// in the block were executed successfully. This is useful for the happy-path scenarios.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any transaction in the block was executed with an error. The panic message
+    ///   lists the hashes of the erroneous transactions together with the errors.
+    pub fn create_block_and_assert_no_errors(&mut self) -> BlockWithTransactions {
+        use std::fmt::Write;
+
+        let block = self.create_block();
+        let mut report = String::new();
+        let mut error_count = 0;
+        for tx in &block {
+            if let Err(error) = tx.status() {
+                error_count += 1;
+                writeln!(report, "  transaction {:?}: {}", tx.content().hash(), error).unwrap();
+            }
+        }
+        assert!(
+            error_count == 0,
+            "{} transaction(s) in the block at height {} were executed with errors:\n{}{}",
+            error_count,
+            block.height(),
+            report,
+            self.shuffle_note()
+        );
+        block
+    }
+
+    /// Creates block with all transactions in the pool, which is proposed by the specified
+    /// validator. Precommits for the block are signed by all validators.
+    ///
+    /// The testkit does not rotate the leader: blocks created with other methods are always
+    /// proposed by the first validator, and the proposer specified for this method takes
+    /// precedence over it for the created block only.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the validator with the specified identifier is absent
+    ///   in the current validator set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::ValidatorId;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// let block = testkit.create_block_with_proposer(ValidatorId(2));
+    /// assert_eq!(block.header.proposer_id(), ValidatorId(2));
+    /// # }
+    /// ```
+    pub fn create_block_with_proposer(&mut self, proposer: ValidatorId) -> BlockWithTransactions {
+        self.ensure_genesis_block();
+        let height = self.height().next();
+        self.try_create_block_with_proposer(proposer)
+            .unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e))
+    }
+
+    /// Creates block with all transactions in the pool, which is proposed by the specified
+    /// validator, or returns an error if the validator is absent in the current validator set.
+    /// See [`create_block_with_proposer`] for details.
+    ///
+    /// [`create_block_with_proposer`]: #method.create_block_with_proposer
+    pub fn try_create_block_with_proposer(
+        &mut self,
+        proposer: ValidatorId,
+    ) -> Result<BlockWithTransactions, Error> {
+        if proposer.0 as usize >= self.network().validators().len() {
+            return Err(Error::UnknownValidator(proposer));
+        }
+        if !self.is_validator_available(proposer) {
+            return Err(Error::ValidatorOffline(proposer));
+        }
+        self.create_block_from_pool(BlockParams {
+            proposer: Some(proposer),
+            ..BlockParams::default()
+        })
+    }
+
+    /// Creates block with all transactions in the pool and the given parameters.
+    fn create_block_from_pool(
+        &mut self,
+        params: BlockParams,
+    ) -> Result<BlockWithTransactions, Error> {
+        self.ensure_genesis_block();
+        self.poll_events();
+
+        let schema = self.core_schema();
+        let mut tx_hashes = self.ordered_pool_transactions(&schema);
+        if let TxOrdering::Custom(..) = *self.tx_ordering {
+            check_pool_transactions(&schema, &tx_hashes)?;
+        } else if let (None, Some(seed)) = (self.pool_order.as_ref(), self.shuffled_blocks_seed) {
+            shuffle::shuffle(&mut tx_hashes, seed);
+            self.last_shuffle_seed = Some(seed);
+        }
+        let block = self.do_create_block(&schema, &tx_hashes, params)?;
+        self.pool_order = None;
+        Ok(block)
+    }
+
+    /// Returns hashes of the transactions in the pool in the order they will be included
+    /// into the next block created with `create_block`. Transactions that have been added
+    /// to the pool after the order was changed follow the reordered ones.
+    fn ordered_pool_transactions(&self, schema: &CoreSchema<Box<dyn Snapshot>>) -> Vec<Hash> {
+        let pool = schema.transactions_pool();
+        match self.pool_order {
+            Some(ref order) => {
+                let mut tx_hashes: Vec<_> = order
+                    .iter()
+                    .filter(|tx_hash| pool.contains(tx_hash))
+                    .cloned()
+                    .collect();
+                let ordered: HashSet<_> = tx_hashes.iter().cloned().collect();
+                tx_hashes.extend(pool.iter().filter(|tx_hash| !ordered.contains(tx_hash)));
+                tx_hashes
+            }
+            None => match *self.tx_ordering {
+                TxOrdering::Fifo => self.pool_transactions_in_arrival_order(schema),
+                TxOrdering::ByHash => pool.iter().collect(),
+                TxOrdering::Custom(ref order) => {
+                    let tx_hashes = self.pool_transactions_in_arrival_order(schema);
+                    order(&tx_hashes, &*self.snapshot())
+                }
+            },
+        }
+    }
+
+    /// Sets the ordering of the pool transactions in the blocks created with [`create_block`]
+    /// and similar methods taking transactions from the pool. By default, the transactions
+    /// are ordered by their hashes.
+    ///
+    /// The ordering applies to all subsequent blocks. The order set with
+    /// [`randomly_reorder_mempool_transactions`] takes precedence over the ordering
+    /// for the next block.
+    ///
+    /// With the `Custom` ordering, the transactions not selected by the ordering function
+    /// remain in the pool. Creating a block panics (or returns an error for the `try_`
+    /// methods) if the function returns a hash of a transaction absent from the pool
+    /// or the same hash several times.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::{crypto::Hash, storage::Snapshot};
+    /// # use exonum_testkit::{TestKitBuilder, TxOrdering};
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// // Transactions are executed in the reverse order of their arrival.
+    /// testkit.set_tx_ordering(TxOrdering::Custom(Box::new(
+    ///     |tx_hashes: &[Hash], _: &dyn Snapshot| tx_hashes.iter().rev().cloned().collect(),
+    /// )));
+    /// testkit.create_block();
+    /// # }
+    /// ```
+    ///
+    /// [`create_block`]: #method.create_block
+    /// [`randomly_reorder_mempool_transactions`]: #method.randomly_reorder_mempool_transactions
+    pub fn set_tx_ordering(&mut self, ordering: TxOrdering) {
+        self.tx_ordering = Arc::new(ordering);
+    }
+
+    /// Returns the hashes of the pool transactions in the order of their addition to the pool.
+    /// Transactions put into the pool bypassing the testkit (e.g., directly via the storage)
+    /// follow the other transactions in the order of their hashes.
+    fn pool_transactions_in_arrival_order(
+        &self,
+        schema: &CoreSchema<Box<dyn Snapshot>>,
+    ) -> Vec<Hash> {
+        let pool = schema.transactions_pool();
+        let mut arrivals = self.pool_arrivals.lock().unwrap();
+        let mut seen = HashSet::new();
+        arrivals.retain(|tx_hash| pool.contains(tx_hash) && seen.insert(*tx_hash));
+
+        let mut tx_hashes = arrivals.clone();
+        tx_hashes.extend(pool.iter().filter(|tx_hash| !seen.contains(tx_hash)));
+        tx_hashes
+    }
+
+    /// Creates a block with the pool transactions at positions `[start, start + count)`
+    /// in the order of their addition to the pool. The other transactions remain in the pool.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the pool contains less than `start + count` transactions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// // The pool is empty, so only an empty slice can be committed.
+    /// let block = testkit.create_block_from_mempool_slice(0, 0);
+    /// assert!(block.is_empty());
+    /// assert_eq!(testkit.height(), Height(1));
+    /// # }
+    /// ```
+    pub fn create_block_from_mempool_slice(
+        &mut self,
+        start: usize,
+        count: usize,
+    ) -> BlockWithTransactions {
+        self.ensure_genesis_block();
+        self.poll_events();
+
+        let schema = self.core_schema();
+        let tx_hashes = self.pool_transactions_in_arrival_order(&schema);
+        let end = start.checked_add(count);
+        let end = match end {
+            Some(end) if end <= tx_hashes.len() => end,
+            _ => panic!(
+                "Cannot take {} transactions starting from position {}: \
+                 the pool contains {} transactions",
+                count,
+                start,
+                tx_hashes.len()
+            ),
+        };
+
+        let height = schema.height().next();
+        self.do_create_block(&schema, &tx_hashes[start..end], BlockParams::default())
+            .unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e))
+    }
+
+    /// Randomly reorders the transactions in the pool, so that the next block created
+    /// with [`create_block`] commits them in a different order. By default, transactions
+    /// from the pool are ordered by their hashes.
+    ///
+    /// The order is determined by a Fisher-Yates shuffle with the given seed, so the same seed
+    /// results in the same order for the same pool contents. This allows to write
+    /// reproducible tests checking that services do not depend on the order of transactions.
+    /// Transactions added to the pool after the call follow the reordered ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// // Send transactions to the testkit...
+    /// testkit.randomly_reorder_mempool_transactions(42);
+    /// // Transactions in the block are shuffled.
+    /// testkit.create_block();
+    /// # }
+    /// ```
+    ///
+    /// [`create_block`]: #method.create_block
+    pub fn randomly_reorder_mempool_transactions(&mut self, seed: u64) {
+        self.poll_events();
+
+        let mut tx_hashes = self.ordered_pool_transactions(&self.core_schema());
+        shuffle::shuffle(&mut tx_hashes, seed);
+        self.pool_order = Some(tx_hashes);
+        self.last_shuffle_seed = Some(seed);
+    }
+
+    /// Creates a block with the given transactions in a random, but reproducible order.
+    /// The order is determined by a Fisher-Yates shuffle with the given seed, so the same
+    /// seed results in the same order of the same transactions. Transactions are otherwise
+    /// handled in the same way as in [`create_block_with_transactions`].
+    ///
+    /// The seed is included into the panic messages of the subsequent testkit assertions,
+    /// so that a failing order can be replayed.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of transactions has been already committed to the blockchain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// // Transactions in the block are executed in the shuffled order.
+    /// let block = testkit.create_block_with_transactions_shuffled(Vec::new(), 42);
+    /// assert!(block.is_empty());
+    /// # }
+    /// ```
+    ///
+    /// [`create_block_with_transactions`]: #method.create_block_with_transactions
+    pub fn create_block_with_transactions_shuffled<I>(
+        &mut self,
+        txs: I,
+        seed: u64,
+    ) -> BlockWithTransactions
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        self.ensure_genesis_block();
+        let height = self.height().next();
+        self.poll_events();
+        let mut tx_hashes = self.add_transactions_into_pool(txs)
+            .unwrap_or_else(|e| panic!("Cannot create block at height {}: {}", height, e));
+        shuffle::shuffle(&mut tx_hashes, seed);
+        self.last_shuffle_seed = Some(seed);
+        self.try_create_block_with_tx_hashes(&tx_hashes)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Cannot create block at height {} (transactions shuffled with seed {}): {}",
+                    height, seed, e
+                )
+            })
+    }
+
+    /// Returns a note on the latest shuffle of transactions to append to the panic messages
+    /// of assertions, or an empty string if transactions have not been shuffled.
+    fn shuffle_note(&self) -> String {
+        match self.last_shuffle_seed {
+            Some(seed) => format!("\n(transactions were shuffled with seed {})", seed),
+            None => String::new(),
+        }
+    }
+
+    /// Creates a block with the given transactions, which is committed without precommits
+    /// from the `excluded` validators. Transactions are handled in the same way as in
+    /// [`create_block_with_transactions`].
+    ///
+    /// The method allows to test logic depending on the number of precommits
+    /// for a block, e.g., to create blocks with exactly `2f + 1` precommits, which is
+    /// the minimum accepted by the Byzantine Fault Tolerance consensus.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `InsufficientPrecommits` error if the number of remaining precommits
+    ///   is less than [`majority_count`]. The blockchain and the pool are left intact
+    ///   in this case.
+    /// - Returns an error in the same cases as [`try_create_block_with_transactions`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::ValidatorId;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// let block = testkit
+    ///     .create_block_without_precommit_from(&[ValidatorId(3)], vec![])
+    ///     .unwrap();
+    /// assert_eq!(block.precommits.len(), 3);
+    /// // 2 precommits are not enough for 4 validators.
+    /// assert!(
+    ///     testkit
+    ///         .create_block_without_precommit_from(&[ValidatorId(2), ValidatorId(3)], vec![])
+    ///         .is_err()
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`create_block_with_transactions`]: #method.create_block_with_transactions
+    /// [`try_create_block_with_transactions`]: #method.try_create_block_with_transactions
+    /// [`majority_count`]: #method.majority_count
+    pub fn create_block_without_precommit_from<I>(
+        &mut self,
+        excluded: &[ValidatorId],
+        txs: I,
+    ) -> Result<BlockWithTransactions, Error>
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        self.ensure_genesis_block();
+        let precommits = self.network()
+            .validators()
+            .iter()
+            .map(|v| v.validator_id().unwrap())
+            .filter(|&id| !excluded.contains(&id) && self.is_validator_available(id))
+            .count();
+        let majority_count = self.majority_count();
+        if precommits < majority_count {
+            return Err(Error::InsufficientPrecommits {
+                precommits,
+                majority_count,
+            });
+        }
+
+        self.poll_events();
+        let tx_hashes = self.add_transactions_into_pool(txs)?;
+        let schema = self.core_schema();
+        self.do_create_block(
+            &schema,
+            &tx_hashes,
+            BlockParams {
+                excluded_precommits: excluded,
+                ..BlockParams::default()
+            },
+        )
+    }
+
+    /// Runs the scenario with this testkit.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any step of the scenario fails. See [`Scenario`] for details.
+    ///
+    /// [`Scenario`]: struct.Scenario.html
+    pub fn run_scenario(&mut self, scenario: Scenario) {
+        scenario.run(self)
+    }
+
+    /// Generates `n` proposals of different blocks at the next height together with
+    /// the precommits of all validators for them. The proposals contain the given
+    /// transactions and are signed by the validators with ids `0..n`, so the proposed blocks
+    /// differ in their proposers.
+    ///
+    /// None of the blocks is committed; the transactions are added to the pool.
+    /// Any of the proposals can be committed afterwards with
+    /// [`create_block_with_custom_precommits`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `n` exceeds the number of validators.
+    /// - Panics if any of the transactions is already committed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::{Height, ValidatorId};
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// let proposals = testkit.concurrent_block_proposals(2, vec![]);
+    /// assert_eq!(proposals.len(), 2);
+    /// assert_eq!(proposals[1].0.validator(), ValidatorId(1));
+    /// assert_ne!(proposals[0].1[0].block_hash(), proposals[1].1[0].block_hash());
+    /// assert_eq!(testkit.height(), Height(0));
+    /// # }
+    /// ```
+    ///
+    /// [`create_block_with_custom_precommits`]: #method.create_block_with_custom_precommits
+    pub fn concurrent_block_proposals(
+        &mut self,
+        n: usize,
+        txs: Vec<Box<dyn Transaction>>,
+    ) -> Vec<(Propose, Vec<Precommit>)> {
+        self.ensure_genesis_block();
+        let validator_count = self.network().validators().len();
+        assert!(
+            n <= validator_count,
+            "Cannot generate {} proposals with different proposers: the network has {} validators",
+            n,
+            validator_count
+        );
+
+        self.poll_events();
+        let new_block_height = self.height().next();
+        let tx_hashes = self.add_transactions_into_pool(txs).unwrap_or_else(|e| {
+            panic!("Cannot generate proposals at height {}: {}", new_block_height, e)
+        });
+        let last_hash = self.last_block_hash();
+        let now = SystemTime::now();
+
+        self.network()
+            .validators()
+            .iter()
+            .take(n)
+            .map(|proposer| {
+                let proposer_id = proposer.validator_id().unwrap();
+                let (block_hash, _) = self.blockchain
+                    .create_patch(proposer_id, new_block_height, &tx_hashes);
+                let propose = proposer.create_propose(new_block_height, &last_hash, &tx_hashes);
+                let precommits = self.network()
+                    .validators()
+                    .iter()
+                    .map(|v| {
+                        let time = self.validator_clock(v.validator_id().unwrap(), now);
+                        v.try_create_precommit_at(&propose, &block_hash, time).unwrap()
+                    })
+                    .collect();
+                (propose, precommits)
+            })
+            .collect()
+    }
+
+    /// Creates a block from the given `Propose` and commits it with the given precommits.
+    ///
+    /// Unlike other methods creating blocks, this method does not generate any consensus
+    /// messages and does not check the precommits in any way: they are stored in the blockchain
+    /// as is. Thus, the method can be used to inject arbitrarily constructed consensus messages,
+    /// e.g., to test signature verification or precommit counting logic.
+    ///
+    /// The block is proposed by the validator specified in `propose` and contains transactions
+    /// listed in `propose`; these transactions should be in the pool.
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the created block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the height of `propose` differs from the height of the next block.
+    /// - Panics if `propose` references transactions unknown to the testkit.
+    /// - Panics if the block cannot be committed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// // Take a propose with the real block hash signed by all validators.
+    /// let (propose, precommits) = testkit.concurrent_block_proposals(1, vec![]).pop().unwrap();
+    /// // Only a single validator has signed the precommit, which is not enough
+    /// // for the block to be accepted by the real network.
+    /// let block = testkit.create_block_with_custom_precommits(propose, precommits[..1].to_vec());
+    /// assert_eq!(block.height(), Height(1));
+    /// assert_eq!(block.precommits.len(), 1);
+    /// # }
+    /// ```
+    pub fn create_block_with_custom_precommits(
+        &mut self,
+        propose: Propose,
+        precommits: Vec<Precommit>,
+    ) -> BlockWithTransactions {
+        self.ensure_genesis_block();
+        self.poll_events();
+
+        let new_block_height = self.height().next();
+        assert_eq!(
+            propose.height(),
+            new_block_height,
+            "The height of the propose should be equal to the height of the next block"
+        );
+        let schema = self.core_schema();
+        let tx_hashes = propose.transactions();
+        let params = BlockParams {
+            custom_messages: Some((&propose, &precommits[..])),
+            ..BlockParams::default()
+        };
+        self.do_create_block(&schema, &tx_hashes, params)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Cannot create block at height {} with custom precommits: {}",
+                    new_block_height, e
+                )
+            })
+    }
+
+    /// Adds transaction into persistent pool.
+    pub fn add_tx(&mut self, transaction: RawMessage) {
+        let mut fork = self.blockchain.fork();
+        let mut schema = CoreSchema::new(&mut fork);
+        schema.add_transaction_into_pool(transaction)
+    }
+
+    /// Checks if transaction can be found in pool
+    pub fn is_tx_in_pool(&self, tx_hash: &Hash) -> bool {
+        let snapshot = self.blockchain.snapshot();
+        let schema = CoreSchema::new(&snapshot);
+        schema.transactions_pool().contains(tx_hash)
+    }
+
+    /// Asserts that all transactions with the specified hashes are in the pool.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of the transactions is not in the pool. The panic message lists
+    ///   all missing hashes and states for each of them whether the transaction is already
+    ///   committed or is unknown to the testkit.
+    pub fn assert_pool_contains(&self, tx_hashes: &[Hash]) {
+        let missing = missing_pool_transactions(&self.core_schema(), tx_hashes);
+        if !missing.is_empty() {
+            panic!("{}", Error::TransactionsNotInPool(MissingTransactions(missing)));
+        }
+    }
+
+    /// Asserts that none of the transactions with the specified hashes is committed
+    /// to the blockchain. The transactions may be in the pool or unknown to the testkit.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of the transactions is committed. The panic message lists
+    ///   the hashes of all committed transactions together with their locations.
+    pub fn assert_not_committed(&self, tx_hashes: &[Hash]) {
+        use std::fmt::Write;
+
+        let schema = self.core_schema();
+        let locations = schema.transactions_locations();
+        let mut report = String::new();
+        let mut committed_count = 0;
+        for hash in tx_hashes {
+            if let Some(location) = locations.get(hash) {
+                committed_count += 1;
+                writeln!(
+                    report,
+                    "  transaction {:?}: block at height {}, position {}",
+                    hash,
+                    location.block_height(),
+                    location.position_in_block()
+                ).unwrap();
+            }
+        }
+        assert!(
+            committed_count == 0,
+            "{} transaction(s) are committed to the blockchain:\n{}",
+            committed_count,
+            report
+        );
+    }
+
+    /// Creates a chain of blocks until a given height.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// # extern crate exonum;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// testkit.create_blocks_until(Height(5));
+    /// assert_eq!(Height(5), testkit.height());
+    /// # }
+    pub fn create_blocks_until(&mut self, height: Height) {
+        self.ensure_genesis_block();
+        while self.height() < height {
+            self.create_block();
+        }
+    }
+
+    /// Creates the specified number of blocks in a row. This method is a faster
+    /// alternative to calling [`create_block`] in a loop, which is useful for moving
+    /// the blockchain far ahead, e.g., to test height-dependent logic of services.
+    ///
+    /// The created blocks are fully valid: they are proposed by the leader and contain
+    /// precommits from all validators, just like blocks created with [`create_block`].
+    /// The speedup comes from skipping the steps that are not needed for empty blocks:
+    /// events are polled only before and after fast-forwarding, and created blocks
+    /// are not loaded back from the storage. Each block is still committed to the storage
+    /// separately, since the patch of a block is created on top of the previous block.
+    /// If the transaction pool is not empty, the method falls back to [`create_block`]
+    /// for each block.
+    ///
+    /// Note that transactions sent to the testkit during fast-forwarding
+    /// (e.g., by services in the `after_commit` hook) are put into the pool
+    /// only after all blocks are created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum_testkit;
+    /// # extern crate exonum;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// testkit.fast_forward(1_000);
+    /// assert_eq!(Height(1_000), testkit.height());
+    /// # }
+    /// ```
+    ///
+    /// [`create_block`]: #method.create_block
+    pub fn fast_forward(&mut self, blocks: u64) {
+        self.ensure_genesis_block();
+        self.poll_events();
+
+        let pool_is_empty = self.core_schema().transactions_pool_len() == 0;
+        if pool_is_empty {
+            let mut height = self.height();
+            let mut last_hash = self.last_block_hash();
+            for _ in 0..blocks {
+                height.increment();
+                last_hash = self.commit_block(height, &last_hash, &[], BlockParams::default())
+                    .unwrap_or_else(|e| {
+                        panic!("Cannot fast-forward to block at height {}: {}", height, e)
+                    });
+                // The pool stays empty until the events are polled, so the blocks trivially
+                // retain all pool transactions they do not include.
+                if let Some(ref mut history) = self.pool_size_history {
+                    history.push((height, 0));
+                }
+            }
+        } else {
+            for _ in 0..blocks {
+                self.create_block();
+            }
+        }
+
+        self.poll_events();
+    }
+
+    /// Returns the hash of latest committed block.
+    pub fn last_block_hash(&self) -> crypto::Hash {
+        self.blockchain.last_hash()
+    }
+
+    /// Returns the height of latest committed block.
+    pub fn height(&self) -> Height {
+        self.blockchain.last_block().height()
+    }
+
+    /// Returns the height observed by transactions executed in the next block, i.e., the value
+    /// returned by `Schema::height()` for the fork passed to `Transaction::execute`.
+    ///
+    /// As the block being created is not committed during transaction execution, this height
+    /// is equal to the height of the latest committed block; the block containing
+    /// the transactions has the next height. The same height is observed by transactions
+    /// executed with [`probe`] and [`probe_all`], so services branching on the height
+    /// behave identically when probed and when included into a block.
+    ///
+    /// [`probe`]: #method.probe
+    /// [`probe_all`]: #method.probe_all
+    pub fn execution_height(&self) -> Height {
+        self.height()
+    }
+
+    /// Returns the block committed at the given height, or `None` if the height exceeds
+    /// the current blockchain height.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the hash of the block at the given height is stored in the blockchain,
+    ///   but the block itself is missing, which indicates the database corruption.
+    pub fn committed_block_at(&self, height: Height) -> Option<Block> {
+        let schema = self.core_schema();
+        let block_hash = schema.block_hash_by_height(height)?;
+        let block = schema.blocks().get(&block_hash).unwrap_or_else(|| {
+            panic!(
+                "Block with hash {:?} at height {} is missing from the database",
+                block_hash, height
+            )
+        });
+        Some(block)
+    }
+
+    /// Returns the block time at the given height, i.e., the median of the times
+    /// of the precommits authorizing the block. For an even number of precommits,
+    /// the median is the midpoint between the two middle times.
+    ///
+    /// Returns `None` if the height exceeds the current blockchain height or the block
+    /// has no precommits (e.g., the genesis block).
+    ///
+    /// See [`set_validator_clock_offset`] for the way to control the times of precommits.
+    ///
+    /// [`set_validator_clock_offset`]: #method.set_validator_clock_offset
+    pub fn block_time(&self, height: Height) -> Option<SystemTime> {
+        let explorer = self.explorer();
+        let block = explorer.block(height)?;
+        let mut times: Vec<SystemTime> = block
+            .precommits()
+            .iter()
+            .map(|precommit| precommit.time().into())
+            .collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort();
+
+        let middle = times.len() / 2;
+        if times.len() % 2 == 1 {
+            Some(times[middle])
+        } else {
+            let (lower, upper) = (times[middle - 1], times[middle]);
+            let half_interval = upper.duration_since(lower).unwrap() / 2;
+            Some(lower + half_interval)
+        }
+    }
+
+    /// Returns the genesis block (i.e., the block at height 0) in the same form
+    /// as it is served by the `v1/block` endpoint of the explorer API.
+    ///
+    /// The genesis block has several special properties:
+    ///
+    /// - It is proposed by the validator with id 0 and has a zero `prev_hash`.
+    /// - It contains no transactions, so its `tx_hash` is zero.
+    /// - It is not authorized by any precommits.
+    /// - It commits the genesis configuration, i.e., the `StoredConfiguration` built from
+    ///   the validator keys and the consensus config of the `GenesisConfig` together with
+    ///   the configurations returned by `Service::initialize` for each service.
+    ///   This configuration has zero `previous_cfg_hash` and `actual_from` height.
+    ///
+    /// The `state_hash` of the genesis block aggregates the state of core tables
+    /// (including the genesis configuration) and the state hashes of services
+    /// after their initialization.
+    pub fn genesis_block(&self) -> BlockInfo {
+        self.explorer()
+            .block(Height::zero())
+            .map(BlockInfo::from)
+            .expect("Genesis block is missing from the database")
+    }
+
+    /// Returns the configurations of services in the genesis block, keyed by service names.
+    ///
+    /// The configurations are returned by `Service::initialize` when the genesis block
+    /// is created, unless overridden with [`TestKitBuilder::with_genesis_service_config`].
+    ///
+    /// [`TestKitBuilder::with_genesis_service_config`]:
+    /// struct.TestKitBuilder.html#method.with_genesis_service_config
+    pub fn genesis_service_configs(&self) -> BTreeMap<String, serde_json::Value> {
+        self.core_schema()
+            .configuration_by_height(Height::zero())
+            .services
+    }
+
+    /// Asserts that the height of the latest committed block is equal to the expected one.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the blockchain height differs from the expected one. The panic message
+    ///   includes the actual height and the hash of the latest block.
+    pub fn assert_block_height_is(&self, expected: Height) {
+        let last_block = self.blockchain.last_block();
+        assert!(
+            last_block.height() == expected,
+            "Expected blockchain height {}, but was {}. Last block hash: {}{}",
+            expected,
+            last_block.height(),
+            self.blockchain.last_hash(),
+            self.shuffle_note()
+        );
+    }
+
+    /// Asserts that the block at the specified height is authorized by the expected number
+    /// of precommits stored in the blockchain.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no block at the specified height.
+    /// - Panics if the number of stored precommits differs from the expected one.
+    ///   The panic message includes the actual number of precommits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::{Height, ValidatorId};
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// testkit.create_block();
+    /// testkit
+    ///     .create_block_without_precommit_from(&[ValidatorId(3)], vec![])
+    ///     .unwrap();
+    /// testkit.assert_precommit_count(Height(1), 4);
+    /// testkit.assert_precommit_count(Height(2), 3);
+    /// # }
+    /// ```
+    pub fn assert_precommit_count(&self, height: Height, expected_count: usize) {
+        let schema = self.core_schema();
+        let block_hash = schema.block_hash_by_height(height).unwrap_or_else(|| {
+            panic!(
+                "Block at height {} does not exist; the blockchain height is {}",
+                height,
+                schema.height()
+            )
+        });
+        let actual_count = schema.precommits(&block_hash).len() as usize;
+        assert!(
+            actual_count == expected_count,
+            "Expected {} precommit(s) for the block at height {}, but {} are stored",
+            expected_count,
+            height,
+            actual_count
+        );
+    }
+
+    /// Asserts that no transaction in the committed blocks, from the genesis block
+    /// to the latest one, was executed with an error. This is useful as a final check
+    /// at the end of a test scenario.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any committed transaction was executed with an error. The panic message
+    ///   lists the heights of the blocks with errors, together with the hashes
+    ///   of the erroneous transactions and the errors.
+    pub fn assert_no_errors_in_any_block(&self) {
+        use std::fmt::Write;
+
+        let snapshot = self.snapshot();
+        let schema = CoreSchema::new(&snapshot);
+        let results = schema.transaction_results();
+        let mut report = String::new();
+        let mut blocks_with_errors = 0;
+        for height in 0..=schema.height().0 {
+            let errors: Vec<_> = schema
+                .block_transactions(Height(height))
+                .iter()
+                .filter_map(|hash| match results.get(&hash) {
+                    Some(Err(e)) => Some((hash, e)),
+                    _ => None,
+                })
+                .collect();
+            if errors.is_empty() {
+                continue;
+            }
+
+            blocks_with_errors += 1;
+            writeln!(report, "block at height {}:", height).unwrap();
+            for (hash, error) in errors {
+                writeln!(report, "  transaction {:?}: {}", hash, error).unwrap();
+            }
+        }
+        assert!(
+            blocks_with_errors == 0,
+            "Transactions were executed with errors in {} block(s):\n{}{}",
+            blocks_with_errors,
+            report,
+            self.shuffle_note()
+        );
+    }
+
+    /// Returns the blockchain explorer instance.
+    pub fn explorer(&self) -> BlockchainExplorer {
+        BlockchainExplorer::new(&self.blockchain)
+    }
+
+    /// Returns the actual blockchain configuration.
+    pub fn actual_configuration(&self) -> StoredConfiguration {
+        CoreSchema::new(&self.snapshot()).actual_configuration()
+    }
+
+    /// Returns the consensus parameters of the actual blockchain configuration.
+    pub fn consensus_config(&self) -> ConsensusConfig {
+        self.actual_configuration().consensus
+    }
+
+    /// Returns the configurations that have been actual at any point of the blockchain history,
+    /// together with the heights since which they are actual. The configurations are listed
+    /// in the ascending order of heights, starting from the genesis configuration
+    /// actual from `Height(0)`; the last configuration is the actual one.
+    ///
+    /// Configurations committed to the blockchain, but scheduled for a future height
+    /// are not included; use [`scheduled_configurations`] to inspect them.
+    ///
+    /// [`scheduled_configurations`]: #method.scheduled_configurations
+    pub fn history_of_configuration_changes(&self) -> Vec<(Height, StoredConfiguration)> {
+        let schema = self.core_schema();
+        let next_height = schema.height().next();
+        let mut history = Vec::new();
+        for cfg_ref in schema.configs_actual_from().iter() {
+            if cfg_ref.actual_from() > next_height {
+                break;
+            }
+            let cfg_hash = cfg_ref.cfg_hash();
+            let config = schema.configuration_by_hash(cfg_hash).unwrap_or_else(|| {
+                panic!("Configuration with hash {:?} is absent in the blockchain", cfg_hash)
+            });
+            history.push((cfg_ref.actual_from(), config));
+        }
+        history
+    }
+
+    /// Asserts that the configuration stored in the genesis block matches the expected one.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the validator keys or the consensus configuration differ from
+    ///   the expected ones.
+    pub fn assert_genesis_config_equals(&self, expected: &GenesisConfig) {
+        let genesis_config = CoreSchema::new(&self.snapshot()).configuration_by_height(Height(0));
+        assert_eq!(
+            genesis_config.validator_keys, expected.validator_keys,
+            "Validator keys in the genesis configuration differ from the expected ones{}",
+            self.shuffle_note()
+        );
+        assert_eq!(
+            genesis_config.consensus, expected.consensus,
+            "Consensus configuration in the genesis block differs from the expected one{}",
+            self.shuffle_note()
+        );
+    }
+
+    /// Asserts that the service configuration stored in the actual blockchain configuration
+    /// matches the expected one. The configuration is looked up by the service name
+    /// in the `services` section of `StoredConfiguration` and is deserialized as `D`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the actual configuration has no section for the service.
+    /// - Panics if the service configuration cannot be deserialized as `D`.
+    /// - Panics if the deserialized configuration differs from `expected`. The panic message
+    ///   contains both configurations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # #[macro_use] extern crate serde_json;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// let mut proposal = testkit.propose_configuration_at(3);
+    /// proposal.set_service_config("my_service", json!({ "limit": 10 }));
+    /// testkit.commit_configuration_change(proposal);
+    /// testkit.create_blocks_until(exonum::helpers::Height(3));
+    /// testkit.assert_service_config("my_service", json!({ "limit": 10 }));
+    /// # }
+    /// ```
+    pub fn assert_service_config<D>(&self, service_name: &str, expected: D)
+    where
+        D: DeserializeOwned + PartialEq + fmt::Debug,
+    {
+        let config = self.actual_configuration();
+        let value = config.services.get(service_name).unwrap_or_else(|| {
+            panic!(
+                "Actual configuration does not contain the configuration of service `{}`",
+                service_name
+            )
+        });
+        let actual: D = serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+            panic!(
+                "Cannot deserialize the configuration of service `{}`: {}",
+                service_name, e
+            )
+        });
+        assert!(
+            actual == expected,
+            "Configuration of service `{}` differs from the expected one\n\
+             expected: {:#?}\n  actual: {:#?}{}",
+            service_name,
+            expected,
+            actual,
+            self.shuffle_note()
+        );
+    }
+
+    /// Returns reference to validator with the given identifier.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if validator with the given id is absent in test network.
+    pub fn validator(&self, id: ValidatorId) -> &TestNode {
+        &self.network.validators()[id.0 as usize]
+    }
+
+    /// Sets the offset of the clock of the specified validator. The time of precommits
+    /// created by the validator is equal to the current system time shifted by the offset,
+    /// backwards if `negative` is `true`. The system time is taken once per block,
+    /// so the difference between the times of precommits in a block is exactly
+    /// the difference between the offsets of the corresponding validators.
+    ///
+    /// The offset is applied to all subsequent blocks until it is cleared with
+    /// [`clear_validator_clock_offset`]. Precommits passed to
+    /// [`create_block_with_custom_precommits`] are not affected.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the validator with the given id is absent in the test network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::{Height, ValidatorId};
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # use std::time::{Duration, SystemTime};
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(3).create();
+    /// testkit.set_validator_clock_offset(ValidatorId(1), Duration::from_secs(5), false);
+    /// testkit.set_validator_clock_offset(ValidatorId(2), Duration::from_secs(5), true);
+    /// let block = testkit.create_block();
+    ///
+    /// // The median time coincides with the time of the validator without offset.
+    /// let precommit_time: SystemTime = block.precommits[0].time().into();
+    /// assert_eq!(testkit.block_time(Height(1)), Some(precommit_time));
+    /// # }
+    /// ```
+    ///
+    /// [`clear_validator_clock_offset`]: #method.clear_validator_clock_offset
+    /// [`create_block_with_custom_precommits`]: #method.create_block_with_custom_precommits
+    pub fn set_validator_clock_offset(
+        &mut self,
+        validator_id: ValidatorId,
+        offset: Duration,
+        negative: bool,
+    ) {
+        assert!(
+            usize::from(validator_id.0) < self.network().validators().len(),
+            "{}",
+            Error::UnknownValidator(validator_id)
+        );
+        self.clock_offsets.insert(validator_id, (offset, negative));
+    }
+
+    /// Clears the offset of the clock of the specified validator set with
+    /// [`set_validator_clock_offset`].
+    ///
+    /// [`set_validator_clock_offset`]: #method.set_validator_clock_offset
+    pub fn clear_validator_clock_offset(&mut self, validator_id: ValidatorId) {
+        self.clock_offsets.remove(&validator_id);
+    }
+
+    /// Shuts down the specified validator. A shut down validator does not propose blocks,
+    /// and its precommits are excluded from all subsequent blocks until it is brought back
+    /// with [`restart_validator`]. Blocks are proposed by the first validator that is online.
+    ///
+    /// Creating a block fails with the `InsufficientPrecommits` error if the number
+    /// of validators online is less than [`majority_count`]. Shutting down a validator that
+    /// is already offline has no effect.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the validator with the given id is absent in the test network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::ValidatorId;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// testkit.shutdown_validator(ValidatorId(0));
+    /// let block = testkit.create_block();
+    /// assert_eq!(block.header.proposer_id(), ValidatorId(1));
+    /// assert_eq!(block.precommits.len(), 3);
+    ///
+    /// testkit.restart_validator(ValidatorId(0));
+    /// assert_eq!(testkit.create_block().precommits.len(), 4);
+    /// # }
+    /// ```
+    ///
+    /// [`restart_validator`]: #method.restart_validator
+    /// [`majority_count`]: #method.majority_count
+    pub fn shutdown_validator(&mut self, validator_id: ValidatorId) {
+        assert!(
+            usize::from(validator_id.0) < self.network().validators().len(),
+            "{}",
+            Error::UnknownValidator(validator_id)
+        );
+        self.trace(format_args!("shutdown validator {}", validator_id));
+        self.offline_validators.insert(validator_id);
+    }
+
+    /// Brings back the validator shut down with [`shutdown_validator`]. Restarting
+    /// a validator that is online has no effect.
+    ///
+    /// [`shutdown_validator`]: #method.shutdown_validator
+    pub fn restart_validator(&mut self, validator_id: ValidatorId) {
+        self.trace(format_args!("restart validator {}", validator_id));
+        self.offline_validators.remove(&validator_id);
+    }
+
+    /// Returns `true` if the specified validator is shut down with [`shutdown_validator`].
+    ///
+    /// [`shutdown_validator`]: #method.shutdown_validator
+    pub fn is_validator_offline(&self, validator_id: ValidatorId) -> bool {
+        self.offline_validators.contains(&validator_id)
+    }
+
+    /// Returns `true` if the validator is neither shut down nor separated from our node
+    /// by a network partition.
+    fn is_validator_available(&self, validator_id: ValidatorId) -> bool {
+        !self.offline_validators.contains(&validator_id)
+            && self.network().is_reachable(validator_id)
+    }
+
+    /// Returns `true` if the network partition created with [`TestNetwork::partition`]
+    /// prevents the consensus on the next block, i.e., the partition group of our node
+    /// contains less than [`majority_count`] validators. Block creation fails with
+    /// the `InsufficientPrecommits` error in this case.
+    ///
+    /// Validators shut down with [`shutdown_validator`] are not taken into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::ValidatorId;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// testkit.network_mut().partition(&[ValidatorId(3)]);
+    /// assert!(!testkit.network_partition_affects_current_block());
+    /// assert_eq!(testkit.create_block().precommits.len(), 3);
+    ///
+    /// testkit.network_mut().partition(&[ValidatorId(0), ValidatorId(1)]);
+    /// assert!(testkit.network_partition_affects_current_block());
+    /// assert!(testkit.try_create_block().is_err());
+    /// # }
+    /// ```
+    ///
+    /// [`TestNetwork::partition`]: struct.TestNetwork.html#method.partition
+    /// [`majority_count`]: #method.majority_count
+    /// [`shutdown_validator`]: #method.shutdown_validator
+    pub fn network_partition_affects_current_block(&self) -> bool {
+        self.network().reachable_validators().len() < self.majority_count()
+    }
+
+    /// Returns the first validator that is online, which proposes blocks by default.
+    fn online_proposer(&self) -> Result<ValidatorId, Error> {
+        self.network()
+            .validators()
+            .iter()
+            .map(|v| v.validator_id().unwrap())
+            .find(|&id| {
+                self.is_validator_available(id)
+                    && self.byzantine_behavior(id) != Some(ByzantineBehavior::DropPropose)
+            })
+            .ok_or_else(|| Error::InsufficientPrecommits {
+                precommits: 0,
+                majority_count: self.majority_count(),
+            })
+    }
+
+    /// Configures the specified validator to exhibit the given Byzantine behavior
+    /// in the blocks created by the testkit, until the behavior is cleared with
+    /// [`clear_byzantine_behavior`]. Setting a behavior replaces the previous one.
+    ///
+    /// The behaviors are emulated as follows:
+    ///
+    /// - `Equivocate`: for each block the validator proposes, it signs a conflicting
+    ///   propose at the same height.
+    /// - `DropPropose`: the validator is skipped when choosing the proposer of a block,
+    ///   unless the proposer is specified explicitly.
+    /// - `SignConflictingPrecommits`: for each block, the validator signs a precommit
+    ///   for a conflicting block in addition to the precommit for the committed one.
+    ///
+    /// The conflicting messages are not committed to the blockchain and are available
+    /// via [`conflicting_messages`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the validator with the given id is absent in the test network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::{Height, ValidatorId};
+    /// # use exonum_testkit::{ByzantineBehavior, ConflictingMessages, TestKitBuilder};
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// testkit.byzantine_validator(ValidatorId(0), ByzantineBehavior::DropPropose);
+    /// testkit.byzantine_validator(ValidatorId(2), ByzantineBehavior::SignConflictingPrecommits);
+    /// let block = testkit.create_block();
+    /// assert_eq!(block.header.proposer_id(), ValidatorId(1));
+    ///
+    /// let messages = testkit.conflicting_messages(Height(1));
+    /// assert_eq!(messages.len(), 1);
+    /// match messages[0] {
+    ///     ConflictingMessages::Precommits(ref committed, ref other) => {
+    ///         assert_eq!(committed.validator(), ValidatorId(2));
+    ///         assert_ne!(committed.block_hash(), other.block_hash());
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`clear_byzantine_behavior`]: #method.clear_byzantine_behavior
+    /// [`conflicting_messages`]: #method.conflicting_messages
+    pub fn byzantine_validator(&mut self, validator_id: ValidatorId, behavior: ByzantineBehavior) {
+        assert!(
+            usize::from(validator_id.0) < self.network().validators().len(),
+            "{}",
+            Error::UnknownValidator(validator_id)
+        );
+        self.trace(format_args!(
+            "byzantine validator {}: {:?}",
+            validator_id, behavior
+        ));
+        self.byzantine_validators.insert(validator_id, behavior);
+    }
+
+    /// Makes the validator configured with [`byzantine_validator`] honest again.
+    ///
+    /// [`byzantine_validator`]: #method.byzantine_validator
+    pub fn clear_byzantine_behavior(&mut self, validator_id: ValidatorId) {
+        self.byzantine_validators.remove(&validator_id);
+    }
+
+    /// Returns the conflicting messages signed by Byzantine validators for the block
+    /// at the specified height. See [`byzantine_validator`] for details. The messages
+    /// of the blocks discarded by a [`rollback`] (e.g., when probing transactions)
+    /// are forgotten.
+    ///
+    /// [`byzantine_validator`]: #method.byzantine_validator
+    /// [`rollback`]: #method.rollback
+    pub fn conflicting_messages(&self, height: Height) -> &[ConflictingMessages] {
+        self.conflicting_messages
+            .get(&height)
+            .map_or(&[], |messages| messages.as_slice())
+    }
+
+    fn byzantine_behavior(&self, validator_id: ValidatorId) -> Option<ByzantineBehavior> {
+        self.byzantine_validators.get(&validator_id).cloned()
+    }
+
+    /// Returns the time of the clock of the specified validator given the system time.
+    fn validator_clock(&self, validator_id: ValidatorId, now: SystemTime) -> SystemTime {
+        match self.clock_offsets.get(&validator_id) {
+            Some(&(offset, false)) => now + offset,
+            Some(&(offset, true)) => now - offset,
+            None => now,
+        }
+    }
+
+    /// Returns sufficient number of validators for the Byzantine Fault Tolerance consensus.
+    pub fn majority_count(&self) -> usize {
+        self.network().majority_count()
+    }
+
+    /// Creates a `Propose` message with no transactions for the given height, which is
+    /// signed by the leader and refers to the last committed block as the previous one.
+    /// The propose is not processed by the testkit; it is intended for the tests
+    /// of the code verifying consensus messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let testkit = TestKitBuilder::validator().with_validators(2).create();
+    /// let propose = testkit.propose_with_empty_tx_hashes(Height(1));
+    /// assert_eq!(propose.height(), Height(1));
+    /// assert_eq!(*propose.prev_hash(), testkit.last_block_hash());
+    /// assert!(propose.transactions().is_empty());
+    /// # }
+    /// ```
+    pub fn propose_with_empty_tx_hashes(&self, height: Height) -> Propose {
+        self.leader()
+            .create_empty_propose(height, &self.last_block_hash())
+    }
+
+    /// Sets the checker of the transactions committed in the subsequent blocks.
+    /// The checker allows to detect transactions that are committed, but have no effect
+    /// because of an unmet precondition, e.g., if a prerequisite transaction is ordered
+    /// after the dependent one. See [`TxDependencyChecker`] for details.
+    ///
+    /// Transactions flagged by the checker can be retrieved with [`flagged_transactions`].
+    /// If the checker is [`panicking`], creating a block with flagged transactions panics
+    /// after the block is committed; this applies to the `try_`-prefixed methods as well.
+    ///
+    /// Transactions are replayed by the checker before each block is committed,
+    /// so the checker slows down block creation. The blocks created to probe transactions
+    /// (e.g., with [`probe_all`] or [`dry_run_block`]) are not checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::{
+    /// #     blockchain::Transaction, helpers::Height, storage::{ListIndex, Snapshot},
+    /// # };
+    /// # use exonum_testkit::{TestKitBuilder, TxDependencyChecker};
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    /// // Transactions of the tested service are expected to append to the `events` list;
+    /// // flag the transactions which do not.
+    /// let checker = TxDependencyChecker::new(
+    ///     |_: &dyn Transaction, before: &dyn Snapshot, after: &dyn Snapshot| {
+    ///         let events_before: ListIndex<_, u64> = ListIndex::new("events", before);
+    ///         let events_after: ListIndex<_, u64> = ListIndex::new("events", after);
+    ///         events_before.len() == events_after.len()
+    ///     },
+    /// );
+    /// testkit.set_tx_dependency_checker(checker.panicking());
+    /// testkit.create_block();
+    /// assert!(testkit.flagged_transactions(Height(1)).is_empty());
+    /// # }
+    /// ```
+    ///
+    /// [`TxDependencyChecker`]: struct.TxDependencyChecker.html
+    /// [`flagged_transactions`]: #method.flagged_transactions
+    /// [`panicking`]: struct.TxDependencyChecker.html#method.panicking
+    /// [`probe_all`]: #method.probe_all
+    /// [`dry_run_block`]: #method.dry_run_block
+    pub fn set_tx_dependency_checker(&mut self, checker: TxDependencyChecker) {
+        self.tx_dependency_checker = Some(Arc::new(checker));
+    }
+
+    /// Removes the checker set with [`set_tx_dependency_checker`].
+    ///
+    /// [`set_tx_dependency_checker`]: #method.set_tx_dependency_checker
+    pub fn clear_tx_dependency_checker(&mut self) {
+        self.tx_dependency_checker = None;
+    }
+
+    /// Returns the hashes of the transactions in the block at the given height flagged
+    /// by the checker set with [`set_tx_dependency_checker`], in the order of their execution.
+    /// Returns an empty slice if no transactions were flagged or the block was created
+    /// without a checker. Flags of the blocks discarded by a [`rollback`] are forgotten.
+    ///
+    /// The flags are not included into the block info returned by the `create_block*`
+    /// methods, since it is the `BlockWithTransactions` type shared with the explorer API
+    /// of the node.
+    ///
+    /// [`set_tx_dependency_checker`]: #method.set_tx_dependency_checker
+    /// [`rollback`]: #method.rollback
+    pub fn flagged_transactions(&self, height: Height) -> &[crypto::Hash] {
+        self.flagged_txs
+            .get(&height)
+            .map_or(&[], |tx_hashes| tx_hashes.as_slice())
+    }
+
+    /// Returns the leader on the current height. At the moment first validator.
+    pub fn leader(&self) -> &TestNode {
+        &self.network().validators()[0]
+    }
+
+    /// Returns the reference to test network.
+    pub fn network(&self) -> &TestNetwork {
+        &self.network
+    }
+
+    /// Returns the mutable reference to test network for manual modifications.
+    pub fn network_mut(&mut self) -> &mut TestNetwork {
+        &mut self.network
+    }
+
+    /// Returns a copy of the actual configuration of the testkit.
+    /// The returned configuration could be modified for use with
+    /// `commit_configuration_change` method.
+    pub fn configuration_change_proposal(&self) -> TestNetworkConfiguration {
+        let stored_configuration = CoreSchema::new(&self.snapshot()).actual_configuration();
+        TestNetworkConfiguration::new(self.network(), stored_configuration)
+    }
+
+    /// Returns a configuration proposal that becomes actual `offset` blocks after
+    /// the current height.
+    ///
+    /// Unlike [`configuration_change_proposal`], the returned proposal is based on the
+    /// latest scheduled configuration, if there is any. Thus, it can be used to schedule
+    /// several configuration changes at once; the proposals will be applied in the order
+    /// of their `actual_from` heights.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().create();
+    ///
+    /// let mut proposal = testkit.propose_configuration_at(3);
+    /// proposal.set_service_config("my_service", "first");
+    /// testkit.commit_configuration_change(proposal);
+    ///
+    /// // This proposal will follow the first one.
+    /// let mut proposal = testkit.propose_configuration_at(5);
+    /// proposal.set_service_config("my_service", "second");
+    /// let stored = proposal.stored_configuration().clone();
+    /// testkit.commit_configuration_change(proposal);
+    ///
+    /// testkit.create_blocks_until(Height(4));
+    /// assert_eq!(testkit.actual_configuration(), stored);
+    /// # }
+    /// ```
+    ///
+    /// [`configuration_change_proposal`]: #method.configuration_change_proposal
+    pub fn propose_configuration_at(&self, offset: u64) -> TestNetworkConfiguration {
+        let mut proposal = match self.cfg_proposals.values().next_back() {
+            Some(last) => last.proposal().following(),
+            None => self.configuration_change_proposal(),
+        };
+        proposal.set_actual_from(Height(self.height().0 + offset));
+        proposal
+    }
+
+    /// Commits a configuration change proposal, which repeats the latest configuration
+    /// (the last scheduled one, or the actual one if no configuration is scheduled)
+    /// except for the `actual_from` height and the hash of the previous configuration.
+    /// Such proposals are useful to test the consumers of the configuration history
+    /// without changing the validator set or the service configurations.
+    ///
+    /// Similarly to [`commit_configuration_change`], the proposal is added to the blockchain
+    /// with the next created block; see [`activate_noop_configuration_change`] to commit
+    /// a proposal and create blocks until it becomes actual.
+    ///
+    /// # Return value
+    ///
+    /// Returns the committed configuration.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the proposal cannot be committed; see [`commit_configuration_change`]
+    ///   for details.
+    ///
+    /// [`commit_configuration_change`]: #method.commit_configuration_change
+    /// [`activate_noop_configuration_change`]: #method.activate_noop_configuration_change
+    pub fn commit_noop_configuration_change(&mut self, actual_from: Height) -> StoredConfiguration {
+        let mut proposal = self.propose_configuration_at(0);
+        proposal.set_actual_from(actual_from);
+        let stored = proposal.stored_configuration().clone();
+        self.commit_configuration_change(proposal);
+        stored
+    }
+
+    /// Commits a no-op configuration change proposal with [`commit_noop_configuration_change`]
+    /// and fast-forwards the blockchain to the height preceding `actual_from`, so that
+    /// the committed configuration becomes actual.
+    ///
+    /// # Return value
+    ///
+    /// Returns the committed configuration.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the proposal cannot be committed; see [`commit_configuration_change`]
+    ///   for details.
+    /// - Panics if the configurations are activated manually (see
+    ///   [`set_manual_config_activation`]), and a scheduled configuration is not activated
+    ///   in time.
+    ///
+    /// [`commit_noop_configuration_change`]: #method.commit_noop_configuration_change
+    /// [`commit_configuration_change`]: #method.commit_configuration_change
+    /// [`set_manual_config_activation`]: #method.set_manual_config_activation
+    pub fn activate_noop_configuration_change(
+        &mut self,
+        actual_from: Height,
+    ) -> StoredConfiguration {
+        let stored = self.commit_noop_configuration_change(actual_from);
+        self.fast_forward(actual_from.previous().0 - self.height().0);
+        stored
+    }
+
+    /// Creates blocks until the `actual_from` height of the nearest scheduled configuration
+    /// (or of the actual configuration, if the block at this height is not created yet),
+    /// so that the block at this height is empty, i.e., contains no transactions even if
+    /// the pool is not empty. The blocks preceding the activation height are created
+    /// with [`create_block`].
+    ///
+    /// Such blocks are the first blocks created with the new configuration, but they are
+    /// still considered empty by the consumers of the explorer API, e.g., with
+    /// the `skip_empty_blocks` parameter of the `v1/blocks` endpoint;
+    /// see [`TestKitApi::assert_block_skipped_as_empty`][skipped_as_empty].
+    ///
+    /// # Return value
+    ///
+    /// Returns information about the block at the activation height.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no scheduled configuration.
+    /// - Panics if the configurations are activated manually (see
+    ///   [`set_manual_config_activation`]), and the scheduled configuration is not activated
+    ///   in time.
+    ///
+    /// [`create_block`]: #method.create_block
+    /// [skipped_as_empty]: struct.TestKitApi.html#method.assert_block_skipped_as_empty
+    /// [`set_manual_config_activation`]: #method.set_manual_config_activation
+    pub fn create_config_activation_block(&mut self) -> BlockWithTransactions {
+        let next_height = self.height().next();
+        let actual_from = if self.actual_configuration().actual_from == next_height {
+            // The configuration is already actual, but the block at its `actual_from` height
+            // is not created yet.
+            next_height
+        } else {
+            self.next_configuration()
+                .map(|proposal| proposal.actual_from())
+                .expect("No configuration change is scheduled")
+        };
+        self.create_blocks_until(actual_from.previous());
+        let block = self.create_block_with_tx_hashes(&[]);
+        debug_assert_eq!(block.height(), actual_from);
+        block
+    }
+
+    /// Adds a new configuration proposal. Remember, to add this proposal to the blockchain,
+    /// you should create at least one block.
+    ///
+    /// Several configuration proposals may be scheduled at the same time, provided that
+    /// each of them refers to the previous one; use [`propose_configuration_at`]
+    /// to create such proposals.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `actual_from` is less than current height or equals.
+    /// - Panics if configuration change has been already proposed, and the new proposal
+    ///   does not follow it.
+    /// - Panics if `actual_from` is less than or equal to `actual_from` of
+    ///   the last scheduled configuration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate exonum;
+    /// extern crate exonum_testkit;
+    /// extern crate serde;
+    /// extern crate serde_json;
     ///
     /// use exonum::blockchain::Schema;
     /// use exonum::crypto::CryptoHash;
     /// use exonum::helpers::{Height, ValidatorId};
     /// use exonum_testkit::TestKitBuilder;
+    /// # use exonum_testkit::doctest_support::DoctestService;
     ///
     /// fn main() {
-    ///    let mut testkit = TestKitBuilder::auditor().with_validators(3).create();
+    ///    let mut testkit = TestKitBuilder::auditor()
+    ///        .with_validators(3)
+    ///        .with_service(DoctestService)
+    ///        .create();
     ///
     ///    let cfg_change_height = Height(5);
     ///    let proposal = {
@@ -900,7 +5043,7 @@ impl TestKit {
     ///         validators.push(testkit.network().us().clone());
     ///         cfg.set_validators(validators);
     ///         // Change configuration of our service.
-    ///         cfg.set_service_config("my_service", "My config");
+    ///         cfg.set_service_config("doctest", "My config");
     ///         // Set the height with which the configuration takes effect.
     ///         cfg.set_actual_from(cfg_change_height);
     ///         cfg
@@ -924,67 +5067,470 @@ impl TestKit {
     ///     );
     /// }
     /// ```
+    ///
+    /// [`propose_configuration_at`]: #method.propose_configuration_at
     pub fn commit_configuration_change(&mut self, proposal: TestNetworkConfiguration) {
+        let actual_from = proposal.actual_from();
+        self.try_commit_configuration_change(proposal)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Cannot commit configuration change with actual_from height {}: {}",
+                    actual_from, e
+                )
+            })
+    }
+
+    /// Adds a new configuration proposal, or returns an error if the proposal cannot be
+    /// scheduled. See [`commit_configuration_change`] for the conditions on the proposal.
+    ///
+    /// [`commit_configuration_change`]: #method.commit_configuration_change
+    pub fn try_commit_configuration_change(
+        &mut self,
+        proposal: TestNetworkConfiguration,
+    ) -> Result<(), Error> {
         use self::ConfigurationProposalState::*;
 
-        assert!(
-            self.height() < proposal.actual_from(),
-            "The `actual_from` height should be greater than the current."
-        );
-        assert!(
-            self.cfg_proposal.is_none(),
-            "There is an active configuration change proposal."
-        );
-        self.cfg_proposal = Some(Uncommitted(proposal));
+        self.trace(format_args!(
+            "commit_configuration_change(actual_from: {}, validators: {})",
+            proposal.actual_from(),
+            proposal.validators().len()
+        ));
+        if self.height() >= proposal.actual_from() {
+            return Err(Error::StaleConfigurationProposal(
+                "The `actual_from` height should be greater than the current.".to_owned(),
+            ));
+        }
+        if let Some(last) = self.cfg_proposals.values().next_back() {
+            let last = last.proposal();
+            if proposal.stored_configuration().previous_cfg_hash
+                != last.stored_configuration().hash()
+            {
+                return Err(Error::StaleConfigurationProposal(
+                    "There is an active configuration change proposal. \
+                     Use `propose_configuration_at` to schedule the following one."
+                        .to_owned(),
+                ));
+            }
+            if last.actual_from() >= proposal.actual_from() {
+                return Err(Error::StaleConfigurationProposal(
+                    "The `actual_from` height should be greater than the one of \
+                     the last scheduled configuration."
+                        .to_owned(),
+                ));
+            }
+        }
+        debug!(
+            target: CONFIG_LOG_TARGET,
+            "Scheduled configuration {:?} with actual_from height {}",
+            proposal.stored_configuration().hash(),
+            proposal.actual_from()
+        );
+        self.cfg_proposals
+            .insert(proposal.actual_from(), Uncommitted(proposal));
+        Ok(())
+    }
+
+    /// Creates blocks up to the `actual_from` height of the given configuration proposal
+    /// and asserts that the validator keys of the actual configuration match the ones
+    /// of the proposal. The proposal should be previously committed with
+    /// [`commit_configuration_change`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the validator keys of the actual configuration differ from the ones
+    ///   of the proposal, e.g., because the proposal was not committed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::Height;
+    /// # use exonum_testkit::{TestKitBuilder, TestNode};
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::validator().with_validators(2).create();
+    /// let proposal = {
+    ///     let mut cfg = testkit.propose_configuration_at(5);
+    ///     let mut validators = cfg.validators().to_vec();
+    ///     validators.push(TestNode::new_auditor());
+    ///     cfg.set_validators(validators);
+    ///     cfg
+    /// };
+    /// testkit.commit_configuration_change(proposal.clone());
+    /// testkit.assert_config_activates_at(&proposal);
+    /// assert_eq!(testkit.height(), Height(5));
+    /// # }
+    /// ```
+    ///
+    /// [`commit_configuration_change`]: #method.commit_configuration_change
+    pub fn assert_config_activates_at(&mut self, proposal: &TestNetworkConfiguration) {
+        let actual_from = proposal.actual_from();
+        self.create_blocks_until(actual_from);
+
+        let expected_keys = &proposal.stored_configuration().validator_keys;
+        let actual_keys = self.actual_configuration().validator_keys;
+        assert!(
+            actual_keys == *expected_keys,
+            "Validator keys of the actual configuration at height {} differ from the ones \
+             of the proposal with actual_from height {}\nexpected: {:#?}\n  actual: {:#?}",
+            self.height(),
+            actual_from,
+            expected_keys,
+            actual_keys
+        );
+    }
+
+    /// Replaces the validator set of the network and creates blocks until the new set
+    /// becomes actual.
+    ///
+    /// The configuration with the new validators takes effect `active_from_offset` blocks
+    /// after the current height; the proposal is based on the latest scheduled configuration,
+    /// as in [`propose_configuration_at`]. Validator ids are reassigned according to the order
+    /// of nodes in `new_validators`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `active_from_offset` is zero.
+    /// - Panics if there is a scheduled configuration change with the `actual_from` height
+    ///   greater than or equal to the one of the new configuration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::{Height, ValidatorId};
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let mut testkit = TestKitBuilder::auditor().with_validators(3).create();
+    ///
+    /// let mut validators = testkit.network().validators().to_vec();
+    /// validators.push(testkit.network().us().clone());
+    /// testkit.reconfigure_validators(validators, 5);
+    ///
+    /// assert_eq!(testkit.height(), Height(4));
+    /// assert_eq!(testkit.network().us().validator_id(), Some(ValidatorId(3)));
+    /// assert_eq!(testkit.actual_configuration().validator_keys.len(), 4);
+    /// # }
+    /// ```
+    ///
+    /// [`propose_configuration_at`]: #method.propose_configuration_at
+    pub fn reconfigure_validators(
+        &mut self,
+        new_validators: Vec<TestNode>,
+        active_from_offset: u64,
+    ) {
+        let mut proposal = self.propose_configuration_at(active_from_offset);
+        proposal.set_validators(new_validators);
+        let actual_from = proposal.actual_from();
+        self.commit_configuration_change(proposal);
+        self.create_blocks_until(actual_from.previous());
+    }
+
+    fn run(mut self, public_api_address: SocketAddr, private_api_address: SocketAddr) {
+        let events_stream = self.remove_events_stream();
+        // Creates complete actix web server with the testkit extensions.
+        let testkit_ref = Arc::new(RwLock::new(self));
+        let system_runtime_config = SystemRuntimeConfig {
+            api_runtimes: vec![
+                ApiRuntimeConfig::new(public_api_address, ApiAccess::Public),
+                ApiRuntimeConfig::new(private_api_address, ApiAccess::Private),
+            ],
+            api_aggregator: server::create_testkit_api_aggregator(&testkit_ref),
+        };
+        let system_runtime = system_runtime_config.start().unwrap();
+        // Run the event stream in a separate thread in order to put transactions to mempool
+        // when they are received. Otherwise, a client would need to call a `poll_events` analogue
+        // each time after a transaction is posted.
+        let mut core = Core::new().unwrap();
+        core.run(events_stream).unwrap();
+
+        system_runtime.stop().unwrap();
+    }
+
+    /// Extracts the event stream from this testkit, replacing it with `futures::stream::empty()`.
+    /// This makes the testkit after the replacement pretty much unusable unless
+    /// the old event stream (which is still capable of processing current and future events)
+    /// is employed to run to completion.
+    ///
+    /// # Returned value
+    ///
+    /// Future that runs the event stream of this testkit to completion.
+    fn remove_events_stream(&mut self) -> Box<dyn Future<Item = (), Error = ()>> {
+        let stream = std::mem::replace(&mut self.events_stream, Box::new(futures::stream::empty()));
+        Box::new(stream.for_each(|_| Ok(())))
+    }
+
+    /// Returns the node in the emulated network, from whose perspective the testkit operates.
+    pub fn us(&self) -> &TestNode {
+        self.network().us()
+    }
+
+    /// Creates a read-only handle to the testkit blockchain.
+    ///
+    /// Unlike the testkit itself, the handle can be cloned and moved into other threads,
+    /// which can use it to inspect the blockchain state while the testkit creates blocks.
+    pub fn reader(&self) -> TestKitReader {
+        TestKitReader {
+            blockchain: self.blockchain.clone(),
+            api_sender: self.api_sender.clone(),
+        }
+    }
+
+    /// Runs API calls concurrently, each in a separate thread, and returns their results
+    /// in the order of the calls. This may be used to check that the service endpoints
+    /// do not leak state between concurrent requests.
+    ///
+    /// `TestKitApi` cannot be shared among threads, so each call receives its own instance
+    /// of the API created for the testkit blockchain, as with [`TestKitReader::api`].
+    /// The calls start simultaneously once all API instances are created.
+    ///
+    /// Transactions sent by the calls are added to the pool on the next [`poll_events`] call.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of the calls panics.
+    ///
+    /// [`TestKitReader::api`]: struct.TestKitReader.html#method.api
+    /// [`poll_events`]: #method.poll_events
+    pub fn concurrent_api_calls(
+        &self,
+        calls: Vec<Box<dyn FnMut(&TestKitApi) -> serde_json::Value + Send>>,
+    ) -> Vec<serde_json::Value> {
+        let barrier = Arc::new(Barrier::new(calls.len()));
+        let handles: Vec<_> = calls
+            .into_iter()
+            .map(|mut call| {
+                let reader = self.reader();
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    let api = reader.api();
+                    barrier.wait();
+                    call(&api)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .enumerate()
+            .map(|(i, handle)| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| panic!("Concurrent API call #{} panicked", i))
+            })
+            .collect()
+    }
+}
+
+/// Read-only handle to the blockchain of a `TestKit`, which can be shared among threads.
+///
+/// The handle observes blocks created by the testkit after the handle has been obtained.
+///
+/// # Example
+///
+/// ```
+/// # extern crate exonum;
+/// # extern crate exonum_testkit;
+/// # use exonum::helpers::Height;
+/// # use exonum_testkit::TestKitBuilder;
+/// # use std::thread;
+/// # fn main() {
+/// let mut testkit = TestKitBuilder::validator().create();
+/// let reader = testkit.reader();
+/// let handle = thread::spawn(move || {
+///     while reader.height() < Height(5) {
+///         thread::yield_now();
+///     }
+/// });
+/// testkit.create_blocks_until(Height(5));
+/// handle.join().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestKitReader {
+    blockchain: Blockchain,
+    api_sender: ApiSender,
+}
+
+impl TestKitReader {
+    /// Returns a snapshot of the current blockchain state.
+    pub fn snapshot(&self) -> Box<dyn Snapshot> {
+        self.blockchain.snapshot()
     }
 
-    fn run(mut self, public_api_address: SocketAddr, private_api_address: SocketAddr) {
-        let events_stream = self.remove_events_stream();
-        // Creates complete actix web server with the testkit extensions.
-        let testkit_ref = Arc::new(RwLock::new(self));
-        let system_runtime_config = SystemRuntimeConfig {
-            api_runtimes: vec![
-                ApiRuntimeConfig::new(public_api_address, ApiAccess::Public),
-                ApiRuntimeConfig::new(private_api_address, ApiAccess::Private),
-            ],
-            api_aggregator: server::create_testkit_api_aggregator(&testkit_ref),
-        };
-        let system_runtime = system_runtime_config.start().unwrap();
-        // Run the event stream in a separate thread in order to put transactions to mempool
-        // when they are received. Otherwise, a client would need to call a `poll_events` analogue
-        // each time after a transaction is posted.
-        let mut core = Core::new().unwrap();
-        core.run(events_stream).unwrap();
+    /// Returns the height of latest committed block.
+    pub fn height(&self) -> Height {
+        self.blockchain.last_block().height()
+    }
 
-        system_runtime.stop().unwrap();
+    /// Creates an instance of `TestKitApi` to test the API provided by services.
+    pub fn api(&self) -> TestKitApi {
+        TestKitApi::for_blockchain(self.blockchain.clone(), self.api_sender.clone())
     }
+}
 
-    /// Extracts the event stream from this testkit, replacing it with `futures::stream::empty()`.
-    /// This makes the testkit after the replacement pretty much unusable unless
-    /// the old event stream (which is still capable of processing current and future events)
-    /// is employed to run to completion.
-    ///
-    /// # Returned value
-    ///
-    /// Future that runs the event stream of this testkit to completion.
-    fn remove_events_stream(&mut self) -> Box<dyn Future<Item = (), Error = ()>> {
-        let stream = std::mem::replace(&mut self.events_stream, Box::new(futures::stream::empty()));
-        Box::new(stream.for_each(|_| Ok(())))
+/// Outcome of the transactions execution returned by
+/// [`TestKit::dry_run_block`](struct.TestKit.html#method.dry_run_block).
+///
+/// Errors are reported as `TransactionError`s, i.e., in the same form as they are stored
+/// in the blockchain; this also covers transactions that panicked during execution.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BlockReport {
+    /// Hashes of the transactions executed successfully.
+    pub executed: Vec<crypto::Hash>,
+    /// Hashes of the transactions whose execution resulted in an error, along with the errors.
+    pub errored: Vec<(crypto::Hash, TransactionError)>,
+    /// Hashes of the transactions that were not executed, because they are already committed,
+    /// are not correct or are duplicated.
+    pub skipped: Vec<crypto::Hash>,
+}
+
+/// Storage statistics of an index returned by
+/// [`TestKit::storage_index_stats`](struct.TestKit.html#method.storage_index_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IndexStats {
+    /// Number of raw storage entries in the index.
+    pub entry_count: u64,
+    /// Estimated size of the index in bytes, calculated as the total length
+    /// of the keys and values of its entries.
+    pub byte_estimate: u64,
+}
+
+/// Returns the transactions from the list that are absent from the pool.
+fn missing_pool_transactions(
+    schema: &CoreSchema<Box<dyn Snapshot>>,
+    tx_hashes: &[Hash],
+) -> Vec<MissingTransaction> {
+    let tx_pool = schema.transactions_pool();
+    tx_hashes
+        .iter()
+        .filter(|hash| !tx_pool.contains(hash))
+        .map(|&hash| {
+            if schema.transactions().contains(&hash) {
+                MissingTransaction::Committed(hash)
+            } else {
+                MissingTransaction::Unknown(hash)
+            }
+        })
+        .collect()
+}
+
+/// Checks that the transactions are in the pool and are not repeated.
+fn check_pool_transactions(
+    schema: &CoreSchema<Box<dyn Snapshot>>,
+    tx_hashes: &[Hash],
+) -> Result<(), Error> {
+    let missing = missing_pool_transactions(schema, tx_hashes);
+    if !missing.is_empty() {
+        return Err(Error::TransactionsNotInPool(MissingTransactions(missing)));
     }
 
-    /// Returns the node in the emulated network, from whose perspective the testkit operates.
-    pub fn us(&self) -> &TestNode {
-        self.network().us()
+    let mut seen_hashes = HashSet::with_capacity(tx_hashes.len());
+    for hash in tx_hashes {
+        if !seen_hashes.insert(hash) {
+            return Err(Error::DuplicateTransaction(*hash));
+        }
+    }
+    Ok(())
+}
+
+/// Result of the signature verification for a stored transaction, which is returned
+/// by `TestKit::verify_transaction_signatures`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureVerificationResult {
+    /// The transaction has a valid signature.
+    Valid,
+    /// The transaction has an invalid signature or cannot be deserialized.
+    Invalid,
+    /// The transaction is not stored in the blockchain.
+    NotFound,
+}
+
+/// Ordering of the pool transactions in the blocks created with
+/// [`TestKit::create_block`](struct.TestKit.html#method.create_block) and similar methods.
+/// See [`TestKit::set_tx_ordering`](struct.TestKit.html#method.set_tx_ordering) for details.
+pub enum TxOrdering {
+    /// Transactions are ordered by the time of their addition to the pool. Transactions
+    /// put into the pool bypassing the testkit follow the other ones in the order of hashes.
+    Fifo,
+    /// Transactions are ordered by their hashes. This is the default ordering.
+    ByHash,
+    /// Transactions are selected and ordered by the function. The function receives
+    /// the hashes of the pool transactions in the `Fifo` order together with the snapshot
+    /// of the blockchain state, and returns the hashes of the transactions to include
+    /// into the block in the order of their execution. The returned hashes should belong
+    /// to the pool and should not repeat.
+    Custom(Box<dyn Fn(&[Hash], &dyn Snapshot) -> Vec<Hash> + Send + Sync>),
+}
+
+impl fmt::Debug for TxOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            TxOrdering::Fifo => f.write_str("Fifo"),
+            TxOrdering::ByHash => f.write_str("ByHash"),
+            TxOrdering::Custom(..) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl Default for TxOrdering {
+    fn default() -> Self {
+        TxOrdering::ByHash
     }
 }
 
+// Parameters of a block created by the testkit.
+#[derive(Debug, Clone, Copy, Default)]
+struct BlockParams<'a> {
+    // Validator proposing the block; the leader if not specified.
+    proposer: Option<ValidatorId>,
+    // Validators whose precommits are not included into the block.
+    excluded_precommits: &'a [ValidatorId],
+    // Propose and precommits stored in the blockchain as is instead of the generated ones.
+    // The other parameters are ignored if the messages are specified.
+    custom_messages: Option<(&'a Propose, &'a [Precommit])>,
+    // Whether the transactions are not checked by the dependency checker; used for blocks
+    // that are rolled back right after creation.
+    skip_dependency_check: bool,
+}
+
+// Patch of a block created by the testkit together with the testkit bookkeeping,
+// which is applied only after the block is committed.
+struct BlockPatch {
+    block_hash: crypto::Hash,
+    patch: Patch,
+    // `actual_from` heights of the configuration proposals committed in the block.
+    committed_configs: Vec<Height>,
+    // Metrics of the block, if execution metrics or timing are enabled.
+    metrics: Option<BlockExecutionMetrics>,
+}
+
 // A new configuration proposal state.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum ConfigurationProposalState {
     Uncommitted(TestNetworkConfiguration),
     Committed(TestNetworkConfiguration),
 }
 
+impl ConfigurationProposalState {
+    fn proposal(&self) -> &TestNetworkConfiguration {
+        use self::ConfigurationProposalState::*;
+
+        match *self {
+            Committed(ref proposal) | Uncommitted(ref proposal) => proposal,
+        }
+    }
+}
+
+#[test]
+fn test_testkit_is_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<TestKit>();
+    assert_send_sync::<TestKitReader>();
+}
+
 #[test]
 fn test_create_block_heights() {
     let mut testkit = TestKitBuilder::validator().create();
@@ -1031,3 +5577,498 @@ fn test_multiple_spec_of_validators_in_builder() {
         .create();
     drop(testkit);
 }
+
+#[test]
+fn test_assert_genesis_config_equals() {
+    let mut testkit = TestKitBuilder::validator().with_validators(3).create();
+    let genesis_config = testkit.network().genesis_config();
+    testkit.create_blocks_until(Height(5));
+    testkit.assert_genesis_config_equals(&genesis_config);
+}
+
+#[test]
+#[should_panic(expected = "Consensus configuration in the genesis block differs")]
+fn test_assert_genesis_config_equals_panic() {
+    let testkit = TestKitBuilder::validator().create();
+    let mut genesis_config = testkit.network().genesis_config();
+    genesis_config.consensus.txs_block_limit += 1;
+    testkit.assert_genesis_config_equals(&genesis_config);
+}
+
+#[test]
+fn test_inject_peer_add_message() {
+    let mut testkit = TestKitBuilder::validator().create();
+    assert!(testkit.known_peers().is_empty());
+
+    let first: SocketAddr = "127.0.0.1:6333".parse().unwrap();
+    let second: SocketAddr = "127.0.0.1:6334".parse().unwrap();
+    testkit.inject_peer_add_message(first);
+    testkit.inject_peer_add_message(second);
+    testkit.inject_peer_add_message(first);
+    assert_eq!(testkit.known_peers(), &[first, second]);
+}
+
+#[test]
+fn test_assert_block_height_is() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.assert_block_height_is(Height(0));
+    testkit.create_blocks_until(Height(3));
+    testkit.assert_block_height_is(Height(3));
+}
+
+#[test]
+#[should_panic(expected = "Expected blockchain height 5, but was 3. Last block hash:")]
+fn test_assert_block_height_is_panic() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.create_blocks_until(Height(3));
+    testkit.assert_block_height_is(Height(5));
+}
+
+#[cfg(test)]
+struct DummyService {
+    id: u16,
+    name: &'static str,
+}
+
+#[cfg(test)]
+impl Service for DummyService {
+    fn service_id(&self) -> u16 {
+        self.id
+    }
+
+    fn service_name(&self) -> &str {
+        self.name
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(
+        &self,
+        _: exonum::messages::RawTransaction,
+    ) -> Result<Box<dyn Transaction>, exonum::encoding::Error> {
+        unimplemented!()
+    }
+}
+
+#[test]
+#[should_panic(expected = "Service `second` has the same id 1 as already added service `first`")]
+fn test_services_with_clashing_ids() {
+    TestKitBuilder::validator()
+        .with_service(DummyService { id: 1, name: "first" })
+        .with_service(DummyService { id: 1, name: "second" });
+}
+
+#[test]
+#[should_panic(
+    expected = "Service with id 2 has the same name `dummy` as already added service with id 1"
+)]
+fn test_services_with_clashing_names() {
+    TestKitBuilder::validator()
+        .with_service(DummyService { id: 1, name: "dummy" })
+        .with_service(DummyService { id: 2, name: "dummy" });
+}
+
+#[test]
+fn test_with_boxed_service() {
+    let service: Box<dyn Service> = Box::new(DummyService { id: 2, name: "boxed" });
+    let testkit = TestKitBuilder::validator()
+        .with_service(DummyService { id: 1, name: "dummy" })
+        .with_boxed_service(service)
+        .create();
+    let service_map = testkit.blockchain().service_map();
+    assert_eq!(service_map[1].service_name(), "dummy");
+    assert_eq!(service_map[2].service_name(), "boxed");
+}
+
+#[cfg(test)]
+struct ExportingService;
+
+#[cfg(test)]
+impl Service for ExportingService {
+    fn service_id(&self) -> u16 {
+        7
+    }
+
+    fn service_name(&self) -> &str {
+        "exporting"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(
+        &self,
+        _: exonum::messages::RawTransaction,
+    ) -> Result<Box<dyn Transaction>, exonum::encoding::Error> {
+        unimplemented!()
+    }
+
+    fn export_state(&self, snapshot: &dyn Snapshot) -> Option<serde_json::Value> {
+        let height = CoreSchema::new(snapshot).height();
+        Some(json!({ "height": height.0 }))
+    }
+}
+
+#[test]
+fn test_export_service_state_with_hook() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_spied_service(ExportingService)
+        .create();
+    assert_eq!(testkit.export_service_state("exporting"), json!({ "height": 0 }));
+    testkit.create_block();
+    assert_eq!(testkit.export_service_state("exporting"), json!({ "height": 1 }));
+}
+
+#[test]
+fn test_all_service_ids() {
+    let testkit = TestKitBuilder::validator()
+        .with_service(DummyService { id: 5, name: "fifth" })
+        .with_service(DummyService { id: 1, name: "first" })
+        .with_service(DummyService { id: 3, name: "third" })
+        .create();
+    // The identifiers are listed in the order of registration.
+    assert_eq!(testkit.all_service_ids(), vec![5, 1, 3]);
+
+    let empty = TestKitBuilder::validator().create();
+    assert!(empty.all_service_ids().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Service `boxed` has the same id 1 as already added service `dummy`")]
+fn test_boxed_service_with_clashing_id() {
+    TestKitBuilder::validator()
+        .with_service(DummyService { id: 1, name: "dummy" })
+        .with_boxed_service(Box::new(DummyService { id: 1, name: "boxed" }));
+}
+
+#[test]
+fn test_committed_block_at() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.create_blocks_until(Height(3));
+
+    let genesis_block = testkit.committed_block_at(Height(0)).unwrap();
+    assert_eq!(genesis_block.height(), Height(0));
+    let block = testkit.committed_block_at(Height(3)).unwrap();
+    assert_eq!(block.height(), Height(3));
+    assert_eq!(block.hash(), testkit.last_block_hash());
+    assert!(testkit.committed_block_at(Height(4)).is_none());
+}
+
+#[test]
+fn test_create_block_with_custom_precommits() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.create_block();
+
+    let validators = testkit.network().validators().to_vec();
+    let last_hash = testkit.last_block_hash();
+    // The propose is created by a validator which is not the leader at this height,
+    // and the precommits reference a bogus block hash.
+    let propose = validators[3].create_propose(Height(2), &last_hash, &[]);
+    let bogus_hash = crypto::hash(b"bogus");
+    let precommits: Vec<_> = validators[..2]
+        .iter()
+        .map(|v| v.create_precommit(&propose, &bogus_hash))
+        .collect();
+
+    let block = testkit.create_block_with_custom_precommits(propose.clone(), precommits.clone());
+    assert_eq!(block.height(), Height(2));
+    assert_eq!(block.header.proposer_id(), ValidatorId(3));
+    assert_eq!(block.header.prev_hash(), &last_hash);
+    assert_eq!(block.precommits, precommits);
+    assert_eq!(testkit.height(), Height(2));
+    // The supplied messages are retained as is.
+    assert_eq!(testkit.last_block_messages(), (propose, precommits));
+}
+
+#[test]
+fn test_concurrent_block_proposals() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    let proposals = testkit.concurrent_block_proposals(3, vec![]);
+    assert_eq!(proposals.len(), 3);
+    assert_eq!(testkit.height(), Height(0));
+
+    let block_hashes: HashSet<_> = proposals
+        .iter()
+        .map(|&(_, ref precommits)| *precommits[0].block_hash())
+        .collect();
+    assert_eq!(block_hashes.len(), 3);
+    for (i, &(ref propose, ref precommits)) in proposals.iter().enumerate() {
+        assert_eq!(propose.validator(), ValidatorId(i as u16));
+        assert_eq!(propose.height(), Height(1));
+        assert_eq!(precommits.len(), 4);
+        assert!(precommits.iter().all(|p| p.propose_hash() == &propose.hash()));
+    }
+
+    // Any of the proposals can be committed.
+    let (propose, precommits) = proposals[2].clone();
+    let expected_hash = *precommits[0].block_hash();
+    let block = testkit.create_block_with_custom_precommits(propose, precommits);
+    assert_eq!(block.header.hash(), expected_hash);
+}
+
+#[test]
+#[should_panic(expected = "Cannot generate 3 proposals with different proposers")]
+fn test_concurrent_block_proposals_with_too_many_proposers() {
+    let mut testkit = TestKitBuilder::validator().with_validators(2).create();
+    testkit.concurrent_block_proposals(3, vec![]);
+}
+
+#[test]
+#[should_panic(expected = "The height of the propose should be equal to the height of the next")]
+fn test_create_block_with_custom_precommits_wrong_height() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let propose = testkit
+        .leader()
+        .create_propose(Height(2), &testkit.last_block_hash(), &[]);
+    testkit.create_block_with_custom_precommits(propose, vec![]);
+}
+
+#[test]
+fn test_create_block_without_precommit_from() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    assert_eq!(testkit.majority_count(), 3);
+
+    let block = testkit
+        .create_block_without_precommit_from(&[ValidatorId(0)], vec![])
+        .unwrap();
+    assert_eq!(block.height(), Height(1));
+    let signers: Vec<_> = block.precommits.iter().map(|p| p.validator()).collect();
+    assert_eq!(signers, vec![ValidatorId(1), ValidatorId(2), ValidatorId(3)]);
+
+    let err = testkit
+        .create_block_without_precommit_from(&[ValidatorId(1), ValidatorId(3)], vec![])
+        .unwrap_err();
+    assert_matches!(
+        err,
+        Error::InsufficientPrecommits {
+            precommits: 2,
+            majority_count: 3,
+        }
+    );
+    assert_eq!(testkit.height(), Height(1));
+}
+
+#[test]
+fn test_create_block_with_proposer() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    for id in &[2, 0, 3, 1] {
+        let block = testkit.create_block_with_proposer(ValidatorId(*id));
+        assert_eq!(block.precommits.len(), 4);
+    }
+    // Blocks created without an explicit proposer are proposed by the leader.
+    testkit.create_block();
+
+    let explorer = testkit.explorer();
+    let proposers: Vec<_> = (1..6)
+        .map(|height| explorer.block(Height(height)).unwrap().header().proposer_id())
+        .collect();
+    assert_eq!(
+        proposers,
+        vec![
+            ValidatorId(2),
+            ValidatorId(0),
+            ValidatorId(3),
+            ValidatorId(1),
+            ValidatorId(0),
+        ]
+    );
+}
+
+#[test]
+fn test_create_block_with_unknown_proposer() {
+    let mut testkit = TestKitBuilder::validator().with_validators(2).create();
+    let result = testkit.try_create_block_with_proposer(ValidatorId(2));
+    assert_matches!(result, Err(Error::UnknownValidator(ValidatorId(2))));
+    assert_eq!(testkit.height(), Height(0));
+}
+
+#[test]
+fn test_assert_precommit_count() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.shutdown_validator(ValidatorId(2));
+    testkit.create_block();
+    testkit.assert_precommit_count(Height(0), 0);
+    testkit.assert_precommit_count(Height(1), 3);
+}
+
+#[test]
+#[should_panic(expected = "Expected 4 precommit(s) for the block at height 1, but 3 are stored")]
+fn test_assert_precommit_count_mismatch() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.shutdown_validator(ValidatorId(2));
+    testkit.create_block();
+    testkit.assert_precommit_count(Height(1), 4);
+}
+
+#[test]
+fn test_shutdown_validator() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.shutdown_validator(ValidatorId(0));
+    testkit.shutdown_validator(ValidatorId(2));
+    assert!(testkit.is_validator_offline(ValidatorId(2)));
+
+    // 2 validators online are not enough for 4 validators.
+    let result = testkit.try_create_block();
+    assert_matches!(
+        result,
+        Err(Error::InsufficientPrecommits {
+            precommits: 2,
+            majority_count: 3,
+        })
+    );
+    let result = testkit.try_create_block_with_proposer(ValidatorId(0));
+    assert_matches!(result, Err(Error::ValidatorOffline(ValidatorId(0))));
+    assert_eq!(testkit.height(), Height(0));
+
+    testkit.restart_validator(ValidatorId(2));
+    let block = testkit.create_block();
+    assert_eq!(block.header.proposer_id(), ValidatorId(1));
+    let signers: Vec<_> = block.precommits.iter().map(Precommit::validator).collect();
+    assert_eq!(signers, vec![ValidatorId(1), ValidatorId(2), ValidatorId(3)]);
+
+    testkit.restart_validator(ValidatorId(0));
+    let block = testkit.create_block();
+    assert_eq!(block.header.proposer_id(), ValidatorId(0));
+    assert_eq!(block.precommits.len(), 4);
+}
+
+#[test]
+fn test_byzantine_validators() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.byzantine_validator(ValidatorId(0), ByzantineBehavior::DropPropose);
+    testkit.byzantine_validator(ValidatorId(1), ByzantineBehavior::Equivocate);
+    let block = testkit.create_block();
+    assert_eq!(block.header.proposer_id(), ValidatorId(1));
+    assert_eq!(block.precommits.len(), 4);
+
+    let messages = testkit.conflicting_messages(Height(1));
+    assert_eq!(messages.len(), 1);
+    match messages[0] {
+        ConflictingMessages::Proposes(ref committed, ref other) => {
+            assert_eq!(committed.hash(), *block.precommits[0].propose_hash());
+            assert_eq!(other.validator(), ValidatorId(1));
+            assert_eq!(other.height(), Height(1));
+            assert_ne!(other.hash(), committed.hash());
+        }
+        ref other => panic!("Unexpected conflicting messages: {:?}", other),
+    }
+
+    testkit.clear_byzantine_behavior(ValidatorId(0));
+    testkit.clear_byzantine_behavior(ValidatorId(1));
+    let block = testkit.create_block();
+    assert_eq!(block.header.proposer_id(), ValidatorId(0));
+    assert!(testkit.conflicting_messages(Height(2)).is_empty());
+}
+
+#[test]
+fn test_dry_run_block_with_offline_validators() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.checkpoint();
+    testkit.create_block();
+    testkit.shutdown_validator(ValidatorId(1));
+    testkit.shutdown_validator(ValidatorId(2));
+    match testkit.dry_run_block(vec![]) {
+        Err(Error::InsufficientPrecommits {
+            precommits: 2,
+            majority_count: 3,
+        }) => {}
+        other => panic!("Unexpected dry run outcome: {:?}", other),
+    }
+    assert_eq!(testkit.height(), Height(1));
+
+    // The checkpoint set by the dry run is rolled back, so the outer one is intact.
+    testkit.rollback();
+    assert_eq!(testkit.height(), Height(0));
+}
+
+#[test]
+fn test_conflicting_messages_of_probed_block() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.byzantine_validator(ValidatorId(0), ByzantineBehavior::Equivocate);
+    testkit.byzantine_validator(ValidatorId(2), ByzantineBehavior::SignConflictingPrecommits);
+
+    testkit.probe_all(vec![]);
+    testkit.dry_run_block(vec![]).unwrap();
+    assert_eq!(testkit.height(), Height(0));
+    assert!(testkit.conflicting_messages(Height(1)).is_empty());
+
+    testkit.create_block();
+    assert_eq!(testkit.conflicting_messages(Height(1)).len(), 2);
+}
+
+#[test]
+fn test_storage_index_stats() {
+    use exonum::storage::ListIndex;
+
+    let mut testkit = TestKitBuilder::validator().create();
+    assert_eq!(testkit.storage_index_stats("test.list"), IndexStats::default());
+
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut list: ListIndex<_, u64> = ListIndex::new("test.list", &mut fork);
+        list.extend(vec![1, 2, 3]);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    let stats = testkit.storage_index_stats("test.list");
+    // Three items and the length of the list.
+    assert_eq!(stats.entry_count, 4);
+    assert_eq!(stats.byte_estimate, 3 * (8 + 8) + 8);
+}
+
+#[test]
+fn test_assert_index_len() {
+    use exonum::storage::{ListIndex, ProofMapIndex};
+
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.assert_index_empty("test.list");
+    testkit.assert_index_len("test.map", 0);
+
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut list: ListIndex<_, u64> = ListIndex::new("test.list", &mut fork);
+        list.extend(vec![1, 2, 3]);
+        let mut map: MapIndex<_, u8, u64> = MapIndex::new("test.map", &mut fork);
+        map.put(&1, 1);
+        let mut proof_map: ProofMapIndex<_, Hash, u64> =
+            ProofMapIndex::new("test.proof_map", &mut fork);
+        for i in 0..5 {
+            proof_map.put(&crypto::hash(&[i]), u64::from(i));
+        }
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    testkit.assert_index_len("test.list", 3);
+    testkit.assert_index_len("test.map", 1);
+    testkit.assert_index_len("test.proof_map", 5);
+
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut list: ListIndex<_, u64> = ListIndex::new("test.list", &mut fork);
+        list.clear();
+        let mut proof_map: ProofMapIndex<_, Hash, u64> =
+            ProofMapIndex::new("test.proof_map", &mut fork);
+        proof_map.clear();
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+    testkit.assert_index_empty("test.list");
+    testkit.assert_index_empty("test.proof_map");
+}
+
+#[test]
+#[should_panic(expected = "Index `test.map` is expected to be empty, but it contains 2 element(s) \
+                           with keys: 01, 02")]
+fn test_assert_index_empty_panic() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut map: MapIndex<_, u8, u64> = MapIndex::new("test.map", &mut fork);
+        map.put(&1, 1);
+        map.put(&2, 2);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+    testkit.assert_index_empty("test.map");
+}
@@ -0,0 +1,153 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution metrics of the blocks created by the testkit.
+
+use exonum::{crypto::Hash, helpers::Height};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Wall-clock execution metrics of a block created by the testkit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockExecutionMetrics {
+    /// Height of the block.
+    pub height: Height,
+    /// Hashes of the transactions in the order of their execution, together with the time
+    /// spent in their `execute` method.
+    pub transactions: Vec<(Hash, Duration)>,
+    /// Time spent on the creation of the block patch, which includes the execution
+    /// of all transactions and the `before_commit` hooks of services.
+    pub patch_creation_time: Duration,
+}
+
+impl BlockExecutionMetrics {
+    /// Returns the total time spent on the execution of transactions in the block.
+    pub fn total_tx_time(&self) -> Duration {
+        self.transactions
+            .iter()
+            .fold(Duration::default(), |total, &(_, time)| total + time)
+    }
+}
+
+/// Execution metrics aggregated across the blocks created by the testkit.
+/// See [`TestKitBuilder::with_execution_metrics`] for details.
+///
+/// [`TestKitBuilder::with_execution_metrics`]:
+/// struct.TestKitBuilder.html#method.with_execution_metrics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionMetrics {
+    blocks: BTreeMap<Height, BlockExecutionMetrics>,
+}
+
+impl ExecutionMetrics {
+    /// Returns the metrics of all created blocks in the order of their heights.
+    pub fn blocks(&self) -> Vec<&BlockExecutionMetrics> {
+        self.blocks.values().collect()
+    }
+
+    /// Returns the metrics of the block at the specified height, if the block has been
+    /// created by the testkit with the metrics enabled.
+    pub fn block(&self, height: Height) -> Option<&BlockExecutionMetrics> {
+        self.blocks.get(&height)
+    }
+
+    /// Returns the number of executed transactions in all blocks.
+    pub fn tx_count(&self) -> usize {
+        self.blocks.values().map(|block| block.transactions.len()).sum()
+    }
+
+    /// Returns the total time spent on the execution of transactions in all blocks.
+    pub fn total_tx_time(&self) -> Duration {
+        self.blocks
+            .values()
+            .fold(Duration::default(), |total, block| total + block.total_tx_time())
+    }
+
+    /// Returns the transaction with the longest execution time, if any.
+    pub fn slowest_transaction(&self) -> Option<(Hash, Duration)> {
+        self.blocks
+            .values()
+            .flat_map(|block| block.transactions.iter().cloned())
+            .max_by_key(|&(_, time)| time)
+    }
+
+    pub(crate) fn insert(&mut self, metrics: BlockExecutionMetrics) {
+        self.blocks.insert(metrics.height, metrics);
+    }
+
+    /// Forgets the metrics of the blocks above the specified height.
+    pub(crate) fn truncate(&mut self, height: Height) {
+        self.blocks.split_off(&height.next());
+    }
+}
+
+/// Shared log of transaction execution times, which is filled by services wrapped
+/// into `TimedService`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExecutionLog {
+    inner: Arc<Mutex<Vec<(Hash, Duration)>>>,
+}
+
+impl ExecutionLog {
+    pub fn record(&self, tx_hash: Hash, time: Duration) {
+        self.inner.lock().unwrap().push((tx_hash, time));
+    }
+
+    /// Returns a copy of the log, which is not shared with this instance.
+    pub fn deep_clone(&self) -> Self {
+        ExecutionLog {
+            inner: Arc::new(Mutex::new(self.inner.lock().unwrap().clone())),
+        }
+    }
+
+    /// Returns all records, clearing the log.
+    pub fn take(&self) -> Vec<(Hash, Duration)> {
+        let mut records = self.inner.lock().unwrap();
+        records.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::crypto::hash;
+
+    use super::*;
+
+    #[test]
+    fn test_aggregation() {
+        let mut metrics = ExecutionMetrics::default();
+        for height in 1..4 {
+            metrics.insert(BlockExecutionMetrics {
+                height: Height(height),
+                transactions: vec![
+                    (hash(&[height as u8]), Duration::from_millis(height)),
+                    (hash(&[height as u8 + 10]), Duration::from_millis(1)),
+                ],
+                patch_creation_time: Duration::from_millis(10),
+            });
+        }
+        assert_eq!(metrics.tx_count(), 6);
+        assert_eq!(metrics.total_tx_time(), Duration::from_millis(9));
+        assert_eq!(
+            metrics.slowest_transaction(),
+            Some((hash(&[3]), Duration::from_millis(3)))
+        );
+
+        metrics.truncate(Height(1));
+        assert_eq!(metrics.blocks().len(), 1);
+        assert!(metrics.block(Height(2)).is_none());
+    }
+}
@@ -15,32 +15,61 @@
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet};
+use std::time::SystemTime;
+
 use exonum::{
     blockchain::{ConsensusConfig, GenesisConfig, StoredConfiguration, ValidatorKeys},
-    crypto::{self, CryptoHash}, helpers::{Height, Round, ValidatorId},
-    messages::{Precommit, Propose},
+    crypto::{self, CryptoHash}, helpers::{Height, Milliseconds, Round, ValidatorId},
+    messages::{Precommit, Propose}, node::state::State as NodeState,
 };
 
+use error::{Error, ValidatorIdOverflow};
+
+/// Length of the hex prefixes of public keys in the description of the network.
+const KEY_PREFIX_LEN: usize = 16;
+
 /// Emulated test network.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TestNetwork {
     us: TestNode,
     validators: Vec<TestNode>,
+    // Validators separated from `us` by a network partition.
+    #[serde(default)]
+    unreachable: BTreeSet<ValidatorId>,
 }
 
 impl TestNetwork {
     /// Creates a new emulated network.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `validator_count` is zero.
     pub fn new(validator_count: u16) -> Self {
         Self::with_our_role(Some(ValidatorId(0)), validator_count)
     }
 
     /// Creates a new emulated network with a specific role of the node
     /// the network will be viewed from.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `validator_count` is zero.
+    /// - Panics if the validator id of our node is not less than `validator_count`.
     pub fn with_our_role(us: Option<ValidatorId>, validator_count: u16) -> Self {
         assert!(
             validator_count > 0,
             "At least one validator should be present in the network."
         );
+        if let Some(ValidatorId(id)) = us {
+            assert!(
+                id < validator_count,
+                "Validator id {} of our node is out of bounds: the network has {} validators",
+                id,
+                validator_count
+            );
+        }
 
         let validators = (0..validator_count)
             .map(ValidatorId)
@@ -52,7 +81,11 @@ impl TestNetwork {
         } else {
             TestNode::new_auditor()
         };
-        TestNetwork { validators, us }
+        TestNetwork {
+            validators,
+            us,
+            unreachable: BTreeSet::new(),
+        }
     }
 
     /// Returns the node in the emulated network, from whose perspective the testkit operates.
@@ -71,13 +104,20 @@ impl TestNetwork {
         GenesisConfig::new(self.validators.iter().map(TestNode::public_keys))
     }
 
-    /// Updates the test network by the new set of nodes.
+    /// Updates the test network by the new set of nodes. If the consensus key of `us`
+    /// does not belong to any of the validators, our node becomes an auditor.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the set of validators is empty.
+    /// - Panics if the same key is used by several validators.
     pub fn update<I: IntoIterator<Item = TestNode>>(&mut self, mut us: TestNode, validators: I) {
+        us.change_role(None);
         let validators = validators
             .into_iter()
             .enumerate()
             .map(|(id, mut validator)| {
-                let validator_id = ValidatorId(id as u16);
+                let validator_id = validator_id_from_index(id);
                 validator.change_role(Some(validator_id));
                 if us.public_keys().consensus_key == validator.public_keys().consensus_key {
                     us.change_role(Some(validator_id));
@@ -85,8 +125,11 @@ impl TestNetwork {
                 validator
             })
             .collect::<Vec<_>>();
+        check_validators(&validators);
         self.validators = validators;
         self.us.clone_from(&us);
+        // Validator ids may change, so the partition is healed.
+        self.unreachable.clear();
     }
 
     /// Updates the test network with a new configuration.
@@ -94,6 +137,77 @@ impl TestNetwork {
         self.update(config.us, config.validators);
     }
 
+    /// Replaces the node from whose perspective the testkit operates, returning the old node.
+    ///
+    /// The validator id of `new_us` is updated according to the current list of validators:
+    /// if the consensus key of the node belongs to one of the validators, the node gets
+    /// the id of this validator; otherwise, the node becomes an auditor.
+    pub fn swap_us(&mut self, mut new_us: TestNode) -> TestNode {
+        let validator_id = self.validators
+            .iter()
+            .position(|x| x.consensus_public_key == new_us.consensus_public_key)
+            .map(validator_id_from_index);
+        new_us.change_role(validator_id);
+        ::std::mem::replace(&mut self.us, new_us)
+    }
+
+    /// Splits the network into two groups: the `isolated` validators and the rest
+    /// of the network. Messages are not delivered between the groups until the partition
+    /// is healed with [`heal_partition`]. A new partition replaces the previous one.
+    ///
+    /// The group of `us` is the one containing our node; an auditor node belongs to the group
+    /// of non-isolated validators. Validators outside of the group of `us` neither propose
+    /// blocks created by the testkit nor sign precommits for them, so the testkit cannot
+    /// create blocks if the group of `us` does not contain a Byzantine majority
+    /// of validators.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of the validators is absent in the network.
+    ///
+    /// [`heal_partition`]: #method.heal_partition
+    pub fn partition(&mut self, isolated: &[ValidatorId]) {
+        for &id in isolated {
+            assert!(
+                usize::from(id.0) < self.validators.len(),
+                "{}",
+                Error::UnknownValidator(id)
+            );
+        }
+
+        let isolated: BTreeSet<_> = isolated.iter().cloned().collect();
+        let us_isolated = self.us
+            .validator_id()
+            .map_or(false, |id| isolated.contains(&id));
+        self.unreachable = self.validators
+            .iter()
+            .filter_map(TestNode::validator_id)
+            .filter(|id| isolated.contains(id) != us_isolated)
+            .collect();
+    }
+
+    /// Heals the partition created with [`partition`], so that all validators are reachable.
+    ///
+    /// [`partition`]: #method.partition
+    pub fn heal_partition(&mut self) {
+        self.unreachable.clear();
+    }
+
+    /// Returns `true` if the validator belongs to the same partition group as `us`.
+    /// All validators are reachable if the network is not partitioned.
+    pub fn is_reachable(&self, id: ValidatorId) -> bool {
+        !self.unreachable.contains(&id)
+    }
+
+    /// Returns the identifiers of the validators in the partition group of `us`.
+    pub fn reachable_validators(&self) -> Vec<ValidatorId> {
+        self.validators
+            .iter()
+            .filter_map(TestNode::validator_id)
+            .filter(|&id| self.is_reachable(id))
+            .collect()
+    }
+
     /// Returns service public key of the validator with given id.
     pub fn service_public_key_of(&self, id: ValidatorId) -> Option<&crypto::PublicKey> {
         self.validators()
@@ -107,10 +221,96 @@ impl TestNetwork {
             .get(id.0 as usize)
             .map(|x| &x.consensus_public_key)
     }
+
+    /// Returns a human-readable table of the network nodes, which is suitable for inclusion
+    /// into panic messages. The table lists the validators in the order of their ids,
+    /// and our node if it is an auditor; public keys are abbreviated to the prefixes
+    /// of their hex representation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_testkit::TestNetwork;
+    /// let network = TestNetwork::new(2);
+    /// let description = network.describe();
+    /// assert!(description.starts_with("ValidatorId | Consensus PubKey"));
+    /// assert_eq!(description.lines().count(), 3);
+    /// ```
+    pub fn describe(&self) -> String {
+        let mut table = format!(
+            "{:<11} | {:<16} | {:<16} | {}",
+            "ValidatorId", "Consensus PubKey", "Service PubKey", "Is Us"
+        );
+        let auditor = if self.us.validator_id.is_none() {
+            Some(&self.us)
+        } else {
+            None
+        };
+        for node in self.validators.iter().chain(auditor) {
+            let role = node.validator_id
+                .map_or_else(|| "auditor".to_owned(), |id| id.to_string());
+            table.push_str(&format!(
+                "\n{:<11} | {:<16} | {:<16} | {}",
+                role,
+                &node.consensus_public_key.to_hex()[..KEY_PREFIX_LEN],
+                &node.service_public_key.to_hex()[..KEY_PREFIX_LEN],
+                if *node == self.us { "yes" } else { "no" }
+            ));
+        }
+        table
+    }
+
+    /// Returns sufficient number of validators for the Byzantine Fault Tolerance consensus
+    /// in this network.
+    pub fn majority_count(&self) -> usize {
+        byzantine_majority_count(self.validators.len())
+    }
+
+    /// Returns the identifier of the validator `offset` positions after the specified one,
+    /// wrapping around the number of validators in the network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate exonum_testkit;
+    /// # use exonum::helpers::ValidatorId;
+    /// # use exonum_testkit::TestKitBuilder;
+    /// # fn main() {
+    /// let testkit = TestKitBuilder::validator().with_validators(4).create();
+    /// let network = testkit.network();
+    /// assert_eq!(network.validator_id_after(ValidatorId(2), 3), ValidatorId(1));
+    /// # }
+    /// ```
+    pub fn validator_id_after(&self, id: ValidatorId, offset: u16) -> ValidatorId {
+        id.wrapping_add(offset, self.validators.len() as u16)
+    }
+}
+
+/// Returns sufficient number of validators for the Byzantine Fault Tolerance consensus
+/// in a network with `validator_count` validators, i.e., the minimal number of votes
+/// exceeding two thirds of `validator_count`.
+///
+/// The number is computed by the same rule as the one used by the nodes, so it can be used
+/// to form precommit sets for hypothetical networks.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_testkit::byzantine_majority_count;
+/// assert_eq!(byzantine_majority_count(1), 1);
+/// assert_eq!(byzantine_majority_count(4), 3);
+/// assert_eq!(byzantine_majority_count(6), 5);
+/// ```
+pub fn byzantine_majority_count(validator_count: usize) -> usize {
+    NodeState::byzantine_majority_count(validator_count)
 }
 
 /// An emulated node in the test network.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// Nodes are ordered by their validator id, with auditors going after all validators;
+/// nodes with the same role are ordered by their consensus public key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TestNode {
     consensus_secret_key: crypto::SecretKey,
     consensus_public_key: crypto::PublicKey,
@@ -134,6 +334,13 @@ impl TestNode {
         }
     }
 
+    /// Creates a new validator with the id corresponding to the given index
+    /// in the list of validators, or returns an error if the index does not fit
+    /// into `ValidatorId`.
+    pub fn new_validator_with_index(index: usize) -> Result<Self, ValidatorIdOverflow> {
+        try_validator_id_from_index(index).map(Self::new_validator)
+    }
+
     /// Creates a new validator with the given id.
     pub fn new_validator(validator_id: ValidatorId) -> Self {
         let (consensus_public_key, consensus_secret_key) = crypto::gen_keypair();
@@ -164,37 +371,101 @@ impl TestNode {
     }
 
     /// Creates a `Propose` message signed by this validator.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the node is not a validator.
     pub fn create_propose(
         &self,
         height: Height,
         last_hash: &crypto::Hash,
         tx_hashes: &[crypto::Hash],
     ) -> Propose {
-        Propose::new(
-            self.validator_id
-                .expect("An attempt to create propose from a non-validator node."),
+        self.try_create_propose(height, last_hash, tx_hashes)
+            .unwrap_or_else(|e| panic!("Cannot create propose at height {}: {}", height, e))
+    }
+
+    /// Creates a `Propose` message signed by this validator, which contains
+    /// no transactions. [`TestKit::propose_with_empty_tx_hashes`] creates such a propose
+    /// on top of the current blockchain state.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the node is not a validator.
+    ///
+    /// [`TestKit::propose_with_empty_tx_hashes`]:
+    /// struct.TestKit.html#method.propose_with_empty_tx_hashes
+    pub fn create_empty_propose(&self, height: Height, last_hash: &crypto::Hash) -> Propose {
+        self.create_propose(height, last_hash, &[])
+    }
+
+    /// Creates a `Propose` message signed by this validator, or returns an error
+    /// if the node is not a validator.
+    pub fn try_create_propose(
+        &self,
+        height: Height,
+        last_hash: &crypto::Hash,
+        tx_hashes: &[crypto::Hash],
+    ) -> Result<Propose, Error> {
+        Ok(Propose::new(
+            self.checked_validator_id()?,
             height,
             Round::first(),
             last_hash,
             tx_hashes,
             &self.consensus_secret_key,
-        )
+        ))
     }
 
     /// Creates a `Precommit` message signed by this validator.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the node is not a validator.
     pub fn create_precommit(&self, propose: &Propose, block_hash: &crypto::Hash) -> Precommit {
-        use std::time::SystemTime;
+        self.try_create_precommit(propose, block_hash)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Cannot create precommit for block {:?} at height {}: {}",
+                    block_hash,
+                    propose.height(),
+                    e
+                )
+            })
+    }
 
-        Precommit::new(
-            self.validator_id
-                .expect("An attempt to create propose from a non-validator node."),
+    /// Creates a `Precommit` message signed by this validator, or returns an error
+    /// if the node is not a validator.
+    pub fn try_create_precommit(
+        &self,
+        propose: &Propose,
+        block_hash: &crypto::Hash,
+    ) -> Result<Precommit, Error> {
+        self.try_create_precommit_at(propose, block_hash, SystemTime::now())
+    }
+
+    /// Creates a `Precommit` message signed by this validator with the specified time,
+    /// or returns an error if the node is not a validator.
+    pub(crate) fn try_create_precommit_at(
+        &self,
+        propose: &Propose,
+        block_hash: &crypto::Hash,
+        time: SystemTime,
+    ) -> Result<Precommit, Error> {
+        Ok(Precommit::new(
+            self.checked_validator_id()?,
             propose.height(),
             propose.round(),
             &propose.hash(),
             block_hash,
-            SystemTime::now().into(),
+            time.into(),
             &self.consensus_secret_key,
-        )
+        ))
+    }
+
+    fn checked_validator_id(&self) -> Result<ValidatorId, Error> {
+        self.validator_id
+            .ok_or_else(|| Error::NotAValidator(self.consensus_public_key))
     }
 
     /// Returns public keys of the node.
@@ -215,18 +486,97 @@ impl TestNode {
         self.validator_id = role;
     }
 
+    /// Converts this auditor node into a validator with the given id, preserving
+    /// the key material of the node.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the node is already a validator.
+    pub fn auditor_to_validator(mut self, validator_id: ValidatorId) -> TestNode {
+        if let Some(ValidatorId(id)) = self.validator_id {
+            panic!("The node is already a validator with id {}", id);
+        }
+        self.validator_id = Some(validator_id);
+        self
+    }
+
+    /// Returns a copy of this node converted into an auditor, preserving the key material
+    /// of the node. The copy may be passed to [`TestNetwork::update`] to demote a validator.
+    ///
+    /// [`TestNetwork::update`]: struct.TestNetwork.html#method.update
+    pub fn to_auditor(&self) -> TestNode {
+        TestNode {
+            validator_id: None,
+            ..self.clone()
+        }
+    }
+
     /// Returns the service keypair.
     pub fn service_keypair(&self) -> (&crypto::PublicKey, &crypto::SecretKey) {
         (&self.service_public_key, &self.service_secret_key)
     }
 }
 
+impl Ord for TestNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |node: &TestNode| {
+            (
+                node.validator_id.is_none(),
+                node.validator_id,
+                node.consensus_public_key,
+                node.service_public_key,
+            )
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for TestNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<TestNode> for ValidatorKeys {
     fn from(node: TestNode) -> Self {
         node.public_keys()
     }
 }
 
+/// Checks that the list of validators is not empty and the keys of validators are unique.
+fn check_validators(validators: &[TestNode]) {
+    assert!(
+        !validators.is_empty(),
+        "At least one validator should be present in the network."
+    );
+
+    let mut keys = HashSet::with_capacity(validators.len() * 2);
+    for validator in validators {
+        for key in &[validator.consensus_public_key, validator.service_public_key] {
+            assert!(
+                keys.insert(*key),
+                "Key {:?} is used by several validators; each consensus and service key \
+                 must be unique",
+                key
+            );
+        }
+    }
+}
+
+/// Converts the index of a validator into its id.
+fn try_validator_id_from_index(index: usize) -> Result<ValidatorId, ValidatorIdOverflow> {
+    if index > usize::from(u16::max_value()) {
+        Err(ValidatorIdOverflow(index))
+    } else {
+        Ok(ValidatorId(index as u16))
+    }
+}
+
+/// Converts the index of a validator into its id, panicking if the index does not fit.
+fn validator_id_from_index(index: usize) -> ValidatorId {
+    try_validator_id_from_index(index).unwrap_or_else(|e| panic!("{}", e))
+}
+
 /// A configuration of the test network.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TestNetworkConfiguration {
@@ -250,11 +600,27 @@ impl TestNetworkConfiguration {
         }
     }
 
+    /// Creates a configuration following this one, i.e., a configuration with the same
+    /// parameters, which refers to this configuration as the previous one.
+    pub(crate) fn following(&self) -> Self {
+        let mut following = self.clone();
+        following.stored_configuration.previous_cfg_hash =
+            CryptoHash::hash(&self.stored_configuration);
+        following
+    }
+
     /// Returns the node from whose perspective the testkit operates.
     pub fn us(&self) -> &TestNode {
         &self.us
     }
 
+    /// Returns the service keypair of the node from whose perspective the testkit operates.
+    /// The keypair may be used to sign the transactions proposing this configuration,
+    /// e.g., for the configuration service.
+    pub fn signing_keypair(&self) -> (&crypto::PublicKey, &crypto::SecretKey) {
+        self.us.service_keypair()
+    }
+
     /// Modifies the node from whose perspective the testkit operates.
     pub fn set_us(&mut self, us: TestNode) {
         self.us = us;
@@ -286,19 +652,75 @@ impl TestNetworkConfiguration {
         self.stored_configuration.consensus = consensus;
     }
 
+    /// Modifies the propose timeouts of the consensus configuration.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `min_timeout` is greater than `max_timeout`.
+    /// - Panics if `max_timeout` is not less than the first round timeout.
+    pub fn set_propose_timeouts(&mut self, min_timeout: Milliseconds, max_timeout: Milliseconds) {
+        assert!(
+            min_timeout <= max_timeout,
+            "Invalid propose timeouts: min_propose_timeout ({}) should be less or equal \
+             than max_propose_timeout ({})",
+            min_timeout,
+            max_timeout
+        );
+        let consensus = &mut self.stored_configuration.consensus;
+        assert!(
+            max_timeout < consensus.first_round_timeout,
+            "Invalid propose timeouts: max_propose_timeout ({}) should be less \
+             than first_round_timeout ({})",
+            max_timeout,
+            consensus.first_round_timeout
+        );
+        consensus.min_propose_timeout = min_timeout;
+        consensus.max_propose_timeout = max_timeout;
+    }
+
+    /// Modifies the amount of transactions in the pool, starting from which
+    /// the minimal propose timeout is used.
+    pub fn set_propose_timeout_threshold(&mut self, threshold: u32) {
+        self.stored_configuration.consensus.propose_timeout_threshold = threshold;
+    }
+
+    /// Modifies the first round timeout of the consensus configuration.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `timeout` is not greater than the maximal propose timeout.
+    pub fn set_first_round_timeout(&mut self, timeout: Milliseconds) {
+        let consensus = &mut self.stored_configuration.consensus;
+        assert!(
+            timeout > consensus.max_propose_timeout,
+            "Invalid first round timeout: first_round_timeout ({}) should be greater \
+             than max_propose_timeout ({})",
+            timeout,
+            consensus.max_propose_timeout
+        );
+        consensus.first_round_timeout = timeout;
+    }
+
     /// Modifies the validators list.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the list of validators is empty.
+    /// - Panics if the same key is used by several validators.
     pub fn set_validators<I>(&mut self, validators: I)
     where
         I: IntoIterator<Item = TestNode>,
     {
-        self.validators = validators
+        let validators: Vec<_> = validators
             .into_iter()
             .enumerate()
             .map(|(idx, mut node)| {
-                node.change_role(Some(ValidatorId(idx as u16)));
+                node.change_role(Some(validator_id_from_index(idx)));
                 node
             })
             .collect();
+        check_validators(&validators);
+        self.validators = validators;
         self.stored_configuration.validator_keys = self.validators
             .iter()
             .cloned()
@@ -308,6 +730,11 @@ impl TestNetworkConfiguration {
     }
 
     /// Returns the configuration for service with the given identifier.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the configuration has no section for the service.
+    /// - Panics if the service configuration cannot be deserialized as `D`.
     pub fn service_config<D>(&self, id: &str) -> D
     where
         for<'de> D: Deserialize<'de>,
@@ -315,8 +742,10 @@ impl TestNetworkConfiguration {
         let value = self.stored_configuration
             .services
             .get(id)
-            .expect("Unable to find configuration for service");
-        serde_json::from_value(value.clone()).unwrap()
+            .unwrap_or_else(|| panic!("Unable to find configuration for service `{}`", id));
+        serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+            panic!("Cannot deserialize the configuration of service `{}`: {}", id, e)
+        })
     }
 
     /// Modifies the configuration of the service with the given identifier.
@@ -337,7 +766,301 @@ impl TestNetworkConfiguration {
         let validator_id = self.validators
             .iter()
             .position(|x| x.public_keys().service_key == self.us.service_public_key)
-            .map(|x| ValidatorId(x as u16));
+            .map(validator_id_from_index);
         self.us.validator_id = validator_id;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use exonum::messages::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_create_empty_propose() {
+        let validator = TestNode::new_validator(ValidatorId(1));
+        let last_hash = crypto::hash(&[1, 2, 3]);
+        let propose = validator.create_empty_propose(Height(5), &last_hash);
+        assert_eq!(propose.validator(), ValidatorId(1));
+        assert_eq!(propose.height(), Height(5));
+        assert_eq!(*propose.prev_hash(), last_hash);
+        assert!(propose.transactions().is_empty());
+        assert!(propose.verify_signature(&validator.public_keys().consensus_key));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot create propose at height 1")]
+    fn test_create_empty_propose_by_auditor() {
+        TestNode::new_auditor().create_empty_propose(Height(1), &crypto::Hash::zero());
+    }
+
+    #[test]
+    fn test_network_description() {
+        let network = TestNetwork::new(3);
+        let description = network.describe();
+        let lines: Vec<_> = description.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "ValidatorId | Consensus PubKey | Service PubKey   | Is Us"
+        );
+
+        for (line, node) in lines[1..].iter().zip(network.validators()) {
+            let columns: Vec<_> = line.split(" | ").map(str::trim).collect();
+            assert_eq!(columns[0], node.validator_id().unwrap().to_string());
+            assert!(node.consensus_public_key.to_hex().starts_with(columns[1]));
+            assert!(node.service_public_key.to_hex().starts_with(columns[2]));
+        }
+        assert!(lines[1].ends_with("| yes"));
+        assert!(lines[2].ends_with("| no"));
+
+        let network = TestNetwork::with_our_role(None, 2);
+        let description = network.describe();
+        let lines: Vec<_> = description.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[3].starts_with("auditor     | "));
+        assert!(lines[3].ends_with("| yes"));
+        assert!(lines[1..3].iter().all(|line| line.ends_with("| no")));
+    }
+
+    #[test]
+    fn test_byzantine_majority_count() {
+        let expected = [(1, 1), (2, 2), (3, 3), (4, 3), (5, 4), (6, 5), (7, 5)];
+        for &(validator_count, majority_count) in &expected {
+            assert_eq!(
+                byzantine_majority_count(validator_count),
+                majority_count,
+                "Unexpected majority count for {} validators",
+                validator_count
+            );
+        }
+    }
+
+    #[test]
+    fn test_network_majority_count() {
+        for validator_count in 1..=7 {
+            let network = TestNetwork::new(validator_count);
+            assert_eq!(
+                network.majority_count(),
+                byzantine_majority_count(usize::from(validator_count))
+            );
+        }
+    }
+
+    #[test]
+    fn test_auditor_to_validator() {
+        let auditor = TestNode::new_auditor();
+        let validator = auditor.clone().auditor_to_validator(ValidatorId(2));
+        assert_eq!(validator.validator_id(), Some(ValidatorId(2)));
+        assert_eq!(validator.public_keys(), auditor.public_keys());
+        assert_eq!(validator.service_keypair(), auditor.service_keypair());
+    }
+
+    #[test]
+    #[should_panic(expected = "The node is already a validator with id 1")]
+    fn test_validator_to_validator() {
+        let validator = TestNode::new_validator(ValidatorId(1));
+        validator.auditor_to_validator(ValidatorId(2));
+    }
+
+    #[test]
+    fn test_to_auditor() {
+        let validator = TestNode::new_validator(ValidatorId(1));
+        let auditor = validator.to_auditor();
+        assert_eq!(auditor.validator_id(), None);
+        assert_eq!(auditor.public_keys(), validator.public_keys());
+        assert_eq!(auditor.service_keypair(), validator.service_keypair());
+        assert_eq!(validator.validator_id(), Some(ValidatorId(1)));
+        assert_eq!(auditor.to_auditor(), auditor);
+    }
+
+    #[test]
+    fn test_auditor_cannot_create_propose() {
+        let auditor = TestNode::new_auditor();
+        let consensus_key = auditor.public_keys().consensus_key;
+        let result = auditor.try_create_propose(Height(1), &crypto::Hash::zero(), &[]);
+        assert_matches!(result, Err(Error::NotAValidator(ref key)) if *key == consensus_key);
+    }
+
+    #[test]
+    fn test_node_ordering() {
+        let auditor = TestNode::new_auditor();
+        let mut nodes = vec![
+            auditor.clone(),
+            TestNode::new_validator(ValidatorId(2)),
+            TestNode::new_validator(ValidatorId(0)),
+            TestNode::new_validator(ValidatorId(1)),
+        ];
+        nodes.sort();
+
+        let ids: Vec<_> = nodes.iter().map(TestNode::validator_id).collect();
+        assert_eq!(
+            ids,
+            vec![Some(ValidatorId(0)), Some(ValidatorId(1)), Some(ValidatorId(2)), None]
+        );
+        assert_eq!(nodes[3], auditor);
+
+        let (first, second) = (TestNode::new_auditor(), TestNode::new_auditor());
+        assert_eq!(
+            first.cmp(&second),
+            first.consensus_public_key.cmp(&second.consensus_public_key)
+        );
+    }
+
+    fn test_configuration() -> TestNetworkConfiguration {
+        let network = TestNetwork::new(1);
+        let genesis = network.genesis_config();
+        let stored = StoredConfiguration {
+            previous_cfg_hash: crypto::Hash::zero(),
+            actual_from: Height(0),
+            validator_keys: genesis.validator_keys,
+            consensus: genesis.consensus,
+            services: Default::default(),
+        };
+        TestNetworkConfiguration::new(&network, stored)
+    }
+
+    #[test]
+    fn test_set_propose_timeouts() {
+        let mut cfg = test_configuration();
+        let first_round_timeout = cfg.consensus_configuration().first_round_timeout;
+        cfg.set_propose_timeouts(10, first_round_timeout - 1);
+        cfg.set_propose_timeout_threshold(7);
+        cfg.set_first_round_timeout(first_round_timeout * 2);
+
+        let consensus = cfg.consensus_configuration();
+        assert_eq!(consensus.min_propose_timeout, 10);
+        assert_eq!(consensus.max_propose_timeout, first_round_timeout - 1);
+        assert_eq!(consensus.propose_timeout_threshold, 7);
+        assert_eq!(consensus.first_round_timeout, first_round_timeout * 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "min_propose_timeout (20) should be less or equal")]
+    fn test_set_propose_timeouts_with_min_exceeding_max() {
+        let mut cfg = test_configuration();
+        cfg.set_propose_timeouts(20, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "should be less than first_round_timeout")]
+    fn test_set_propose_timeouts_exceeding_first_round_timeout() {
+        let mut cfg = test_configuration();
+        let first_round_timeout = cfg.consensus_configuration().first_round_timeout;
+        cfg.set_propose_timeouts(10, first_round_timeout);
+    }
+
+    #[test]
+    fn test_new_validator_with_index() {
+        let validator = TestNode::new_validator_with_index(5).unwrap();
+        assert_eq!(validator.validator_id(), Some(ValidatorId(5)));
+        let validator = TestNode::new_validator_with_index(65_535).unwrap();
+        assert_eq!(validator.validator_id(), Some(ValidatorId(65_535)));
+
+        let result = TestNode::new_validator_with_index(65_536);
+        assert_matches!(result, Err(ValidatorIdOverflow(65_536)));
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one validator should be present in the network")]
+    fn test_network_without_validators() {
+        TestNetwork::new(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Validator id 3 of our node is out of bounds")]
+    fn test_network_with_our_id_out_of_bounds() {
+        TestNetwork::with_our_role(Some(ValidatorId(3)), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one validator should be present in the network")]
+    fn test_update_network_without_validators() {
+        let mut network = TestNetwork::new(2);
+        let us = network.us().clone();
+        network.update(us, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is used by several validators")]
+    fn test_update_network_with_duplicate_validators() {
+        let mut network = TestNetwork::new(2);
+        let us = network.us().clone();
+        let validator = network.validators()[1].clone();
+        network.update(us, vec![validator.clone(), validator]);
+    }
+
+    #[test]
+    fn test_update_network_without_us() {
+        let mut network = TestNetwork::new(3);
+        let us = network.us().clone();
+        let validators = network.validators()[1..].to_vec();
+        network.update(us, validators);
+
+        assert_eq!(network.us().validator_id(), None);
+        assert_eq!(network.validators().len(), 2);
+        assert_eq!(network.validators()[0].validator_id(), Some(ValidatorId(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one validator should be present in the network")]
+    fn test_set_empty_validators() {
+        let mut cfg = test_configuration();
+        cfg.set_validators(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is used by several validators")]
+    fn test_set_duplicate_validators() {
+        let mut cfg = test_configuration();
+        let validator = cfg.validators()[0].clone();
+        cfg.set_validators(vec![validator.clone(), validator]);
+    }
+
+    #[test]
+    fn test_swap_us() {
+        let mut network = TestNetwork::new(3);
+        let old_us = network.us().clone();
+
+        let mut validator = network.validators()[2].clone();
+        validator.change_role(None);
+        assert_eq!(network.swap_us(validator.clone()), old_us);
+        assert_eq!(network.us().validator_id(), Some(ValidatorId(2)));
+        assert_eq!(network.us().public_keys(), validator.public_keys());
+
+        let auditor = TestNode::new_validator(ValidatorId(1));
+        network.swap_us(auditor);
+        assert_eq!(network.us().validator_id(), None);
+        assert_eq!(network.validators().len(), 3);
+    }
+
+    #[test]
+    fn test_partition() {
+        let mut network = TestNetwork::new(4);
+        assert_eq!(network.reachable_validators().len(), 4);
+
+        network.partition(&[ValidatorId(1), ValidatorId(2)]);
+        assert_eq!(
+            network.reachable_validators(),
+            vec![ValidatorId(0), ValidatorId(3)]
+        );
+        // Our node is in the isolated group.
+        network.partition(&[ValidatorId(0), ValidatorId(2)]);
+        assert_eq!(
+            network.reachable_validators(),
+            vec![ValidatorId(0), ValidatorId(2)]
+        );
+        assert!(!network.is_reachable(ValidatorId(3)));
+
+        network.heal_partition();
+        assert!(network.is_reachable(ValidatorId(3)));
+
+        let mut auditor_network = TestNetwork::with_our_role(None, 3);
+        auditor_network.partition(&[ValidatorId(0)]);
+        assert_eq!(
+            auditor_network.reachable_validators(),
+            vec![ValidatorId(1), ValidatorId(2)]
+        );
+    }
+}
@@ -12,9 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use futures::{executor, Async, Poll, Stream};
+use futures::{executor::{self, Notify}, Async, Poll, Stream};
 
 use std::fmt;
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
 
 /// Stream that terminates as soon as the underlying stream does not have items ready.
 struct TakeWhileReady<S> {
@@ -72,6 +75,49 @@ pub fn poll_events<S: Stream<Item = (), Error = ()>>(stream: &mut S) {
     spawn.wait_future().expect("Error polling events");
 }
 
+/// Notification handle, which unparks the thread polling a stream.
+struct ThreadNotify {
+    thread: Thread,
+}
+
+impl Notify for ThreadNotify {
+    fn notify(&self, _id: usize) {
+        self.thread.unpark();
+    }
+}
+
+/// Polls events from a stream of events until the timeout expires, parking the current
+/// thread while no events are ready. Returns the number of polled events.
+/// The stream is not closed.
+pub fn poll_events_for<S: Stream<Item = (), Error = ()>>(
+    stream: &mut S,
+    timeout: Duration,
+) -> usize {
+    let deadline = Instant::now() + timeout;
+    let notify = Arc::new(ThreadNotify {
+        thread: thread::current(),
+    });
+    let mut spawn = executor::spawn(stream.by_ref());
+    let mut count = 0;
+    loop {
+        match spawn
+            .poll_stream_notify(&notify, 0)
+            .expect("Error polling events")
+        {
+            Async::Ready(Some(())) => count += 1,
+            Async::Ready(None) => return count,
+            Async::NotReady => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return count;
+                }
+                // The thread may be unparked spuriously; the stream is polled again anyway.
+                thread::park_timeout(deadline - now);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,6 +143,33 @@ mod tests {
         assert_eq!(collected, vec![Ok(1), Ok(2), Ok(3), Ok(4)]);
     }
 
+    #[test]
+    fn test_poll_events_for() {
+        let (mut sender, mut receiver) = mpsc::channel::<()>(16);
+        sender.try_send(()).unwrap();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            sender.try_send(()).unwrap();
+            sender.try_send(()).unwrap();
+            // Dropping the sender terminates the stream, so polling finishes before timeout.
+        });
+
+        let start = Instant::now();
+        let count = poll_events_for(&mut receiver, Duration::from_secs(10));
+        assert_eq!(count, 3);
+        assert!(start.elapsed() < Duration::from_secs(10));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_poll_events_for_timeout() {
+        let (_sender, mut receiver) = mpsc::channel::<()>(16);
+        let start = Instant::now();
+        let count = poll_events_for(&mut receiver, Duration::from_millis(50));
+        assert_eq!(count, 0);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
     #[test]
     fn test_take_while_ready_with_executor() {
         let (mut sender, mut receiver) = mpsc::channel(16);
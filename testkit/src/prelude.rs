@@ -0,0 +1,106 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The testkit prelude.
+//!
+//! The prelude re-exports the testkit types together with the items of the `exonum` crate,
+//! which are commonly needed to define a service and test it with the testkit:
+//! transactions, services, storage views, consensus-related types, cryptographic helpers
+//! and the service API builder. Tests importing these items from the prelude
+//! rather than from `exonum` directly are not affected by the reorganization
+//! of the `exonum` modules.
+//!
+//! Note that the macros defined in `exonum` (e.g., `transactions!` and `encoding_struct!`)
+//! cannot be re-exported; they still require `#[macro_use] extern crate exonum`.
+//!
+//! # Examples
+//!
+//! ```
+//! #[macro_use]
+//! extern crate exonum;
+//! extern crate exonum_testkit;
+//!
+//! use exonum_testkit::prelude::*;
+//!
+//! transactions! {
+//!     PingTransactions {
+//!         const SERVICE_ID = 1;
+//!
+//!         struct TxPing {
+//!             from: &PublicKey,
+//!         }
+//!     }
+//! }
+//!
+//! impl Transaction for TxPing {
+//!     fn verify(&self) -> bool {
+//!         self.verify_signature(self.from())
+//!     }
+//!
+//!     fn execute(&self, _: &mut Fork) -> ExecutionResult {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! struct PingService;
+//!
+//! impl Service for PingService {
+//!     fn service_id(&self) -> u16 {
+//!         1
+//!     }
+//!
+//!     fn service_name(&self) -> &str {
+//!         "ping"
+//!     }
+//!
+//!     fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+//!         vec![]
+//!     }
+//!
+//!     fn tx_from_raw(
+//!         &self,
+//!         raw: RawTransaction,
+//!     ) -> Result<Box<dyn Transaction>, encoding::Error> {
+//!         Ok(PingTransactions::tx_from_raw(raw)?.into())
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let mut testkit = TestKit::for_service(PingService);
+//!     let (pubkey, key) = crypto::gen_keypair();
+//!     let tx = TxPing::new(&pubkey, &key);
+//!     testkit.create_block_with_transaction(tx.clone());
+//!
+//!     assert_eq!(testkit.height(), Height(1));
+//!     let snapshot = testkit.snapshot();
+//!     assert!(CoreSchema::new(&snapshot).transactions().contains(&tx.hash()));
+//! }
+//! ```
+
+pub use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState},
+    blockchain::{
+        ConsensusConfig, ExecutionError, ExecutionResult, Schema as CoreSchema, Service,
+        ServiceContext, StoredConfiguration, Transaction, TransactionError, TransactionErrorType,
+        TransactionSet,
+    },
+    crypto::{self, CryptoHash, Hash, PublicKey, SecretKey}, encoding,
+    helpers::{Height, Round, ValidatorId}, messages::{Message, RawTransaction},
+    storage::{Fork, Snapshot},
+};
+
+pub use {
+    ApiKind, ComparableSnapshot, TestKit, TestKitApi, TestKitBuilder, TestNetwork,
+    TestNetworkConfiguration, TestNode,
+};
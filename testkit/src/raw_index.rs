@@ -0,0 +1,218 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Inspection of the elements of indexes based on their raw storage entries.
+//!
+//! The layout of the raw entries mirrors the implementation of the `exonum` storage:
+//! list-like indexes store their length under the empty key, and Merkelized indexes
+//! store branch nodes alongside the elements.
+
+use serde_json::{Map, Value};
+
+use exonum::{encoding::serialize::encode_hex, storage::{Iterator as StorageIterator, Snapshot}};
+
+/// Name of the table storing the types of indexes.
+const INDEXES_METADATA: &str = "__INDEXES_METADATA__";
+
+// Types of indexes as stored in the metadata table.
+const LIST: u8 = 2;
+const SPARSE_LIST: u8 = 3;
+const PROOF_LIST: u8 = 5;
+const PROOF_MAP: u8 = 6;
+
+/// Height of the leaves in the keys of `ProofListIndex`, which is stored in the first byte.
+const PROOF_LIST_LEAF_HEIGHT: u8 = 1;
+/// Prefix of the leaf keys of `ProofMapIndex`.
+const PROOF_MAP_LEAF_PREFIX: u8 = 1;
+/// Length of the keys of `ProofMapIndex`.
+const PROOF_MAP_KEY_LEN: usize = 34;
+
+/// Maximal number of keys included into the sample.
+const SAMPLE_LEN: usize = 5;
+
+/// Number of elements in an index together with the raw keys of the first elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IndexElements {
+    pub count: u64,
+    pub sample: Vec<Vec<u8>>,
+}
+
+impl IndexElements {
+    /// Loads the elements of the ordinary index with the given name. An index that does not
+    /// exist in the storage has no elements.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the index is an index family.
+    pub fn load(snapshot: &dyn Snapshot, index_name: &str) -> Self {
+        let metadata = snapshot.get(INDEXES_METADATA, index_name.as_bytes());
+        let index_type = metadata.as_ref().map(|metadata| metadata[0]);
+        let is_family = metadata.as_ref().map_or(false, |metadata| metadata[1] != 0);
+        assert!(
+            !is_family,
+            "Index `{}` is an index family; only ordinary indexes can be inspected",
+            index_name
+        );
+
+        let mut elements = IndexElements {
+            count: 0,
+            sample: Vec::new(),
+        };
+        let mut iter = snapshot.iter(index_name, &[]);
+        while let Some((key, _)) = iter.next() {
+            if is_element_key(index_type, key) {
+                elements.count += 1;
+                if elements.sample.len() < SAMPLE_LEN {
+                    elements.sample.push(key.to_vec());
+                }
+            }
+        }
+        elements
+    }
+
+    /// Returns the hex representation of the sampled keys.
+    pub fn describe_sample(&self) -> String {
+        let mut keys: Vec<_> = self.sample.iter().map(encode_hex).collect();
+        if self.count > self.sample.len() as u64 {
+            keys.push("...".to_owned());
+        }
+        keys.join(", ")
+    }
+}
+
+/// Returns the names of the indexes registered in the storage, which start with the given
+/// prefix, in the lexicographic order.
+pub(crate) fn index_names(snapshot: &dyn Snapshot, prefix: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut iter = snapshot.iter(INDEXES_METADATA, prefix.as_bytes());
+    while let Some((key, _)) = iter.next() {
+        if !key.starts_with(prefix.as_bytes()) {
+            break;
+        }
+        names.push(String::from_utf8_lossy(key).into_owned());
+    }
+    names
+}
+
+/// Exports the raw storage entries of the elements of the index with the given name
+/// as a JSON object mapping the hex representation of keys to the hex representation
+/// of values. Auxiliary entries (e.g., the length of a list) are skipped.
+pub(crate) fn export_entries(snapshot: &dyn Snapshot, index_name: &str) -> Value {
+    let index_type = snapshot
+        .get(INDEXES_METADATA, index_name.as_bytes())
+        .map(|metadata| metadata[0]);
+    let mut entries = Map::new();
+    let mut iter = snapshot.iter(index_name, &[]);
+    while let Some((key, value)) = iter.next() {
+        if is_element_key(index_type, key) {
+            entries.insert(encode_hex(key), Value::String(encode_hex(value)));
+        }
+    }
+    Value::Object(entries)
+}
+
+/// Checks if the raw key corresponds to an element of the index rather than to
+/// an auxiliary entry.
+fn is_element_key(index_type: Option<u8>, key: &[u8]) -> bool {
+    match index_type {
+        Some(LIST) | Some(SPARSE_LIST) => !key.is_empty(),
+        Some(PROOF_LIST) => key.len() == 8 && key[0] == PROOF_LIST_LEAF_HEIGHT,
+        Some(PROOF_MAP) => key.len() == PROOF_MAP_KEY_LEN && key[0] == PROOF_MAP_LEAF_PREFIX,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::storage::{
+        Database, KeySetIndex, ListIndex, MapIndex, MemoryDB, ProofListIndex, ProofMapIndex,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_index_elements() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut list = ListIndex::new("list", &mut fork);
+            list.extend(vec![1_u64, 2, 3]);
+            let mut proof_list = ProofListIndex::new("proof_list", &mut fork);
+            proof_list.extend(vec![1_u64, 2, 3, 4, 5, 6, 7]);
+            let mut map = MapIndex::new("map", &mut fork);
+            map.put(&1_u8, 1_u64);
+            map.put(&2_u8, 2_u64);
+            let mut proof_map = ProofMapIndex::new("proof_map", &mut fork);
+            for i in 0_u8..10 {
+                proof_map.put(&[i; 32], u64::from(i));
+            }
+            let mut key_set = KeySetIndex::new("key_set", &mut fork);
+            key_set.insert(1_u8);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let count = |name| IndexElements::load(&*snapshot, name).count;
+        assert_eq!(count("list"), 3);
+        assert_eq!(count("proof_list"), 7);
+        assert_eq!(count("map"), 2);
+        assert_eq!(count("proof_map"), 10);
+        assert_eq!(count("key_set"), 1);
+        assert_eq!(count("unknown"), 0);
+
+        let elements = IndexElements::load(&*snapshot, "map");
+        assert_eq!(elements.sample, vec![vec![1], vec![2]]);
+        assert_eq!(elements.describe_sample(), "01, 02");
+        let elements = IndexElements::load(&*snapshot, "proof_map");
+        assert_eq!(elements.sample.len(), SAMPLE_LEN);
+        assert!(elements.describe_sample().ends_with(", ..."));
+    }
+
+    #[test]
+    fn test_export_entries() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut map = MapIndex::new("service.map", &mut fork);
+            map.put(&1_u8, 2_u8);
+            let mut list = ListIndex::new("service.list", &mut fork);
+            list.push(3_u8);
+            let mut proof_map = ProofMapIndex::new("service.proof_map", &mut fork);
+            proof_map.put(&[1; 32], 1_u8);
+            proof_map.put(&[2; 32], 2_u8);
+            let mut other = MapIndex::new("other.map", &mut fork);
+            other.put(&1_u8, 1_u8);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        assert_eq!(
+            index_names(&*snapshot, "service."),
+            vec![
+                "service.list".to_owned(),
+                "service.map".to_owned(),
+                "service.proof_map".to_owned(),
+            ]
+        );
+        assert_eq!(export_entries(&*snapshot, "service.map"), json!({ "01": "02" }));
+        // The length of the list and the branch nodes of the proof map are not exported.
+        assert_eq!(
+            export_entries(&*snapshot, "service.list"),
+            json!({ "0000000000000000": "03" })
+        );
+        let proof_map = export_entries(&*snapshot, "service.proof_map");
+        let values: Vec<_> = proof_map.as_object().unwrap().values().cloned().collect();
+        assert_eq!(values, vec![json!("01"), json!("02")]);
+    }
+}
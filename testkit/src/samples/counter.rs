@@ -0,0 +1,211 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimalistic service maintaining a single counter.
+//!
+//! The service is intentionally simple; it may be used by downstream crates
+//! to compare the performance of their own services against a known baseline.
+
+use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState},
+    blockchain::{ExecutionError, ExecutionResult, Service, Transaction, TransactionSet},
+    crypto::{Hash, PublicKey}, encoding, messages::{Message, RawTransaction},
+    node::TransactionSend, storage::{Entry, Fork, Snapshot},
+};
+
+use super::TransactionResponse;
+
+/// Identifier of the counter service.
+pub const COUNTER_SERVICE_ID: u16 = 1_000;
+/// Name of the counter service.
+pub const COUNTER_SERVICE_NAME: &str = "counter";
+
+/// Schema of the counter service.
+#[derive(Debug)]
+pub struct CounterSchema<T> {
+    view: T,
+}
+
+impl<T: AsRef<dyn Snapshot>> CounterSchema<T> {
+    /// Creates a new schema instance.
+    pub fn new(view: T) -> Self {
+        CounterSchema { view }
+    }
+
+    fn entry(&self) -> Entry<&dyn Snapshot, u64> {
+        Entry::new("counter.count", self.view.as_ref())
+    }
+
+    /// Returns the current counter value.
+    pub fn count(&self) -> Option<u64> {
+        self.entry().get()
+    }
+}
+
+impl<'a> CounterSchema<&'a mut Fork> {
+    fn entry_mut(&mut self) -> Entry<&mut Fork, u64> {
+        Entry::new("counter.count", &mut *self.view)
+    }
+
+    fn inc_count(&mut self, inc: u64) -> u64 {
+        let count = self.count().unwrap_or(0) + inc;
+        self.entry_mut().set(count);
+        count
+    }
+}
+
+transactions! {
+    /// Transactions of the counter service.
+    pub CounterTransactions {
+        const SERVICE_ID = COUNTER_SERVICE_ID;
+
+        /// Transaction increasing the counter value.
+        struct TxIncrement {
+            /// Author of the transaction.
+            author: &PublicKey,
+            /// Value to increase the counter by.
+            by: u64,
+        }
+
+        /// Transaction resetting the counter value to zero.
+        struct TxReset {
+            /// Author of the transaction.
+            author: &PublicKey,
+        }
+    }
+}
+
+impl Transaction for TxIncrement {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    /// Increases the counter value. Fails with the error code 0 if the increment is zero.
+    /// Panics on the counter overflow.
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        if self.by() == 0 {
+            Err(ExecutionError::with_description(
+                0,
+                "Adding zero does nothing!".to_string(),
+            ))?;
+        }
+        CounterSchema::new(fork).inc_count(self.by());
+        Ok(())
+    }
+}
+
+impl Transaction for TxReset {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        CounterSchema::new(fork).entry_mut().set(0);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CounterApi;
+
+impl CounterApi {
+    fn count(state: &ServiceApiState, _query: ()) -> api::Result<u64> {
+        let snapshot = state.snapshot();
+        Ok(CounterSchema::new(&snapshot).count().unwrap_or_default())
+    }
+
+    fn post_transaction(
+        state: &ServiceApiState,
+        transaction: Box<dyn Transaction>,
+    ) -> api::Result<TransactionResponse> {
+        let tx_hash = transaction.hash();
+        state.sender().send(transaction)?;
+        Ok(TransactionResponse { tx_hash })
+    }
+
+    fn increment(
+        state: &ServiceApiState,
+        transaction: TxIncrement,
+    ) -> api::Result<TransactionResponse> {
+        Self::post_transaction(state, transaction.into())
+    }
+
+    fn reset(state: &ServiceApiState, transaction: TxReset) -> api::Result<TransactionResponse> {
+        Self::post_transaction(state, transaction.into())
+    }
+
+    fn wire(builder: &mut ServiceApiBuilder) {
+        builder
+            .public_scope()
+            .endpoint("count", Self::count)
+            .endpoint_mut("count", Self::increment);
+        builder
+            .private_scope()
+            .endpoint("count", Self::count)
+            .endpoint_mut("reset", Self::reset);
+    }
+}
+
+/// Service maintaining a single counter.
+///
+/// The current value of the counter is available via the `count` endpoint of the public
+/// and private APIs. The counter can be increased with `TxIncrement` transactions, which
+/// can be submitted to the `count` endpoint of the public API, and reset with `TxReset`
+/// transactions submitted to the `reset` endpoint of the private API.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate exonum;
+/// # extern crate exonum_testkit;
+/// use exonum::crypto;
+/// use exonum_testkit::TestKitBuilder;
+/// use exonum_testkit::samples::{CounterSchema, CounterService, TxIncrement};
+///
+/// # fn main() {
+/// let mut testkit = TestKitBuilder::validator()
+///     .with_service(CounterService)
+///     .create();
+/// let (pubkey, key) = crypto::gen_keypair();
+/// testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 5, &key));
+///
+/// let snapshot = testkit.snapshot();
+/// assert_eq!(CounterSchema::new(&snapshot).count(), Some(5));
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CounterService;
+
+impl Service for CounterService {
+    fn service_id(&self) -> u16 {
+        COUNTER_SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        COUNTER_SERVICE_NAME
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        let tx = CounterTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        CounterApi::wire(builder)
+    }
+}
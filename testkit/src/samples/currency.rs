@@ -0,0 +1,342 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simple cryptocurrency service with a configurable initial balance of wallets.
+
+// Workaround for `failure` see https://github.com/rust-lang-nursery/failure/issues/223 and
+// ECR-1771 for the details.
+#![allow(bare_trait_objects)]
+
+use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState},
+    blockchain::{
+        ExecutionError, ExecutionResult, Schema as CoreSchema, Service, Transaction,
+        TransactionSet,
+    },
+    crypto::{Hash, PublicKey}, encoding, messages::{Message, RawTransaction},
+    node::TransactionSend, storage::{Fork, ProofMapIndex, Snapshot},
+};
+use serde_json::{self, Value};
+
+use super::TransactionResponse;
+
+/// Identifier of the currency service.
+pub const CURRENCY_SERVICE_ID: u16 = 1_001;
+/// Name of the currency service.
+pub const CURRENCY_SERVICE_NAME: &str = "currency";
+
+/// Configuration of the currency service.
+///
+/// The configuration is stored in the `services` section of the blockchain configuration
+/// under the `CURRENCY_SERVICE_NAME` key, so it can be changed with
+/// `TestNetworkConfiguration::set_service_config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrencyConfig {
+    /// Balance of newly created wallets.
+    pub initial_balance: u64,
+}
+
+impl Default for CurrencyConfig {
+    fn default() -> Self {
+        CurrencyConfig {
+            initial_balance: 100,
+        }
+    }
+}
+
+encoding_struct! {
+    /// Wallet of the currency service.
+    struct Wallet {
+        /// Public key of the wallet owner.
+        pub_key: &PublicKey,
+        /// Name of the wallet owner.
+        name: &str,
+        /// Current balance of the wallet.
+        balance: u64,
+    }
+}
+
+impl Wallet {
+    /// Returns a copy of this wallet with the balance increased by the specified amount.
+    pub fn increase(self, amount: u64) -> Self {
+        let balance = self.balance() + amount;
+        Self::new(self.pub_key(), self.name(), balance)
+    }
+
+    /// Returns a copy of this wallet with the balance decreased by the specified amount.
+    pub fn decrease(self, amount: u64) -> Self {
+        let balance = self.balance() - amount;
+        Self::new(self.pub_key(), self.name(), balance)
+    }
+}
+
+/// Schema of the currency service.
+#[derive(Debug)]
+pub struct CurrencySchema<T> {
+    view: T,
+}
+
+impl<T: AsRef<dyn Snapshot>> CurrencySchema<T> {
+    /// Creates a new schema instance.
+    pub fn new(view: T) -> Self {
+        CurrencySchema { view }
+    }
+
+    /// Returns the table with all wallets keyed by the public key of their owner.
+    pub fn wallets(&self) -> ProofMapIndex<&dyn Snapshot, PublicKey, Wallet> {
+        ProofMapIndex::new("currency.wallets", self.view.as_ref())
+    }
+
+    /// Returns the wallet with the specified owner, if any.
+    pub fn wallet(&self, pub_key: &PublicKey) -> Option<Wallet> {
+        self.wallets().get(pub_key)
+    }
+
+    /// Returns the configuration of the service, which is currently in effect.
+    pub fn config(&self) -> CurrencyConfig {
+        CoreSchema::new(self.view.as_ref())
+            .actual_configuration()
+            .services
+            .get(CURRENCY_SERVICE_NAME)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the state hash of the service.
+    pub fn state_hash(&self) -> Vec<Hash> {
+        vec![self.wallets().merkle_root()]
+    }
+}
+
+impl<'a> CurrencySchema<&'a mut Fork> {
+    fn wallets_mut(&mut self) -> ProofMapIndex<&mut Fork, PublicKey, Wallet> {
+        ProofMapIndex::new("currency.wallets", &mut *self.view)
+    }
+}
+
+/// Error codes emitted by the currency transactions during execution.
+#[derive(Debug, Fail)]
+#[repr(u8)]
+pub enum Error {
+    /// Wallet already exists.
+    ///
+    /// Can be emitted by `TxCreateWallet`.
+    #[fail(display = "Wallet already exists")]
+    WalletAlreadyExists = 0,
+
+    /// Sender doesn't exist.
+    ///
+    /// Can be emitted by `TxTransfer`.
+    #[fail(display = "Sender doesn't exist")]
+    SenderNotFound = 1,
+
+    /// Receiver doesn't exist.
+    ///
+    /// Can be emitted by `TxTransfer`.
+    #[fail(display = "Receiver doesn't exist")]
+    ReceiverNotFound = 2,
+
+    /// Insufficient currency amount.
+    ///
+    /// Can be emitted by `TxTransfer`.
+    #[fail(display = "Insufficient currency amount")]
+    InsufficientCurrencyAmount = 3,
+}
+
+impl From<Error> for ExecutionError {
+    fn from(value: Error) -> ExecutionError {
+        let description = format!("{}", value);
+        ExecutionError::with_description(value as u8, description)
+    }
+}
+
+transactions! {
+    /// Transactions of the currency service.
+    pub CurrencyTransactions {
+        const SERVICE_ID = CURRENCY_SERVICE_ID;
+
+        /// Transaction creating a new wallet with the initial balance taken
+        /// from the service configuration.
+        struct TxCreateWallet {
+            /// Public key of the wallet owner.
+            pub_key: &PublicKey,
+            /// Name of the wallet owner.
+            name: &str,
+        }
+
+        /// Transaction transferring currency from one wallet to another.
+        struct TxTransfer {
+            /// Public key of the sender.
+            from: &PublicKey,
+            /// Public key of the receiver.
+            to: &PublicKey,
+            /// Amount of currency to transfer.
+            amount: u64,
+            /// Auxiliary number making otherwise identical transfers distinct.
+            seed: u64,
+        }
+    }
+}
+
+impl Transaction for TxCreateWallet {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = CurrencySchema::new(fork);
+        if schema.wallet(self.pub_key()).is_some() {
+            Err(Error::WalletAlreadyExists)?;
+        }
+
+        let initial_balance = schema.config().initial_balance;
+        let wallet = Wallet::new(self.pub_key(), self.name(), initial_balance);
+        schema.wallets_mut().put(self.pub_key(), wallet);
+        Ok(())
+    }
+}
+
+impl Transaction for TxTransfer {
+    fn verify(&self) -> bool {
+        self.from() != self.to() && self.verify_signature(self.from())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = CurrencySchema::new(fork);
+        let sender = schema.wallet(self.from()).ok_or(Error::SenderNotFound)?;
+        let receiver = schema.wallet(self.to()).ok_or(Error::ReceiverNotFound)?;
+
+        let amount = self.amount();
+        if sender.balance() < amount {
+            Err(Error::InsufficientCurrencyAmount)?;
+        }
+
+        let mut wallets = schema.wallets_mut();
+        wallets.put(self.from(), sender.decrease(amount));
+        wallets.put(self.to(), receiver.increase(amount));
+        Ok(())
+    }
+}
+
+/// Query for a single wallet via the public API.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WalletQuery {
+    /// Public key of the wallet owner.
+    pub pub_key: PublicKey,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CurrencyApi;
+
+impl CurrencyApi {
+    fn wallet(state: &ServiceApiState, query: WalletQuery) -> api::Result<Wallet> {
+        let snapshot = state.snapshot();
+        CurrencySchema::new(&snapshot)
+            .wallet(&query.pub_key)
+            .ok_or_else(|| api::Error::NotFound("Wallet not found".to_owned()))
+    }
+
+    fn wallets(state: &ServiceApiState, _query: ()) -> api::Result<Vec<Wallet>> {
+        let snapshot = state.snapshot();
+        let schema = CurrencySchema::new(&snapshot);
+        let wallets = schema.wallets().values().collect();
+        Ok(wallets)
+    }
+
+    fn post_transaction(
+        state: &ServiceApiState,
+        transaction: CurrencyTransactions,
+    ) -> api::Result<TransactionResponse> {
+        let transaction: Box<dyn Transaction> = transaction.into();
+        let tx_hash = transaction.hash();
+        state.sender().send(transaction)?;
+        Ok(TransactionResponse { tx_hash })
+    }
+
+    fn wire(builder: &mut ServiceApiBuilder) {
+        builder
+            .public_scope()
+            .endpoint("v1/wallet", Self::wallet)
+            .endpoint_mut("v1/wallets/transaction", Self::post_transaction);
+        builder
+            .private_scope()
+            .endpoint("v1/wallets", Self::wallets);
+    }
+}
+
+/// Simple cryptocurrency service.
+///
+/// Wallets are created with `TxCreateWallet` transactions and receive the initial balance
+/// specified in the `CurrencyConfig` of the service; the currency can be moved between
+/// wallets with `TxTransfer` transactions. Both kinds of transactions can be submitted to
+/// the `v1/wallets/transaction` endpoint of the public API. A single wallet is available
+/// via the `v1/wallet` endpoint of the public API, and all wallets via the `v1/wallets`
+/// endpoint of the private API.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate exonum;
+/// # extern crate exonum_testkit;
+/// use exonum::crypto;
+/// use exonum_testkit::TestKitBuilder;
+/// use exonum_testkit::samples::{CurrencySchema, CurrencyService, TxCreateWallet, TxTransfer};
+///
+/// # fn main() {
+/// let mut testkit = TestKitBuilder::validator()
+///     .with_service(CurrencyService)
+///     .create();
+/// let (alice, alice_key) = crypto::gen_keypair();
+/// let (bob, bob_key) = crypto::gen_keypair();
+/// testkit.create_block_with_transactions(vec![
+///     TxCreateWallet::new(&alice, "Alice", &alice_key).into(),
+///     TxCreateWallet::new(&bob, "Bob", &bob_key).into(),
+/// ]);
+/// testkit.create_block_with_transaction(TxTransfer::new(&alice, &bob, 10, 0, &alice_key));
+///
+/// let snapshot = testkit.snapshot();
+/// let schema = CurrencySchema::new(&snapshot);
+/// assert_eq!(schema.wallet(&alice).unwrap().balance(), 90);
+/// assert_eq!(schema.wallet(&bob).unwrap().balance(), 110);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CurrencyService;
+
+impl Service for CurrencyService {
+    fn service_id(&self) -> u16 {
+        CURRENCY_SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        CURRENCY_SERVICE_NAME
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        CurrencySchema::new(snapshot).state_hash()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        let tx = CurrencyTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+
+    fn initialize(&self, _fork: &mut Fork) -> Value {
+        serde_json::to_value(CurrencyConfig::default()).unwrap()
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        CurrencyApi::wire(builder)
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sample services used in the testkit tests, benchmarks and documentation.
+//!
+//! The services are intentionally simple, yet complete: they define transactions,
+//! schemas and public / private HTTP API endpoints. They may be used by downstream crates
+//! to get acquainted with the testkit, or to compare the performance of their own services
+//! against a known baseline.
+//!
+//! This module is available with the `examples` feature of the crate, which is enabled
+//! by default.
+
+pub use self::counter::{
+    CounterSchema, CounterService, CounterTransactions, TxIncrement, TxReset,
+    COUNTER_SERVICE_ID, COUNTER_SERVICE_NAME,
+};
+pub use self::currency::{
+    CurrencyConfig, CurrencySchema, CurrencyService, CurrencyTransactions, TxCreateWallet,
+    TxTransfer, Wallet, CURRENCY_SERVICE_ID, CURRENCY_SERVICE_NAME,
+};
+
+use exonum::crypto::Hash;
+
+pub mod counter;
+pub mod currency;
+
+/// Response to the transaction submission via the HTTP API.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TransactionResponse {
+    /// Hash of the submitted transaction.
+    pub tx_hash: Hash,
+}
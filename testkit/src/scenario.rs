@@ -0,0 +1,320 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Declarative multi-step test scenarios.
+
+// Workaround for `failure` see https://github.com/rust-lang-nursery/failure/issues/223 and
+// ECR-1771 for the details.
+#![allow(bare_trait_objects)]
+
+use serde_json::{self, Value};
+
+use std::fmt;
+
+use exonum::{
+    blockchain::Transaction, crypto::CryptoHash, encoding::serialize::reexport::DeserializeOwned,
+    helpers::Height, node::TransactionSend,
+};
+
+use {ApiKind, TestKit, TestKitApi};
+
+/// Sequence of steps performed with a testkit: sending transactions, creating blocks
+/// and checking the responses of public API endpoints.
+///
+/// Steps are executed in the order they were added to the scenario. If a step fails,
+/// the scenario is stopped, and the returned [`ScenarioError`] describes the failed step,
+/// the blockchain height at the moment of the failure and, for API checks, the response body.
+///
+/// A step fails in the following cases:
+///
+/// - `send` and `send_many`: a transaction does not pass verification or is already
+///   committed to the blockchain.
+/// - `block` and `blocks`: the block cannot be committed, or any transaction in the block
+///   has returned an execution error.
+/// - `assert_api`: the endpoint returns an error, the response cannot be deserialized
+///   into the expected type, or the check returns `false`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate exonum;
+/// # extern crate exonum_testkit;
+/// use exonum::api::node::public::explorer::BlocksRange;
+/// use exonum::helpers::Height;
+/// use exonum_testkit::{ApiKind, Scenario, TestKitBuilder};
+///
+/// # fn main() {
+/// let mut testkit = TestKitBuilder::validator().create();
+/// Scenario::new()
+///     .blocks(2)
+///     .assert_api(ApiKind::Explorer, "v1/blocks?count=10", |range: BlocksRange| {
+///         range.range.end == Height(3)
+///     })
+///     .block()
+///     .run(&mut testkit);
+/// assert_eq!(testkit.height(), Height(3));
+/// # }
+/// ```
+///
+/// [`ScenarioError`]: struct.ScenarioError.html
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+enum Step {
+    Send(Box<dyn Transaction>),
+    SendMany(Vec<Box<dyn Transaction>>),
+    Blocks(usize),
+    AssertApi {
+        kind: ApiKind,
+        endpoint: String,
+        check: Box<dyn Fn(Value) -> Result<(), String>>,
+    },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Send(tx) => write!(f, "send {:?}", tx.hash()),
+            Step::SendMany(txs) => write!(f, "send_many of {} transactions", txs.len()),
+            Step::Blocks(1) => write!(f, "block"),
+            Step::Blocks(count) => write!(f, "blocks {}", count),
+            Step::AssertApi { kind, endpoint, .. } => write!(f, "assert_api {}/{}", kind, endpoint),
+        }
+    }
+}
+
+impl fmt::Debug for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let steps: Vec<_> = self.steps.iter().map(Step::to_string).collect();
+        f.debug_struct("Scenario").field("steps", &steps).finish()
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scenario {
+    /// Creates an empty scenario.
+    pub fn new() -> Self {
+        Scenario { steps: Vec::new() }
+    }
+
+    /// Adds a step sending the transaction to the testkit, as if it were received
+    /// from the network or submitted via the API.
+    pub fn send<T>(mut self, transaction: T) -> Self
+    where
+        T: Into<Box<dyn Transaction>>,
+    {
+        self.steps.push(Step::Send(transaction.into()));
+        self
+    }
+
+    /// Adds a step sending several transactions to the testkit.
+    pub fn send_many<I>(mut self, transactions: I) -> Self
+    where
+        I: IntoIterator<Item = Box<dyn Transaction>>,
+    {
+        self.steps
+            .push(Step::SendMany(transactions.into_iter().collect()));
+        self
+    }
+
+    /// Adds a step creating a block with all transactions in the pool.
+    pub fn block(self) -> Self {
+        self.blocks(1)
+    }
+
+    /// Adds a step creating the specified number of blocks in a row. The first block
+    /// includes all transactions in the pool.
+    pub fn blocks(mut self, count: usize) -> Self {
+        self.steps.push(Step::Blocks(count));
+        self
+    }
+
+    /// Adds a step sending a GET request to the public API endpoint and checking
+    /// the response with the given closure. The step fails if the closure returns `false`.
+    pub fn assert_api<T, F>(mut self, kind: ApiKind, endpoint: &str, check: F) -> Self
+    where
+        T: DeserializeOwned + 'static,
+        F: Fn(T) -> bool + 'static,
+    {
+        let check = move |response: Value| {
+            let response = serde_json::from_value(response)
+                .map_err(|e| format!("cannot deserialize response: {}", e))?;
+            if check(response) {
+                Ok(())
+            } else {
+                Err("check has failed".to_owned())
+            }
+        };
+        self.steps.push(Step::AssertApi {
+            kind,
+            endpoint: endpoint.to_owned(),
+            check: Box::new(check),
+        });
+        self
+    }
+
+    /// Returns the number of steps in the scenario.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if the scenario has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Runs the scenario with the given testkit.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any step of the scenario fails. The panic message describes the failed step.
+    pub fn run(self, testkit: &mut TestKit) {
+        self.try_run(testkit).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Runs the scenario with the given testkit, or returns an error describing the first
+    /// failed step. The changes made by the steps preceding the failed one are retained.
+    pub fn try_run(self, testkit: &mut TestKit) -> Result<(), ScenarioError> {
+        // The API is created lazily, since creating it launches a web server.
+        let mut api: Option<TestKitApi> = None;
+
+        for (index, step) in self.steps.into_iter().enumerate() {
+            let step_description = step.to_string();
+            let result = match step {
+                Step::Send(tx) => send(testkit, tx),
+                Step::SendMany(txs) => txs.into_iter().map(|tx| send(testkit, tx)).collect(),
+                Step::Blocks(count) => create_blocks(testkit, count),
+                Step::AssertApi {
+                    kind,
+                    endpoint,
+                    check,
+                } => {
+                    let api = api.get_or_insert_with(|| testkit.api());
+                    match api.public(kind).get::<Value>(&endpoint) {
+                        Ok(response) => check(response.clone()).map_err(|reason| Failure {
+                            reason,
+                            response: Some(response.to_string()),
+                        }),
+                        Err(e) => Err(Failure::new(format!("API error: {}", e))),
+                    }
+                }
+            };
+
+            if let Err(failure) = result {
+                return Err(ScenarioError {
+                    step: index + 1,
+                    step_description,
+                    height: testkit.height(),
+                    reason: failure.reason,
+                    response: failure.response,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Failure of a single scenario step.
+struct Failure {
+    reason: String,
+    response: Option<String>,
+}
+
+impl Failure {
+    fn new(reason: String) -> Self {
+        Failure {
+            reason,
+            response: None,
+        }
+    }
+}
+
+fn send(testkit: &mut TestKit, tx: Box<dyn Transaction>) -> Result<(), Failure> {
+    let tx_hash = tx.hash();
+    if !tx.verify() {
+        return Err(Failure::new(format!(
+            "transaction {:?} does not pass verification",
+            tx_hash
+        )));
+    }
+    if testkit.core_schema().transactions().contains(&tx_hash) {
+        return Err(Failure::new(format!(
+            "transaction {:?} is already committed",
+            tx_hash
+        )));
+    }
+    testkit
+        .api_sender
+        .send(tx)
+        .map_err(|e| Failure::new(e.to_string()))
+}
+
+fn create_blocks(testkit: &mut TestKit, count: usize) -> Result<(), Failure> {
+    for _ in 0..count {
+        let block = testkit.try_create_block().map_err(|e| Failure::new(e.to_string()))?;
+        let errors: Vec<_> = block
+            .transactions
+            .iter()
+            .filter_map(|tx| {
+                tx.status()
+                    .err()
+                    .map(|e| format!("{:?}: {}", tx.content().hash(), e))
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(Failure::new(format!(
+                "transactions have failed: {}",
+                errors.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Error returned by [`Scenario::try_run`], which describes the failed step.
+///
+/// [`Scenario::try_run`]: struct.Scenario.html#method.try_run
+#[derive(Debug, Fail)]
+pub struct ScenarioError {
+    /// One-based index of the failed step.
+    pub step: usize,
+    /// Human-readable description of the failed step, e.g., `assert_api api/system/v1/stats`.
+    pub step_description: String,
+    /// Blockchain height at the moment of the failure.
+    pub height: Height,
+    /// Reason of the failure.
+    pub reason: String,
+    /// Body of the API response, if the failed step is an API check.
+    pub response: Option<String>,
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Step {} ({}) failed at height {}: {}",
+            self.step, self.step_description, self.height, self.reason
+        )?;
+        if let Some(ref response) = self.response {
+            write!(f, "; response: {}", response)?;
+        }
+        Ok(())
+    }
+}
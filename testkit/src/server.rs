@@ -168,6 +168,7 @@ mod tests {
     use exonum::storage::{Fork, Snapshot};
 
     use super::*;
+    use api::api_surfaces;
     use {TestKitApi, TestKitBuilder};
 
     type DeBlock = BlockWithTransactions<serde_json::Value>;
@@ -233,11 +234,12 @@ mod tests {
             .create();
 
         let api_sender = testkit.api_sender.clone();
+        let api_surfaces = api_surfaces(testkit.blockchain());
         let testkit = Arc::new(RwLock::new(testkit));
         let aggregator = create_testkit_api_aggregator(&testkit);
         let (testkit, api) = (
             Arc::clone(&testkit),
-            TestKitApi::from_raw_parts(aggregator, api_sender),
+            TestKitApi::from_raw_parts(aggregator, api_surfaces, api_sender),
         );
 
         testkit.write().unwrap().create_blocks_until(height);
@@ -0,0 +1,521 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wrappers changing the behavior of services added to the testkit.
+
+use serde_json::Value;
+
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use exonum::{
+    api::ServiceApiBuilder,
+    blockchain::{ExecutionResult, Schema as CoreSchema, Service, ServiceContext, Transaction},
+    crypto::{CryptoHash, Hash},
+    encoding::{
+        serialize::{json::ExonumJson, WriteBufferWrapper}, Error as MessageError, Offset,
+    },
+    helpers::Height, messages::{Message, RawMessage, RawTransaction},
+    storage::{Fork, Snapshot},
+};
+
+use metrics::ExecutionLog;
+
+/// Service which configuration in the genesis block is overridden by the testkit.
+///
+/// The `initialize` method of the wrapped service is still invoked, so that the service
+/// may put its initial data into the storage, but its return value is replaced.
+pub(crate) struct GenesisConfigOverride {
+    inner: Box<dyn Service>,
+    config: Value,
+}
+
+impl GenesisConfigOverride {
+    pub fn new(inner: Box<dyn Service>, config: Value) -> Self {
+        GenesisConfigOverride { inner, config }
+    }
+}
+
+impl Service for GenesisConfigOverride {
+    fn service_id(&self) -> u16 {
+        self.inner.service_id()
+    }
+
+    fn service_name(&self) -> &str {
+        self.inner.service_name()
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        self.inner.state_hash(snapshot)
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+        self.inner.tx_from_raw(raw)
+    }
+
+    fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+        self.inner.transaction_type_name(tx)
+    }
+
+    fn export_state(&self, snapshot: &dyn Snapshot) -> Option<Value> {
+        self.inner.export_state(snapshot)
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> Value {
+        self.inner.initialize(fork);
+        self.config.clone()
+    }
+
+    fn before_commit(&self, fork: &mut Fork) {
+        self.inner.before_commit(fork)
+    }
+
+    fn after_commit(&self, context: &ServiceContext) {
+        self.inner.after_commit(context)
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        self.inner.wire_api(builder)
+    }
+}
+
+/// Service shared among several blockchains, e.g., ones of a testkit and its copies.
+pub(crate) struct SharedService {
+    inner: Arc<dyn Service>,
+}
+
+impl SharedService {
+    pub fn new(inner: Arc<dyn Service>) -> Self {
+        SharedService { inner }
+    }
+}
+
+impl Service for SharedService {
+    fn service_id(&self) -> u16 {
+        self.inner.service_id()
+    }
+
+    fn service_name(&self) -> &str {
+        self.inner.service_name()
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        self.inner.state_hash(snapshot)
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+        self.inner.tx_from_raw(raw)
+    }
+
+    fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+        self.inner.transaction_type_name(tx)
+    }
+
+    fn export_state(&self, snapshot: &dyn Snapshot) -> Option<Value> {
+        self.inner.export_state(snapshot)
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> Value {
+        self.inner.initialize(fork)
+    }
+
+    fn before_commit(&self, fork: &mut Fork) {
+        self.inner.before_commit(fork)
+    }
+
+    fn after_commit(&self, context: &ServiceContext) {
+        self.inner.after_commit(context)
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        self.inner.wire_api(builder)
+    }
+}
+
+/// Service which records the execution time of its transactions into the shared log.
+pub(crate) struct TimedService {
+    inner: Arc<dyn Service>,
+    log: ExecutionLog,
+}
+
+impl TimedService {
+    pub fn new(inner: Arc<dyn Service>, log: ExecutionLog) -> Self {
+        TimedService { inner, log }
+    }
+}
+
+impl Service for TimedService {
+    fn service_id(&self) -> u16 {
+        self.inner.service_id()
+    }
+
+    fn service_name(&self) -> &str {
+        self.inner.service_name()
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        self.inner.state_hash(snapshot)
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+        let inner = self.inner.tx_from_raw(raw)?;
+        Ok(Box::new(TimedTransaction {
+            inner,
+            log: self.log.clone(),
+        }))
+    }
+
+    fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+        self.inner.transaction_type_name(tx)
+    }
+
+    fn export_state(&self, snapshot: &dyn Snapshot) -> Option<Value> {
+        self.inner.export_state(snapshot)
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> Value {
+        self.inner.initialize(fork)
+    }
+
+    fn before_commit(&self, fork: &mut Fork) {
+        self.inner.before_commit(fork)
+    }
+
+    fn after_commit(&self, context: &ServiceContext) {
+        self.inner.after_commit(context)
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        self.inner.wire_api(builder)
+    }
+}
+
+/// Transaction created by `TimedService`, which measures the time of its execution.
+struct TimedTransaction {
+    inner: Box<dyn Transaction>,
+    log: ExecutionLog,
+}
+
+impl fmt::Debug for TimedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+impl Message for TimedTransaction {
+    fn from_raw(_: RawMessage) -> Result<Self, MessageError> {
+        unreachable!("Timed transactions are created by `TimedService` only")
+    }
+
+    fn raw(&self) -> &RawMessage {
+        self.inner.raw()
+    }
+}
+
+impl ExonumJson for TimedTransaction {
+    fn deserialize_field<B: WriteBufferWrapper>(
+        _: &Value,
+        _: &mut B,
+        _: Offset,
+        _: Offset,
+    ) -> Result<(), Box<dyn Error>> {
+        unreachable!("Timed transactions are created by `TimedService` only")
+    }
+
+    fn serialize_field(&self) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        self.inner.serialize_field()
+    }
+}
+
+impl Transaction for TimedTransaction {
+    fn verify(&self) -> bool {
+        self.inner.verify()
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        // The time is recorded even if the execution panics.
+        let _timer = ExecutionTimer {
+            tx_hash: self.hash(),
+            start: Instant::now(),
+            log: &self.log,
+        };
+        self.inner.execute(fork)
+    }
+}
+
+/// Guard recording the time elapsed since its creation on drop.
+struct ExecutionTimer<'a> {
+    tx_hash: Hash,
+    start: Instant,
+    log: &'a ExecutionLog,
+}
+
+impl<'a> Drop for ExecutionTimer<'a> {
+    fn drop(&mut self) {
+        self.log.record(self.tx_hash, self.start.elapsed());
+    }
+}
+
+/// Call of a service hook recorded by [`ServiceSpy`].
+///
+/// [`ServiceSpy`]: struct.ServiceSpy.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceCall {
+    /// `Service::initialize` invoked during the creation of the genesis block.
+    Initialize,
+    /// `Transaction::execute` invoked for the transaction with the specified hash.
+    Execute(Hash),
+    /// `Service::after_commit` invoked after the block is committed.
+    AfterCommit,
+}
+
+/// Shared record of the calls of the service hooks, which is filled by [`ServiceSpy`].
+/// Each call is recorded together with the height of the block it relates to: the genesis
+/// block for `initialize`, the block being created for `execute`, and the committed block
+/// for `after_commit`.
+///
+/// [`ServiceSpy`]: struct.ServiceSpy.html
+#[derive(Debug, Clone, Default)]
+pub struct ServiceCalls {
+    inner: Arc<Mutex<Vec<(Height, ServiceCall)>>>,
+}
+
+impl ServiceCalls {
+    fn record(&self, height: Height, call: ServiceCall) {
+        self.inner.lock().unwrap().push((height, call));
+    }
+
+    /// Returns a copy of the recorded calls, which is not shared with this instance.
+    pub(crate) fn deep_clone(&self) -> Self {
+        ServiceCalls {
+            inner: Arc::new(Mutex::new(self.all())),
+        }
+    }
+
+    /// Forgets the calls related to the blocks above the specified height.
+    pub(crate) fn truncate(&self, height: Height) {
+        self.inner
+            .lock()
+            .unwrap()
+            .retain(|&(call_height, _)| call_height <= height);
+    }
+
+    /// Returns all recorded calls in the order of their invocation.
+    pub fn all(&self) -> Vec<(Height, ServiceCall)> {
+        self.inner.lock().unwrap().clone()
+    }
+
+    /// Returns the number of `initialize` calls.
+    pub fn initialize_count(&self) -> usize {
+        self.heights(|call| call == ServiceCall::Initialize).len()
+    }
+
+    /// Returns the heights of the blocks, in which the service transactions were executed,
+    /// with a separate entry for each execution.
+    pub fn execute_heights(&self) -> Vec<Height> {
+        self.heights(|call| match call {
+            ServiceCall::Execute(_) => true,
+            _ => false,
+        })
+    }
+
+    /// Returns the heights of the blocks, after the commit of which `after_commit` was called,
+    /// with a separate entry for each call.
+    pub fn after_commit_heights(&self) -> Vec<Height> {
+        self.heights(|call| call == ServiceCall::AfterCommit)
+    }
+
+    fn heights<F: Fn(ServiceCall) -> bool>(&self, predicate: F) -> Vec<Height> {
+        self.inner
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|&&(_, call)| predicate(call))
+            .map(|&(height, _)| height)
+            .collect()
+    }
+}
+
+/// Service decorator recording the calls of the hooks of the wrapped service, such as
+/// `initialize`, `execute` of its transactions and `after_commit`. The decorator is
+/// transparent: it has the same id and name as the wrapped service and delegates
+/// all behavior to it.
+///
+/// The recorded calls are available via [`calls`], or via [`TestKit::service_calls`]
+/// for the services added with [`TestKitBuilder::with_spied_service`]. In the latter case,
+/// the calls related to the blocks discarded by [`TestKit::rollback`] (including the blocks
+/// created by probes) are forgotten.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate exonum;
+/// # extern crate exonum_testkit;
+/// # use exonum_testkit::doctest_support::DoctestService;
+/// use exonum::helpers::Height;
+/// use exonum_testkit::{ServiceSpy, TestKitBuilder};
+///
+/// # fn main() {
+/// let spy = ServiceSpy::wrap(DoctestService);
+/// let calls = spy.calls();
+/// let mut testkit = TestKitBuilder::validator().with_service(spy).create();
+/// testkit.create_blocks_until(Height(2));
+///
+/// assert_eq!(calls.initialize_count(), 1);
+/// assert_eq!(calls.after_commit_heights(), vec![Height(1), Height(2)]);
+/// # }
+/// ```
+///
+/// [`calls`]: #method.calls
+/// [`TestKit::service_calls`]: struct.TestKit.html#method.service_calls
+/// [`TestKitBuilder::with_spied_service`]: struct.TestKitBuilder.html#method.with_spied_service
+/// [`TestKit::rollback`]: struct.TestKit.html#method.rollback
+pub struct ServiceSpy {
+    inner: Arc<dyn Service>,
+    calls: ServiceCalls,
+}
+
+impl fmt::Debug for ServiceSpy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServiceSpy")
+            .field("service", &self.inner.service_name())
+            .field("calls", &self.calls)
+            .finish()
+    }
+}
+
+impl ServiceSpy {
+    /// Wraps the service into a spy.
+    pub fn wrap<S: Service + 'static>(service: S) -> Self {
+        Self::wrap_boxed(Box::new(service))
+    }
+
+    /// Wraps the boxed service into a spy.
+    pub fn wrap_boxed(service: Box<dyn Service>) -> Self {
+        Self::with_calls(Arc::from(service), ServiceCalls::default())
+    }
+
+    /// Wraps the shared service into a spy recording the calls into `calls`.
+    pub(crate) fn with_calls(service: Arc<dyn Service>, calls: ServiceCalls) -> Self {
+        ServiceSpy {
+            inner: service,
+            calls,
+        }
+    }
+
+    /// Returns a handle to the calls recorded by the spy.
+    pub fn calls(&self) -> ServiceCalls {
+        self.calls.clone()
+    }
+}
+
+impl Service for ServiceSpy {
+    fn service_id(&self) -> u16 {
+        self.inner.service_id()
+    }
+
+    fn service_name(&self) -> &str {
+        self.inner.service_name()
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        self.inner.state_hash(snapshot)
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+        let inner = self.inner.tx_from_raw(raw)?;
+        Ok(Box::new(SpiedTransaction {
+            inner,
+            calls: self.calls.clone(),
+        }))
+    }
+
+    fn transaction_type_name(&self, tx: &dyn Transaction) -> Option<&'static str> {
+        self.inner.transaction_type_name(tx)
+    }
+
+    fn export_state(&self, snapshot: &dyn Snapshot) -> Option<Value> {
+        self.inner.export_state(snapshot)
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> Value {
+        self.calls.record(Height(0), ServiceCall::Initialize);
+        self.inner.initialize(fork)
+    }
+
+    fn before_commit(&self, fork: &mut Fork) {
+        self.inner.before_commit(fork)
+    }
+
+    fn after_commit(&self, context: &ServiceContext) {
+        self.calls.record(context.height(), ServiceCall::AfterCommit);
+        self.inner.after_commit(context)
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        self.inner.wire_api(builder)
+    }
+}
+
+/// Transaction created by `ServiceSpy`, which records its execution.
+struct SpiedTransaction {
+    inner: Box<dyn Transaction>,
+    calls: ServiceCalls,
+}
+
+impl fmt::Debug for SpiedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+impl Message for SpiedTransaction {
+    fn from_raw(_: RawMessage) -> Result<Self, MessageError> {
+        unreachable!("Spied transactions are created by `ServiceSpy` only")
+    }
+
+    fn raw(&self) -> &RawMessage {
+        self.inner.raw()
+    }
+}
+
+impl ExonumJson for SpiedTransaction {
+    fn deserialize_field<B: WriteBufferWrapper>(
+        _: &Value,
+        _: &mut B,
+        _: Offset,
+        _: Offset,
+    ) -> Result<(), Box<dyn Error>> {
+        unreachable!("Spied transactions are created by `ServiceSpy` only")
+    }
+
+    fn serialize_field(&self) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        self.inner.serialize_field()
+    }
+}
+
+impl Transaction for SpiedTransaction {
+    fn verify(&self) -> bool {
+        self.inner.verify()
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        // Transactions are executed on top of the last committed block.
+        let height = CoreSchema::new(&*fork).height().next();
+        self.calls.record(height, ServiceCall::Execute(self.hash()));
+        self.inner.execute(fork)
+    }
+}
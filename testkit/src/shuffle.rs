@@ -0,0 +1,91 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic shuffling used to emulate different orders of transactions.
+
+/// Pseudo-random number generator based on the SplitMix64 algorithm. It is not suitable
+/// for cryptographic purposes, but is fast, has no dependencies and produces the same sequence
+/// for the same seed on all platforms, which makes the shuffles reproducible.
+#[derive(Debug, Clone)]
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the range `0..=max`. The modulo bias is negligible
+    /// for the slice lengths occurring in tests.
+    fn next_up_to(&mut self, max: usize) -> usize {
+        (self.next_u64() % (max as u64 + 1)) as usize
+    }
+}
+
+/// Shuffles the items in place with the Fisher-Yates algorithm. The same seed always
+/// results in the same permutation for slices of the same length.
+pub(crate) fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64::new(seed);
+    for i in (1..items.len()).rev() {
+        let j = rng.next_up_to(i);
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shuffle;
+
+    #[test]
+    fn test_shuffle_is_deterministic() {
+        let mut first: Vec<_> = (0..100).collect();
+        let mut second = first.clone();
+        shuffle(&mut first, 42);
+        shuffle(&mut second, 42);
+        assert_eq!(first, second);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+        assert_ne!(first, sorted);
+    }
+
+    #[test]
+    fn test_shuffle_depends_on_seed() {
+        let mut first: Vec<_> = (0..100).collect();
+        let mut second = first.clone();
+        shuffle(&mut first, 1);
+        shuffle(&mut second, 2);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_shuffle_short_slices() {
+        let mut empty: Vec<u32> = vec![];
+        shuffle(&mut empty, 1);
+        assert!(empty.is_empty());
+        let mut single = vec![1];
+        shuffle(&mut single, 1);
+        assert_eq!(single, vec![1]);
+    }
+}
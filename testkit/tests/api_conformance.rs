@@ -0,0 +1,57 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance of the API mounted by the testkit to the API of a running node.
+//!
+//! The test requires a running node and is ignored by default. To run it, start a node
+//! and specify the base URL of its public API in the `EXONUM_NODE_URL` variable:
+//!
+//! ```text
+//! EXONUM_NODE_URL=http://127.0.0.1:8200 \
+//!     cargo test --features conformance --test api_conformance -- --ignored
+//! ```
+
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::helpers::Height;
+use exonum_testkit::conformance::{check_conformance, Route};
+use exonum_testkit::{ApiKind, TestKitBuilder};
+
+use std::env;
+
+const DEFAULT_NODE_URL: &str = "http://127.0.0.1:8200";
+
+#[test]
+#[ignore]
+fn builtin_api_conformance() {
+    let node_url = env::var("EXONUM_NODE_URL").unwrap_or_else(|_| DEFAULT_NODE_URL.to_owned());
+
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.create_blocks_until(Height(3));
+    let api = testkit.api();
+
+    let routes = [
+        Route::new(ApiKind::System, "v1/mempool"),
+        Route::new(ApiKind::System, "v1/healthcheck"),
+        Route::new(ApiKind::System, "v1/user_agent"),
+        Route::new(ApiKind::Explorer, "v1/blocks?count=3"),
+        Route::new(ApiKind::Explorer, "v1/blocks?count=3&skip_empty_blocks=true"),
+        Route::new(ApiKind::Explorer, "v1/block?height=1"),
+        Route::new(ApiKind::Explorer, "v1/block?height=1000000"),
+    ];
+    let report = check_conformance(&node_url, &api, &routes);
+    println!("{}", report);
+    assert!(report.is_conformant(), "API does not conform to the node:\n{}", report);
+}
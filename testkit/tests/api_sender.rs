@@ -0,0 +1,205 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for services holding the `ApiSender` of the testkit.
+
+#[macro_use]
+extern crate exonum;
+extern crate exonum_testkit;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use exonum::{crypto::Signature, node::{ApiSender, TransactionSend}};
+use exonum_testkit::prelude::*;
+
+use std::thread;
+use std::time::Duration;
+
+const SERVICE_ID: u16 = 768;
+const SERVICE_NAME: &str = "broadcast";
+
+transactions! {
+    BroadcastTransactions {
+        const SERVICE_ID = SERVICE_ID;
+
+        struct TxMarker {
+            seed: u64,
+        }
+    }
+}
+
+impl Transaction for TxMarker {
+    fn verify(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, _fork: &mut Fork) -> ExecutionResult {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BroadcastQuery {
+    seed: u64,
+}
+
+/// Service broadcasting transactions from its API handler via the sender
+/// passed on construction, either immediately or from a background thread after a delay.
+struct BroadcastService {
+    sender: ApiSender,
+}
+
+impl Service for BroadcastService {
+    fn service_name(&self) -> &str {
+        SERVICE_NAME
+    }
+
+    fn state_hash(&self, _: &Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<Transaction>, encoding::Error> {
+        let tx = BroadcastTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        let sender = self.sender.clone();
+        builder.public_scope().endpoint_mut(
+            "v1/broadcast",
+            move |_: &ServiceApiState, query: BroadcastQuery| -> api::Result<Hash> {
+                let tx = TxMarker::new_with_signature(query.seed, &Signature::zero());
+                let tx_hash = tx.hash();
+                sender.send(Box::new(tx))?;
+                Ok(tx_hash)
+            },
+        );
+
+        let sender = self.sender.clone();
+        builder.public_scope().endpoint_mut(
+            "v1/broadcast-later",
+            move |_: &ServiceApiState, query: BroadcastQuery| -> api::Result<Hash> {
+                let tx = TxMarker::new_with_signature(query.seed, &Signature::zero());
+                let tx_hash = tx.hash();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(100));
+                    sender.send(Box::new(tx)).unwrap();
+                });
+                Ok(tx_hash)
+            },
+        );
+    }
+}
+
+#[test]
+fn test_service_with_api_sender() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service_fn(|sender| BroadcastService { sender })
+        .create();
+    let api = testkit.api();
+
+    let tx_hash: Hash = api.service(SERVICE_NAME).post("v1/broadcast", &BroadcastQuery { seed: 1 });
+    assert_eq!(
+        tx_hash,
+        TxMarker::new_with_signature(1, &Signature::zero()).hash()
+    );
+
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&tx_hash));
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 1);
+    assert_eq!(block[0].content().hash(), tx_hash);
+}
+
+#[test]
+fn test_testkit_api_sender() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service_fn(|sender| BroadcastService { sender })
+        .create();
+
+    let tx = TxMarker::new_with_signature(2, &Signature::zero());
+    testkit.api_sender().send(Box::new(tx.clone())).unwrap();
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+}
+
+#[test]
+fn test_poll_events_for_delayed_broadcast() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service_fn(|sender| BroadcastService { sender })
+        .create();
+    let api = testkit.api();
+
+    let tx_hash: Hash =
+        api.service(SERVICE_NAME).post("v1/broadcast-later", &BroadcastQuery { seed: 3 });
+    // The transaction is sent by the service later, so `poll_events` may miss it.
+    let processed = testkit.poll_events_for(Duration::from_secs(2));
+    assert_eq!(processed, 1);
+    assert!(testkit.is_tx_in_pool(&tx_hash));
+
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 1);
+    assert_eq!(block[0].content().hash(), tx_hash);
+}
+
+#[test]
+fn test_events_processed_count() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service_fn(|sender| BroadcastService { sender })
+        .create();
+    let initial_count = testkit.events_processed_count();
+
+    let sender = testkit.api_sender();
+    for seed in 10..13 {
+        let tx = TxMarker::new_with_signature(seed, &Signature::zero());
+        sender.send(Box::new(tx)).unwrap();
+    }
+    assert_eq!(testkit.events_processed_count(), initial_count);
+    testkit.poll_events();
+    assert_eq!(testkit.events_processed_count(), initial_count + 3);
+
+    // The channel is drained, so creating a block only counts the poll after the commit.
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 3);
+    assert_eq!(testkit.events_processed_count(), initial_count + 4);
+
+    // Events sent before creating a block are processed by the testkit.
+    let tx = TxMarker::new_with_signature(13, &Signature::zero());
+    sender.send(Box::new(tx)).unwrap();
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 1);
+    assert_eq!(testkit.events_processed_count(), initial_count + 6);
+}
+
+#[test]
+fn test_events_processed_count_with_empty_event_stream() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.poll_events();
+    let initial_count = testkit.events_processed_count();
+
+    testkit.create_block();
+    assert_eq!(testkit.events_processed_count(), initial_count + 1);
+    testkit.create_blocks_until(Height(3));
+    assert_eq!(testkit.events_processed_count(), initial_count + 3);
+    // Polling the empty event stream directly does not affect the counter.
+    testkit.poll_events();
+    assert_eq!(testkit.events_processed_count(), initial_count + 3);
+}
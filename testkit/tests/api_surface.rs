@@ -0,0 +1,141 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the services that do not mount endpoints on one or both of their APIs.
+
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState}, blockchain::{Service, Transaction},
+    crypto::Hash, encoding, messages::RawTransaction, storage::Snapshot,
+};
+use exonum_testkit::{TestKit, TestKitApi, TestKitBuilder};
+
+/// Service mounting a single `name` endpoint on the specified APIs.
+struct SurfaceService {
+    id: u16,
+    name: &'static str,
+    public: bool,
+    private: bool,
+}
+
+impl Service for SurfaceService {
+    fn service_id(&self) -> u16 {
+        self.id
+    }
+
+    fn service_name(&self) -> &str {
+        self.name
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        unimplemented!()
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        let name = self.name;
+        let handler =
+            move |_: &ServiceApiState, _: ()| -> api::Result<String> { Ok(name.to_owned()) };
+        if self.public {
+            builder.public_scope().endpoint("name", handler);
+        }
+        if self.private {
+            builder.private_scope().endpoint("name", handler);
+        }
+    }
+}
+
+fn testkit() -> TestKit {
+    TestKitBuilder::validator()
+        .with_service(SurfaceService {
+            id: 1024,
+            name: "public_only",
+            public: true,
+            private: false,
+        })
+        .with_service(SurfaceService {
+            id: 1025,
+            name: "private_only",
+            public: false,
+            private: true,
+        })
+        .with_service(SurfaceService {
+            id: 1026,
+            name: "no_api",
+            public: false,
+            private: false,
+        })
+        .create()
+}
+
+#[test]
+fn test_service_api_surface() {
+    let testkit = testkit();
+    assert_eq!(testkit.service_api_surface("public_only"), (true, false));
+    assert_eq!(testkit.service_api_surface("private_only"), (false, true));
+    assert_eq!(testkit.service_api_surface("no_api"), (false, false));
+}
+
+#[test]
+#[should_panic(expected = "Service `unknown` is not registered in the blockchain")]
+fn test_service_api_surface_unknown_service() {
+    testkit().service_api_surface("unknown");
+}
+
+#[test]
+fn test_partial_apis_are_accessible() {
+    let testkit = testkit();
+    let api = testkit.api();
+
+    let name: String = api.service("public_only").get("name");
+    assert_eq!(name, "public_only");
+    let name: String = api.service("private_only").private().get("name");
+    assert_eq!(name, "private_only");
+    let name: String = api.service_private("private_only").get("name").unwrap();
+    assert_eq!(name, "private_only");
+}
+
+#[test]
+fn test_isolated_api_surface() {
+    let testkit = testkit();
+    let api = TestKitApi::isolated(&testkit, "private_only").unwrap();
+    let name: String = api.service("private_only").private().get("name");
+    assert_eq!(name, "private_only");
+}
+
+#[test]
+#[should_panic(expected = "Service `public_only` has no private API")]
+fn test_missing_private_api() {
+    let testkit = testkit();
+    testkit.api().service("public_only").private().get::<String>("name");
+}
+
+#[test]
+#[should_panic(expected = "Service `private_only` has no public API")]
+fn test_missing_public_api() {
+    let testkit = testkit();
+    testkit.api().service_public("private_only");
+}
+
+#[test]
+#[should_panic(expected = "Service `no_api` has no public API")]
+fn test_service_without_api() {
+    let testkit = testkit();
+    testkit.api().service("no_api").get::<String>("name");
+}
@@ -21,10 +21,7 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 
-use exonum::{
-    blockchain::Schema, crypto::CryptoHash, helpers::{Height, ValidatorId},
-};
-use exonum_testkit::TestKitBuilder;
+use exonum_testkit::prelude::*;
 
 #[test]
 fn test_following_config() {
@@ -40,13 +37,13 @@ fn test_following_config() {
     testkit.commit_configuration_change(proposal);
     // Check that the following configuration is none.
     assert_eq!(
-        Schema::new(&testkit.snapshot()).following_configuration(),
+        CoreSchema::new(&testkit.snapshot()).following_configuration(),
         None
     );
     testkit.create_block();
     // Check that the following configuration has appeared.
     assert_eq!(
-        Schema::new(&testkit.snapshot()).following_configuration(),
+        CoreSchema::new(&testkit.snapshot()).following_configuration(),
         Some(stored)
     );
 }
@@ -85,6 +82,132 @@ fn test_configuration_and_rollbacks() {
     assert_eq!(testkit.actual_configuration(), old_config);
 }
 
+#[test]
+fn test_history_of_configuration_changes() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let genesis_config = testkit.actual_configuration();
+    assert_eq!(
+        testkit.history_of_configuration_changes(),
+        vec![(Height(0), genesis_config.clone())]
+    );
+
+    let proposal = {
+        let mut cfg = testkit.configuration_change_proposal();
+        cfg.set_actual_from(Height(5));
+        cfg.set_service_config("service", "first");
+        cfg
+    };
+    let first_config = proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(proposal);
+    testkit.create_block();
+    // The committed configuration is not actual yet.
+    assert_eq!(testkit.history_of_configuration_changes().len(), 1);
+
+    testkit.create_blocks_until(Height(4));
+    let proposal = {
+        let mut cfg = testkit.configuration_change_proposal();
+        cfg.set_actual_from(Height(8));
+        cfg.set_service_config("service", "second");
+        cfg
+    };
+    let second_config = proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(7));
+
+    let history = testkit.history_of_configuration_changes();
+    assert_eq!(
+        history,
+        vec![
+            (Height(0), genesis_config),
+            (Height(5), first_config),
+            (Height(8), second_config),
+        ]
+    );
+    assert_eq!(history.last().unwrap().1, testkit.actual_configuration());
+}
+
+#[test]
+fn test_noop_configuration_changes() {
+    let mut testkit = TestKitBuilder::validator().with_validators(3).create();
+    let genesis_config = testkit.actual_configuration();
+    let validators = testkit.network().validators().to_vec();
+
+    let first = testkit.activate_noop_configuration_change(Height(5));
+    assert_eq!(testkit.height(), Height(4));
+    assert_eq!(testkit.actual_configuration(), first);
+    assert_eq!(first.previous_cfg_hash, genesis_config.hash());
+    assert_eq!(first.validator_keys, genesis_config.validator_keys);
+    assert_eq!(first.consensus, genesis_config.consensus);
+    assert_eq!(first.services, genesis_config.services);
+
+    // Several no-op changes may be scheduled at once.
+    let second = testkit.commit_noop_configuration_change(Height(8));
+    let third = testkit.activate_noop_configuration_change(Height(10));
+    assert_eq!(second.previous_cfg_hash, first.hash());
+    assert_eq!(third.previous_cfg_hash, second.hash());
+    assert_eq!(testkit.actual_configuration(), third);
+
+    let history = testkit.history_of_configuration_changes();
+    let heights: Vec<_> = history.iter().map(|&(height, _)| height).collect();
+    assert_eq!(heights, vec![Height(0), Height(5), Height(8), Height(10)]);
+    assert_eq!(testkit.network().validators(), &validators[..]);
+    assert_eq!(testkit.network().us().validator_id(), Some(ValidatorId(0)));
+}
+
+// Blocks activating a configuration change without transactions are empty for
+// the explorer API consumers.
+#[test]
+fn test_config_activation_block_is_skipped_as_empty() {
+    use exonum::api::node::public::explorer::BlocksQuery;
+
+    let mut testkit = TestKitBuilder::validator().create();
+    let api = testkit.api();
+    let stored = testkit.commit_noop_configuration_change(Height(5));
+
+    let block = testkit.create_config_activation_block();
+    assert_eq!(block.height(), Height(5));
+    assert!(block.is_empty());
+    assert_eq!(testkit.actual_configuration(), stored);
+    api.assert_block_skipped_as_empty(Height(5));
+
+    let query = BlocksQuery {
+        count: 10,
+        latest: None,
+        skip_empty_blocks: true,
+    };
+    assert!(api.explorer_blocks(&query).blocks.is_empty());
+    let query = BlocksQuery {
+        skip_empty_blocks: false,
+        ..query
+    };
+    assert_eq!(api.explorer_blocks(&query).blocks.len(), 6);
+
+    // The activation block may be created right before the configuration takes effect.
+    testkit.commit_noop_configuration_change(Height(8));
+    testkit.create_blocks_until(Height(7));
+    let block = testkit.create_config_activation_block();
+    assert_eq!(block.height(), Height(8));
+    api.assert_block_skipped_as_empty(Height(8));
+}
+
+#[test]
+fn test_signing_keypair() {
+    let testkit = TestKitBuilder::validator().with_validators(2).create();
+    let mut proposal = testkit.configuration_change_proposal();
+    assert_eq!(
+        proposal.signing_keypair(),
+        testkit.network().us().service_keypair()
+    );
+
+    let other = proposal.validators()[1].clone();
+    proposal.set_us(other.clone());
+    assert_eq!(proposal.signing_keypair(), other.service_keypair());
+    assert_ne!(
+        proposal.signing_keypair().0,
+        testkit.network().us().service_keypair().0
+    );
+}
+
 #[test]
 fn test_add_to_validators() {
     let mut testkit = TestKitBuilder::auditor().with_validators(1).create();
@@ -107,7 +230,7 @@ fn test_add_to_validators() {
     assert_eq!(&testkit.network().validators()[1], testkit.network().us());
     assert_eq!(testkit.actual_configuration(), stored);
     assert_eq!(
-        Schema::new(&testkit.snapshot())
+        CoreSchema::new(&testkit.snapshot())
             .previous_configuration()
             .unwrap()
             .hash(),
@@ -136,7 +259,7 @@ fn test_exclude_from_validators() {
     assert_eq!(testkit.network().validators().len(), 1);
     assert_eq!(testkit.actual_configuration(), stored);
     assert_eq!(
-        Schema::new(&testkit.snapshot())
+        CoreSchema::new(&testkit.snapshot())
             .previous_configuration()
             .unwrap()
             .hash(),
@@ -205,3 +328,263 @@ fn test_another_configuration_change_proposal() {
     };
     testkit.commit_configuration_change(second_proposal);
 }
+
+#[test]
+fn test_chained_configuration_changes() {
+    let mut testkit = TestKitBuilder::validator().create();
+
+    let first_proposal = {
+        let mut cfg = testkit.propose_configuration_at(2);
+        cfg.set_service_config("service", "first");
+        cfg
+    };
+    let first_stored = first_proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(first_proposal);
+
+    let second_proposal = {
+        let mut cfg = testkit.propose_configuration_at(5);
+        cfg.set_service_config("service", "second");
+        cfg
+    };
+    let second_stored = second_proposal.stored_configuration().clone();
+    assert_eq!(second_stored.previous_cfg_hash, first_stored.hash());
+    testkit.commit_configuration_change(second_proposal);
+
+    assert_eq!(testkit.scheduled_configurations().len(), 2);
+    assert_eq!(
+        testkit.next_configuration().map(|cfg| cfg.actual_from()),
+        Some(Height(2))
+    );
+
+    testkit.create_block();
+    assert_eq!(testkit.actual_configuration(), first_stored);
+    assert_eq!(
+        CoreSchema::new(&testkit.snapshot()).following_configuration(),
+        Some(second_stored.clone())
+    );
+
+    testkit.create_blocks_until(Height(3));
+    assert_eq!(
+        testkit.next_configuration().map(|cfg| cfg.actual_from()),
+        Some(Height(5))
+    );
+
+    testkit.create_blocks_until(Height(4));
+    assert_eq!(testkit.actual_configuration(), second_stored);
+    assert!(testkit.next_configuration().is_none());
+    assert_eq!(
+        CoreSchema::new(&testkit.snapshot()).configs_actual_from().len(),
+        3
+    );
+}
+
+#[test]
+#[should_panic(expected = "greater than the one of the last scheduled configuration")]
+fn test_chained_configuration_change_with_incorrect_order() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let first_proposal = testkit.propose_configuration_at(5);
+    testkit.commit_configuration_change(first_proposal);
+    let second_proposal = testkit.propose_configuration_at(3);
+    testkit.commit_configuration_change(second_proposal);
+}
+
+#[test]
+fn test_reconfigure_validators() {
+    let mut testkit = TestKitBuilder::validator().with_validators(3).create();
+    testkit.create_blocks_until(Height(2));
+
+    let validators = testkit.network().validators()[1..].to_vec();
+    let new_keys: Vec<_> = validators.iter().map(|node| node.public_keys()).collect();
+    testkit.reconfigure_validators(validators, 3);
+
+    assert_eq!(testkit.height(), Height(4));
+    assert!(testkit.scheduled_configurations().is_empty());
+    let actual = testkit.actual_configuration();
+    assert_eq!(actual.actual_from, Height(5));
+    assert_eq!(
+        actual
+            .validator_keys
+            .iter()
+            .map(|keys| keys.consensus_key)
+            .collect::<Vec<_>>(),
+        new_keys.iter().map(|keys| keys.consensus_key).collect::<Vec<_>>()
+    );
+    // We were the first validator and have been excluded from the validator set.
+    assert_eq!(testkit.network().us().validator_id(), None);
+    assert_eq!(testkit.network().validators().len(), 2);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct LimitConfig {
+    limit: u64,
+}
+
+#[test]
+fn test_assert_service_config() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let mut proposal = testkit.propose_configuration_at(5);
+    proposal.set_service_config("limits", LimitConfig { limit: 10 });
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(5));
+
+    testkit.assert_service_config("limits", LimitConfig { limit: 10 });
+}
+
+#[test]
+#[should_panic(expected = "Configuration of service `limits` differs from the expected one")]
+fn test_assert_service_config_mismatch() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let mut proposal = testkit.propose_configuration_at(5);
+    proposal.set_service_config("limits", LimitConfig { limit: 10 });
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(5));
+
+    testkit.assert_service_config("limits", LimitConfig { limit: 20 });
+}
+
+#[test]
+#[should_panic(expected = "does not contain the configuration of service `limits`")]
+fn test_assert_service_config_missing() {
+    let testkit = TestKitBuilder::validator().create();
+    testkit.assert_service_config("limits", LimitConfig { limit: 10 });
+}
+
+#[test]
+fn test_manual_config_activation() {
+    let mut testkit = TestKitBuilder::validator().with_validators(2).create();
+    testkit.set_manual_config_activation(true);
+    let old_validators = testkit.network().validators().to_vec();
+
+    let mut proposal = testkit.propose_configuration_at(3);
+    let new_validator = TestNode::new_validator(ValidatorId(2));
+    let mut validators = old_validators.clone();
+    validators.push(new_validator);
+    proposal.set_validators(validators.clone());
+    let stored = proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(proposal);
+    testkit.create_block();
+
+    // The configuration is committed, but the testkit still runs with the old validators.
+    let snapshot = testkit.snapshot();
+    let schema = CoreSchema::new(&snapshot);
+    assert_eq!(schema.following_configuration(), Some(stored.clone()));
+    assert_ne!(schema.actual_configuration(), stored);
+    assert_eq!(testkit.network().validators(), &old_validators[..]);
+
+    testkit.activate_pending_configuration();
+    assert_eq!(testkit.network().validators().len(), 3);
+    let block = testkit.create_block();
+    assert_eq!(block.precommits.len(), 3);
+    assert_eq!(testkit.actual_configuration(), stored);
+}
+
+#[test]
+#[should_panic(expected = "Configuration with actual_from height 3 should be activated")]
+fn test_manual_config_activation_without_activation() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.set_manual_config_activation(true);
+    let proposal = testkit.propose_configuration_at(3);
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(2));
+}
+
+#[test]
+#[should_panic(expected = "No committed configuration change is due to be activated")]
+fn test_activate_configuration_too_early() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.set_manual_config_activation(true);
+    let proposal = testkit.propose_configuration_at(5);
+    testkit.commit_configuration_change(proposal);
+    testkit.create_block();
+    testkit.activate_pending_configuration();
+}
+
+#[test]
+fn test_assert_config_activates_at() {
+    let mut testkit = TestKitBuilder::validator().with_validators(3).create();
+    let proposal = {
+        let mut cfg = testkit.propose_configuration_at(4);
+        let validators = cfg.validators()[..2].to_vec();
+        cfg.set_validators(validators);
+        cfg
+    };
+    testkit.commit_configuration_change(proposal.clone());
+    testkit.assert_config_activates_at(&proposal);
+    assert_eq!(testkit.height(), Height(4));
+    assert_eq!(testkit.network().validators().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Validator keys of the actual configuration at height 3 differ")]
+fn test_assert_config_activates_at_without_commit() {
+    let mut testkit = TestKitBuilder::validator().with_validators(3).create();
+    let proposal = {
+        let mut cfg = testkit.propose_configuration_at(3);
+        let validators = cfg.validators()[..2].to_vec();
+        cfg.set_validators(validators);
+        cfg
+    };
+    testkit.assert_config_activates_at(&proposal);
+}
+
+/// Service exposing the propose timeouts of the actual configuration via its API.
+struct TimeoutsService;
+
+impl Service for TimeoutsService {
+    fn service_id(&self) -> u16 {
+        1
+    }
+
+    fn service_name(&self) -> &str {
+        "timeouts"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        unimplemented!()
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        builder.public_scope().endpoint(
+            "v1/propose-timeouts",
+            |state: &ServiceApiState, _: ()| -> api::Result<(u64, u64)> {
+                let consensus = CoreSchema::new(state.snapshot()).actual_configuration().consensus;
+                Ok((consensus.min_propose_timeout, consensus.max_propose_timeout))
+            },
+        );
+    }
+}
+
+#[test]
+fn test_change_propose_timeouts() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(TimeoutsService)
+        .create();
+    let api = testkit.api();
+    let initial = testkit.consensus_config();
+    let timeouts: (u64, u64) = api.service("timeouts").get("v1/propose-timeouts");
+    assert_eq!(
+        timeouts,
+        (initial.min_propose_timeout, initial.max_propose_timeout)
+    );
+
+    let proposal = {
+        let mut cfg = testkit.propose_configuration_at(3);
+        cfg.set_propose_timeouts(50, 100);
+        cfg.set_propose_timeout_threshold(10);
+        cfg
+    };
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(3));
+
+    let consensus = testkit.consensus_config();
+    assert_eq!(consensus.min_propose_timeout, 50);
+    assert_eq!(consensus.max_propose_timeout, 100);
+    assert_eq!(consensus.propose_timeout_threshold, 10);
+    assert_eq!(consensus.first_round_timeout, initial.first_round_timeout);
+    let timeouts: (u64, u64) = api.service("timeouts").get("v1/propose-timeouts");
+    assert_eq!(timeouts, (50, 100));
+}
@@ -0,0 +1,110 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the clock skew between validators.
+
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::{explorer::BlockWithTransactions, helpers::{Height, ValidatorId}};
+use exonum_testkit::{TestKit, TestKitBuilder};
+
+use std::time::{Duration, SystemTime};
+
+fn precommit_times(block: &BlockWithTransactions) -> Vec<SystemTime> {
+    block
+        .precommits
+        .iter()
+        .map(|precommit| precommit.time().into())
+        .collect()
+}
+
+fn create_testkit(validator_count: u16) -> TestKit {
+    TestKitBuilder::validator()
+        .with_validators(validator_count)
+        .create()
+}
+
+#[test]
+fn test_clock_offsets_persist_until_cleared() {
+    let mut testkit = create_testkit(3);
+    testkit.set_validator_clock_offset(ValidatorId(1), Duration::from_secs(2), false);
+    testkit.set_validator_clock_offset(ValidatorId(2), Duration::from_millis(3_500), true);
+
+    for _ in 0..2 {
+        let block = testkit.create_block();
+        let times = precommit_times(&block);
+        assert_eq!(times.len(), 3);
+        assert_eq!(times[1], times[0] + Duration::from_secs(2));
+        assert_eq!(times[2], times[0] - Duration::from_millis(3_500));
+    }
+
+    testkit.clear_validator_clock_offset(ValidatorId(1));
+    let block = testkit.create_block();
+    let times = precommit_times(&block);
+    assert_eq!(times[1], times[0]);
+    assert_eq!(times[2], times[0] - Duration::from_millis(3_500));
+
+    testkit.clear_validator_clock_offset(ValidatorId(2));
+    let block = testkit.create_block();
+    let times = precommit_times(&block);
+    assert!(times.iter().all(|&time| time == times[0]));
+}
+
+#[test]
+fn test_block_time_with_wildly_off_validator() {
+    let mut testkit = create_testkit(5);
+    testkit.set_validator_clock_offset(ValidatorId(1), Duration::from_secs(1), true);
+    testkit.set_validator_clock_offset(ValidatorId(2), Duration::from_secs(2), false);
+    testkit.set_validator_clock_offset(ValidatorId(3), Duration::from_secs(3), false);
+    // One validator is a day ahead.
+    testkit.set_validator_clock_offset(ValidatorId(4), Duration::from_secs(86_400), false);
+
+    let block = testkit.create_block();
+    let times = precommit_times(&block);
+    assert_eq!(testkit.block_time(Height(1)), Some(times[2]));
+    assert_eq!(times[2], times[0] + Duration::from_secs(2));
+}
+
+#[test]
+fn test_block_time_with_even_number_of_precommits() {
+    let mut testkit = create_testkit(4);
+    testkit.set_validator_clock_offset(ValidatorId(1), Duration::from_secs(2), false);
+    testkit.set_validator_clock_offset(ValidatorId(2), Duration::from_secs(4), false);
+    testkit.set_validator_clock_offset(ValidatorId(3), Duration::from_secs(86_400), true);
+
+    let block = testkit.create_block();
+    let times = precommit_times(&block);
+    // The median is the midpoint between the times of validators 0 and 1.
+    assert_eq!(
+        testkit.block_time(Height(1)),
+        Some(times[0] + Duration::from_secs(1))
+    );
+}
+
+#[test]
+fn test_block_time_without_precommits() {
+    let mut testkit = create_testkit(2);
+    assert_eq!(testkit.block_time(Height(0)), None);
+    assert_eq!(testkit.block_time(Height(1)), None);
+    testkit.create_block();
+    assert!(testkit.block_time(Height(1)).is_some());
+}
+
+#[test]
+#[should_panic(expected = "Validator 3 is absent in the current validator set")]
+fn test_clock_offset_for_unknown_validator() {
+    let mut testkit = create_testkit(3);
+    testkit.set_validator_clock_offset(ValidatorId(3), Duration::from_secs(1), false);
+}
@@ -193,6 +193,10 @@ impl Service for CounterService {
         Ok(tx.into())
     }
 
+    fn transaction_type_name(&self, tx: &Transaction) -> Option<&'static str> {
+        CounterTransactions::transaction_type_name(tx.raw())
+    }
+
     fn wire_api(&self, builder: &mut api::ServiceApiBuilder) {
         CounterApi::wire(builder)
     }
@@ -30,13 +30,20 @@ extern crate serde_json;
 
 use exonum::{
     api::{node::public::explorer::TransactionQuery, Error as ApiError},
-    blockchain::{Transaction, TransactionErrorType as ErrorType},
-    crypto::{self, CryptoHash, PublicKey}, encoding::serialize::{json::ExonumJson, FromHex},
-    helpers::Height, messages::Message,
+    blockchain::{Block, Schema, Transaction, TransactionErrorType as ErrorType},
+    crypto::{self, CryptoHash, Hash, PublicKey, Signature},
+    encoding::serialize::{json::ExonumJson, FromHex}, explorer::BlockWithTransactions,
+    helpers::Height, messages::{Message, Precommit},
+    storage::{ListIndex, MapIndex, ProofMapIndex, Snapshot},
+};
+use exonum_testkit::{
+    compare::JsonChange, ApiKind, ChainVerificationError, ComparableSnapshot, Error, Scenario,
+    SignatureVerificationResult, StatusCode, TestKit, TestKitApi, TestKitBuilder, TxOrdering,
 };
-use exonum_testkit::{ApiKind, ComparableSnapshot, TestKit, TestKitApi, TestKitBuilder};
 use serde_json::Value;
 
+use std::time::{Duration, Instant};
+
 use counter::{
     CounterSchema, CounterService, TransactionResponse, TxIncrement, TxReset, ADMIN_KEY,
 };
@@ -54,10 +61,7 @@ fn inc_count(api: &TestKitApi, by: u64) -> TxIncrement {
     // Create a pre-signed transaction
     let tx = TxIncrement::new(&pubkey, by, &key);
 
-    let tx_info: TransactionResponse = api.public(ApiKind::Service("counter"))
-        .query(&tx)
-        .post("count")
-        .unwrap();
+    let tx_info: TransactionResponse = api.service("counter").post("count", &tx);
     assert_eq!(tx_info.tx_hash, tx.hash());
     tx
 }
@@ -71,9 +75,7 @@ fn test_inc_count_create_block() {
     testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 5, &key));
 
     // Check that the user indeed is persisted by the service
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 5);
 
     testkit.create_block_with_transactions(txvec![
@@ -81,9 +83,7 @@ fn test_inc_count_create_block() {
         TxIncrement::new(&pubkey, 1, &key),
     ]);
 
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 10);
 }
 
@@ -105,10 +105,54 @@ fn test_inc_count_api() {
     testkit.create_block();
 
     // Check that the user indeed is persisted by the service
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
+    assert_eq!(counter, 5);
+}
+
+#[test]
+fn test_timed_get() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 5);
+    testkit.create_block();
+
+    let (counter, elapsed): (u64, _) = api.timed_get(ApiKind::Service("counter"), "count");
     assert_eq!(counter, 5);
+    assert!(elapsed < Duration::from_secs(10));
+}
+
+#[test]
+#[should_panic(expected = "Timed request to endpoint nonexistent failed")]
+fn test_timed_get_error() {
+    let (_, api) = init_testkit();
+    let _: (u64, _) = api.timed_get(ApiKind::Service("counter"), "nonexistent");
+}
+
+#[test]
+fn test_get_many() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 5);
+    testkit.create_block();
+
+    let responses: Vec<Value> = api.get_many(vec![
+        (ApiKind::Service("counter"), "count"),
+        (ApiKind::Explorer, "v1/blocks?count=1"),
+    ]);
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0], json!(5));
+    assert_eq!(
+        responses[1]["range"]["end"],
+        serde_json::to_value(Height(2)).unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "Request to endpoint nonexistent of api/services/counter failed")]
+fn test_get_many_error() {
+    let (_, api) = init_testkit();
+    let _: Vec<u64> = api.get_many(vec![
+        (ApiKind::Service("counter"), "count"),
+        (ApiKind::Service("counter"), "nonexistent"),
+    ]);
 }
 
 #[test]
@@ -125,9 +169,7 @@ fn test_inc_count_with_multiple_transactions() {
     }
 
     assert_eq!(testkit.height(), Height(100));
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 1_000);
 }
 
@@ -139,24 +181,59 @@ fn test_inc_count_with_manual_tx_control() {
 
     // Empty block
     testkit.create_block_with_tx_hashes(&[]);
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 0);
 
     testkit.create_block_with_tx_hashes(&[tx_b.hash()]);
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 3);
 
     testkit.create_block_with_tx_hashes(&[tx_a.hash()]);
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 8);
 }
 
+#[test]
+fn test_selective_inclusion_keeps_other_txs_pooled() {
+    let (mut testkit, api) = init_testkit();
+    let txs: Vec<_> = (1..5).map(|by| inc_count(&api, by)).collect();
+    let hashes: Vec<_> = txs.iter().map(|tx| tx.hash()).collect();
+    testkit.poll_events();
+    testkit.assert_pool_contains(&hashes);
+    testkit.assert_not_committed(&hashes);
+
+    for i in 0..hashes.len() {
+        testkit.create_block_with_tx_hashes(&[hashes[i]]);
+        testkit.assert_pool_contains(&hashes[i + 1..]);
+        testkit.assert_not_committed(&hashes[i + 1..]);
+        assert!(!testkit.is_tx_in_pool(&hashes[i]));
+    }
+    let counter: u64 = api.service("counter").get("count");
+    assert_eq!(counter, 10);
+}
+
+#[test]
+#[should_panic(expected = "(already committed)")]
+fn test_assert_pool_contains_with_committed_tx() {
+    let (mut testkit, api) = init_testkit();
+    let tx_a = inc_count(&api, 5);
+    let tx_b = inc_count(&api, 3);
+    testkit.poll_events();
+    testkit.create_block_with_tx_hashes(&[tx_a.hash()]);
+    testkit.assert_pool_contains(&[tx_a.hash(), tx_b.hash()]);
+}
+
+#[test]
+#[should_panic(expected = "1 transaction(s) are committed to the blockchain")]
+fn test_assert_not_committed_with_committed_tx() {
+    let (mut testkit, api) = init_testkit();
+    let tx_a = inc_count(&api, 5);
+    let tx_b = inc_count(&api, 3);
+    testkit.poll_events();
+    testkit.create_block_with_tx_hashes(&[tx_b.hash()]);
+    testkit.assert_not_committed(&[tx_a.hash(), tx_b.hash()]);
+}
+
 #[test]
 fn test_private_api() {
     let (mut testkit, api) = init_testkit();
@@ -164,9 +241,7 @@ fn test_private_api() {
     inc_count(&api, 3);
 
     testkit.create_block();
-    let counter: u64 = api.private(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").private().get("count");
     assert_eq!(counter, 8);
 
     let (pubkey, key) = crypto::gen_keypair_from_seed(&crypto::Seed::from_slice(
@@ -175,17 +250,56 @@ fn test_private_api() {
     assert_eq!(pubkey, PublicKey::from_hex(ADMIN_KEY).unwrap());
 
     let tx = TxReset::new(&pubkey, &key);
-    let tx_info: TransactionResponse = api.private(ApiKind::Service("counter"))
-        .query(&tx)
-        .post("reset")
-        .unwrap();
+    let tx_info: TransactionResponse = api.service("counter").private().post("reset", &tx);
     assert_eq!(tx_info.tx_hash, tx.hash());
 
     testkit.create_block();
-    let counter: u64 = api.private(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").private().get("count");
+    assert_eq!(counter, 0);
+}
+
+#[test]
+fn test_service_private_api_handle() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 5);
+    testkit.create_block();
+
+    let admin = api.service_private("counter");
+    let counter: u64 = admin.get("count").unwrap();
+    assert_eq!(counter, 5);
+
+    let (pubkey, key) = crypto::gen_keypair_from_seed(&crypto::Seed::from_slice(
+        &crypto::hash(b"correct horse battery staple")[..],
+    ).unwrap());
+    let tx = TxReset::new(&pubkey, &key);
+
+    // The `reset` endpoint is not mounted in the public scope.
+    let status = api.service_public("counter").head("reset");
+    assert_eq!(status, StatusCode::NotFound);
+
+    let tx_info: TransactionResponse = admin.query(&tx).post("reset").unwrap();
+    assert_eq!(tx_info.tx_hash, tx.hash());
+    testkit.create_block();
+
+    let counter: u64 = admin.get("count").unwrap();
     assert_eq!(counter, 0);
+    let counter: u64 = api.service_public("counter").get("count").unwrap();
+    assert_eq!(counter, 0);
+}
+
+#[test]
+fn test_service_api_handle_errors() {
+    let (_, api) = init_testkit();
+    let counter = api.service("counter");
+    assert_matches!(counter.get_err("unknown"), ApiError::NotFound(_));
+    assert_matches!(counter.public().get_err("reset"), ApiError::NotFound(_));
+}
+
+#[test]
+#[should_panic(expected = "Service `conter` is not registered in the testkit")]
+fn test_service_api_handle_unknown_service() {
+    let (_, api) = init_testkit();
+    api.service("conter");
 }
 
 #[test]
@@ -201,9 +315,7 @@ fn test_probe() {
     let schema = CounterSchema::new(&snapshot);
     assert_eq!(schema.count(), Some(5));
     // Verify that the patch has not been applied to the blockchain
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 0);
 
     let other_tx = {
@@ -216,10 +328,7 @@ fn test_probe() {
     assert_eq!(schema.count(), Some(8));
 
     // Posting a transaction is not enough to change the blockchain!
-    let _: TransactionResponse = api.public(ApiKind::Service("counter"))
-        .query(&tx)
-        .post("count")
-        .unwrap();
+    let _: TransactionResponse = api.service("counter").post("count", &tx);
     let snapshot = testkit.probe(other_tx.clone());
     let schema = CounterSchema::new(&snapshot);
     assert_eq!(schema.count(), Some(3));
@@ -229,27 +338,93 @@ fn test_probe() {
     assert_eq!(schema.count(), Some(8));
 }
 
+#[test]
+fn test_fork_testkit() {
+    fn count(testkit: &TestKit) -> Option<u64> {
+        CounterSchema::new(&testkit.snapshot()).count()
+    }
+
+    let (mut testkit, api) = init_testkit();
+    testkit.create_block_with_transaction(inc_count(&api, 5));
+    // The pool is copied together with the storage.
+    let pooled_tx = inc_count(&api, 1);
+    api.send(pooled_tx.clone());
+    testkit.poll_events();
+
+    let mut first = testkit.fork_testkit().unwrap();
+    let mut second = testkit.fork_testkit().unwrap();
+    assert_eq!(first.last_block_hash(), testkit.last_block_hash());
+
+    first.create_block_with_transaction(inc_count(&api, 3));
+    let second_api = second.api();
+    second_api.send(inc_count(&second_api, 10));
+    second.create_block();
+
+    assert_eq!(count(&first), Some(8));
+    assert_eq!(count(&second), Some(16));
+    assert!(first.is_tx_in_pool(&pooled_tx.hash()));
+    assert!(!second.is_tx_in_pool(&pooled_tx.hash()));
+    assert_ne!(first.last_block_hash(), second.last_block_hash());
+
+    // The original testkit is unaffected.
+    assert_eq!(testkit.height(), Height(1));
+    assert_eq!(count(&testkit), Some(5));
+    testkit.poll_events();
+    assert_eq!(Schema::new(&testkit.snapshot()).transactions_pool_len(), 1);
+}
+
 #[test]
 fn test_duplicate_tx() {
     let (mut testkit, api) = init_testkit();
 
     let tx = inc_count(&api, 5);
     testkit.create_block();
-    let _: TransactionResponse = api.public(ApiKind::Service("counter"))
-        .query(&tx)
-        .post("count")
-        .unwrap();
-    let _: TransactionResponse = api.public(ApiKind::Service("counter"))
-        .query(&tx)
-        .post("count")
-        .unwrap();
+    let _: TransactionResponse = api.service("counter").post("count", &tx);
+    let _: TransactionResponse = api.service("counter").post("count", &tx);
     testkit.create_block();
-    let counter: u64 = api.public(ApiKind::Service("counter"))
-        .get("count")
-        .unwrap();
+    let counter: u64 = api.service("counter").get("count");
     assert_eq!(counter, 5);
 }
 
+#[test]
+fn test_create_block_with_duplicate_transactions() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx_a = TxIncrement::new(&pubkey, 5, &key);
+    let tx_b = TxIncrement::new(&pubkey, 3, &key);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        tx_a.clone(),
+        tx_b.clone(),
+        tx_a.clone(),
+    ]);
+    let tx_hashes: Vec<_> = block.transactions.iter().map(|tx| tx.content().hash()).collect();
+    assert_eq!(tx_hashes, vec![tx_a.hash(), tx_b.hash()]);
+
+    let snapshot = testkit.snapshot();
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(8));
+}
+
+#[test]
+fn test_create_block_with_pooled_transactions() {
+    let (mut testkit, api) = init_testkit();
+    let pooled_tx = inc_count(&api, 5);
+    let other_pooled_tx = inc_count(&api, 7);
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 3, &key);
+
+    let block = testkit.create_block_with_transactions(txvec![tx.clone(), pooled_tx.clone()]);
+    let tx_hashes: Vec<_> = block.transactions.iter().map(|tx| tx.content().hash()).collect();
+    assert_eq!(tx_hashes, vec![tx.hash(), pooled_tx.hash()]);
+
+    // The pooled transaction is not added to the pool once again.
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.transactions_pool_len(), 1);
+    assert!(schema.transactions_pool().contains(&other_pooled_tx.hash()));
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(8));
+}
+
 #[test]
 fn test_probe_advanced() {
     let (mut testkit, api) = init_testkit();
@@ -342,6 +517,46 @@ fn test_probe_duplicate_tx() {
     assert_eq!(schema.count(), Some(12));
 }
 
+#[test]
+fn test_dry_run_block() {
+    let (mut testkit, api) = init_testkit();
+    let committed_tx = inc_count(&api, 5);
+    testkit.create_block();
+
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 3, &key);
+    let zero_tx = TxIncrement::new(&pubkey, 0, &key);
+    let overflow_tx = TxIncrement::new(&pubkey, u64::max_value(), &key);
+    let reset_tx = TxReset::new(&pubkey, &key);
+
+    let report = testkit
+        .dry_run_block(txvec![
+            committed_tx.clone(),
+            tx.clone(),
+            zero_tx.clone(),
+            tx.clone(),
+            overflow_tx.clone(),
+            reset_tx.clone(),
+        ])
+        .unwrap();
+    assert_eq!(report.executed, vec![tx.hash()]);
+    assert_eq!(report.errored.len(), 2);
+    assert_eq!(report.errored[0].0, zero_tx.hash());
+    assert_eq!(report.errored[0].1.error_type(), ErrorType::Code(0));
+    assert_eq!(report.errored[0].1.description(), Some("Adding zero does nothing!"));
+    assert_eq!(report.errored[1].0, overflow_tx.hash());
+    assert_eq!(report.errored[1].1.error_type(), ErrorType::Panic);
+    assert_eq!(
+        report.skipped,
+        vec![committed_tx.hash(), tx.hash(), reset_tx.hash()]
+    );
+
+    // The blockchain state is not changed.
+    assert_eq!(testkit.height(), Height(1));
+    let snapshot = testkit.snapshot();
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(5));
+}
+
 #[test]
 fn test_snapshot_comparison() {
     let (mut testkit, api) = init_testkit();
@@ -711,3 +926,975 @@ fn test_boxed_tx() {
     assert_eq!(block.len(), 1);
     assert_eq!(block[0].content().raw().service_id(), counter::SERVICE_ID);
 }
+
+#[test]
+fn test_scenario() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    Scenario::new()
+        .send(TxIncrement::new(&pubkey, 5, &key))
+        .block()
+        .assert_api(ApiKind::Service("counter"), "count", |count: u64| count == 5)
+        .send_many(txvec![
+            TxIncrement::new(&pubkey, 1, &key),
+            TxIncrement::new(&pubkey, 2, &key),
+        ])
+        .blocks(3)
+        .assert_api(ApiKind::Service("counter"), "count", |count: u64| count == 8)
+        .run(&mut testkit);
+
+    assert_eq!(testkit.height(), Height(4));
+    let block = testkit.explorer().block(Height(2)).unwrap();
+    assert_eq!(block.len(), 2);
+}
+
+#[test]
+fn test_scenario_send_failures() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    // Only the administrator can reset the counter.
+    let tx = TxReset::new(&pubkey, &key);
+    let err = Scenario::new()
+        .block()
+        .send(tx.clone())
+        .try_run(&mut testkit)
+        .unwrap_err();
+    assert_eq!(err.step, 2);
+    assert_eq!(err.step_description, format!("send {:?}", tx.hash()));
+    assert_eq!(err.height, Height(1));
+    assert!(err.reason.contains("does not pass verification"));
+    assert_eq!(err.response, None);
+
+    let tx = TxIncrement::new(&pubkey, 5, &key);
+    testkit.create_block_with_transaction(tx.clone());
+    let err = Scenario::new()
+        .send_many(txvec![TxIncrement::new(&pubkey, 1, &key), tx.clone()])
+        .try_run(&mut testkit)
+        .unwrap_err();
+    assert_eq!(err.step, 1);
+    assert_eq!(err.step_description, "send_many of 2 transactions");
+    assert_eq!(err.height, Height(2));
+    assert!(err.reason.contains("already committed"));
+}
+
+#[test]
+fn test_scenario_block_failure() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    let zero_tx = TxIncrement::new(&pubkey, 0, &key);
+    let err = Scenario::new()
+        .blocks(2)
+        .send(zero_tx.clone())
+        .block()
+        .try_run(&mut testkit)
+        .unwrap_err();
+    assert_eq!(err.step, 3);
+    assert_eq!(err.step_description, "block");
+    // The failed block is committed nevertheless.
+    assert_eq!(err.height, Height(3));
+    assert!(err.reason.contains(&format!("{:?}", zero_tx.hash())));
+    assert!(err.reason.contains("Adding zero does nothing!"));
+}
+
+#[test]
+fn test_scenario_assert_api_failures() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    let err = Scenario::new()
+        .send(TxIncrement::new(&pubkey, 5, &key))
+        .block()
+        .assert_api(ApiKind::Service("counter"), "count", |count: u64| count == 4)
+        .block()
+        .try_run(&mut testkit)
+        .unwrap_err();
+    assert_eq!(err.step, 3);
+    assert_eq!(err.step_description, "assert_api api/services/counter/count");
+    assert_eq!(err.height, Height(1));
+    assert_eq!(err.response, Some("5".to_owned()));
+    assert_eq!(
+        err.to_string(),
+        "Step 3 (assert_api api/services/counter/count) failed at height 1: \
+         check has failed; response: 5"
+    );
+
+    let err = Scenario::new()
+        .assert_api(ApiKind::Service("counter"), "count", |_: String| true)
+        .try_run(&mut testkit)
+        .unwrap_err();
+    assert!(err.reason.contains("cannot deserialize response"));
+    assert_eq!(err.response, Some("5".to_owned()));
+
+    let err = Scenario::new()
+        .assert_api(ApiKind::Service("counter"), "unknown", |_: Value| true)
+        .try_run(&mut testkit)
+        .unwrap_err();
+    assert!(err.reason.starts_with("API error"));
+    assert_eq!(err.response, None);
+}
+
+#[test]
+#[should_panic(expected = "Step 2 (blocks 2) failed at height 1")]
+fn test_run_scenario_panics_on_failure() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let scenario = Scenario::new()
+        .send(TxIncrement::new(&pubkey, 0, &key))
+        .blocks(2);
+    testkit.run_scenario(scenario);
+}
+
+#[test]
+fn test_transaction_pool_size_history() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_pool_size_tracking()
+        .create();
+    let api = testkit.api();
+
+    let txs: Vec<_> = (1..4).map(|by| inc_count(&api, by)).collect();
+    testkit.create_block_with_tx_hashes(&[txs[0].hash()]);
+    testkit.create_block();
+    testkit.create_block();
+    // Probes do not influence the history.
+    testkit.probe(inc_count(&api, 10));
+    assert_eq!(
+        testkit.transaction_pool_size_history(),
+        vec![(Height(1), 3), (Height(2), 2), (Height(3), 0)]
+    );
+
+    testkit.checkpoint();
+    testkit.create_block();
+    testkit.rollback();
+    assert_eq!(testkit.transaction_pool_size_history().len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "Pool size tracking is not enabled")]
+fn test_transaction_pool_size_history_without_tracking() {
+    let (testkit, _) = init_testkit();
+    testkit.transaction_pool_size_history();
+}
+
+#[test]
+fn test_export_and_import_blockchain() {
+    let (mut testkit, api) = init_testkit();
+    for by in 1..6 {
+        inc_count(&api, by);
+        testkit.create_block();
+    }
+    let mut proposal = testkit.propose_configuration_at(3);
+    proposal.set_service_config("counter", "config");
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(7));
+    let pending_proposal = testkit.propose_configuration_at(5);
+    testkit.commit_configuration_change(pending_proposal.clone());
+    let pooled_tx = inc_count(&api, 10);
+
+    let mut fixture = Vec::new();
+    testkit.export_blockchain(&mut fixture).unwrap();
+    let mut restored = TestKitBuilder::from_exported_blockchain(&fixture[..])
+        .with_service(CounterService)
+        .create();
+
+    assert_eq!(restored.height(), Height(7));
+    assert_eq!(restored.last_block_hash(), testkit.last_block_hash());
+    assert_eq!(restored.actual_configuration(), testkit.actual_configuration());
+    assert_eq!(restored.network().us(), testkit.network().us());
+    assert_eq!(
+        restored.next_configuration().map(|cfg| cfg.stored_configuration()),
+        Some(pending_proposal.stored_configuration())
+    );
+    assert!(restored.is_tx_in_pool(&pooled_tx.hash()));
+    let counter: u64 = restored.api().service("counter").get("count");
+    assert_eq!(counter, 15);
+
+    // Both testkits create identical blocks, including ones activating the pending proposal.
+    testkit.create_blocks_until(Height(13));
+    restored.create_blocks_until(Height(13));
+    assert_eq!(restored.last_block_hash(), testkit.last_block_hash());
+    assert_eq!(restored.actual_configuration(), testkit.actual_configuration());
+}
+
+#[test]
+#[should_panic(expected = "Cannot decode exported transaction")]
+fn test_import_blockchain_without_services() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 1);
+    testkit.create_block();
+
+    let mut fixture = Vec::new();
+    testkit.export_blockchain(&mut fixture).unwrap();
+    TestKitBuilder::from_exported_blockchain(&fixture[..]).create();
+}
+
+#[test]
+fn test_randomly_reorder_mempool_transactions() {
+    let (mut testkit, api) = init_testkit();
+    let txs: Vec<_> = (1..20).map(|by| inc_count(&api, by)).collect();
+    let mut sorted_hashes: Vec<_> = txs.iter().map(|tx| tx.hash()).collect();
+    sorted_hashes.sort();
+
+    let block_hashes = |block: &BlockWithTransactions| -> Vec<_> {
+        block.transactions.iter().map(|tx| tx.content().hash()).collect()
+    };
+
+    testkit.checkpoint();
+    testkit.randomly_reorder_mempool_transactions(42);
+    let first_order = block_hashes(&testkit.create_block());
+    testkit.rollback();
+
+    testkit.checkpoint();
+    testkit.randomly_reorder_mempool_transactions(42);
+    let late_tx = inc_count(&api, 100);
+    let second_order = block_hashes(&testkit.create_block());
+    testkit.rollback();
+
+    // The same seed leads to the same order; new transactions follow the reordered ones.
+    assert_ne!(first_order, sorted_hashes);
+    assert_eq!(second_order[..first_order.len()], first_order[..]);
+    assert_eq!(second_order[first_order.len()..], [late_tx.hash()]);
+
+    testkit.randomly_reorder_mempool_transactions(7);
+    let third_order = block_hashes(&testkit.create_block());
+    assert_ne!(third_order, first_order);
+    let counter: u64 = api.service("counter").get("count");
+    assert_eq!(counter, 190);
+}
+
+/// Creates increment transactions signed with deterministic keys, so that the transactions
+/// are the same across test runs.
+fn seeded_increments(count: u8) -> Vec<TxIncrement> {
+    (0..count)
+        .map(|i| {
+            let seed = crypto::Seed::from_slice(&crypto::hash(&[i])[..]).unwrap();
+            let (pubkey, key) = crypto::gen_keypair_from_seed(&seed);
+            TxIncrement::new(&pubkey, u64::from(i) + 1, &key)
+        })
+        .collect()
+}
+
+fn block_tx_hashes(block: &BlockWithTransactions) -> Vec<Hash> {
+    block.transactions.iter().map(|tx| tx.content().hash()).collect()
+}
+
+fn shuffled_block_order(seed: u64) -> Vec<Hash> {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_shuffled_blocks(seed)
+        .create();
+    let api = testkit.api();
+    for tx in seeded_increments(20) {
+        api.send(tx);
+    }
+    block_tx_hashes(&testkit.create_block())
+}
+
+#[test]
+fn test_shuffled_blocks_are_reproducible() {
+    let mut sorted_hashes: Vec<_> = seeded_increments(20).iter().map(|tx| tx.hash()).collect();
+    sorted_hashes.sort();
+
+    let first_order = shuffled_block_order(42);
+    assert_eq!(first_order.len(), 20);
+    assert_ne!(first_order, sorted_hashes);
+    assert_eq!(shuffled_block_order(42), first_order);
+    assert_ne!(shuffled_block_order(7), first_order);
+}
+
+#[test]
+fn test_create_block_with_transactions_shuffled() {
+    let txs = || -> Vec<Box<dyn Transaction>> {
+        seeded_increments(20).into_iter().map(Into::into).collect()
+    };
+    let given_order: Vec<_> = seeded_increments(20).iter().map(|tx| tx.hash()).collect();
+
+    let (mut testkit, api) = init_testkit();
+    let first_order = block_tx_hashes(&testkit.create_block_with_transactions_shuffled(txs(), 42));
+    assert_ne!(first_order, given_order);
+    let counter: u64 = api.service("counter").get("count");
+    assert_eq!(counter, 210);
+
+    let (mut testkit, _) = init_testkit();
+    let second_order = block_tx_hashes(&testkit.create_block_with_transactions_shuffled(txs(), 42));
+    assert_eq!(second_order, first_order);
+}
+
+#[test]
+#[should_panic(expected = "(transactions were shuffled with seed 42)")]
+fn test_shuffle_seed_in_assertion_message() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_shuffled_blocks(42)
+        .create();
+    testkit.create_block();
+    testkit.assert_block_height_is(Height(2));
+}
+
+#[test]
+fn test_execution_metrics() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_execution_metrics()
+        .create();
+    let api = testkit.api();
+
+    let mut txs = seeded_increments(8).into_iter();
+    let mut blocks = Vec::new();
+    for count in &[5, 0, 3] {
+        for tx in txs.by_ref().take(*count) {
+            api.send(tx);
+        }
+        // Probed transactions are not included into the metrics.
+        testkit.probe(seeded_increments(10).pop().unwrap());
+        blocks.push(testkit.create_block());
+    }
+
+    let metrics = testkit.execution_metrics();
+    assert_eq!(metrics.blocks().len(), 3);
+    assert_eq!(metrics.tx_count(), 8);
+    for block in &blocks {
+        let block_metrics = metrics.block(block.height()).unwrap();
+        let timed_hashes: Vec<_> = block_metrics
+            .transactions
+            .iter()
+            .map(|&(tx_hash, _)| tx_hash)
+            .collect();
+        assert_eq!(timed_hashes, block_tx_hashes(block));
+        assert!(block_metrics.total_tx_time() <= block_metrics.patch_creation_time);
+    }
+    let heights: Vec<_> = metrics.blocks().iter().map(|block| block.height).collect();
+    assert_eq!(heights, vec![Height(1), Height(2), Height(3)]);
+    assert!(metrics.slowest_transaction().is_some());
+
+    testkit.checkpoint();
+    testkit.create_block_with_transactions(txvec![seeded_increments(10).pop().unwrap()]);
+    assert_eq!(testkit.execution_metrics().tx_count(), 9);
+    testkit.rollback();
+    assert_eq!(testkit.execution_metrics().tx_count(), 8);
+}
+
+#[test]
+fn test_execution_metrics_in_forked_testkit() {
+    use std::thread;
+
+    // Creates blocks with 4 transactions each in a separate thread, returning the heights
+    // of the blocks together with the hashes of their transactions.
+    fn create_blocks(
+        mut testkit: TestKit,
+        txs: Vec<TxIncrement>,
+    ) -> thread::JoinHandle<(TestKit, Vec<(Height, Vec<Hash>)>)> {
+        thread::spawn(move || {
+            let blocks = txs
+                .chunks(4)
+                .map(|chunk| {
+                    let txs = chunk.iter().map(|tx| Box::new(tx.clone()) as Box<dyn Transaction>);
+                    let block = testkit.create_block_with_transactions(txs);
+                    (block.height(), block_tx_hashes(&block))
+                })
+                .collect();
+            (testkit, blocks)
+        })
+    }
+
+    fn check_metrics(testkit: &TestKit, blocks: &[(Height, Vec<Hash>)]) {
+        let metrics = testkit.execution_metrics();
+        assert_eq!(metrics.blocks().len(), 5);
+        assert_eq!(metrics.tx_count(), 20);
+        for &(height, ref tx_hashes) in blocks {
+            let timed_hashes: Vec<_> = metrics
+                .block(height)
+                .unwrap()
+                .transactions
+                .iter()
+                .map(|&(tx_hash, _)| tx_hash)
+                .collect();
+            assert_eq!(timed_hashes, *tx_hashes);
+        }
+    }
+
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_execution_metrics()
+        .create();
+    let mut txs = seeded_increments(40);
+    let fork_txs = txs.split_off(20);
+    testkit.checkpoint();
+    let fork = testkit.fork_testkit().unwrap();
+
+    // The testkit and its copy create blocks concurrently with different transactions.
+    let handle = create_blocks(fork, fork_txs);
+    let (mut testkit, blocks) = create_blocks(testkit, txs).join().unwrap();
+    let (fork, fork_blocks) = handle.join().unwrap();
+    check_metrics(&testkit, &blocks);
+    check_metrics(&fork, &fork_blocks);
+
+    // Rolling back the testkit does not affect the metrics of its copy.
+    testkit.rollback();
+    assert_eq!(testkit.execution_metrics().tx_count(), 0);
+    assert_eq!(fork.execution_metrics().tx_count(), 20);
+}
+
+#[test]
+#[should_panic(expected = "Execution metrics are not enabled")]
+fn test_execution_metrics_disabled() {
+    let (testkit, _) = init_testkit();
+    testkit.execution_metrics();
+}
+
+#[test]
+fn test_block_execution_timing() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_execution_timing()
+        .create();
+    let api = testkit.api();
+    testkit.create_block_with_transaction(inc_count(&api, 5));
+    testkit.checkpoint();
+    testkit.create_blocks_until(Height(4));
+
+    let times: Vec<_> = (1..5)
+        .map(|height| testkit.block_execution_time(Height(height)).unwrap())
+        .collect();
+    let total = times.iter().fold(Duration::default(), |total, &time| total + time);
+    assert_eq!(testkit.mean_block_execution_time(), total / 4);
+
+    testkit.rollback();
+    assert!(testkit.block_execution_time(Height(1)).is_some());
+    assert!(testkit.block_execution_time(Height(2)).is_none());
+    assert_eq!(testkit.mean_block_execution_time(), times[0]);
+}
+
+#[test]
+#[should_panic(expected = "Execution timing is not enabled")]
+fn test_block_execution_timing_disabled() {
+    let (testkit, _) = init_testkit();
+    testkit.block_execution_time(Height(1));
+}
+
+#[test]
+fn test_last_block_messages() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with_service(CounterService)
+        .create();
+    let api = testkit.api();
+    let tx_a = inc_count(&api, 1);
+    let tx_b = inc_count(&api, 2);
+    let block = testkit.create_block();
+
+    let (propose, precommits) = testkit.last_block_messages();
+    let block_tx_hashes: Vec<_> = block.iter().map(|tx| tx.content().hash()).collect();
+    assert_eq!(propose.height(), block.height());
+    assert_eq!(propose.transactions(), block_tx_hashes);
+    assert_eq!(block_tx_hashes.len(), 2);
+    assert!(block_tx_hashes.contains(&tx_a.hash()) && block_tx_hashes.contains(&tx_b.hash()));
+    let proposer = testkit.validator(propose.validator());
+    assert!(propose.verify_signature(&proposer.public_keys().consensus_key));
+
+    assert_eq!(precommits.len(), 4);
+    for precommit in &precommits {
+        let validator = testkit.validator(precommit.validator());
+        assert!(precommit.verify_signature(&validator.public_keys().consensus_key));
+        assert_eq!(*precommit.propose_hash(), propose.hash());
+        assert_eq!(*precommit.block_hash(), testkit.last_block_hash());
+    }
+}
+
+#[test]
+fn test_retained_block_messages() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_retained_block_messages(2)
+        .create();
+    testkit.create_blocks_until(Height(2));
+    testkit.checkpoint();
+    testkit.fast_forward(2);
+
+    assert!(testkit.block_messages(Height(2)).is_none());
+    let (propose, _) = testkit.block_messages(Height(3)).unwrap();
+    assert_eq!(propose.height(), Height(3));
+    assert_eq!(testkit.last_block_messages().0.height(), Height(4));
+
+    testkit.rollback();
+    assert!(testkit.block_messages(Height(3)).is_none());
+    assert!(testkit.block_messages(Height(4)).is_none());
+}
+
+#[test]
+#[should_panic(expected = "Messages of the block at height 0 are not retained by the testkit")]
+fn test_last_block_messages_for_genesis_block() {
+    let (testkit, _) = init_testkit();
+    testkit.last_block_messages();
+}
+
+#[test]
+fn test_event_delay() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CounterService)
+        .with_event_delay(30)
+        .create();
+    let api = testkit.api();
+    api.send(inc_count(&api, 1));
+    api.send(inc_count(&api, 2));
+
+    let start = Instant::now();
+    testkit.poll_events();
+    assert!(start.elapsed() >= Duration::from_millis(60));
+    assert_eq!(Schema::new(&testkit.snapshot()).transactions_pool_len(), 2);
+
+    testkit.simulate_slow_network(50);
+    api.send(inc_count(&api, 3));
+    let start = Instant::now();
+    let block = testkit.create_block();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(block.len(), 3);
+
+    testkit.clear_event_delay();
+    api.send(inc_count(&api, 4));
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 1);
+}
+
+#[test]
+fn test_export_service_state() {
+    let (mut testkit, api) = init_testkit();
+    let before = testkit.export_service_state("counter");
+    assert_eq!(before, json!({}));
+
+    testkit.create_block_with_transaction(inc_count(&api, 5));
+    let after = testkit.export_service_state("counter");
+    assert_eq!(after, json!({ "counter.count": { "": "0500000000000000" } }));
+    assert_ne!(before, after);
+}
+
+#[test]
+#[should_panic(expected = "Service `unknown` is not registered in the blockchain")]
+fn test_export_service_state_unknown_service() {
+    let (testkit, _) = init_testkit();
+    testkit.export_service_state("unknown");
+}
+
+#[test]
+fn test_create_block_with_forged_hash() {
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 5, &key);
+    let forged_hash = crypto::hash(b"forged");
+    assert_matches!(testkit.verify_transaction_hashes(), Ok(()));
+
+    let block = testkit.create_block_with_forged_hash(tx.clone().into(), forged_hash);
+    assert_eq!(block.len(), 1);
+    assert!(block[0].status().is_ok());
+    assert_eq!(block[0].content().hash(), tx.hash());
+    assert_eq!(
+        *testkit.explorer().block(block.height()).unwrap().transaction_hashes(),
+        [forged_hash]
+    );
+    let counter: u64 = api.service("counter").get("count");
+    assert_eq!(counter, 5);
+
+    assert_matches!(
+        testkit.verify_transaction_hashes(),
+        Err(Error::TransactionHashMismatch { stored, actual })
+            if stored == forged_hash && actual == tx.hash()
+    );
+}
+
+/// Creates a testkit restored from the exported blockchain with several blocks
+/// and a configuration change.
+fn init_restored_testkit() -> TestKit {
+    let (mut testkit, api) = init_testkit();
+    for by in 1..4 {
+        inc_count(&api, by);
+        testkit.create_block();
+    }
+    let mut proposal = testkit.propose_configuration_at(2);
+    proposal.set_service_config("counter", "config");
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(6));
+
+    let mut fixture = Vec::new();
+    testkit.export_blockchain(&mut fixture).unwrap();
+    TestKitBuilder::from_exported_blockchain(&fixture[..])
+        .with_service(CounterService)
+        .create()
+}
+
+#[test]
+fn test_verify_chain() {
+    let (mut testkit, _) = init_testkit();
+    assert_eq!(testkit.verify_chain(), Ok(()));
+    testkit.create_blocks_until(Height(3));
+    assert_eq!(testkit.verify_chain(), Ok(()));
+
+    let restored = init_restored_testkit();
+    assert_eq!(restored.height(), Height(6));
+    assert_eq!(restored.verify_chain(), Ok(()));
+}
+
+#[test]
+fn test_verify_chain_with_corrupted_block() {
+    let mut testkit = init_restored_testkit();
+    let block_hash = testkit.explorer().block(Height(2)).unwrap().header().hash();
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut blocks: MapIndex<_, Hash, Block> = MapIndex::new("core.blocks", &mut fork);
+            let block = blocks.get(&block_hash).unwrap();
+            let forged = Block::new(
+                block.proposer_id(),
+                block.height(),
+                block.tx_count(),
+                block.prev_hash(),
+                block.tx_hash(),
+                &crypto::hash(b"forged state"),
+            );
+            blocks.put(&block_hash, forged);
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    assert_matches!(
+        testkit.verify_chain(),
+        Err(ChainVerificationError::BlockHashMismatch { height: Height(2), stored, .. })
+            if stored == block_hash
+    );
+}
+
+#[test]
+fn test_verify_chain_with_corrupted_precommits() {
+    let mut testkit = init_restored_testkit();
+    let (block_hash, precommits) = {
+        let explorer = testkit.explorer();
+        let block = explorer.block(Height(3)).unwrap();
+        let precommits = block.precommits().to_vec();
+        (block.header().hash(), precommits)
+    };
+    let clear_precommits = |testkit: &mut TestKit, duplicate: bool| {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut index: ListIndex<_, Precommit> =
+                ListIndex::new_in_family("core.precommits", &block_hash, &mut fork);
+            index.clear();
+            if duplicate {
+                index.extend(precommits.iter().cloned());
+                index.push(precommits[0].clone());
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    };
+
+    clear_precommits(&mut testkit, true);
+    assert_matches!(
+        testkit.verify_chain(),
+        Err(ChainVerificationError::InvalidPrecommit {
+            height: Height(3),
+            index: 1,
+            ref reason,
+            ..
+        }) if reason.contains("already signed")
+    );
+
+    clear_precommits(&mut testkit, false);
+    assert_matches!(
+        testkit.verify_chain(),
+        Err(ChainVerificationError::InsufficientPrecommits {
+            height: Height(3),
+            count: 0,
+            required: 1,
+        })
+    );
+}
+
+#[test]
+fn test_verify_chain_with_forged_transaction_hash() {
+    let (mut testkit, _) = init_testkit();
+    testkit.create_block();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 5, &key);
+    let forged_hash = crypto::hash(b"forged");
+    testkit.create_block_with_forged_hash(tx.clone().into(), forged_hash);
+    testkit.create_block();
+
+    assert_matches!(
+        testkit.verify_chain(),
+        Err(ChainVerificationError::TransactionHashMismatch { height: Height(2), stored, actual })
+            if stored == forged_hash && actual == tx.hash()
+    );
+}
+
+#[test]
+fn test_verify_chain_with_corrupted_state_hash() {
+    let mut testkit = init_restored_testkit();
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut aggregator: ProofMapIndex<_, Hash, Hash> =
+                ProofMapIndex::new("core.state_hash_aggregator", &mut fork);
+            aggregator.put(&crypto::hash(b"table"), crypto::hash(b"root"));
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    assert_matches!(
+        testkit.verify_chain(),
+        Err(ChainVerificationError::StateHashMismatch { height: Height(6), .. })
+    );
+}
+
+#[test]
+fn test_verify_transaction_signatures() {
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let committed_tx = TxIncrement::new(&pubkey, 5, &key);
+    testkit.create_block_with_transaction(committed_tx.clone());
+    let pool_tx = inc_count(&api, 3);
+
+    // Transactions with invalid signatures are rejected by the testkit, so such a transaction
+    // is committed bypassing the verification.
+    let unsigned_tx = TxIncrement::new_with_signature(&pubkey, 1, &Signature::zero());
+    let forged_hash = crypto::hash(b"unsigned");
+    testkit.create_block_with_forged_hash(unsigned_tx.into(), forged_hash);
+
+    let unknown_hash = crypto::hash(b"unknown");
+    let results = testkit.verify_transaction_signatures(&[
+        committed_tx.hash(),
+        unknown_hash,
+        forged_hash,
+        pool_tx.hash(),
+    ]);
+    assert_eq!(
+        results,
+        vec![
+            (committed_tx.hash(), SignatureVerificationResult::Valid),
+            (unknown_hash, SignatureVerificationResult::NotFound),
+            (forged_hash, SignatureVerificationResult::Invalid),
+            (pool_tx.hash(), SignatureVerificationResult::Valid),
+        ]
+    );
+}
+
+#[test]
+fn test_transaction_type_name() {
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let committed_tx = TxReset::new(&pubkey, &key);
+    testkit.create_block_with_transaction(committed_tx.clone());
+    let pool_tx = inc_count(&api, 3);
+
+    assert_eq!(
+        testkit.transaction_type_name(&committed_tx.hash()),
+        Some("TxReset".to_owned())
+    );
+    assert_eq!(
+        testkit.transaction_type_name(&pool_tx.hash()),
+        Some("TxIncrement".to_owned())
+    );
+    assert_eq!(testkit.transaction_type_name(&crypto::hash(b"unknown")), None);
+}
+
+#[test]
+fn test_assert_no_errors_in_any_block() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 5, &key));
+    testkit.create_block();
+    testkit.assert_no_errors_in_any_block();
+}
+
+#[test]
+#[should_panic(expected = "Transactions were executed with errors in 2 block(s)")]
+fn test_assert_no_errors_in_any_block_with_errors() {
+    let (mut testkit, _) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(txvec![
+        TxIncrement::new(&pubkey, 0, &key),
+        TxIncrement::new(&pubkey, 1, &key),
+    ]);
+    testkit.create_block();
+    // Overflow leads to a panic during execution.
+    testkit.create_block_with_transactions(txvec![
+        TxIncrement::new(&pubkey, u64::max_value(), &key),
+        TxIncrement::new(&pubkey, 2, &key),
+    ]);
+    testkit.assert_no_errors_in_any_block();
+}
+
+#[test]
+fn test_create_block_and_assert_no_errors() {
+    let (mut testkit, api) = init_testkit();
+    let tx = inc_count(&api, 5);
+    let block = testkit.create_block_and_assert_no_errors();
+    assert_eq!(block.len(), 1);
+    assert_eq!(block[0].content().hash(), tx.hash());
+}
+
+#[test]
+#[should_panic(expected = "1 transaction(s) in the block at height 1 were executed with errors")]
+fn test_create_block_and_assert_no_errors_with_errors() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 5);
+    // Adding zero leads to an execution error.
+    inc_count(&api, 0);
+    testkit.create_block_and_assert_no_errors();
+}
+
+#[test]
+fn test_diff_after() {
+    let (mut testkit, api) = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    let diff = api.diff_after(&mut testkit, ApiKind::Service("counter"), "count", |testkit| {
+        testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 5, &key));
+    });
+    assert_eq!(
+        diff.changes()[""],
+        JsonChange::Changed {
+            old: json!(0),
+            new: json!(5),
+        }
+    );
+
+    let diff = api.diff_after(&mut testkit, ApiKind::Explorer, "v1/blocks?count=1", |testkit| {
+        testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 1, &key));
+    });
+    diff.assert_only_changed(&["range", "blocks.0"]);
+    assert!(diff.changes().contains_key("blocks.0.height"));
+    assert!(diff.changes().contains_key("blocks.0.prev_hash"));
+    // Both blocks contain a single transaction and are proposed by the same validator.
+    assert!(!diff.changes().contains_key("blocks.0.tx_count"));
+    assert!(!diff.changes().contains_key("blocks.0.proposer_id"));
+}
+
+#[test]
+fn test_create_block_from_mempool_slice() {
+    let (mut testkit, api) = init_testkit();
+    let txs: Vec<_> = (1..=5).map(|by| inc_count(&api, by)).collect();
+    testkit.poll_events();
+
+    let block = testkit.create_block_from_mempool_slice(1, 2);
+    let committed: Vec<_> = block.iter().map(|tx| tx.content().hash()).collect();
+    let mut expected = vec![txs[1].hash(), txs[2].hash()];
+    expected.sort();
+    let mut committed_sorted = committed.clone();
+    committed_sorted.sort();
+    assert_eq!(committed_sorted, expected);
+
+    for tx in &[&txs[0], &txs[3], &txs[4]] {
+        assert!(testkit.is_tx_in_pool(&tx.hash()));
+    }
+
+    // Positions are recomputed after the committed transactions leave the pool.
+    let block = testkit.create_block_from_mempool_slice(2, 1);
+    assert_eq!(block.len(), 1);
+    assert_eq!(block[0].content().hash(), txs[4].hash());
+    assert!(testkit.is_tx_in_pool(&txs[0].hash()));
+    assert!(testkit.is_tx_in_pool(&txs[3].hash()));
+}
+
+#[test]
+#[should_panic(expected = "Cannot take 2 transactions starting from position 1")]
+fn test_create_block_from_mempool_slice_out_of_bounds() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 1);
+    inc_count(&api, 2);
+    testkit.create_block_from_mempool_slice(1, 2);
+}
+
+#[test]
+fn test_trace_mode() {
+    let (mut testkit, api) = init_testkit();
+    testkit.trace_mode(true);
+
+    let tx = inc_count(&api, 5);
+    testkit.poll_events();
+    let snapshot = testkit.probe_all(vec![Box::new(tx.clone()) as Box<Transaction>]);
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(5));
+    testkit.create_block();
+    testkit.api();
+
+    testkit.trace_mode(false);
+    testkit.create_block();
+    assert_eq!(testkit.height(), Height(2));
+    assert_eq!(CounterSchema::new(&testkit.snapshot()).count(), Some(5));
+}
+
+fn reset_count(api: &TestKitApi) -> TxReset {
+    let (pubkey, key) = crypto::gen_keypair_from_seed(&crypto::Seed::from_slice(
+        &crypto::hash(b"correct horse battery staple")[..],
+    ).unwrap());
+    let tx = TxReset::new(&pubkey, &key);
+    let tx_info: TransactionResponse = api.service("counter").private().post("reset", &tx);
+    assert_eq!(tx_info.tx_hash, tx.hash());
+    tx
+}
+
+fn count_after_block_with_ordering(ordering: TxOrdering) -> u64 {
+    let (mut testkit, api) = init_testkit();
+    testkit.set_tx_ordering(ordering);
+    inc_count(&api, 5);
+    testkit.poll_events();
+    reset_count(&api);
+    testkit.poll_events();
+    inc_count(&api, 3);
+
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 3);
+    assert!(block.iter().all(|tx| tx.status().is_ok()));
+    let snapshot = testkit.snapshot();
+    CounterSchema::new(&snapshot).count().unwrap()
+}
+
+#[test]
+fn test_tx_ordering() {
+    // Only the increment sent after the reset contributes to the counter.
+    assert_eq!(count_after_block_with_ordering(TxOrdering::Fifo), 3);
+    let reverse = |tx_hashes: &[Hash], _: &Snapshot| tx_hashes.iter().rev().cloned().collect();
+    assert_eq!(
+        count_after_block_with_ordering(TxOrdering::Custom(Box::new(reverse))),
+        5
+    );
+}
+
+#[test]
+fn test_custom_tx_ordering_with_selection() {
+    let (mut testkit, api) = init_testkit();
+    let txs: Vec<_> = (1..=4).map(|by| inc_count(&api, by)).collect();
+    // Include only the first two transactions.
+    testkit.set_tx_ordering(TxOrdering::Custom(Box::new(
+        |tx_hashes: &[Hash], _: &Snapshot| tx_hashes.iter().take(2).cloned().collect(),
+    )));
+
+    let block = testkit.create_block();
+    let tx_hashes: Vec<_> = block.iter().map(|tx| tx.content().hash()).collect();
+    assert_eq!(tx_hashes, vec![txs[0].hash(), txs[1].hash()]);
+    assert!(testkit.is_tx_in_pool(&txs[2].hash()));
+    assert!(testkit.is_tx_in_pool(&txs[3].hash()));
+
+    testkit.set_tx_ordering(TxOrdering::ByHash);
+    let block = testkit.create_block();
+    assert_eq!(block.len(), 2);
+}
+
+#[test]
+fn test_custom_tx_ordering_with_unknown_transaction() {
+    let (mut testkit, api) = init_testkit();
+    inc_count(&api, 1);
+    let unknown_hash = crypto::hash(b"unknown");
+    testkit.set_tx_ordering(TxOrdering::Custom(Box::new(
+        move |_: &[Hash], _: &Snapshot| vec![unknown_hash],
+    )));
+
+    assert_matches!(
+        testkit.try_create_block(),
+        Err(Error::TransactionsNotInPool(_))
+    );
+    assert_eq!(testkit.height(), Height(0));
+}
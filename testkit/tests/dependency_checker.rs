@@ -0,0 +1,186 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the detection of transactions that are committed, but have no effect.
+
+#[macro_use]
+extern crate exonum;
+#[macro_use]
+extern crate exonum_testkit;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use exonum::{crypto::Signature, storage::MapIndex};
+use exonum_testkit::{prelude::*, TxDependencyChecker};
+
+const SERVICE_ID: u16 = 896;
+const BALANCES: &str = "deps.balances";
+
+transactions! {
+    AccountTransactions {
+        const SERVICE_ID = SERVICE_ID;
+
+        struct TxCreateAccount {
+            name: &str,
+        }
+
+        struct TxDeposit {
+            name: &str,
+            amount: u64,
+        }
+    }
+}
+
+impl Transaction for TxCreateAccount {
+    fn verify(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut balances: MapIndex<_, String, u64> = MapIndex::new(BALANCES, fork);
+        if !balances.contains(self.name()) {
+            balances.put(&self.name().to_owned(), 0_u64);
+        }
+        Ok(())
+    }
+}
+
+impl Transaction for TxDeposit {
+    fn verify(&self) -> bool {
+        true
+    }
+
+    // Deposits to an unknown account do nothing.
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut balances: MapIndex<_, String, u64> = MapIndex::new(BALANCES, fork);
+        if let Some(balance) = balances.get(self.name()) {
+            balances.put(&self.name().to_owned(), balance + self.amount());
+        }
+        Ok(())
+    }
+}
+
+struct AccountService;
+
+impl Service for AccountService {
+    fn service_name(&self) -> &str {
+        "accounts"
+    }
+
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn state_hash(&self, _: &Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<Transaction>, encoding::Error> {
+        let tx = AccountTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+}
+
+fn balances(snapshot: &Snapshot) -> Vec<(String, u64)> {
+    let balances: MapIndex<_, String, u64> = MapIndex::new(BALANCES, snapshot);
+    balances.iter().collect()
+}
+
+/// Flags the transactions that do not change balances.
+fn checker() -> TxDependencyChecker {
+    TxDependencyChecker::new(|_: &Transaction, before: &Snapshot, after: &Snapshot| {
+        balances(before) == balances(after)
+    })
+}
+
+fn create_transactions() -> (TxCreateAccount, TxDeposit) {
+    let create = TxCreateAccount::new_with_signature("alice", &Signature::zero());
+    let deposit = TxDeposit::new_with_signature("alice", 10, &Signature::zero());
+    (create, deposit)
+}
+
+#[test]
+fn test_transactions_in_correct_order() {
+    let mut testkit = TestKit::for_service(AccountService);
+    testkit.set_tx_dependency_checker(checker().panicking());
+
+    let (create, deposit) = create_transactions();
+    testkit.create_block_with_transactions(txvec![create, deposit]);
+    assert!(testkit.flagged_transactions(Height(1)).is_empty());
+    assert_eq!(
+        balances(&testkit.snapshot()),
+        vec![("alice".to_owned(), 10)]
+    );
+}
+
+#[test]
+fn test_transactions_in_wrong_order() {
+    let mut testkit = TestKit::for_service(AccountService);
+    testkit.set_tx_dependency_checker(checker());
+
+    let (create, deposit) = create_transactions();
+    let block = testkit.create_block_with_transactions(txvec![deposit.clone(), create]);
+    // Both transactions are committed successfully...
+    assert_eq!(block.len(), 2);
+    assert!(block.iter().all(|tx| tx.status().is_ok()));
+    // ...but the deposit has no effect.
+    assert_eq!(testkit.flagged_transactions(Height(1)), &[deposit.hash()]);
+    assert_eq!(balances(&testkit.snapshot()), vec![("alice".to_owned(), 0)]);
+
+    testkit.clear_tx_dependency_checker();
+    testkit.create_block_with_transactions(txvec![TxDeposit::new_with_signature(
+        "bob",
+        1,
+        &Signature::zero()
+    )]);
+    assert!(testkit.flagged_transactions(Height(2)).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Transactions committed at height 1 had no effect")]
+fn test_panicking_checker() {
+    let mut testkit = TestKit::for_service(AccountService);
+    testkit.set_tx_dependency_checker(checker().panicking());
+
+    let (create, deposit) = create_transactions();
+    testkit.create_block_with_transactions(txvec![deposit, create]);
+}
+
+#[test]
+fn test_probed_transactions_are_not_checked() {
+    let mut testkit = TestKit::for_service(AccountService);
+    testkit.set_tx_dependency_checker(checker().panicking());
+
+    let (_, deposit) = create_transactions();
+    let snapshot = testkit.probe(deposit.clone());
+    assert!(balances(&snapshot).is_empty());
+    let report = testkit.dry_run_block(txvec![deposit]).unwrap();
+    assert_eq!(report.executed.len(), 1);
+    assert!(testkit.flagged_transactions(Height(1)).is_empty());
+}
+
+#[test]
+fn test_flagged_transactions_after_rollback() {
+    let mut testkit = TestKit::for_service(AccountService);
+    testkit.set_tx_dependency_checker(checker());
+
+    let (create, deposit) = create_transactions();
+    testkit.checkpoint();
+    testkit.create_block_with_transactions(txvec![deposit.clone(), create]);
+    assert_eq!(testkit.flagged_transactions(Height(1)), &[deposit.hash()]);
+    testkit.rollback();
+    assert!(testkit.flagged_transactions(Height(1)).is_empty());
+}
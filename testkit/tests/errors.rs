@@ -0,0 +1,375 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the errors returned by the fallible testkit methods.
+
+#[macro_use]
+extern crate assert_matches;
+extern crate exonum;
+#[macro_use]
+extern crate exonum_testkit;
+extern crate futures;
+
+use exonum::{
+    blockchain::Blockchain, node::ApiSender,
+    storage::{self, Database, Entry, MemoryDB, Patch},
+};
+use exonum_testkit::{
+    prelude::*, samples::{CounterService, TxIncrement}, Error, MissingTransaction,
+};
+use futures::sync::mpsc;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+
+fn init_testkit() -> TestKit {
+    TestKitBuilder::validator()
+        .with_validators(2)
+        .with_service(CounterService)
+        .create()
+}
+
+/// Runs the closure and returns the message of the panic raised by it.
+fn panic_message<F: FnOnce()>(f: F) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).expect_err("Closure did not panic");
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .expect("Panic payload is not a string")
+}
+
+/// Database rejecting the patches that modify the tables with the `frozen.` prefix
+/// until the flag wrapped in it is set.
+struct FreezingDb(MemoryDB, Arc<AtomicBool>);
+
+impl Database for FreezingDb {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        self.0.snapshot()
+    }
+
+    fn merge(&self, patch: Patch) -> storage::Result<()> {
+        let thawed = self.1.load(Ordering::SeqCst);
+        if let Some((name, _)) = patch.iter().find(|&(name, _)| name.starts_with("frozen.")) {
+            if !thawed {
+                return Err(storage::Error::new(format!("Table `{}` is read-only", name)));
+            }
+        }
+        self.0.merge(patch)
+    }
+
+    fn merge_sync(&self, patch: Patch) -> storage::Result<()> {
+        self.merge(patch)
+    }
+}
+
+/// Service writing to a table rejected by `FreezingDb` before each block is committed.
+struct MisbehavingService;
+
+impl Service for MisbehavingService {
+    fn service_id(&self) -> u16 {
+        1024
+    }
+
+    fn service_name(&self) -> &str {
+        "misbehaving"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        unimplemented!()
+    }
+
+    fn before_commit(&self, fork: &mut Fork) {
+        Entry::new("frozen.value", fork).set(1_u64);
+    }
+}
+
+#[test]
+fn test_send_to_dropped_testkit() {
+    let testkit = init_testkit();
+    let api = testkit.api();
+    drop(testkit);
+
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    assert_matches!(api.try_send(tx), Err(Error::ChannelSend(_)));
+}
+
+#[test]
+fn test_create_block_with_unknown_tx_hash() {
+    let mut testkit = init_testkit();
+    let unknown_hash = crypto::hash(b"unknown");
+
+    let result = testkit.try_create_block_with_tx_hashes(&[unknown_hash]);
+    assert_matches!(
+        result,
+        Err(Error::TransactionsNotInPool(ref missing))
+            if missing.0 == vec![MissingTransaction::Unknown(unknown_hash)]
+    );
+    assert_eq!(testkit.height(), Height(0));
+}
+
+#[test]
+fn test_create_block_with_committed_tx_hash() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.create_block_with_transaction(tx.clone());
+    let other_tx = TxIncrement::new(&pubkey, 2, &key);
+    testkit.api().send(other_tx.clone());
+    let unknown_hash = crypto::hash(b"unknown");
+
+    let result =
+        testkit.try_create_block_with_tx_hashes(&[tx.hash(), other_tx.hash(), unknown_hash]);
+    assert_matches!(
+        result,
+        Err(Error::TransactionsNotInPool(ref missing))
+            if missing.0 == vec![
+                MissingTransaction::Committed(tx.hash()),
+                MissingTransaction::Unknown(unknown_hash),
+            ]
+    );
+    assert_eq!(testkit.height(), Height(1));
+}
+
+#[test]
+fn test_create_block_with_unpolled_tx_hash() {
+    // Transactions sent to the testkit are taken into account even if events
+    // have not been polled explicitly.
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.api().send(tx.clone());
+
+    let block = testkit.try_create_block_with_tx_hashes(&[tx.hash()]).unwrap();
+    assert_eq!(block.len(), 1);
+}
+
+#[test]
+fn test_missing_transactions_description() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.create_block_with_transaction(tx.clone());
+    let unknown_hash = crypto::hash(b"unknown");
+
+    let err = testkit
+        .try_create_block_with_tx_hashes(&[tx.hash(), unknown_hash])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Transactions are not in the pool: {:?} (already committed), \
+             {:?} (unknown, has never been sent to the testkit)",
+            tx.hash(),
+            unknown_hash
+        )
+    );
+}
+
+#[test]
+fn test_create_block_with_duplicate_tx_hashes() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.api().send(tx.clone());
+
+    let result = testkit.try_create_block_with_tx_hashes(&[tx.hash(), tx.hash()]);
+    assert_matches!(result, Err(Error::DuplicateTransaction(hash)) if hash == tx.hash());
+    assert_eq!(testkit.height(), Height(0));
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+}
+
+#[test]
+fn test_create_block_with_committed_transaction() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.create_block_with_transaction(tx.clone());
+    let other_tx = TxIncrement::new(&pubkey, 2, &key);
+
+    let result = testkit.try_create_block_with_transactions(txvec![other_tx.clone(), tx.clone()]);
+    assert_matches!(result, Err(Error::TransactionAlreadyCommitted(hash)) if hash == tx.hash());
+    // The pool is left intact.
+    assert_eq!(testkit.height(), Height(1));
+    assert!(!testkit.is_tx_in_pool(&other_tx.hash()));
+}
+
+#[test]
+fn test_commit_stale_configuration_change() {
+    let mut testkit = init_testkit();
+    testkit.create_blocks_until(Height(5));
+
+    let mut proposal = testkit.configuration_change_proposal();
+    proposal.set_actual_from(Height(3));
+    let result = testkit.try_commit_configuration_change(proposal);
+    assert_matches!(result, Err(Error::StaleConfigurationProposal(_)));
+    assert!(testkit.next_configuration().is_none());
+
+    let proposal = testkit.propose_configuration_at(2);
+    testkit.try_commit_configuration_change(proposal).unwrap();
+    let mut proposal = testkit.configuration_change_proposal();
+    proposal.set_actual_from(Height(10));
+    let result = testkit.try_commit_configuration_change(proposal);
+    assert_matches!(result, Err(Error::StaleConfigurationProposal(_)));
+}
+
+#[test]
+fn test_panic_messages_for_block_creation() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    let message = panic_message(|| {
+        testkit.create_block_with_transaction(tx.clone());
+    });
+    assert!(message.starts_with("Cannot create block at height 2: "));
+    assert!(message.contains(&format!("{:?}", tx.hash())));
+
+    let unknown_hash = crypto::hash(b"unknown");
+    let message = panic_message(|| {
+        testkit.create_block_with_tx_hashes(&[unknown_hash]);
+    });
+    assert!(message.starts_with("Cannot create block at height 2: "));
+    assert!(message.contains(&format!("{:?}", unknown_hash)));
+}
+
+#[test]
+fn test_panic_message_for_send() {
+    let testkit = init_testkit();
+    let api = testkit.api();
+    drop(testkit);
+
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    let message = panic_message(|| api.send(tx.clone()));
+    assert!(message.starts_with(&format!("Failed to send transaction {:?}: ", tx.hash())));
+}
+
+#[test]
+fn test_panic_message_for_configuration_change() {
+    let mut testkit = init_testkit();
+    testkit.create_blocks_until(Height(5));
+
+    let mut proposal = testkit.configuration_change_proposal();
+    proposal.set_actual_from(Height(3));
+    let message = panic_message(|| testkit.commit_configuration_change(proposal));
+    assert!(message.starts_with("Cannot commit configuration change with actual_from height 3: "));
+}
+
+#[test]
+fn test_panic_message_for_auditor_propose() {
+    let testkit = TestKitBuilder::auditor().create();
+    let last_hash = testkit.last_block_hash();
+
+    let message = panic_message(|| {
+        testkit.us().create_propose(Height(1), &last_hash, &[]);
+    });
+    assert!(message.starts_with("Cannot create propose at height 1: "));
+    let consensus_key = testkit.us().public_keys().consensus_key;
+    assert!(message.contains(&format!("{:?} is not a validator", consensus_key)));
+}
+
+/// Creates a testkit with `MisbehavingService`, which fails to commit blocks
+/// until `thawed` is set.
+fn init_freezing_testkit(thawed: Arc<AtomicBool>) -> TestKit {
+    let network = TestNetwork::new(1);
+    let blockchain = {
+        let (service_key, service_secret_key) = network.us().service_keypair();
+        Blockchain::new(
+            Arc::new(FreezingDb(MemoryDB::new(), thawed)) as Arc<dyn Database>,
+            vec![
+                Box::new(CounterService) as Box<dyn Service>,
+                Box::new(MisbehavingService),
+            ],
+            *service_key,
+            service_secret_key.clone(),
+            ApiSender::new(mpsc::channel(1).0),
+        )
+    };
+    TestKit::from_blockchain(blockchain, network).unwrap()
+}
+
+#[test]
+fn test_create_block_with_storage_failure() {
+    let mut testkit = init_freezing_testkit(Arc::default());
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    testkit.api().send(tx.clone());
+
+    let err = testkit.try_create_block().unwrap_err();
+    assert_matches!(
+        err,
+        Error::Commit { height: Height(1), proposer: ValidatorId(0), ref tx_hashes, .. }
+            if *tx_hashes == vec![tx.hash()]
+    );
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Cannot commit block at height 1 proposed by validator 0 with transactions {:?}: \
+             Table `frozen.value` is read-only",
+            vec![tx.hash()]
+        )
+    );
+    // The failed block leaves the blockchain intact.
+    assert_eq!(testkit.height(), Height(0));
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+
+    let message = panic_message(|| {
+        testkit.create_block();
+    });
+    assert!(message.starts_with("Cannot create block at height 1: Cannot commit block"));
+}
+
+#[test]
+fn test_storage_failure_keeps_testkit_consistent() {
+    let thawed = Arc::new(AtomicBool::new(false));
+    let mut testkit = init_freezing_testkit(Arc::clone(&thawed));
+    let mut proposal = testkit.propose_configuration_at(3);
+    let mut validators = testkit.network().validators().to_vec();
+    validators.push(TestNode::new_validator(ValidatorId(1)));
+    proposal.set_validators(validators);
+    let stored = proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(proposal);
+
+    for _ in 0..2 {
+        assert_matches!(
+            testkit.try_create_block(),
+            Err(Error::Commit { height: Height(1), .. })
+        );
+    }
+    // Neither the configuration nor the network are affected by the failed blocks.
+    assert_eq!(testkit.height(), Height(0));
+    assert_eq!(testkit.network().validators().len(), 1);
+    assert_eq!(testkit.scheduled_configurations().len(), 1);
+
+    thawed.store(true, Ordering::SeqCst);
+    testkit.create_block();
+    // The configuration is committed in the first block created successfully.
+    let snapshot = testkit.snapshot();
+    assert_eq!(CoreSchema::new(&snapshot).following_configuration(), Some(stored.clone()));
+    assert_eq!(testkit.network().validators().len(), 1);
+
+    testkit.create_block();
+    assert_eq!(testkit.network().validators().len(), 2);
+    let block = testkit.create_block();
+    assert_eq!(block.height(), Height(3));
+    assert_eq!(block.precommits.len(), 2);
+    assert_eq!(testkit.actual_configuration(), stored);
+}
@@ -0,0 +1,114 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks that transactions observe the same blockchain height during execution
+//! when they are probed and when they are included into a block.
+
+#[macro_use]
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum_testkit::prelude::*;
+
+const SERVICE_ID: u16 = 1_025;
+const OBSERVED_HEIGHT: &str = "height_recorder.observed";
+
+transactions! {
+    HeightRecorderTransactions {
+        const SERVICE_ID = SERVICE_ID;
+
+        struct TxRecordHeight {
+            author: &PublicKey,
+            seed: u64,
+        }
+    }
+}
+
+fn observed_height(snapshot: &dyn Snapshot) -> Option<Height> {
+    snapshot
+        .get(OBSERVED_HEIGHT, &[])
+        .map(|bytes| Height(String::from_utf8(bytes).unwrap().parse().unwrap()))
+}
+
+impl Transaction for TxRecordHeight {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let height = CoreSchema::new(&*fork).height();
+        fork.put(OBSERVED_HEIGHT, vec![], height.0.to_string().into_bytes());
+        Ok(())
+    }
+}
+
+struct HeightRecorderService;
+
+impl Service for HeightRecorderService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        "height_recorder"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        let tx = HeightRecorderTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+}
+
+#[test]
+fn test_probe_and_block_observe_same_height() {
+    let mut testkit = TestKit::for_service(HeightRecorderService);
+    testkit.create_blocks_until(Height(3));
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxRecordHeight::new(&pubkey, 1, &key);
+
+    let execution_height = testkit.execution_height();
+    assert_eq!(execution_height, Height(3));
+
+    let snapshot = testkit.probe(tx.clone());
+    let probed_height = observed_height(snapshot.as_ref());
+    assert_eq!(probed_height, Some(execution_height));
+    assert_eq!(testkit.height(), Height(3));
+
+    testkit.create_block_with_transaction(tx);
+    let committed_height = observed_height(testkit.snapshot().as_ref());
+    assert_eq!(committed_height, probed_height);
+    assert_eq!(testkit.height(), execution_height.next());
+    assert_eq!(testkit.execution_height(), Height(4));
+}
+
+#[test]
+fn test_probe_all_observes_same_height_as_block() {
+    let mut testkit = TestKit::for_service(HeightRecorderService);
+    let (pubkey, key) = crypto::gen_keypair();
+    let txs = vec![
+        TxRecordHeight::new(&pubkey, 1, &key),
+        TxRecordHeight::new(&pubkey, 2, &key),
+    ];
+
+    let snapshot = testkit.probe_all(txs.iter().cloned().map(Into::into));
+    let probed_height = observed_height(snapshot.as_ref());
+    assert_eq!(probed_height, Some(testkit.execution_height()));
+
+    testkit.create_block_with_transactions(txs.into_iter().map(Into::into));
+    assert_eq!(observed_height(testkit.snapshot().as_ref()), probed_height);
+}
@@ -0,0 +1,118 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+extern crate exonum;
+extern crate exonum_testkit;
+#[macro_use]
+extern crate pretty_assertions;
+extern crate rand;
+
+use exonum::{
+    blockchain::Schema, crypto::CryptoHash, helpers::Height, messages::Message,
+};
+use exonum_testkit::{TestKit, TestKitBuilder};
+use rand::Rng;
+
+/// Checks that the block at the given height is linked to the previous block
+/// and has valid precommits from all validators.
+fn assert_block_is_valid(testkit: &TestKit, height: Height) {
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+
+    let block_hash = schema.block_hash_by_height(height).unwrap();
+    let block = schema.blocks().get(&block_hash).unwrap();
+    assert_eq!(block.height(), height);
+    assert_eq!(block.hash(), block_hash);
+    assert_eq!(
+        *block.prev_hash(),
+        schema.block_hash_by_height(height.previous()).unwrap()
+    );
+
+    let precommits = schema.precommits(&block_hash);
+    assert_eq!(
+        precommits.len() as usize,
+        testkit.network().validators().len()
+    );
+    for precommit in precommits.iter() {
+        assert_eq!(precommit.height(), height);
+        assert_eq!(*precommit.block_hash(), block_hash);
+        let validator = testkit.validator(precommit.validator());
+        assert!(precommit.verify_signature(&validator.public_keys().consensus_key));
+    }
+}
+
+#[test]
+fn test_fast_forward_height() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.fast_forward(10);
+    assert_eq!(testkit.height(), Height(10));
+    testkit.create_block();
+    assert_eq!(testkit.height(), Height(11));
+    testkit.fast_forward(0);
+    assert_eq!(testkit.height(), Height(11));
+}
+
+#[test]
+fn test_fast_forward_produces_valid_blocks() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    testkit.fast_forward(200);
+    assert_eq!(testkit.height(), Height(200));
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        let height = Height(rng.gen_range(1, 201));
+        assert_block_is_valid(&testkit, height);
+    }
+}
+
+#[test]
+fn test_fast_forward_is_equivalent_to_create_block() {
+    let mut testkit = TestKitBuilder::validator().with_validators(2).create();
+    testkit.checkpoint();
+    testkit.create_blocks_until(Height(5));
+    let expected_hash = testkit.last_block_hash();
+    testkit.rollback();
+
+    testkit.fast_forward(5);
+    assert_eq!(testkit.last_block_hash(), expected_hash);
+}
+
+#[test]
+fn test_fast_forward_with_config_change() {
+    let mut testkit = TestKitBuilder::validator().create();
+    let proposal = {
+        let mut cfg = testkit.configuration_change_proposal();
+        cfg.set_actual_from(Height(10));
+        cfg.set_service_config("service", "config");
+        cfg
+    };
+    let stored = proposal.stored_configuration().clone();
+    testkit.commit_configuration_change(proposal);
+
+    testkit.fast_forward(20);
+    assert_eq!(testkit.actual_configuration(), stored);
+    assert_block_is_valid(&testkit, Height(10));
+}
+
+#[test]
+fn test_fast_forward_records_pool_size() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_pool_size_tracking()
+        .create();
+    testkit.create_block();
+    testkit.fast_forward(3);
+    assert_eq!(
+        testkit.transaction_pool_size_history(),
+        vec![(Height(1), 0), (Height(2), 0), (Height(3), 0), (Height(4), 0)]
+    );
+}
@@ -0,0 +1,124 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for creating a testkit around an existing blockchain.
+
+#[macro_use]
+extern crate assert_matches;
+extern crate exonum;
+extern crate exonum_testkit;
+extern crate futures;
+
+use exonum::{
+    blockchain::Blockchain, crypto::{self, PublicKey, SecretKey}, helpers::{Height, ValidatorId},
+    node::ApiSender, storage::MemoryDB,
+};
+use exonum_testkit::{Error, TestKit, TestNetwork, TestNode};
+use futures::sync::mpsc;
+
+fn create_blockchain(service_keypair: (&PublicKey, &SecretKey)) -> Blockchain {
+    let (api_sender, _) = mpsc::channel(1);
+    Blockchain::new(
+        MemoryDB::new(),
+        vec![],
+        *service_keypair.0,
+        service_keypair.1.clone(),
+        ApiSender::new(api_sender),
+    )
+}
+
+#[test]
+fn test_from_fresh_blockchain() {
+    let network = TestNetwork::new(4);
+    let blockchain = create_blockchain(network.us().service_keypair());
+
+    let mut testkit = TestKit::from_blockchain(blockchain, network.clone()).unwrap();
+    assert_eq!(testkit.height(), Height(0));
+    assert_eq!(
+        testkit.actual_configuration().validator_keys,
+        network.genesis_config().validator_keys
+    );
+
+    let block = testkit.create_block();
+    assert_eq!(block.height(), Height(1));
+    assert_eq!(block.precommits.len(), 4);
+}
+
+#[test]
+fn test_from_initialized_blockchain() {
+    let network = TestNetwork::new(2);
+    let mut blockchain = create_blockchain(network.us().service_keypair());
+    blockchain.initialize(network.genesis_config()).unwrap();
+    let genesis_hash = blockchain.last_hash();
+
+    let mut testkit = TestKit::from_blockchain(blockchain, network).unwrap();
+    assert_eq!(testkit.height(), Height(0));
+    assert_eq!(testkit.last_block_hash(), genesis_hash);
+
+    testkit.create_blocks_until(Height(3));
+    assert_eq!(testkit.height(), Height(3));
+    let block = testkit.committed_block_at(Height(1)).unwrap();
+    assert_eq!(*block.prev_hash(), genesis_hash);
+}
+
+#[test]
+fn test_from_blockchain_with_other_service_key() {
+    let network = TestNetwork::new(1);
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let blockchain = create_blockchain((&public_key, &secret_key));
+
+    let result = TestKit::from_blockchain(blockchain, network.clone());
+    assert_matches!(
+        result,
+        Err(Error::ServiceKeyMismatch { expected, actual })
+            if expected == *network.us().service_keypair().0 && actual == public_key
+    );
+}
+
+#[test]
+fn test_from_blockchain_with_other_validators() {
+    let mut network = TestNetwork::new(2);
+    let mut blockchain = create_blockchain(network.us().service_keypair());
+    blockchain.initialize(network.genesis_config()).unwrap();
+
+    let us = network.us().clone();
+    network.update(us.clone(), vec![us, TestNode::new_validator(ValidatorId(1))]);
+    let result = TestKit::from_blockchain(blockchain, network);
+    assert_matches!(result, Err(Error::ValidatorsMismatch));
+}
+
+#[test]
+#[should_panic(expected = "Checkpoints are not supported")]
+fn test_checkpoint_for_testkit_from_blockchain() {
+    let network = TestNetwork::new(1);
+    let blockchain = create_blockchain(network.us().service_keypair());
+    let mut testkit = TestKit::from_blockchain(blockchain, network).unwrap();
+    testkit.checkpoint();
+}
+
+#[test]
+fn test_fork_testkit_from_blockchain() {
+    let network = TestNetwork::new(1);
+    let blockchain = create_blockchain(network.us().service_keypair());
+    let testkit = TestKit::from_blockchain(blockchain, network).unwrap();
+    assert_matches!(testkit.fork_testkit(), Err(Error::Unsupported(_)));
+}
+
+#[test]
+fn test_dry_run_block_is_unsupported() {
+    let network = TestNetwork::new(1);
+    let blockchain = create_blockchain(network.us().service_keypair());
+    let mut testkit = TestKit::from_blockchain(blockchain, network).unwrap();
+    assert_matches!(testkit.dry_run_block(vec![]), Err(Error::Unsupported(_)));
+}
@@ -0,0 +1,131 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the fuzz driver of the testkit.
+
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::{blockchain::Transaction, crypto::{self, Seed, SEED_LENGTH}, helpers::Height};
+use exonum_testkit::{
+    fuzz::{self, FuzzDecoder, FuzzOp, FuzzRunner, FuzzStats},
+    samples::{CounterSchema, CounterService, TxIncrement}, TestKit, TestKitBuilder,
+};
+
+/// Decoder mapping each byte to an operation: `0` creates a block, `1` rolls back,
+/// `0x7f` increments the counter by `u64::MAX`, and other bytes increment the counter
+/// by the byte value minus 2, with the high bit turning the increment into a probe.
+struct ByteDecoder;
+
+impl FuzzDecoder for ByteDecoder {
+    fn decode_op(&self, input: &mut &[u8]) -> Option<FuzzOp> {
+        let data = *input;
+        let (&byte, rest) = data.split_first()?;
+        *input = rest;
+        let op = match byte {
+            0 => FuzzOp::CreateBlock,
+            1 => FuzzOp::Rollback,
+            0x7f => FuzzOp::Transaction(increment(u64::max_value())),
+            byte if byte & 0x80 != 0 => FuzzOp::Probe(increment(by_value(byte & 0x7f))),
+            byte => FuzzOp::Transaction(increment(by_value(byte))),
+        };
+        Some(op)
+    }
+}
+
+fn by_value(byte: u8) -> u64 {
+    u64::from(byte).saturating_sub(2)
+}
+
+fn increment(by: u64) -> Box<dyn Transaction> {
+    let (pubkey, key) = crypto::gen_keypair_from_seed(&Seed::new([1; SEED_LENGTH]));
+    Box::new(TxIncrement::new(&pubkey, by, &key))
+}
+
+fn init_testkit() -> TestKit {
+    TestKitBuilder::validator()
+        .with_service(CounterService)
+        .create()
+}
+
+fn count(testkit: &TestKit) -> Option<u64> {
+    CounterSchema::new(&testkit.snapshot()).count()
+}
+
+#[test]
+fn test_fuzz_run_creates_blocks() {
+    let mut testkit = init_testkit();
+    let stats = fuzz::run_transactions(&mut testkit, &ByteDecoder, &[3, 4, 0, 5, 0]);
+    assert_eq!(
+        stats,
+        FuzzStats {
+            operations: 5,
+            blocks: 2,
+            ..FuzzStats::default()
+        }
+    );
+    assert_eq!(testkit.height(), Height(2));
+    assert_eq!(count(&testkit), Some(6));
+}
+
+#[test]
+fn test_fuzz_run_swallows_expected_errors() {
+    let mut testkit = init_testkit();
+    // The zero increment fails; the repeated increment by 1 cannot be committed again.
+    // The trailing increment is committed in the final block.
+    let stats = fuzz::run_transactions(&mut testkit, &ByteDecoder, &[2, 3, 0, 3, 0, 4]);
+    assert_eq!(stats.blocks, 2);
+    assert_eq!(stats.rejected_blocks, 1);
+    assert_eq!(stats.failed_transactions, 1);
+    assert_eq!(testkit.height(), Height(2));
+    assert_eq!(count(&testkit), Some(3));
+}
+
+#[test]
+fn test_fuzz_run_with_rollbacks() {
+    let input = [3, 0, 4, 0, 1, 1, 1];
+
+    let mut testkit = init_testkit();
+    let stats = fuzz::run_transactions(&mut testkit, &ByteDecoder, &input);
+    assert_eq!(stats.rollbacks, 0);
+    assert_eq!(testkit.height(), Height(2));
+
+    let mut testkit = init_testkit();
+    let stats = FuzzRunner::new()
+        .with_rollbacks()
+        .run(&mut testkit, &ByteDecoder, &input);
+    assert_eq!(stats.blocks, 2);
+    assert_eq!(stats.rollbacks, 2);
+    assert_eq!(testkit.height(), Height(0));
+    assert_eq!(count(&testkit), None);
+}
+
+#[test]
+fn test_fuzz_run_probes() {
+    let mut testkit = init_testkit();
+    let stats = fuzz::run_transactions(&mut testkit, &ByteDecoder, &[0x83, 0x82, 0]);
+    assert_eq!(stats.probes, 2);
+    assert_eq!(stats.failed_transactions, 1);
+    assert_eq!(stats.blocks, 1);
+    assert_eq!(count(&testkit), None);
+}
+
+// The counter overflow panics only if overflow checks are enabled.
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "panicked during execution")]
+fn test_fuzz_run_propagates_panics() {
+    let mut testkit = init_testkit();
+    fuzz::run_transactions(&mut testkit, &ByteDecoder, &[0x7f, 3, 0]);
+}
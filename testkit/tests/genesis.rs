@@ -0,0 +1,276 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the genesis block, checking that it corresponds to the genesis configuration
+//! and is served by the explorer API in the same way as by a real node, and for
+//! the configurations of services in the genesis block.
+
+#[macro_use]
+extern crate serde_json;
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::{
+    api::node::public::explorer::{BlockInfo, BlockQuery},
+    blockchain::{Block, Schema, Service, Transaction}, crypto::{CryptoHash, Hash}, encoding,
+    helpers::{Height, ValidatorId}, messages::RawTransaction, storage::{Entry, Fork, Snapshot},
+};
+use exonum_testkit::{ApiKind, TestKit, TestKitBuilder};
+
+const SERVICE_NAME: &str = "genesis";
+
+fn genesis_entry<T>(view: T) -> Entry<T, u64> {
+    Entry::new("genesis.value", view)
+}
+
+/// Service that writes to the storage and returns a configuration on initialization.
+struct GenesisService;
+
+impl Service for GenesisService {
+    fn service_id(&self) -> u16 {
+        512
+    }
+
+    fn service_name(&self) -> &str {
+        SERVICE_NAME
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        vec![genesis_entry(snapshot).hash()]
+    }
+
+    fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        unimplemented!()
+    }
+
+    fn initialize(&self, fork: &mut Fork) -> serde_json::Value {
+        genesis_entry(fork).set(42);
+        json!({ "value": 42 })
+    }
+}
+
+fn create_testkit() -> TestKit {
+    TestKitBuilder::validator()
+        .with_validators(4)
+        .with_service(GenesisService)
+        .create()
+}
+
+#[test]
+fn test_genesis_block_header() {
+    let testkit = create_testkit();
+    let genesis = testkit.genesis_block();
+
+    let header = &genesis.block;
+    assert_eq!(header.height(), Height(0));
+    assert_eq!(header.proposer_id(), ValidatorId(0));
+    assert_eq!(header.tx_count(), 0);
+    assert_eq!(*header.prev_hash(), Hash::zero());
+    assert_eq!(*header.tx_hash(), Hash::zero());
+    assert!(genesis.precommits.is_empty());
+    assert!(genesis.txs.is_empty());
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.block_hash_by_height(Height(0)), Some(header.hash()));
+}
+
+#[test]
+fn test_genesis_configuration() {
+    let testkit = create_testkit();
+    let genesis_config = testkit.network().genesis_config();
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    let stored = schema.configuration_by_height(Height(0));
+    assert_eq!(stored.previous_cfg_hash, Hash::zero());
+    assert_eq!(stored.actual_from, Height(0));
+    assert_eq!(stored.validator_keys, genesis_config.validator_keys);
+    assert_eq!(stored.consensus, genesis_config.consensus);
+    assert_eq!(stored.services.len(), 1);
+    assert_eq!(stored.services[SERVICE_NAME], json!({ "value": 42 }));
+
+    // The genesis configuration is the only one committed to the blockchain.
+    let references: Vec<_> = schema.configs_actual_from().iter().collect();
+    assert_eq!(references.len(), 1);
+    assert_eq!(*references[0].cfg_hash(), stored.hash());
+    assert_eq!(references[0].actual_from(), Height(0));
+}
+
+#[test]
+fn test_genesis_state_hash() {
+    let mut testkit = create_testkit();
+    let genesis = testkit.genesis_block();
+
+    // The state hash aggregates the core tables (including the genesis configuration)
+    // and the service tables initialized in `Service::initialize`.
+    {
+        let snapshot = testkit.snapshot();
+        let schema = Schema::new(&snapshot);
+        let aggregator = schema.state_hash_aggregator();
+        assert_eq!(aggregator.merkle_root(), *genesis.block.state_hash());
+
+        let service_state = genesis_entry(&snapshot).hash();
+        assert_ne!(service_state, Hash::zero());
+        assert!(aggregator.values().any(|hash| hash == service_state));
+        let configs_hash = schema.configs().merkle_root();
+        assert!(aggregator.values().any(|hash| hash == configs_hash));
+    }
+
+    // The genesis block does not change with the blockchain growth.
+    testkit.create_blocks_until(Height(3));
+    assert_eq!(testkit.genesis_block(), genesis);
+}
+
+#[test]
+fn test_genesis_block_in_explorer_api() {
+    let testkit = create_testkit();
+    let genesis = testkit.genesis_block();
+    let api = testkit.api();
+
+    let info: Option<BlockInfo> = api.public(ApiKind::Explorer)
+        .query(&BlockQuery::new(Height(0)))
+        .get("v1/block")
+        .unwrap();
+    assert_eq!(info.as_ref(), Some(&genesis));
+
+    // Check the shape of the response.
+    let json: serde_json::Value = api.public(ApiKind::Explorer)
+        .query(&BlockQuery::new(Height(0)))
+        .get("v1/block")
+        .unwrap();
+    let expected_header = Block::new(
+        ValidatorId(0),
+        Height(0),
+        0,
+        &Hash::zero(),
+        &Hash::zero(),
+        genesis.block.state_hash(),
+    );
+    assert_eq!(
+        json,
+        json!({
+            "block": serde_json::to_value(&expected_header).unwrap(),
+            "precommits": [],
+            "txs": [],
+        })
+    );
+    let header_fields = json["block"].as_object().unwrap();
+    let mut keys: Vec<_> = header_fields.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec!["height", "prev_hash", "proposer_id", "state_hash", "tx_count", "tx_hash"]
+    );
+}
+
+#[test]
+fn test_genesis_service_configs() {
+    let testkit = create_testkit();
+    let configs = testkit.genesis_service_configs();
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[SERVICE_NAME], json!({ "value": 42 }));
+    assert_eq!(configs, testkit.actual_configuration().services);
+
+    let snapshot = testkit.snapshot();
+    assert_eq!(genesis_entry(&snapshot).get(), Some(42));
+}
+
+#[test]
+fn test_genesis_service_config_override() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(GenesisService)
+        .with_genesis_service_config(SERVICE_NAME, json!({ "value": 1 }))
+        .create();
+    let configs = testkit.genesis_service_configs();
+    assert_eq!(configs[SERVICE_NAME], json!({ "value": 1 }));
+
+    // `Service::initialize` is still invoked and seeds the storage.
+    let snapshot = testkit.snapshot();
+    assert_eq!(genesis_entry(&snapshot).get(), Some(42));
+
+    // The overridden configuration persists with the blockchain growth.
+    testkit.create_blocks_until(Height(2));
+    assert_eq!(testkit.genesis_service_configs(), configs);
+    assert_eq!(testkit.actual_configuration().services, configs);
+}
+
+#[test]
+#[should_panic(expected = "Cannot override genesis configuration of service `unknown`")]
+fn test_genesis_service_config_override_for_unknown_service() {
+    TestKitBuilder::validator()
+        .with_service(GenesisService)
+        .with_genesis_service_config("unknown", json!(null))
+        .create();
+}
+
+#[test]
+fn test_deferred_genesis_block() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with_service(GenesisService)
+        .without_genesis_block()
+        .create();
+
+    // The storage is empty until the first block is created.
+    let snapshot = testkit.snapshot();
+    assert!(Schema::new(&snapshot).block_hashes_by_height().is_empty());
+    assert_eq!(genesis_entry(&snapshot).get(), None);
+
+    let block = testkit.create_block();
+    assert_eq!(block.header.height(), Height(1));
+    assert_eq!(testkit.height(), Height(1));
+    assert_eq!(testkit.genesis_block().block.height(), Height(0));
+
+    let snapshot = testkit.snapshot();
+    assert_eq!(genesis_entry(&snapshot).get(), Some(42));
+    assert_eq!(testkit.genesis_service_configs()[SERVICE_NAME], json!({ "value": 42 }));
+}
+
+#[test]
+#[should_panic(expected = "during creating the genesis block")]
+fn test_deferred_genesis_block_height() {
+    let testkit = TestKitBuilder::validator()
+        .with_service(GenesisService)
+        .without_genesis_block()
+        .create();
+    testkit.height();
+}
+
+#[test]
+fn test_deferred_genesis_block_with_fast_forward() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(GenesisService)
+        .without_genesis_block()
+        .create();
+
+    testkit.fast_forward(3);
+    assert_eq!(testkit.height(), Height(3));
+    assert_eq!(testkit.genesis_block().block.height(), Height(0));
+    let snapshot = testkit.snapshot();
+    assert_eq!(genesis_entry(&snapshot).get(), Some(42));
+}
+
+#[test]
+fn test_deferred_genesis_block_with_blocks_until() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(GenesisService)
+        .without_genesis_block()
+        .create();
+
+    testkit.create_blocks_until(Height(2));
+    assert_eq!(testkit.height(), Height(2));
+    let snapshot = testkit.snapshot();
+    assert_eq!(genesis_entry(&snapshot).get(), Some(42));
+}
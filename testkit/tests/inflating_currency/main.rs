@@ -29,7 +29,7 @@ extern crate serde_derive;
 use exonum::{
     blockchain::Transaction, crypto::{self, CryptoHash, PublicKey, SecretKey}, helpers::Height,
 };
-use exonum_testkit::{ApiKind, TestKit, TestKitApi, TestKitBuilder};
+use exonum_testkit::{TestKit, TestKitApi, TestKitBuilder};
 use rand::Rng;
 
 use inflating_cryptocurrency::{CurrencyService, TransactionResponse, TxCreateWallet, TxTransfer};
@@ -48,19 +48,16 @@ fn create_wallet(api: &TestKitApi, name: &str) -> (TxCreateWallet, SecretKey) {
     // Create a pre-signed transaction
     let tx = TxCreateWallet::new(&pubkey, name, &key);
 
-    let tx_info: TransactionResponse = api.public(ApiKind::Service("cryptocurrency"))
-        .query(&tx)
-        .post("v1/wallets/transaction")
-        .unwrap();
+    let tx_info: TransactionResponse =
+        api.service("cryptocurrency").post("v1/wallets/transaction", &tx);
     assert_eq!(tx_info.tx_hash, tx.hash());
 
     (tx, key)
 }
 
 fn get_balance(api: &TestKitApi, pubkey: &PublicKey) -> u64 {
-    api.public(ApiKind::Service("cryptocurrency"))
+    api.service("cryptocurrency")
         .get(&format!("v1/balance?pub_key={}", pubkey.to_hex()))
-        .unwrap()
 }
 
 #[test]
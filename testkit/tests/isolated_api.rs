@@ -0,0 +1,153 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the collisions of service APIs and for the isolated service API, which mounts
+//! the API of a single service.
+
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState}, blockchain::{Service, Transaction},
+    crypto::Hash, encoding, messages::RawTransaction, storage::Snapshot,
+};
+use exonum_testkit::{ApiKind, Error, TestKit, TestKitApi, TestKitBuilder};
+
+/// Service with a single public endpoint returning a fixed string.
+struct InfoService {
+    id: u16,
+    name: &'static str,
+    endpoint: &'static str,
+}
+
+impl Service for InfoService {
+    fn service_id(&self) -> u16 {
+        self.id
+    }
+
+    fn service_name(&self) -> &str {
+        self.name
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        unimplemented!()
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        let name = self.name;
+        builder.public_scope().endpoint(
+            self.endpoint,
+            move |_: &ServiceApiState, _: ()| -> api::Result<String> { Ok(name.to_owned()) },
+        );
+    }
+}
+
+fn colliding_testkit() -> TestKit {
+    // Both endpoints are mounted at `api/services/info/v1/name`.
+    TestKitBuilder::validator()
+        .with_service(InfoService {
+            id: 1024,
+            name: "info",
+            endpoint: "v1/name",
+        })
+        .with_service(InfoService {
+            id: 1025,
+            name: "info/v1",
+            endpoint: "name",
+        })
+        .create()
+}
+
+#[test]
+#[should_panic(expected = "services `info` and `info/v1` collide at path `api/services/info`")]
+fn test_api_with_nested_mounts() {
+    colliding_testkit().api();
+}
+
+#[test]
+#[should_panic(expected = "services `info` and `info` collide at path `api/services/info`")]
+fn test_api_with_same_service_names() {
+    // The genesis block would panic because of the duplicate service name.
+    let testkit = TestKitBuilder::validator()
+        .with_service(InfoService {
+            id: 1024,
+            name: "info",
+            endpoint: "v1/name",
+        })
+        .with_service(InfoService {
+            id: 1025,
+            name: "info",
+            endpoint: "v2/name",
+        })
+        .without_genesis_block()
+        .create();
+    testkit.api();
+}
+
+#[test]
+fn test_api_with_similar_service_names() {
+    let testkit = TestKitBuilder::validator()
+        .with_service(InfoService {
+            id: 1024,
+            name: "info",
+            endpoint: "name",
+        })
+        .with_service(InfoService {
+            id: 1025,
+            name: "info2",
+            endpoint: "name",
+        })
+        .create();
+
+    let api = testkit.api();
+    let name: String = api.public(ApiKind::Service("info")).get("name").unwrap();
+    assert_eq!(name, "info");
+    let name: String = api.public(ApiKind::Service("info2")).get("name").unwrap();
+    assert_eq!(name, "info2");
+}
+
+#[test]
+fn test_isolated_api_with_colliding_mounts() {
+    let testkit = colliding_testkit();
+    for &(service_name, endpoint) in &[("info", "v1/name"), ("info/v1", "name")] {
+        let isolated_api = TestKitApi::isolated(&testkit, service_name).unwrap();
+        let name: String = isolated_api
+            .public(ApiKind::Service(service_name))
+            .get(endpoint)
+            .unwrap();
+        assert_eq!(name, service_name);
+    }
+
+    // Other services are not mounted.
+    let isolated_api = TestKitApi::isolated(&testkit, "info/v1").unwrap();
+    assert!(
+        isolated_api
+            .public(ApiKind::Service("info"))
+            .get::<String>("v1/name")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_isolated_api_for_unknown_service() {
+    let testkit = TestKitBuilder::validator().create();
+    match TestKitApi::isolated(&testkit, "unknown") {
+        Err(Error::UnknownService(name)) => assert_eq!(name, "unknown"),
+        other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the log records emitted by the testkit. The tests are placed into a separate
+//! crate, since the logger can be set only once per process.
+
+extern crate exonum;
+extern crate exonum_testkit;
+#[macro_use]
+extern crate lazy_static;
+extern crate log;
+
+use exonum::{crypto::{self, CryptoHash}, helpers::Height};
+use exonum_testkit::{
+    samples::{CounterService, TxIncrement}, TestKitBuilder,
+};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use std::sync::Mutex;
+
+#[derive(Debug, Clone)]
+struct LogRecord {
+    target: String,
+    level: Level,
+    message: String,
+}
+
+/// Logger capturing all records emitted by the testkit.
+#[derive(Debug, Default)]
+struct TestLogger {
+    records: Mutex<Vec<LogRecord>>,
+}
+
+impl Log for TestLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("exonum_testkit::")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.records.lock().unwrap().push(LogRecord {
+                target: record.target().to_owned(),
+                level: record.level(),
+                message: record.args().to_string(),
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+lazy_static! {
+    static ref LOGGER: TestLogger = TestLogger::default();
+}
+
+fn take_records() -> Vec<LogRecord> {
+    LOGGER.records.lock().unwrap().drain(..).collect()
+}
+
+fn assert_logged(records: &[LogRecord], target: &str, level: Level, message: &str) {
+    assert!(
+        records
+            .iter()
+            .any(|r| r.target == target && r.level == level && r.message.contains(message)),
+        "Missing {} record with target `{}` containing `{}`; records: {:#?}",
+        level,
+        target,
+        message,
+        records
+    );
+}
+
+#[test]
+fn test_testkit_log_records() {
+    log::set_logger(&*LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(2)
+        .with_service(CounterService)
+        .create();
+    let api = testkit.api();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    // Transactions received via API.
+    let tx = TxIncrement::new(&pubkey, 1, &key);
+    api.send(tx.clone());
+    testkit.create_block();
+    let records = take_records();
+    assert_logged(
+        &records,
+        "exonum_testkit::tx",
+        Level::Trace,
+        &format!("Pooled transaction {:?} received via API", tx.hash()),
+    );
+    assert_logged(
+        &records,
+        "exonum_testkit::block",
+        Level::Debug,
+        "at height 1 with 1 transactions, proposed by validator",
+    );
+
+    // Transactions passed to the testkit directly.
+    let tx = TxIncrement::new(&pubkey, 2, &key);
+    let (_, other_key) = crypto::gen_keypair();
+    let invalid_tx = TxIncrement::new(&pubkey, 3, &other_key);
+    testkit.create_block_with_transactions(vec![tx.clone().into(), invalid_tx.clone().into()]);
+    let records = take_records();
+    assert_logged(
+        &records,
+        "exonum_testkit::tx",
+        Level::Trace,
+        &format!("Pooled transaction {:?} passed to the testkit directly", tx.hash()),
+    );
+    assert_logged(
+        &records,
+        "exonum_testkit::tx",
+        Level::Trace,
+        &format!("Rejected transaction {:?}: verification failed", invalid_tx.hash()),
+    );
+    assert_logged(
+        &records,
+        "exonum_testkit::block",
+        Level::Debug,
+        "at height 2 with 1 transactions",
+    );
+
+    // Probes.
+    testkit.probe(TxIncrement::new(&pubkey, 4, &key));
+    let records = take_records();
+    assert_logged(
+        &records,
+        "exonum_testkit::probe",
+        Level::Debug,
+        "Probed 1 transactions at height 3, success: true",
+    );
+    assert_eq!(testkit.height(), Height(2));
+
+    // Configuration changes.
+    let proposal = testkit.propose_configuration_at(3);
+    let cfg_hash = proposal.stored_configuration().hash();
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(5));
+    let records = take_records();
+    for &action in &["Scheduled", "Committed", "Activated"] {
+        assert_logged(
+            &records,
+            "exonum_testkit::config",
+            Level::Debug,
+            &format!("{} configuration {:?}", action, cfg_hash),
+        );
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the requests to paginated API endpoints.
+
+extern crate exonum;
+extern crate exonum_testkit;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+use exonum_testkit::prelude::*;
+
+const PAGE_SIZE: u64 = 3;
+const LIMIT: u64 = 10;
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    page: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NumbersPage {
+    items: Vec<u64>,
+    next_page_token: Option<String>,
+}
+
+/// Service returning numbers below `LIMIT` page by page.
+struct NumbersService;
+
+impl Service for NumbersService {
+    fn service_id(&self) -> u16 {
+        1
+    }
+
+    fn service_name(&self) -> &str {
+        "numbers"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        unimplemented!()
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        builder
+            .public_scope()
+            .endpoint(
+                "v1/numbers",
+                |_: &ServiceApiState, query: PageQuery| -> api::Result<NumbersPage> {
+                    let start = match query.page {
+                        Some(token) => token
+                            .parse()
+                            .map_err(|_| api::Error::BadRequest("Invalid page token".to_owned()))?,
+                        None => 0,
+                    };
+                    let end = (start + PAGE_SIZE).min(LIMIT);
+                    Ok(NumbersPage {
+                        items: (start..end).collect(),
+                        next_page_token: if end < LIMIT {
+                            Some(end.to_string())
+                        } else {
+                            None
+                        },
+                    })
+                },
+            )
+            .endpoint(
+                "v1/single",
+                |_: &ServiceApiState, _: PageQuery| -> api::Result<NumbersPage> {
+                    Ok(NumbersPage {
+                        items: vec![0],
+                        next_page_token: None,
+                    })
+                },
+            )
+            .endpoint(
+                "v1/looped",
+                |_: &ServiceApiState, _: PageQuery| -> api::Result<NumbersPage> {
+                    Ok(NumbersPage {
+                        items: vec![0],
+                        next_page_token: Some("0".to_owned()),
+                    })
+                },
+            );
+    }
+}
+
+#[test]
+fn test_get_list() {
+    let testkit = TestKitBuilder::validator()
+        .with_service(NumbersService)
+        .create();
+    let api = testkit.api();
+
+    let numbers: Vec<u64> = api.get_list(ApiKind::Service("numbers"), "v1/numbers", "page");
+    assert_eq!(numbers, (0..LIMIT).collect::<Vec<_>>());
+    let numbers: Vec<u64> = api.get_list(ApiKind::Service("numbers"), "v1/single", "page");
+    assert_eq!(numbers, vec![0]);
+}
+
+#[test]
+#[should_panic(expected = "returned the token \"0\" of an already requested page")]
+fn test_get_list_with_looped_pagination() {
+    let testkit = TestKitBuilder::validator()
+        .with_service(NumbersService)
+        .create();
+    let api = testkit.api();
+    let _: Vec<u64> = api.get_list(ApiKind::Service("numbers"), "v1/looped", "page");
+}
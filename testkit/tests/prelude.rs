@@ -0,0 +1,129 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks that the testkit prelude suffices to define and test a typical service.
+//! No items are imported from `exonum` directly; the crate is only needed for its macros.
+
+#[macro_use]
+extern crate exonum;
+extern crate exonum_testkit;
+
+use exonum_testkit::prelude::*;
+
+const SERVICE_ID: u16 = 1_024;
+
+transactions! {
+    TotalTransactions {
+        const SERVICE_ID = SERVICE_ID;
+
+        struct TxAdd {
+            author: &PublicKey,
+            amount: u64,
+        }
+    }
+}
+
+fn total(snapshot: &dyn Snapshot) -> u64 {
+    snapshot
+        .get("total", &[])
+        .map(|bytes| String::from_utf8(bytes).unwrap().parse().unwrap())
+        .unwrap_or(0)
+}
+
+impl Transaction for TxAdd {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        if self.amount() == 0 {
+            return Err(ExecutionError::new(0));
+        }
+        let total = total(&*fork) + self.amount();
+        fork.put("total", vec![], total.to_string().into_bytes());
+        Ok(())
+    }
+}
+
+struct TotalService;
+
+impl Service for TotalService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        "total"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, encoding::Error> {
+        let tx = TotalTransactions::tx_from_raw(raw)?;
+        Ok(tx.into())
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        builder.public_scope().endpoint(
+            "v1/total",
+            |state: &ServiceApiState, _: ()| -> api::Result<u64> {
+                Ok(total(state.snapshot().as_ref()))
+            },
+        );
+    }
+}
+
+#[test]
+fn test_prelude_suffices() {
+    let mut testkit: TestKit = TestKitBuilder::validator()
+        .with_validators(2)
+        .with_service(TotalService)
+        .create();
+    let (pubkey, key): (PublicKey, SecretKey) = crypto::gen_keypair();
+
+    let block = testkit.create_block_with_transactions(vec![
+        TxAdd::new(&pubkey, 3, &key).into(),
+        TxAdd::new(&pubkey, 0, &key).into(),
+    ]);
+    assert!(block[0].status().is_ok());
+    let error: &TransactionError = block[1].status().unwrap_err();
+    assert_eq!(error.error_type(), TransactionErrorType::Code(0));
+
+    let api: TestKitApi = testkit.api();
+    let total: u64 = api.service("total").get("v1/total");
+    assert_eq!(total, 3);
+
+    let height: Height = testkit.height();
+    assert_eq!(height, Height(1));
+    let us: &TestNode = testkit.us();
+    assert_eq!(us.validator_id(), Some(ValidatorId(0)));
+
+    let mut proposal: TestNetworkConfiguration = testkit.propose_configuration_at(2);
+    let consensus = ConsensusConfig {
+        max_message_len: 2 * 1024 * 1024,
+        ..proposal.consensus_configuration().clone()
+    };
+    proposal.set_consensus_configuration(consensus.clone());
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(2));
+    let stored: StoredConfiguration = testkit.actual_configuration();
+    assert_eq!(stored.consensus, consensus);
+    assert_eq!(Round::first(), Round(1));
+
+    let snapshot = testkit.snapshot();
+    let schema = CoreSchema::new(&snapshot);
+    assert!(schema.transactions().contains(&TxAdd::new(&pubkey, 3, &key).hash()));
+}
@@ -0,0 +1,270 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! End-to-end tests for the sample services shipped with the testkit.
+
+extern crate exonum;
+extern crate exonum_testkit;
+#[macro_use]
+extern crate pretty_assertions;
+
+use exonum::storage::ProofMapIndex;
+use exonum_testkit::{
+    prelude::*,
+    samples::{
+        currency::{Error, WalletQuery}, CounterSchema, CounterService, CurrencyConfig,
+        CurrencySchema, CurrencyService, TransactionResponse, TxCreateWallet, TxIncrement,
+        TxReset, TxTransfer, Wallet, COUNTER_SERVICE_NAME, CURRENCY_SERVICE_ID,
+        CURRENCY_SERVICE_NAME,
+    },
+    ChainVerificationError,
+};
+
+fn init_testkit() -> TestKit {
+    TestKitBuilder::validator()
+        .with_validators(2)
+        .with_service(CounterService)
+        .with_service(CurrencyService)
+        .create()
+}
+
+#[test]
+fn test_counter_api() {
+    let mut testkit = init_testkit();
+    let api = testkit.api();
+    let (pubkey, key) = crypto::gen_keypair();
+
+    let tx = TxIncrement::new(&pubkey, 5, &key);
+    let response: TransactionResponse = api.service(COUNTER_SERVICE_NAME).post("count", &tx);
+    assert_eq!(response.tx_hash, tx.hash());
+
+    testkit.create_block();
+    let count: u64 = api.service(COUNTER_SERVICE_NAME).get("count");
+    assert_eq!(count, 5);
+
+    let tx = TxReset::new(&pubkey, &key);
+    let response: TransactionResponse =
+        api.service(COUNTER_SERVICE_NAME).private().post("reset", &tx);
+    assert_eq!(response.tx_hash, tx.hash());
+
+    testkit.create_block();
+    let count: u64 = api.service(COUNTER_SERVICE_NAME).private().get("count");
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_counter_probe() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(TxIncrement::new(&pubkey, 5, &key));
+
+    let snapshot = testkit.probe(TxIncrement::new(&pubkey, 3, &key));
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(8));
+    let snapshot = testkit.probe(TxReset::new(&pubkey, &key));
+    assert_eq!(CounterSchema::new(&snapshot).count(), Some(0));
+    // Probing does not change the blockchain state.
+    assert_eq!(CounterSchema::new(&testkit.snapshot()).count(), Some(5));
+}
+
+#[test]
+fn test_counter_zero_increment() {
+    let mut testkit = init_testkit();
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx = TxIncrement::new(&pubkey, 5, &key);
+    let zero_tx = TxIncrement::new(&pubkey, 0, &key);
+
+    let report = testkit
+        .dry_run_block(vec![tx.clone().into(), zero_tx.clone().into()])
+        .unwrap();
+    assert_eq!(report.executed, vec![tx.hash()]);
+    assert_eq!(report.errored.len(), 1);
+    assert_eq!(report.errored[0].0, zero_tx.hash());
+    assert_eq!(report.errored[0].1.error_type(), TransactionErrorType::Code(0));
+}
+
+#[test]
+fn test_currency_transfer() {
+    let mut testkit = init_testkit();
+    let (alice, alice_key) = crypto::gen_keypair();
+    let (bob, bob_key) = crypto::gen_keypair();
+
+    testkit.create_block_with_transactions(vec![
+        TxCreateWallet::new(&alice, "Alice", &alice_key).into(),
+        TxCreateWallet::new(&bob, "Bob", &bob_key).into(),
+    ]);
+    testkit.create_block_with_transaction(TxTransfer::new(&alice, &bob, 30, 0, &alice_key));
+
+    let snapshot = testkit.snapshot();
+    let schema = CurrencySchema::new(&snapshot);
+    assert_eq!(schema.wallet(&alice), Some(Wallet::new(&alice, "Alice", 70)));
+    assert_eq!(schema.wallet(&bob), Some(Wallet::new(&bob, "Bob", 130)));
+    assert_eq!(schema.state_hash(), vec![schema.wallets().merkle_root()]);
+}
+
+#[test]
+fn test_currency_errors() {
+    let mut testkit = init_testkit();
+    let (alice, alice_key) = crypto::gen_keypair();
+    let (bob, _) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(TxCreateWallet::new(&alice, "Alice", &alice_key));
+
+    let duplicate_wallet = TxCreateWallet::new(&alice, "Alice II", &alice_key);
+    let unknown_receiver = TxTransfer::new(&alice, &bob, 10, 0, &alice_key);
+    let report = testkit
+        .dry_run_block(vec![
+            duplicate_wallet.clone().into(),
+            unknown_receiver.clone().into(),
+        ])
+        .unwrap();
+    assert!(report.executed.is_empty());
+
+    let errors: Vec<_> = report
+        .errored
+        .iter()
+        .map(|&(hash, ref error)| (hash, error.error_type()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                duplicate_wallet.hash(),
+                TransactionErrorType::Code(Error::WalletAlreadyExists as u8),
+            ),
+            (
+                unknown_receiver.hash(),
+                TransactionErrorType::Code(Error::ReceiverNotFound as u8),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_currency_insufficient_funds() {
+    let mut testkit = init_testkit();
+    let (alice, alice_key) = crypto::gen_keypair();
+    let (bob, bob_key) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(vec![
+        TxCreateWallet::new(&alice, "Alice", &alice_key).into(),
+        TxCreateWallet::new(&bob, "Bob", &bob_key).into(),
+    ]);
+
+    let tx = TxTransfer::new(&alice, &bob, 1_000, 0, &alice_key);
+    let report = testkit.dry_run_block(vec![tx.clone().into()]).unwrap();
+    assert_eq!(report.errored.len(), 1);
+    let (hash, ref error) = report.errored[0];
+    assert_eq!(hash, tx.hash());
+    assert_eq!(
+        error.error_type(),
+        TransactionErrorType::Code(Error::InsufficientCurrencyAmount as u8)
+    );
+    assert_eq!(error.description(), Some("Insufficient currency amount"));
+
+    let snapshot = testkit.snapshot();
+    let schema = CurrencySchema::new(&snapshot);
+    assert_eq!(schema.wallet(&alice).unwrap().balance(), 100);
+}
+
+#[test]
+fn test_currency_config_change() {
+    let mut testkit = init_testkit();
+    assert_eq!(
+        CurrencySchema::new(&testkit.snapshot()).config(),
+        CurrencyConfig::default()
+    );
+
+    let new_config = CurrencyConfig { initial_balance: 50 };
+    let proposal = {
+        let mut cfg = testkit.configuration_change_proposal();
+        cfg.set_actual_from(Height(3));
+        cfg.set_service_config(CURRENCY_SERVICE_NAME, new_config);
+        cfg
+    };
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(Height(3));
+    assert_eq!(CurrencySchema::new(&testkit.snapshot()).config(), new_config);
+
+    let (alice, alice_key) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(TxCreateWallet::new(&alice, "Alice", &alice_key));
+    let snapshot = testkit.snapshot();
+    let wallet = CurrencySchema::new(&snapshot).wallet(&alice).unwrap();
+    assert_eq!(wallet.balance(), 50);
+}
+
+#[test]
+fn test_currency_api() {
+    let mut testkit = init_testkit();
+    let api = testkit.api();
+    let (alice, alice_key) = crypto::gen_keypair();
+    let (bob, bob_key) = crypto::gen_keypair();
+
+    let txs = vec![
+        TxCreateWallet::new(&alice, "Alice", &alice_key),
+        TxCreateWallet::new(&bob, "Bob", &bob_key),
+    ];
+    for tx in &txs {
+        let response: TransactionResponse =
+            api.service(CURRENCY_SERVICE_NAME).post("v1/wallets/transaction", tx);
+        assert_eq!(response.tx_hash, tx.hash());
+    }
+    testkit.create_block();
+
+    let wallet: Wallet = api.service(CURRENCY_SERVICE_NAME)
+        .get_with_query("v1/wallet", &WalletQuery { pub_key: alice });
+    assert_eq!(wallet, Wallet::new(&alice, "Alice", 100));
+
+    let mut wallets: Vec<Wallet> = api.service(CURRENCY_SERVICE_NAME).private().get("v1/wallets");
+    wallets.sort_by(|a, b| a.name().cmp(b.name()));
+    assert_eq!(
+        wallets,
+        vec![
+            Wallet::new(&alice, "Alice", 100),
+            Wallet::new(&bob, "Bob", 100),
+        ]
+    );
+
+    let (carol, _) = crypto::gen_keypair();
+    api.public(ApiKind::Service(CURRENCY_SERVICE_NAME))
+        .query(&WalletQuery { pub_key: carol })
+        .get::<Wallet>("v1/wallet")
+        .unwrap_err();
+}
+
+#[test]
+fn test_verify_chain_with_tampered_service_state() {
+    let mut testkit = init_testkit();
+    let (alice, alice_key) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(TxCreateWallet::new(&alice, "Alice", &alice_key));
+    assert_eq!(testkit.verify_chain(), Ok(()));
+
+    // Change the balance bypassing transactions.
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut wallets: ProofMapIndex<_, PublicKey, Wallet> =
+                ProofMapIndex::new("currency.wallets", &mut fork);
+            wallets.put(&alice, Wallet::new(&alice, "Alice", 1_000_000));
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    assert_eq!(
+        testkit.verify_chain(),
+        Err(ChainVerificationError::StateTableMismatch {
+            height: Height(1),
+            service_id: CURRENCY_SERVICE_ID,
+            table_idx: 0,
+        })
+    );
+}
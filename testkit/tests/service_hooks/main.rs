@@ -22,9 +22,9 @@ extern crate serde_json;
 pub use hooks::{AfterCommitService, TxAfterCommit};
 
 use exonum::{
-    crypto::{CryptoHash, Signature}, helpers::Height, messages::Message,
+    blockchain::Schema, crypto::{CryptoHash, Signature}, helpers::Height, messages::Message,
 };
-use exonum_testkit::TestKitBuilder;
+use exonum_testkit::{ServiceCall, TestKitBuilder};
 
 mod hooks;
 
@@ -54,3 +54,106 @@ fn test_after_commit() {
         .all(|block| block.len() == if block.height() == Height(1) { 0 } else { 1 });
     assert!(expected_block_sizes);
 }
+
+#[test]
+fn test_commit_callback_runs_before_after_commit_transactions_are_pooled() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(AfterCommitService)
+        .create();
+    testkit.create_block();
+
+    let tx = TxAfterCommit::new_with_signature(Height(2), &Signature::zero());
+    let mut callback_called = false;
+    let block = testkit.create_block_with_commit_callback(vec![], |block, snapshot| {
+        assert_eq!(block.height(), Height(2));
+        assert_eq!(block.tx_count(), 0);
+        assert!(!Schema::new(snapshot).transactions_pool().contains(&tx.hash()));
+        callback_called = true;
+    });
+    assert!(callback_called);
+    assert_eq!(block.height(), Height(2));
+    assert!(testkit.is_tx_in_pool(&tx.hash()));
+}
+
+#[test]
+fn test_service_spy_records_hooks() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_spied_service(AfterCommitService)
+        .create();
+    testkit.create_blocks_until(Height(4));
+
+    let calls = testkit.service_calls("after_commit");
+    assert_eq!(calls.initialize_count(), 1);
+    // Exactly one `after_commit` call for each created block.
+    assert_eq!(
+        calls.after_commit_heights(),
+        vec![Height(1), Height(2), Height(3), Height(4)]
+    );
+    // The transaction generated after the commit of each block is executed in the next one.
+    assert_eq!(calls.execute_heights(), vec![Height(2), Height(3), Height(4)]);
+    let tx = TxAfterCommit::new_with_signature(Height(1), &Signature::zero());
+    assert!(calls.all().contains(&(Height(2), ServiceCall::Execute(tx.hash()))));
+}
+
+#[test]
+fn test_service_spy_ignores_probes() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_spied_service(AfterCommitService)
+        .create();
+    testkit.create_block();
+    let calls = testkit.service_calls("after_commit");
+    let recorded = calls.all();
+
+    let tx = TxAfterCommit::new_with_signature(Height(100), &Signature::zero());
+    testkit.probe(tx.clone());
+    assert_eq!(calls.all(), recorded);
+
+    testkit.create_block();
+    assert_eq!(calls.after_commit_heights(), vec![Height(1), Height(2)]);
+    assert!(!calls.all().contains(&(Height(2), ServiceCall::Execute(tx.hash()))));
+}
+
+#[test]
+fn test_service_spy_in_forked_testkit() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_spied_service(AfterCommitService)
+        .create();
+    testkit.create_block();
+    testkit.checkpoint();
+    let mut fork = testkit.fork_testkit().unwrap();
+
+    // The copy starts with the calls recorded by the testkit, but records its own calls.
+    assert_eq!(
+        fork.service_calls("after_commit").all(),
+        testkit.service_calls("after_commit").all()
+    );
+    fork.create_blocks_until(Height(3));
+    testkit.create_block();
+    assert_eq!(
+        fork.service_calls("after_commit").after_commit_heights(),
+        vec![Height(1), Height(2), Height(3)]
+    );
+    assert_eq!(
+        testkit.service_calls("after_commit").after_commit_heights(),
+        vec![Height(1), Height(2)]
+    );
+
+    // Rolling back the testkit does not affect the calls recorded by the copy.
+    testkit.rollback();
+    assert_eq!(
+        testkit.service_calls("after_commit").after_commit_heights(),
+        vec![Height(1)]
+    );
+    let calls = fork.service_calls("after_commit");
+    assert_eq!(calls.after_commit_heights().len(), 3);
+    assert_eq!(calls.execute_heights(), vec![Height(2), Height(3)]);
+}
+
+#[test]
+#[should_panic(expected = "Service `after_commit` is not spied")]
+fn test_service_calls_of_not_spied_service() {
+    let testkit = TestKitBuilder::validator()
+        .with_service(AfterCommitService)
+        .create();
+    testkit.service_calls("after_commit");
+}
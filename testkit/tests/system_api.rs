@@ -23,7 +23,7 @@ use exonum::{
     },
     helpers::user_agent, messages::PROTOCOL_MAJOR_VERSION,
 };
-use exonum_testkit::{ApiKind, TestKitBuilder};
+use exonum_testkit::{ApiKind, StatusCode, TestKitBuilder};
 
 #[test]
 fn healthcheck() {
@@ -53,6 +53,32 @@ fn user_agent_info() {
     assert_eq!(info, expected);
 }
 
+#[test]
+fn user_agent_components() {
+    let testkit = TestKitBuilder::validator().create();
+    let api = testkit.api();
+
+    let user_agent = api.user_agent();
+    assert_eq!(user_agent.name, "exonum");
+    assert!(
+        user_agent
+            .version
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().next().unwrap().is_digit(10)),
+        "Unexpected Exonum version: {:?}",
+        user_agent.version
+    );
+    assert!(
+        user_agent.rust_version.starts_with("rustc "),
+        "Unexpected Rust version: {:?}",
+        user_agent.rust_version
+    );
+    assert_eq!(
+        format!("{} {}/{}", user_agent.name, user_agent.version, user_agent.rust_version),
+        user_agent::versions()
+    );
+}
+
 #[test]
 fn network() {
     let testkit = TestKitBuilder::validator().with_validators(2).create();
@@ -89,3 +115,24 @@ fn rebroadcast() {
         ()
     )
 }
+
+#[test]
+fn head_unknown_endpoint() {
+    let testkit = TestKitBuilder::validator().create();
+    let api = testkit.api();
+
+    let status = api.public(ApiKind::System).head("v1/unknown");
+    assert_eq!(status, StatusCode::NotFound);
+}
+
+#[test]
+fn head_existing_endpoint() {
+    let testkit = TestKitBuilder::validator().create();
+    let api = testkit.api();
+
+    // `HEAD` is served by the `GET` handler; the empty body is checked by `head` itself.
+    assert_eq!(api.head(ApiKind::System, "v1/healthcheck"), StatusCode::Ok);
+    assert_eq!(api.head(ApiKind::Explorer, "v1/blocks?count=1"), StatusCode::Ok);
+    // `POST` endpoints do not accept `HEAD` requests.
+    assert_ne!(api.private(ApiKind::System).head("v1/rebroadcast"), StatusCode::Ok);
+}
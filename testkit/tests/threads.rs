@@ -0,0 +1,105 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+extern crate exonum;
+extern crate exonum_testkit;
+extern crate serde_json;
+
+use exonum::{
+    api::node::public::explorer::{BlocksQuery, BlocksRange}, blockchain::Schema,
+    helpers::Height,
+};
+use exonum_testkit::{ApiKind, TestKitApi, TestKitBuilder};
+use serde_json::Value;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const THREADS_COUNT: usize = 4;
+
+#[test]
+fn test_reading_from_several_threads() {
+    let mut testkit = TestKitBuilder::validator().with_validators(4).create();
+    let reader = testkit.reader();
+    let is_done = Arc::new(AtomicBool::new(false));
+
+    let handles: Vec<_> = (0..THREADS_COUNT)
+        .map(|_| {
+            let reader = reader.clone();
+            let is_done = Arc::clone(&is_done);
+            thread::spawn(move || {
+                let api = reader.api();
+                let mut last_height = Height(0);
+                loop {
+                    // Load the flag before reading, so that the last iteration
+                    // observes the final state of the blockchain.
+                    let is_done = is_done.load(Ordering::SeqCst);
+
+                    let height = Schema::new(&reader.snapshot()).height();
+                    assert!(height >= last_height);
+                    last_height = height;
+
+                    let response: BlocksRange = api.public(ApiKind::Explorer)
+                        .query(&BlocksQuery {
+                            count: 1,
+                            ..Default::default()
+                        })
+                        .get("v1/blocks")
+                        .unwrap();
+                    assert!(response.range.end > last_height);
+
+                    if is_done {
+                        return last_height;
+                    }
+                }
+            })
+        })
+        .collect();
+
+    testkit.create_blocks_until(Height(20));
+    is_done.store(true, Ordering::SeqCst);
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), Height(20));
+    }
+    assert_eq!(reader.height(), Height(20));
+}
+
+#[test]
+fn test_concurrent_api_calls() {
+    let mut testkit = TestKitBuilder::validator().create();
+    testkit.create_blocks_until(Height(10));
+
+    let calls = (1..=THREADS_COUNT)
+        .map(|count| {
+            Box::new(move |api: &TestKitApi| -> Value {
+                api.public(ApiKind::Explorer)
+                    .query(&BlocksQuery {
+                        count,
+                        ..Default::default()
+                    })
+                    .get("v1/blocks")
+                    .unwrap()
+            }) as Box<dyn FnMut(&TestKitApi) -> Value + Send>
+        })
+        .collect();
+    let responses = testkit.concurrent_api_calls(calls);
+
+    assert_eq!(responses.len(), THREADS_COUNT);
+    for (i, response) in responses.into_iter().enumerate() {
+        let response: BlocksRange = serde_json::from_value(response).unwrap();
+        assert_eq!(response.blocks.len(), i + 1);
+        assert_eq!(response.blocks[0].height(), Height(10));
+    }
+}